@@ -0,0 +1,203 @@
+//! Blueprints are groups of tiles written out as a short line of text, so they can be shared anywhere text can.
+//!
+//! A blueprint string looks like `automancy-bp:1:<data>`, where the number is the version of the format,
+//! and the data is the tiles written as RON, compressed with zstd, and encoded as URL-safe base64.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::{Id, IdRaw};
+use automancy_resources::data::{DataMap, DataMapRaw};
+use automancy_resources::ResourceManager;
+
+use crate::map::EXPORT_PATH;
+
+pub const BLUEPRINT_PREFIX: &str = "automancy-bp";
+pub const BLUEPRINT_VERSION: u32 = 1;
+
+/// The largest a blueprint can be once decompressed, so a small string can't take up all the memory.
+const MAX_BLUEPRINT_SIZE: usize = 16 * 1024 * 1024;
+const COMPRESSION_LEVEL: i32 = 19;
+/// The extension of the files holding a blueprint string, which are looked for in [`EXPORT_PATH`].
+pub const BLUEPRINT_EXT: &str = ".blueprint";
+
+#[derive(Debug, Error)]
+pub enum BlueprintError {
+    #[error("the text is not a blueprint")]
+    NotABlueprint,
+    #[error(
+        "the blueprint is version {0}, but only up to version {BLUEPRINT_VERSION} can be read"
+    )]
+    UnsupportedVersion(u32),
+    #[error("the blueprint is damaged: {0}")]
+    Malformed(String),
+    #[error("the blueprint needs mods that are not loaded: {}", .0.join(", "))]
+    MissingMods(Vec<String>),
+    #[error("the blueprint has no tiles")]
+    Empty,
+    #[error("the blueprint file could not be read: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlueprintRaw {
+    /// the namespaces of all the tiles, so what a blueprint needs can be seen without reading the tiles
+    mods: BTreeSet<String>,
+    tiles: Vec<(TileCoord, IdRaw, DataMapRaw)>,
+}
+
+/// A group of tiles and their configs, positioned relative to its center.
+#[derive(Debug, Clone, Default)]
+pub struct Blueprint {
+    pub tiles: Vec<(TileCoord, Id, DataMap)>,
+}
+
+impl Blueprint {
+    /// Makes a blueprint from tiles anywhere in the world, moving them to be around the center.
+    ///
+    /// The items held by the tiles are left out, so sharing a blueprint can't make items out of nothing.
+    pub fn new(resource_man: &ResourceManager, tiles: Vec<(TileCoord, Id, DataMap)>) -> Self {
+        if tiles.is_empty() {
+            return Self::default();
+        }
+
+        let center = TileCoord::from(TileBounds::from_iter(tiles.iter().map(|v| v.0)).center);
+
+        Self {
+            tiles: tiles
+                .into_iter()
+                .map(|(coord, id, mut data)| {
+                    data.remove(&resource_man.registry.data_ids.buffer);
+
+                    (coord - center, id, data)
+                })
+                .collect(),
+        }
+    }
+
+    /// Gets the namespaces of the mods the blueprint's tiles come from.
+    pub fn required_mods(&self, resource_man: &ResourceManager) -> BTreeSet<String> {
+        self.tiles
+            .iter()
+            .map(|(_, id, _)| IdRaw::parse(resource_man.interner.resolve(*id).unwrap()))
+            .map(|id| id.namespace().to_string())
+            .collect()
+    }
+
+    /// Writes the blueprint out as a blueprint string.
+    pub fn encode(&self, resource_man: &ResourceManager) -> String {
+        let raw = BlueprintRaw {
+            mods: self.required_mods(resource_man),
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(coord, id, data)| {
+                    (
+                        *coord,
+                        IdRaw::parse(resource_man.interner.resolve(*id).unwrap()),
+                        data.to_raw(&resource_man.interner),
+                    )
+                })
+                .collect(),
+        };
+
+        let ron = ron::to_string(&raw).unwrap();
+        let compressed = zstd::encode_all(ron.as_bytes(), COMPRESSION_LEVEL).unwrap();
+
+        format!(
+            "{BLUEPRINT_PREFIX}:{BLUEPRINT_VERSION}:{}",
+            URL_SAFE_NO_PAD.encode(compressed)
+        )
+    }
+
+    /// Reads a blueprint string. Whitespace around it is ignored, as chat programs like to add some.
+    pub fn decode(resource_man: &ResourceManager, string: &str) -> Result<Self, BlueprintError> {
+        let mut parts = string.trim().splitn(3, ':');
+
+        if parts.next() != Some(BLUEPRINT_PREFIX) {
+            return Err(BlueprintError::NotABlueprint);
+        }
+
+        let version = parts
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or(BlueprintError::NotABlueprint)?;
+        if version > BLUEPRINT_VERSION {
+            return Err(BlueprintError::UnsupportedVersion(version));
+        }
+
+        let data = parts.next().ok_or(BlueprintError::NotABlueprint)?;
+        let compressed = URL_SAFE_NO_PAD
+            .decode(data)
+            .map_err(|err| BlueprintError::Malformed(err.to_string()))?;
+        let ron = zstd::bulk::decompress(&compressed, MAX_BLUEPRINT_SIZE)
+            .map_err(|err| BlueprintError::Malformed(err.to_string()))?;
+        let raw: BlueprintRaw =
+            ron::de::from_bytes(&ron).map_err(|err| BlueprintError::Malformed(err.to_string()))?;
+
+        let missing = raw
+            .tiles
+            .iter()
+            .filter(|(_, id, _)| {
+                !resource_man
+                    .interner
+                    .get(id.to_string())
+                    .is_some_and(|id| resource_man.registry.tiles.contains_key(&id))
+            })
+            .map(|(_, id, _)| id.namespace().to_string())
+            .collect::<BTreeSet<_>>();
+        if !missing.is_empty() {
+            return Err(BlueprintError::MissingMods(missing.into_iter().collect()));
+        }
+
+        if raw.tiles.is_empty() {
+            return Err(BlueprintError::Empty);
+        }
+
+        Ok(Self {
+            tiles: raw
+                .tiles
+                .into_iter()
+                .map(|(coord, id, data)| {
+                    (
+                        coord,
+                        resource_man.interner.get(id.to_string()).unwrap(),
+                        data.to_data(&resource_man.interner),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    /// Reads a blueprint from a file holding its string.
+    pub fn read_file(resource_man: &ResourceManager, path: &Path) -> Result<Self, BlueprintError> {
+        Self::decode(resource_man, &fs::read_to_string(path)?)
+    }
+
+    /// Lists the blueprint files in the exports folder.
+    pub fn list_files() -> Vec<PathBuf> {
+        let Ok(dir) = fs::read_dir(EXPORT_PATH) else {
+            return vec![];
+        };
+
+        let mut files = dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.to_str()
+                    .is_some_and(|path| path.ends_with(BLUEPRINT_EXT))
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+
+        files
+    }
+}
@@ -0,0 +1,75 @@
+//! Clocks that can be stopped, so whatever is timed by them stops along with them.
+//!
+//! The game keeps three kinds of time:
+//! - real time, which never stops, and is read with [`std::time::Instant`] as usual, for things like frame pacing;
+//! - simulation time, which stops while the simulation is paused, for the animations in the world, the statistics and autosaves;
+//! - UI time, which stops while the game is paused or in the background, for the animations on the GUI.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A time on a [`Clock`], counted from when it was made.
+pub type ClockTime = Duration;
+
+#[derive(Debug)]
+struct ClockState {
+    /// the time counted up to when the clock was last started
+    counted: Duration,
+    /// when the clock was last started, if it is running
+    started: Option<Instant>,
+}
+
+/// A clock that can be stopped and started again, shared between its clones.
+#[derive(Debug, Clone)]
+pub struct Clock(Arc<Mutex<ClockState>>);
+
+impl Default for Clock {
+    /// A clock starting from zero, running.
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(ClockState {
+            counted: Duration::ZERO,
+            started: Some(Instant::now()),
+        })))
+    }
+}
+
+impl Clock {
+    /// The time on the clock.
+    pub fn now(&self) -> ClockTime {
+        let state = self.0.lock().unwrap();
+
+        state.counted + state.started.map_or(Duration::ZERO, |v| v.elapsed())
+    }
+
+    /// How long it has been on the clock since the time.
+    pub fn since(&self, time: ClockTime) -> Duration {
+        self.now().saturating_sub(time)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.0.lock().unwrap().started.is_some()
+    }
+
+    /// Starts or stops the clock. Nothing happens if it already is.
+    pub fn set_running(&self, running: bool) {
+        let mut state = self.0.lock().unwrap();
+
+        match (running, state.started) {
+            (true, None) => state.started = Some(Instant::now()),
+            (false, Some(started)) => {
+                state.counted += started.elapsed();
+                state.started = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The clocks the game is timed by, other than real time.
+#[derive(Debug, Clone, Default)]
+pub struct GameClocks {
+    /// stopped while the simulation is paused. The game system doesn't tick while it is stopped.
+    pub simulation: Clock,
+    /// stopped while the game is paused or in the background.
+    pub ui: Clock,
+}
@@ -0,0 +1,260 @@
+//! Hashes of the game's state, to catch the simulation going differently between two runs of the same map.
+//!
+//! Multiplayer and replays both rely on every game ticking the same way from the same start,
+//! so if two runs of a map ever hash differently at the same tick, something in the game is nondeterministic.
+//! IDs are hashed by their names, and unordered collections are sorted first, so the hashes don't depend on
+//! the order things were loaded in.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use xxhash_rust::xxh3::Xxh3;
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, Interner};
+use automancy_resources::data::{Data, DataMap};
+
+fn hash_coord(hasher: &mut Xxh3, coord: TileCoord) {
+    hasher.update(&coord.x.to_le_bytes());
+    hasher.update(&coord.y.to_le_bytes());
+}
+
+fn hash_id(hasher: &mut Xxh3, interner: &Interner, id: Id) {
+    hasher.update(interner.resolve(id).unwrap_or_default().as_bytes());
+    // so that two names in a row can't run together into the same bytes
+    hasher.update(&[0]);
+}
+
+fn sorted_ids<'a>(interner: &Interner, ids: impl IntoIterator<Item = &'a Id>) -> Vec<Id> {
+    let mut ids = ids.into_iter().copied().collect::<Vec<_>>();
+    ids.sort_by_key(|id| interner.resolve(*id));
+
+    ids
+}
+
+fn hash_data(hasher: &mut Xxh3, interner: &Interner, data: &Data) {
+    match data {
+        Data::Inventory(inventory) => {
+            hasher.update(b"inventory");
+
+            for id in sorted_ids(interner, inventory.keys()) {
+                hash_id(hasher, interner, id);
+                hasher.update(&inventory[&id].to_le_bytes());
+            }
+        }
+        Data::Coord(coord) => {
+            hasher.update(b"coord");
+            hash_coord(hasher, *coord);
+        }
+        Data::VecCoord(coords) => {
+            hasher.update(b"vec_coord");
+
+            for coord in coords {
+                hash_coord(hasher, *coord);
+            }
+        }
+        Data::TileBounds(bounds) => {
+            hasher.update(b"tile_bounds");
+            hash_coord(hasher, bounds.center.into());
+            hasher.update(&bounds.radius.to_le_bytes());
+        }
+        Data::Id(id) => {
+            hasher.update(b"id");
+            hash_id(hasher, interner, *id);
+        }
+        Data::Color(color) => {
+            hasher.update(b"color");
+
+            for v in color.to_array() {
+                hasher.update(&v.to_bits().to_le_bytes());
+            }
+        }
+        Data::VecId(ids) => {
+            hasher.update(b"vec_id");
+
+            for id in ids {
+                hash_id(hasher, interner, *id);
+            }
+        }
+        Data::SetId(ids) => {
+            hasher.update(b"set_id");
+
+            for id in sorted_ids(interner, ids) {
+                hash_id(hasher, interner, id);
+            }
+        }
+        Data::Amount(amount) => {
+            hasher.update(b"amount");
+            hasher.update(&amount.to_le_bytes());
+        }
+        Data::Bool(v) => {
+            hasher.update(b"bool");
+            hasher.update(&[*v as u8]);
+        }
+        Data::TileMap(tiles) => {
+            hasher.update(b"tile_map");
+
+            let mut tiles = tiles.iter().collect::<Vec<_>>();
+            tiles.sort_by_key(|(coord, _)| (coord.x, coord.y));
+
+            for (coord, id) in tiles {
+                hash_coord(hasher, *coord);
+                hash_id(hasher, interner, *id);
+            }
+        }
+        Data::MapSetId(map) => {
+            hasher.update(b"map_set_id");
+
+            for key in sorted_ids(interner, map.keys()) {
+                hash_id(hasher, interner, key);
+
+                for id in sorted_ids(interner, &map[&key]) {
+                    hash_id(hasher, interner, id);
+                }
+            }
+        }
+    }
+}
+
+/// Hashes a single tile and its data.
+pub fn tile_hash(interner: &Interner, id: Id, data: &DataMap) -> u64 {
+    let mut hasher = Xxh3::new();
+
+    hash_id(&mut hasher, interner, id);
+
+    for key in sorted_ids(interner, data.keys()) {
+        hash_id(&mut hasher, interner, key);
+        hash_data(&mut hasher, interner, &data[&key]);
+    }
+
+    hasher.digest()
+}
+
+/// Hashes each tile, sorted by position.
+fn tile_hashes<'a>(
+    interner: &Interner,
+    tiles: impl IntoIterator<Item = (TileCoord, Id, &'a DataMap)>,
+) -> Vec<(TileCoord, u64)> {
+    let mut hashes = tiles
+        .into_iter()
+        .map(|(coord, id, data)| (coord, tile_hash(interner, id, data)))
+        .collect::<Vec<_>>();
+    hashes.sort_by_key(|(coord, _)| (coord.x, coord.y));
+
+    hashes
+}
+
+/// Hashes all the given tiles and their data. The order they are given in doesn't matter.
+pub fn tiles_hash<'a>(
+    interner: &Interner,
+    tiles: impl IntoIterator<Item = (TileCoord, Id, &'a DataMap)>,
+) -> u64 {
+    let mut hasher = Xxh3::new();
+
+    for (coord, hash) in tile_hashes(interner, tiles) {
+        hash_coord(&mut hasher, coord);
+        hasher.update(&hash.to_le_bytes());
+    }
+
+    hasher.digest()
+}
+
+/// Lists the positions where two sets of tiles differ, including where only one of them has a tile, sorted by position.
+pub fn diff_tiles<'a, 'b>(
+    interner: &Interner,
+    a: impl IntoIterator<Item = (TileCoord, Id, &'a DataMap)>,
+    b: impl IntoIterator<Item = (TileCoord, Id, &'b DataMap)>,
+) -> Vec<TileCoord> {
+    let a = tile_hashes(interner, a);
+    let b = tile_hashes(interner, b);
+
+    let mut diff = a
+        .iter()
+        .filter(|v| !b.contains(v))
+        .chain(b.iter().filter(|v| !a.contains(v)))
+        .map(|(coord, _)| *coord)
+        .collect::<Vec<_>>();
+    diff.sort_by_key(|coord| (coord.x, coord.y));
+    diff.dedup();
+
+    diff
+}
+
+/// Writes the hash of the game's state after every tick to a file, one line per tick.
+///
+/// A line with the map's name is written whenever a map is loaded, and the tick count starts over from there,
+/// so two runs can be lined up with [`compare_hash_logs`].
+#[derive(Debug)]
+pub struct HashLog {
+    writer: BufWriter<File>,
+    ticks: u64,
+}
+
+impl HashLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            ticks: 0,
+        })
+    }
+
+    /// Marks that a map was loaded, and starts counting ticks from the start again.
+    pub fn start_map(&mut self, map_name: &str) -> io::Result<()> {
+        self.ticks = 0;
+
+        writeln!(self.writer, "map {map_name}")?;
+        self.writer.flush()
+    }
+
+    /// Writes the hash of the state after a tick.
+    pub fn write(&mut self, hash: u64) -> io::Result<()> {
+        self.ticks += 1;
+
+        writeln!(self.writer, "{} {hash:016x}", self.ticks)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// How two runs compare.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    /// The runs went the same way for all the ticks they both have.
+    Same { ticks: usize },
+    /// The runs first went differently at this tick, counted from when the map was loaded.
+    Diverged { map: String, tick: u64 },
+}
+
+/// Compares the contents of two files written by [`HashLog`], and finds the first tick where they went differently.
+pub fn compare_hash_logs(a: &str, b: &str) -> Comparison {
+    let mut map = String::new();
+    let mut ticks = 0;
+
+    for (a, b) in a.lines().zip(b.lines()) {
+        let tick = match a.split_once(' ') {
+            Some(("map", name)) => {
+                map = name.to_string();
+
+                None
+            }
+            Some((tick, _)) => tick.parse::<u64>().ok(),
+            None => None,
+        };
+
+        if a != b {
+            return Comparison::Diverged {
+                map,
+                tick: tick.unwrap_or_default(),
+            };
+        }
+
+        if tick.is_some() {
+            ticks += 1;
+        }
+    }
+
+    Comparison::Same { ticks }
+}
@@ -0,0 +1,1613 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::mem;
+use std::ops::Div;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use hashbrown::{HashMap, HashSet};
+use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
+use rayon::prelude::*;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+
+use automancy_defs::coord::{ChunkCoord, TileBounds, TileCoord};
+use automancy_defs::id::Id;
+use automancy_defs::log;
+use automancy_defs::rendering::InstanceData;
+use automancy_resources::data::inventory::Inventory;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::clock::{Clock, ClockTime};
+use crate::determinism::{tiles_hash, HashLog};
+use crate::game::GameSystemMessage::*;
+use crate::history::History;
+use crate::map::{Map, MapError, MapInfo, LOCAL_PLAYER, MAIN_MENU};
+use crate::tile_entity::{Outgoing, TileEntities, TileEntity, TileEntityMsg, TileHandle};
+use crate::zone::{Zone, ZoneStats};
+
+/// How long a machine can go without working before it counts as stalled.
+pub const STALL_TIME: Duration = Duration::from_secs(10);
+/// How long a tile waits after saying it did work before saying so again, so busy machines don't flood the client.
+const WORK_EVENT_INTERVAL: Duration = Duration::from_millis(500);
+/// The most work events sent in a single tick.
+const MAX_WORK_EVENTS_PER_TICK: usize = 8;
+
+/// Game ticks per second
+pub const TPS: u64 = 30;
+pub const TICK_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / TPS);
+pub const MAX_ALLOWED_TICK_INTERVAL: Duration = TICK_INTERVAL.saturating_mul(5);
+/// How many times a tile can crash before it is quarantined, and stops ticking.
+pub const MAX_TILE_RESTARTS: u32 = 3;
+/// How long a single tile should take to tick. Tiles averaging above this are reported as slow.
+pub const TILE_TICK_BUDGET: Duration = Duration::from_micros(500);
+/// How many messages between tile entities are delivered each tick. The rest wait for the next one.
+pub const MAX_TILE_MESSAGES_PER_TICK: usize = 65536;
+/// How far out of the way a route is allowed to go to get around tiles.
+pub const ROUTE_DETOUR: u32 = 16;
+/// How often the map is saved and copied into an autosave while it is being played.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How many tiles away from the player items on the ground can be picked up from.
+pub const PICK_UP_REACH: u32 = 8;
+
+pub const TRANSACTION_ANIMATION_SPEED: Duration = Duration::from_nanos(666_666_666);
+/// How many transaction records are kept at most by default. The oldest ones are dropped past it.
+pub const DEFAULT_TRANSACTION_RECORD_LIMIT: usize = 4096;
+/// How many transaction records are kept at most between any two tiles.
+pub const MAX_TRANSACTION_RECORDS_PER_EDGE: usize = 8;
+pub const TAKE_ITEM_ANIMATION_SPEED: Duration = Duration::from_nanos(200_000_000);
+
+pub type TickUnit = u16;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionRecord {
+    pub stack: ItemStack,
+    pub source_id: Id,
+    pub id: Id,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderUnit {
+    /// positioned by the renderer, relative to the camera's origin tile
+    pub instance: InstanceData,
+    pub tile_id: Id,
+    pub model: Id,
+    /// whether the tile entity has crashed before
+    pub crashed: bool,
+}
+
+/// The transactions between each pair of tiles, along with the simulated time they were made at.
+pub type TransactionRecords =
+    HashMap<(TileCoord, TileCoord), VecDeque<(Duration, TransactionRecord)>>;
+
+/// The data of a group of tiles, split up by chunk so it can be looked up by area.
+#[derive(Debug, Clone, Default)]
+pub struct TileData {
+    chunks: HashMap<ChunkCoord, HashMap<TileCoord, Arc<DataMap>>>,
+}
+
+impl TileData {
+    pub fn get(&self, coord: &TileCoord) -> Option<&DataMap> {
+        self.chunks.get(&coord.chunk())?.get(coord).map(Arc::as_ref)
+    }
+
+    pub fn insert(&mut self, coord: TileCoord, data: Arc<DataMap>) {
+        self.chunks
+            .entry(coord.chunk())
+            .or_default()
+            .insert(coord, data);
+    }
+
+    /// Gets the data of the tiles within the bounds, only going through the chunks they overlap.
+    pub fn in_range(&self, bounds: TileBounds) -> impl Iterator<Item = (&TileCoord, &DataMap)> {
+        bounds
+            .chunks()
+            .flat_map(move |chunk| self.chunks.get(&chunk))
+            .flatten()
+            .filter(move |(coord, _)| bounds.is_in_bounds(***coord))
+            .map(|(coord, data)| (coord, data.as_ref()))
+    }
+}
+
+/// The data of all the tile entities as of the end of the last tick, readable without going through the game.
+///
+/// The tiles' data is shared between snapshots, so only the tiles that changed during a tick are copied.
+#[derive(Debug, Clone, Default)]
+pub struct TileSnapshot {
+    front: Arc<RwLock<Arc<TileData>>>,
+    last_tick: Arc<RwLock<TickStamp>>,
+}
+
+/// When a tick happened, both in the simulation and on the simulation clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickStamp {
+    /// how long the simulation had run after the tick, going up by exactly [`TICK_INTERVAL`] each tick
+    pub simulated: Duration,
+    /// when the tick happened on the simulation clock
+    pub at: ClockTime,
+}
+
+impl TickStamp {
+    /// The simulated time to draw at, at the time on the simulation clock.
+    ///
+    /// It is interpolated between this tick and the one before it, so whatever moves by the simulated time
+    /// moves smoothly even when the ticks come unevenly, at the cost of being drawn a tick behind.
+    /// When a tick is late, it holds still at this tick rather than running ahead of the simulation.
+    pub fn interpolate(&self, now: ClockTime) -> Duration {
+        let alpha =
+            (now.saturating_sub(self.at).as_secs_f64() / TICK_INTERVAL.as_secs_f64()).min(1.0);
+
+        self.simulated.saturating_sub(TICK_INTERVAL) + TICK_INTERVAL.mul_f64(alpha)
+    }
+}
+
+impl TileSnapshot {
+    /// Gets the latest snapshot. It stays as it is even as the game moves on.
+    pub fn get(&self) -> Arc<TileData> {
+        self.front.read().unwrap().clone()
+    }
+
+    /// When the tick the latest snapshot was taken after happened.
+    pub fn last_tick(&self) -> TickStamp {
+        *self.last_tick.read().unwrap()
+    }
+
+    /// Makes the data the latest snapshot.
+    fn publish(&self, data: TileData) {
+        *self.front.write().unwrap() = Arc::new(data);
+    }
+}
+
+#[derive(Debug)]
+pub struct GameSystemState {
+    /// a count of all the ticks that have happened
+    tick_count: TickUnit,
+    /// how long the simulation has run, as if every tick took exactly [`TICK_INTERVAL`]
+    simulated_time: Duration,
+    /// is the game stopped
+    stopped: bool,
+
+    /// the tile entities
+    tile_entities: TileEntities,
+    /// the messages between tile entities that are yet to be delivered
+    pending: VecDeque<(TileCoord, TileEntityMsg)>,
+    /// the map
+    map: Map,
+
+    /// what to do to undo or redo the player's last changes
+    history: History,
+    /// records transactions to be drawn
+    transaction_records: TransactionRecords,
+    /// how many transaction records are kept at most
+    transaction_record_limit: usize,
+    /// the tiles that are going over the tick budget, and their average tick time
+    slow_tiles: HashMap<TileCoord, Duration>,
+    /// how many times the tile entities have crashed and been restarted
+    tile_restarts: HashMap<TileCoord, u32>,
+    /// the tiles that crashed too many times, and are no longer ticked
+    quarantined_tiles: HashSet<TileCoord>,
+    /// when each tile last sent something to another tile, or was first asked about
+    last_worked: HashMap<TileCoord, ClockTime>,
+    /// when each tile last sent out [`GameEvent::TileWorked`]
+    last_work_event: HashMap<TileCoord, ClockTime>,
+    /// where the hash of the state is written after each tick, if the determinism audit is on
+    hash_log: Option<HashLog>,
+    /// when the map was last autosaved, or loaded
+    last_autosave: ClockTime,
+    /// the simulation clock, shared with [`GameSystem::clock`]
+    clock: Clock,
+}
+
+/// Represents a message the game receives
+#[derive(Debug)]
+pub enum GameSystemMessage {
+    /// tick the tile once
+    Tick,
+    StopTicking,
+    /// send a message to a tile entity
+    ForwardMsgToTile(TileCoord, TileEntityMsg),
+    /// place a tile at the given position
+    PlaceTile {
+        coord: TileCoord,
+        id: Id,
+        data: Option<DataMap>,
+        record: bool,
+        reply: Option<RpcReplyPort<PlaceTileResponse>>,
+    },
+    /// place all the given tiles as [`GameSystemMessage::PlaceTile`] would, recorded as one undo step
+    PlaceTiles {
+        tiles: Vec<(TileCoord, Id, DataMap)>,
+        record: bool,
+    },
+    MoveTiles(Vec<TileCoord>, TileCoord, bool),
+    /// place all the given tiles, replacing what was there, as one undo step
+    ImportTiles(Vec<(TileCoord, Id, DataMap)>),
+    /// set a value in the data of the tile at the given position, or remove it if there is none, as the player configuring it
+    ConfigureTile {
+        coord: TileCoord,
+        key: Id,
+        value: Option<Data>,
+        record: bool,
+    },
+    /// reverse the player's last change
+    Undo,
+    /// make the last change that was undone again
+    Redo,
+
+    /// load a map, replying whether it was loaded. If it couldn't be, the current map stays
+    LoadMap(String, RpcReplyPort<bool>),
+    /// discard the current state and load the current map again from its last save, replying whether it was loaded
+    ReloadMap(RpcReplyPort<bool>),
+    /// save the map
+    SaveMap(RpcReplyPort<()>),
+
+    GetMapInfoAndName(RpcReplyPort<(Arc<Mutex<MapInfo>>, String)>),
+
+    /// get the tile at the given position
+    GetTile(TileCoord, RpcReplyPort<Option<Id>>),
+    /// get the tiles at the given positions and their data, leaving out the empty positions
+    GetTiles(Vec<TileCoord>, RpcReplyPort<Vec<(TileCoord, Id, DataMap)>>),
+    /// get the statistics of the tiles in the zone at the given index, or of the whole map if there is none
+    GetZoneStats(Option<usize>, RpcReplyPort<ZoneStats>),
+    /// get which of the given positions already have a tile
+    GetOccupied(Vec<TileCoord>, RpcReplyPort<Vec<TileCoord>>),
+    /// find a path of free positions between two positions, both included, or None if they can't be connected
+    FindRoute {
+        from: TileCoord,
+        to: TileCoord,
+        reply: RpcReplyPort<Option<Vec<TileCoord>>>,
+    },
+    /// get a handle to the tile entity at the given position
+    GetTileEntity(TileCoord, RpcReplyPort<Option<TileHandle>>),
+    /// get the data of the tiles in the given range
+    GetDataInRange {
+        culling_range: TileBounds,
+        reply: RpcReplyPort<TileData>,
+    },
+    /// get all the tiles needing to be rendered, and their info
+    GetAllRenderUnits {
+        culling_range: TileBounds,
+        reply: RpcReplyPort<HashMap<TileCoord, RenderUnit>>,
+    },
+
+    GetRecordedTransactions(RpcReplyPort<TransactionRecords>),
+    /// set how many transaction records are kept at most
+    SetTransactionRecordLimit(usize),
+
+    /// take items from the player's inventory, and drop them on the ground at the given position
+    DropItems(TileCoord, ItemStack),
+    /// move all the items on the ground at the second position into the player's inventory, if it is within [`PICK_UP_REACH`] of the player at the first
+    PickUpItems(TileCoord, TileCoord),
+    /// get the items lying on the ground in the given range, one per position
+    GetGroundItems {
+        culling_range: TileBounds,
+        reply: RpcReplyPort<Vec<(TileCoord, Id)>>,
+    },
+
+    /// get all the tiles going over the tick budget, slowest first
+    GetSlowTiles(RpcReplyPort<Vec<(TileCoord, Id, Duration)>>),
+
+    /// start writing the hash of the state to the given file after each tick, or stop if there is none
+    SetHashLog(Option<PathBuf>),
+    /// get the hash of all the tiles and their data, to compare with another run
+    GetStateHash(RpcReplyPort<u64>),
+}
+
+/// Something that happened in the game, for the client to react to.
+#[derive(Debug, Copy, Clone)]
+pub enum GameEvent {
+    /// a tile was placed by the player
+    TilePlaced(TileCoord, Id),
+    /// a tile was removed by the player
+    TileRemoved(TileCoord, Id),
+    /// a tile did work, such as sending an item to another tile
+    TileWorked(TileCoord, Id),
+    /// a tile entity crashed, and had to be restarted
+    TileCrashed(TileCoord, Id),
+    /// the player completed a research
+    ResearchComplete(Id),
+    /// an error or an alert was shown to the player
+    Alert,
+    /// the map was autosaved
+    Autosaved,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum PlaceTileResponse {
+    Placed,
+    Removed,
+    Ignored,
+}
+
+pub struct GameSystem {
+    pub resource_man: Arc<ResourceManager>,
+    /// where the game events are sent to
+    pub events: UnboundedSender<GameEvent>,
+    /// where the data of the tile entities is published after each tick
+    pub snapshot: TileSnapshot,
+    /// the simulation clock everything in the game is timed by. The game doesn't tick while it is stopped.
+    pub clock: Clock,
+}
+
+#[async_trait::async_trait]
+impl Actor for GameSystem {
+    type Msg = GameSystemMessage;
+    type State = GameSystemState;
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(GameSystemState {
+            last_autosave: self.clock.now(),
+            clock: self.clock.clone(),
+            ..Default::default()
+        })
+    }
+
+    async fn handle(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match message {
+            LoadMap(name, reply) => {
+                let loaded = replace_map(&self.resource_man, state, &name).is_ok();
+
+                if loaded {
+                    self.publish_snapshot(state);
+
+                    log::info!("Successfully loaded map {name}!");
+                }
+
+                reply.send(loaded)?;
+                return Ok(());
+            }
+            ReloadMap(reply) => {
+                let name = state.map.map_name.clone();
+
+                let loaded = replace_map(&self.resource_man, state, &name).is_ok();
+
+                if loaded {
+                    self.publish_snapshot(state);
+
+                    log::info!("Successfully reloaded map {name}!");
+                }
+
+                reply.send(loaded)?;
+                return Ok(());
+            }
+            SaveMap(reply) => {
+                if state
+                    .map
+                    .save(&self.resource_man, &state.tile_entities)
+                    .await
+                {
+                    log::info!("Saved map {}", state.map.map_name.clone());
+                }
+                reply.send(())?;
+            }
+            GetMapInfoAndName(reply) => {
+                reply.send((state.map.info.clone(), state.map.map_name.clone()))?;
+
+                return Ok(());
+            }
+            rest => {
+                if state.stopped {
+                    return Ok(());
+                }
+
+                match rest {
+                    Tick => {
+                        if !state.clock.is_running() {
+                            return Ok(());
+                        }
+
+                        let (out, crashed) = tick(&self.resource_man, state);
+
+                        for (coord, panic_msg) in crashed {
+                            self.restart_tile(state, coord, panic_msg);
+                        }
+                        self.handle_outgoing(state, out);
+                        state.trim_transaction_records();
+                        self.publish_snapshot(state);
+                        write_hash_log(&self.resource_man, state);
+                        autosave(&self.resource_man, &self.events, state).await;
+
+                        let mut lock = state.map.info.lock().await;
+                        let info = &mut *lock;
+
+                        info.playtime += TICK_INTERVAL;
+
+                        if let Some(scenario) = &mut info.scenario {
+                            scenario.tick(
+                                &self.resource_man,
+                                info.players
+                                    .get(LOCAL_PLAYER)
+                                    .unwrap_or(&DataMap::default()),
+                            );
+                        }
+                    }
+                    GetAllRenderUnits {
+                        culling_range,
+                        reply,
+                    } => {
+                        let instances = state
+                            .tile_entities
+                            .in_range(culling_range)
+                            .flat_map(|tile_entity| {
+                                let coord = tile_entity.coord;
+                                let id = tile_entity.id;
+
+                                self.resource_man
+                                    .registry
+                                    .tiles
+                                    .get(&id)
+                                    .map(|tile| self.resource_man.get_model(tile.model))
+                                    .map(|model| {
+                                        (
+                                            coord,
+                                            RenderUnit {
+                                                instance: InstanceData::default(),
+                                                tile_id: id,
+                                                model,
+                                                crashed: state.tile_restarts.contains_key(&coord),
+                                            },
+                                        )
+                                    })
+                            })
+                            .collect();
+
+                        reply.send(instances).unwrap();
+                    }
+                    GetDataInRange {
+                        culling_range,
+                        reply,
+                    } => {
+                        let mut data = TileData::default();
+
+                        for tile_entity in state.tile_entities.in_range(culling_range) {
+                            data.insert(tile_entity.coord, Arc::new(tile_entity.data()));
+                        }
+
+                        reply.send(data)?;
+                    }
+                    PlaceTile {
+                        coord,
+                        id,
+                        data,
+                        record,
+                        reply,
+                    } => {
+                        if !coord.is_in_world() {
+                            if let Some(reply) = reply {
+                                reply.send(PlaceTileResponse::Ignored)?;
+                            }
+
+                            return Ok(());
+                        }
+
+                        if let Some(old_id) = state.map.tiles.get(&coord) {
+                            if *old_id == id {
+                                if let Some(reply) = reply {
+                                    reply.send(PlaceTileResponse::Ignored)?;
+                                }
+
+                                return Ok(());
+                            }
+                        }
+
+                        if !try_pay_for_tile(&self.resource_man, state, coord, id).await {
+                            if let Some(reply) = reply {
+                                reply.send(PlaceTileResponse::Ignored)?;
+                            }
+                            return Ok(());
+                        }
+
+                        let removing = id == self.resource_man.registry.none;
+
+                        let old_tile = if removing {
+                            remove_tile(&self.resource_man, state, coord).await
+                        } else {
+                            insert_new_tile(&self.resource_man, state, coord, id, data).await
+                        };
+
+                        // there was nothing to remove, or the tile couldn't be paid for
+                        let changed = if removing {
+                            old_tile.is_some()
+                        } else {
+                            state.map.tiles.get(&coord) == Some(&id)
+                        };
+
+                        if !changed {
+                            if let Some(reply) = reply {
+                                reply.send(PlaceTileResponse::Ignored)?;
+                            }
+
+                            return Ok(());
+                        }
+
+                        if let Some(reply) = reply {
+                            reply.send(if removing {
+                                PlaceTileResponse::Removed
+                            } else {
+                                PlaceTileResponse::Placed
+                            })?;
+                        }
+
+                        let event = match old_tile {
+                            Some((old_id, _)) if removing => GameEvent::TileRemoved(coord, old_id),
+                            _ => GameEvent::TilePlaced(coord, id),
+                        };
+
+                        // the items stored in the old tile are dropped instead of being lost with it
+                        let old_tile = old_tile.map(|(old_id, data)| {
+                            let data = data.map(|mut data| {
+                                if let Some(Data::Inventory(items)) =
+                                    data.remove(&self.resource_man.registry.data_ids.buffer)
+                                {
+                                    let ground = state.map.ground_items.entry(coord).or_default();
+
+                                    for (item, amount) in items.into_inner() {
+                                        ground.add(item, amount);
+                                    }
+                                }
+
+                                data
+                            });
+
+                            (old_id, data)
+                        });
+
+                        if record {
+                            let (id, data) =
+                                old_tile.unwrap_or((self.resource_man.registry.none, None));
+
+                            state.history.record(vec![PlaceTile {
+                                coord,
+                                id,
+                                record: true,
+                                reply: None,
+                                data,
+                            }]);
+
+                            // undoing and redoing replay the change, which shouldn't be heard again
+                            if !state.history.is_replaying() {
+                                let _ = self.events.send(event);
+                            }
+                        }
+                    }
+                    PlaceTiles { tiles, record } => {
+                        if record {
+                            state.history.start_batch();
+                        }
+
+                        for (coord, id, data) in tiles {
+                            self.handle(
+                                myself.clone(),
+                                PlaceTile {
+                                    coord,
+                                    id,
+                                    data: Some(data),
+                                    record,
+                                    reply: None,
+                                },
+                                state,
+                            )
+                            .await?;
+                        }
+
+                        if record {
+                            state.history.finish();
+                        }
+                    }
+                    GetTile(coord, reply) => {
+                        reply.send(state.map.tiles.get(&coord).cloned())?;
+                    }
+                    GetZoneStats(zone, reply) => {
+                        let zone = match zone {
+                            Some(index) => match state.map.info.lock().await.zones.get(index) {
+                                Some(zone) => Some(zone.clone()),
+                                None => {
+                                    reply.send(Default::default())?;
+
+                                    return Ok(());
+                                }
+                            },
+                            None => None,
+                        };
+
+                        reply.send(zone_stats(&self.resource_man, state, zone.as_ref()))?;
+                    }
+                    DropItems(coord, stack) => {
+                        if !coord.is_in_world() {
+                            return Ok(());
+                        }
+
+                        let taken = if let Data::Inventory(inventory) = state
+                            .map
+                            .info
+                            .lock()
+                            .await
+                            .player_data_mut(LOCAL_PLAYER)
+                            .entry(self.resource_man.registry.data_ids.player_inventory)
+                            .or_insert_with(|| Data::Inventory(Default::default()))
+                        {
+                            inventory.take(stack.item.id, stack.amount)
+                        } else {
+                            None
+                        };
+
+                        if let Some(taken) = taken {
+                            state
+                                .map
+                                .ground_items
+                                .entry(coord)
+                                .or_default()
+                                .add(stack.item.id, taken);
+                        }
+                    }
+                    PickUpItems(player, coord) => {
+                        if player.distance(coord) > PICK_UP_REACH {
+                            return Ok(());
+                        }
+
+                        if let Some(items) = state.map.ground_items.remove(&coord) {
+                            if let Data::Inventory(inventory) = state
+                                .map
+                                .info
+                                .lock()
+                                .await
+                                .player_data_mut(LOCAL_PLAYER)
+                                .entry(self.resource_man.registry.data_ids.player_inventory)
+                                .or_insert_with(|| Data::Inventory(Default::default()))
+                            {
+                                for (item, amount) in items.into_inner() {
+                                    inventory.add(item, amount);
+                                }
+                            }
+                        }
+                    }
+                    GetGroundItems {
+                        culling_range,
+                        reply,
+                    } => {
+                        let items = state
+                            .map
+                            .ground_items
+                            .iter()
+                            .filter(|(coord, _)| culling_range.is_in_bounds(***coord))
+                            .flat_map(|(coord, items)| {
+                                items
+                                    .iter()
+                                    .filter(|(_, amount)| **amount > 0)
+                                    .max_by_key(|(_, amount)| **amount)
+                                    .map(|(item, _)| (*coord, *item))
+                            })
+                            .collect();
+
+                        reply.send(items)?;
+                    }
+                    GetTiles(coords, reply) => {
+                        reply.send(
+                            coords
+                                .into_iter()
+                                .flat_map(|coord| {
+                                    let id = *state.map.tiles.get(&coord)?;
+                                    let data = state
+                                        .tile_entities
+                                        .get(&coord)
+                                        .map(TileEntity::data)
+                                        .unwrap_or_default();
+
+                                    Some((coord, id, data))
+                                })
+                                .collect(),
+                        )?;
+                    }
+                    GetOccupied(coords, reply) => {
+                        reply.send(
+                            coords
+                                .into_iter()
+                                .filter(|coord| state.map.tiles.contains_key(coord))
+                                .collect(),
+                        )?;
+                    }
+                    FindRoute { from, to, reply } => {
+                        reply.send(from.route_to(to, ROUTE_DETOUR, |coord| {
+                            !state.map.tiles.contains_key(&coord)
+                        }))?;
+                    }
+                    GetTileEntity(coord, reply) => {
+                        reply.send(
+                            state
+                                .tile_entities
+                                .contains_key(&coord)
+                                .then(|| TileHandle::new(coord, myself.clone())),
+                        )?;
+                    }
+                    ForwardMsgToTile(coord, msg) => {
+                        let mut out = Outgoing::default();
+
+                        if let Some(tile_entity) = state.tile_entities.get_mut(&coord) {
+                            if let Err(panic_msg) = run_tile(tile_entity, |tile_entity| {
+                                tile_entity.handle(&self.resource_man, msg, &mut out)
+                            }) {
+                                self.restart_tile(state, coord, panic_msg);
+                            }
+                        }
+
+                        self.handle_outgoing(state, out);
+                    }
+                    StopTicking => {
+                        state.stopped = true;
+                    }
+                    Undo => {
+                        if let Some(step) = state.history.start_undo() {
+                            for msg in step {
+                                self.handle(myself.clone(), msg, state).await?;
+                            }
+
+                            state.history.finish();
+                        }
+                    }
+                    Redo => {
+                        if let Some(step) = state.history.start_redo() {
+                            for msg in step {
+                                self.handle(myself.clone(), msg, state).await?;
+                            }
+
+                            state.history.finish();
+                        }
+                    }
+                    ConfigureTile {
+                        coord,
+                        key,
+                        value,
+                        record,
+                    } => {
+                        let Some(tile_entity) = state.tile_entities.get_mut(&coord) else {
+                            return Ok(());
+                        };
+
+                        let old = tile_entity.get_data_value(key).cloned();
+                        if old == value {
+                            return Ok(());
+                        }
+
+                        let msg = match value {
+                            Some(value) => TileEntityMsg::SetDataValue(key, value),
+                            None => TileEntityMsg::RemoveData(key),
+                        };
+
+                        let mut out = Outgoing::default();
+
+                        if let Err(panic_msg) = run_tile(tile_entity, |tile_entity| {
+                            tile_entity.handle(&self.resource_man, msg, &mut out)
+                        }) {
+                            self.restart_tile(state, coord, panic_msg);
+                        }
+
+                        self.handle_outgoing(state, out);
+
+                        if record {
+                            state.history.record(vec![ConfigureTile {
+                                coord,
+                                key,
+                                value: old,
+                                record: true,
+                            }]);
+                        }
+                    }
+                    GetRecordedTransactions(reply) => {
+                        reply.send(state.transaction_records.clone())?;
+                    }
+                    SetTransactionRecordLimit(limit) => {
+                        state.transaction_record_limit = limit;
+                        state.trim_transaction_records();
+                    }
+                    GetSlowTiles(reply) => {
+                        let mut slow_tiles = state
+                            .slow_tiles
+                            .iter()
+                            .flat_map(|(coord, tick_time)| {
+                                state
+                                    .map
+                                    .tiles
+                                    .get(coord)
+                                    .map(|id| (*coord, *id, *tick_time))
+                            })
+                            .collect::<Vec<_>>();
+
+                        slow_tiles.sort_by(|a, b| b.2.cmp(&a.2));
+
+                        reply.send(slow_tiles)?;
+                    }
+                    SetHashLog(path) => {
+                        state.hash_log = None;
+
+                        if let Some(path) = path {
+                            match HashLog::create(&path) {
+                                Ok(hash_log) => {
+                                    log::info!("Writing the state hash to {}", path.display());
+
+                                    state.hash_log = Some(hash_log);
+                                    start_hash_log(state);
+                                }
+                                Err(err) => log::error!(
+                                    "Could not create the state hash log at {}: {err}",
+                                    path.display()
+                                ),
+                            }
+                        }
+                    }
+                    GetStateHash(reply) => {
+                        reply.send(state_hash(&self.resource_man, state))?;
+                    }
+                    MoveTiles(tiles, direction, record) => {
+                        let selected = tiles.iter().copied().collect::<HashSet<_>>();
+
+                        // nothing moves if any tile would leave the world, or land on a tile that stays
+                        if tiles.iter().any(|coord| {
+                            let new_coord = *coord + direction;
+
+                            !new_coord.is_in_world()
+                                || (state.map.tiles.contains_key(&new_coord)
+                                    && !selected.contains(&new_coord))
+                        }) {
+                            return Ok(());
+                        }
+
+                        let mut undo = vec![];
+
+                        let mut removed = Vec::new();
+
+                        for coord in tiles {
+                            if let Some(old) = remove_tile(&self.resource_man, state, coord).await {
+                                removed.push((coord, old));
+                            }
+                        }
+
+                        for (coord, (id, data)) in removed {
+                            let new_coord = coord + direction;
+
+                            insert_new_tile(&self.resource_man, state, new_coord, id, data).await;
+
+                            if state.map.tiles.get(&new_coord) == Some(&id) {
+                                undo.push(new_coord);
+                            }
+                        }
+
+                        if record {
+                            state
+                                .history
+                                .record(vec![MoveTiles(undo, -direction, true)]);
+                        }
+                    }
+                    ImportTiles(tiles) => {
+                        let mut undo = vec![];
+
+                        for (coord, id, data) in tiles {
+                            if !coord.is_in_world()
+                                || !try_pay_for_tile(&self.resource_man, state, coord, id).await
+                            {
+                                continue;
+                            }
+
+                            let old =
+                                insert_new_tile(&self.resource_man, state, coord, id, Some(data))
+                                    .await;
+
+                            // only the tiles that were actually placed are undone
+                            if state.map.tiles.get(&coord) != Some(&id) {
+                                continue;
+                            }
+
+                            let (id, data) = old.unwrap_or((self.resource_man.registry.none, None));
+
+                            undo.push(PlaceTile {
+                                coord,
+                                id,
+                                record: true,
+                                reply: None,
+                                data,
+                            });
+                        }
+
+                        if !undo.is_empty() {
+                            state.history.record(undo);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GameSystem {
+    /// Deals with everything the tile entities sent out: delivers their messages later on,
+    /// records their transactions, and keeps track of which of them are slow.
+    fn handle_outgoing(&self, state: &mut GameSystemState, out: Outgoing) {
+        state.pending.extend(out.messages);
+
+        let now = state.clock.now();
+        let mut work_events = 0;
+
+        for (stack, source_coord, coord) in out.records {
+            state.last_worked.insert(source_coord, now);
+
+            if let Some((time, _)) = state
+                .transaction_records
+                .get(&(source_coord, coord))
+                .and_then(|v| v.back())
+            {
+                if state.simulated_time.saturating_sub(*time) < TRANSACTION_ANIMATION_SPEED.div(4) {
+                    continue;
+                }
+            }
+
+            if let Some((source_id, id)) = state
+                .map
+                .tiles
+                .get(&source_coord)
+                .cloned()
+                .zip(state.map.tiles.get(&coord).cloned())
+            {
+                let last_event = state.last_work_event.get(&source_coord);
+
+                if work_events < MAX_WORK_EVENTS_PER_TICK
+                    && last_event.map_or(true, |v| now.saturating_sub(*v) >= WORK_EVENT_INTERVAL)
+                {
+                    work_events += 1;
+                    state.last_work_event.insert(source_coord, now);
+
+                    let _ = self
+                        .events
+                        .send(GameEvent::TileWorked(source_coord, source_id));
+                }
+
+                let records = state
+                    .transaction_records
+                    .entry((source_coord, coord))
+                    .or_insert_with(Default::default);
+
+                records.push_back((
+                    state.simulated_time,
+                    TransactionRecord {
+                        stack,
+                        source_id,
+                        id,
+                    },
+                ));
+                if records.len() > MAX_TRANSACTION_RECORDS_PER_EDGE {
+                    records.pop_front();
+                }
+            }
+        }
+
+        for (coord, tick_time) in out.slow {
+            if let Some(tick_time) = tick_time {
+                if state.tile_entities.contains_key(&coord) {
+                    log::warn!(
+                        "Tile at {coord} is going over the tick budget, averaging {tick_time:?}"
+                    );
+
+                    state.slow_tiles.insert(coord, tick_time);
+                }
+            } else {
+                state.slow_tiles.remove(&coord);
+            }
+        }
+    }
+
+    /// Gathers the data of all the tile entities as they are now, and publishes it as the latest snapshot.
+    fn publish_snapshot(&self, state: &mut GameSystemState) {
+        let mut data = TileData::default();
+
+        for tile_entity in state.tile_entities.values_mut() {
+            data.insert(tile_entity.coord, tile_entity.snapshot());
+        }
+
+        self.snapshot.publish(data);
+        *self.snapshot.last_tick.write().unwrap() = TickStamp {
+            simulated: state.simulated_time,
+            at: state.clock.now(),
+        };
+    }
+
+    /// Replaces a tile entity that panicked with a fresh one, and quarantines it if it keeps on crashing.
+    fn restart_tile(&self, state: &mut GameSystemState, coord: TileCoord, panic_msg: String) {
+        let Some(id) = state.map.tiles.get(&coord).cloned() else {
+            log::error!("Unknown tile entity at {coord} panicked with '{panic_msg}'");
+            return;
+        };
+
+        log::error!("Tile entity at {coord} panicked with '{panic_msg}', restarting it");
+
+        let restarts = {
+            let restarts = state.tile_restarts.entry(coord).or_insert(0);
+            *restarts += 1;
+
+            *restarts
+        };
+
+        state.tile_entities.insert(TileEntity::new(id, coord));
+
+        let _ = self.events.send(GameEvent::TileCrashed(coord, id));
+
+        let tile_name = self.resource_man.tile_name(&id);
+
+        if restarts >= MAX_TILE_RESTARTS {
+            state.quarantined_tiles.insert(coord);
+
+            self.resource_man.error_man.push(
+                (
+                    self.resource_man.registry.err_ids.tile_quarantined,
+                    vec![tile_name, coord.to_string()],
+                ),
+                &self.resource_man,
+            );
+        } else {
+            self.resource_man.error_man.push(
+                (
+                    self.resource_man.registry.err_ids.tile_crashed,
+                    vec![tile_name, coord.to_string(), panic_msg],
+                ),
+                &self.resource_man,
+            );
+        }
+    }
+}
+
+pub fn try_category(resource_man: &ResourceManager, id: Id, category_item: impl FnOnce(Id)) {
+    if let Some(Data::Id(category)) = resource_man.registry.tiles[&id]
+        .data
+        .get(&resource_man.registry.data_ids.category)
+    {
+        if !resource_man.registry.tiles[&id]
+            .data
+            .flag(resource_man.registry.data_ids.default_tile)
+        {
+            if let Some(item) = resource_man
+                .registry
+                .categories
+                .get(category)
+                .and_then(|v| v.item)
+            {
+                category_item(item);
+            }
+        }
+    }
+}
+
+/// Takes the cost of the tile from the inventory, and refunds the cost of the tile it replaces, if any.
+/// Returns false without changing anything if the inventory doesn't have enough items.
+pub fn pay_for_tile(
+    resource_man: &ResourceManager,
+    inventory: &mut Inventory,
+    id: Id,
+    old_id: Option<Id>,
+) -> bool {
+    let cost = resource_man
+        .registry
+        .tiles
+        .get(&id)
+        .map(|tile| tile.cost.as_slice())
+        .unwrap_or_default();
+
+    if cost
+        .iter()
+        .any(|(item, amount)| inventory.get(*item) < *amount)
+    {
+        return false;
+    }
+
+    for (item, amount) in cost {
+        inventory.take(*item, *amount);
+    }
+
+    if let Some(old) = old_id.and_then(|id| resource_man.registry.tiles.get(&id)) {
+        for (item, amount) in &old.cost {
+            inventory.add(*item, *amount);
+        }
+    }
+
+    true
+}
+
+/// Checks that the player has the item of the tile, and on survival maps pays for it the way [`pay_for_tile`] does.
+/// Returns false without changing anything if the player can't place it. Nothing is needed in editor mode.
+async fn try_pay_for_tile(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+    coord: TileCoord,
+    id: Id,
+) -> bool {
+    let lock = &mut state.map.info.lock().await;
+
+    if lock.editor_mode {
+        return true;
+    }
+
+    let mut paid = true;
+
+    try_category(resource_man, id, |item| {
+        if let Data::Inventory(inventory) = lock
+            .player_data_mut(LOCAL_PLAYER)
+            .entry(resource_man.registry.data_ids.player_inventory)
+            .or_insert_with(|| Data::Inventory(Default::default()))
+        {
+            if inventory.get(item) < 1 {
+                paid = false
+            }
+        }
+    });
+
+    if lock.survival && paid {
+        let old_id = state.map.tiles.get(&coord).cloned();
+
+        if let Data::Inventory(inventory) = lock
+            .player_data_mut(LOCAL_PLAYER)
+            .entry(resource_man.registry.data_ids.player_inventory)
+            .or_insert_with(|| Data::Inventory(Default::default()))
+        {
+            paid = pay_for_tile(resource_man, inventory, id, old_id);
+        }
+    }
+
+    paid
+}
+
+/// Counts the tiles in the zone, or the whole map, and finds the ones that are in trouble.
+fn zone_stats(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+    zone: Option<&Zone>,
+) -> ZoneStats {
+    let now = state.clock.now();
+    let mut stats = ZoneStats::default();
+
+    for (coord, id) in state.map.tiles.iter() {
+        if zone.is_some_and(|zone| !zone.contains(*coord)) {
+            continue;
+        }
+
+        *stats.tiles.entry(*id).or_default() += 1;
+
+        if state.tile_restarts.contains_key(coord) || state.quarantined_tiles.contains(coord) {
+            stats.crashed.push(*coord);
+        }
+
+        let has_script = state.tile_entities.get(coord).is_some_and(|tile_entity| {
+            tile_entity
+                .get_data_value(resource_man.registry.data_ids.script)
+                .is_some()
+        });
+
+        if has_script {
+            // a machine that was never seen working gets until STALL_TIME from now
+            let last_worked = *state.last_worked.entry(*coord).or_insert(now);
+
+            if now.saturating_sub(last_worked) > STALL_TIME {
+                stats.stalled.push(*coord);
+            }
+        }
+    }
+
+    stats
+}
+
+/// Hashes all the tile entities and their data.
+fn state_hash(resource_man: &ResourceManager, state: &GameSystemState) -> u64 {
+    let tiles = state
+        .tile_entities
+        .values()
+        .map(|tile_entity| (tile_entity.coord, tile_entity.id, tile_entity.data()))
+        .collect::<Vec<_>>();
+
+    tiles_hash(
+        &resource_man.interner,
+        tiles.iter().map(|(coord, id, data)| (*coord, *id, data)),
+    )
+}
+
+/// Writes the hash of the state to the hash log, if there is one. The log is dropped if it can't be written to.
+fn write_hash_log(resource_man: &ResourceManager, state: &mut GameSystemState) {
+    if state.hash_log.is_none() {
+        return;
+    }
+
+    let hash = state_hash(resource_man, state);
+
+    if let Some(Err(err)) = state.hash_log.as_mut().map(|v| v.write(hash)) {
+        log::error!("Could not write the state hash, and stopped writing it: {err}");
+        state.hash_log = None;
+    }
+}
+
+/// Marks the start of the current map in the hash log, if there is one.
+fn start_hash_log(state: &mut GameSystemState) {
+    let name = &state.map.map_name;
+
+    if let Some(Err(err)) = state.hash_log.as_mut().map(|v| v.start_map(name)) {
+        log::error!("Could not write the state hash, and stopped writing it: {err}");
+        state.hash_log = None;
+    }
+}
+
+/// Saves the map and keeps a copy of it as an autosave, once it has been [`AUTOSAVE_INTERVAL`] since the last one.
+async fn autosave(
+    resource_man: &ResourceManager,
+    events: &UnboundedSender<GameEvent>,
+    state: &mut GameSystemState,
+) {
+    if state.clock.since(state.last_autosave) < AUTOSAVE_INTERVAL || state.map.map_name == MAIN_MENU
+    {
+        return;
+    }
+
+    state.last_autosave = state.clock.now();
+
+    if !state.map.save(resource_man, &state.tile_entities).await {
+        return;
+    }
+
+    match Map::write_backup(&state.map.map_name) {
+        Ok(path) => {
+            log::info!("Autosaved map {} to {}", state.map.map_name, path.display());
+            let _ = events.send(GameEvent::Autosaved);
+        }
+        Err(err) => log::error!("Could not autosave map {}: {err}", state.map.map_name),
+    }
+}
+
+/// Drops all the current tile entities, and loads the given map in place of the current one.
+///
+/// If the map is damaged, its files are left alone, the error is shown to the player, and the current map stays.
+fn replace_map(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+    name: &str,
+) -> Result<(), MapError> {
+    let (map, tile_entities) = match Map::load(resource_man, name) {
+        Ok(v) => v,
+        Err(err) => {
+            log::error!("Could not load the map {name}: {err}");
+
+            resource_man.error_man.push(
+                (
+                    resource_man.registry.err_ids.map_damaged,
+                    vec![name.to_string(), err.to_string()],
+                ),
+                resource_man,
+            );
+
+            return Err(err);
+        }
+    };
+
+    if name != MAIN_MENU {
+        // the map was only just loaded, so nothing else can be holding its info
+        if let Ok(mut info) = map.info.try_lock() {
+            info.last_played = Some(SystemTime::now());
+        }
+    }
+
+    state.map = map;
+    state.tile_entities = tile_entities;
+    state.pending.clear();
+    state.transaction_records.clear();
+    state.history.clear();
+    state.slow_tiles.clear();
+    state.tile_restarts.clear();
+    state.quarantined_tiles.clear();
+    state.last_worked.clear();
+    state.last_work_event.clear();
+    state.last_autosave = state.clock.now();
+
+    // so that two runs of a map tick the same way, however long the game ran before it was loaded
+    state.tick_count = 0;
+    state.simulated_time = Duration::ZERO;
+
+    start_hash_log(state);
+
+    Ok(())
+}
+
+/// Removes a tile from the game, along with its tile entity
+async fn remove_tile(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+    coord: TileCoord,
+) -> Option<(Id, Option<DataMap>)> {
+    if let Some((tile, mut tile_entity)) = state
+        .map
+        .tiles
+        .remove(&coord)
+        .zip(state.tile_entities.remove(&coord))
+    {
+        state.slow_tiles.remove(&coord);
+        state.tile_restarts.remove(&coord);
+        state.quarantined_tiles.remove(&coord);
+        state.last_worked.remove(&coord);
+        state.last_work_event.remove(&coord);
+
+        {
+            let lock = &mut state.map.info.lock().await;
+
+            if !lock.editor_mode {
+                try_category(resource_man, tile, |item| {
+                    if let Data::Inventory(inventory) = lock
+                        .player_data_mut(LOCAL_PLAYER)
+                        .entry(resource_man.registry.data_ids.player_inventory)
+                        .or_insert_with(|| Data::Inventory(Default::default()))
+                    {
+                        inventory.add(item, 1);
+                    }
+                });
+            }
+        }
+
+        Some((tile, Some(tile_entity.take_data())))
+    } else {
+        None
+    }
+}
+
+/// Makes a new tile and add it into both the map and the game
+async fn insert_new_tile(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+    coord: TileCoord,
+    tile: Id,
+    data: Option<DataMap>,
+) -> Option<(Id, Option<DataMap>)> {
+    if !coord.is_in_world() {
+        return None;
+    }
+
+    // checked before the old tile is removed, so a tile that can't be paid for leaves the old one in place
+    {
+        let lock = &mut state.map.info.lock().await;
+
+        if !lock.editor_mode {
+            // removing the old tile gives its item back, which can pay for the new one
+            let mut refund = None;
+            if let Some(old_id) = state.map.tiles.get(&coord) {
+                try_category(resource_man, *old_id, |item| refund = Some(item));
+            }
+
+            let mut skip = false;
+
+            try_category(resource_man, tile, |item| {
+                if let Data::Inventory(inventory) = lock
+                    .player_data_mut(LOCAL_PLAYER)
+                    .entry(resource_man.registry.data_ids.player_inventory)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    if inventory.get(item) < 1 && refund != Some(item) {
+                        skip = true;
+                    }
+                }
+            });
+
+            if skip {
+                return None;
+            }
+        }
+    }
+
+    let old = remove_tile(resource_man, state, coord).await;
+
+    {
+        let lock = &mut state.map.info.lock().await;
+
+        if !lock.editor_mode {
+            try_category(resource_man, tile, |item| {
+                if let Data::Inventory(inventory) = lock
+                    .player_data_mut(LOCAL_PLAYER)
+                    .entry(resource_man.registry.data_ids.player_inventory)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                {
+                    inventory.take(item, 1);
+                }
+            });
+        }
+    }
+
+    let mut tile_entity = TileEntity::new(tile, coord);
+
+    if let Some(data) = data {
+        tile_entity.set_data(data);
+    }
+
+    state.tile_entities.insert(tile_entity);
+    state.map.tiles.insert(coord, tile);
+
+    old
+}
+
+/// Runs something on a tile entity, returning the panic message if it panicked.
+fn run_tile(tile_entity: &mut TileEntity, f: impl FnOnce(&mut TileEntity)) -> Result<(), String> {
+    catch_unwind(AssertUnwindSafe(|| f(tile_entity))).map_err(|err| {
+        err.downcast_ref::<&str>()
+            .map(|v| v.to_string())
+            .or_else(|| err.downcast_ref::<String>().cloned())
+            .unwrap_or_default()
+    })
+}
+
+fn inner_tick(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+) -> (Outgoing, Vec<(TileCoord, String)>) {
+    let tick_count = state.tick_count;
+
+    let ticked = state
+        .tile_entities
+        .values_mut()
+        .filter(|tile_entity| !state.quarantined_tiles.contains(&tile_entity.coord))
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|tile_entity| {
+            let coord = tile_entity.coord;
+            let mut out = Outgoing::default();
+
+            let result = run_tile(tile_entity, |tile_entity| {
+                tile_entity.tick(resource_man, tick_count, &mut out)
+            });
+
+            (out, result.err().map(|panic_msg| (coord, panic_msg)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = Outgoing::default();
+    let mut crashed = vec![];
+
+    for (ticked_out, panic) in ticked {
+        out.append(ticked_out);
+        crashed.extend(panic);
+    }
+
+    // deliver what the tiles sent each other, along with whatever that made them send in turn
+    state.pending.extend(mem::take(&mut out.messages));
+
+    for _ in 0..MAX_TILE_MESSAGES_PER_TICK {
+        let Some((coord, message)) = state.pending.pop_front() else {
+            break;
+        };
+        let Some(tile_entity) = state.tile_entities.get_mut(&coord) else {
+            continue;
+        };
+
+        let mut message_out = Outgoing::default();
+
+        if let Err(panic_msg) = run_tile(tile_entity, |tile_entity| {
+            tile_entity.handle(resource_man, message, &mut message_out)
+        }) {
+            crashed.push((coord, panic_msg));
+        }
+
+        state.pending.extend(mem::take(&mut message_out.messages));
+        out.append(message_out);
+    }
+
+    state.tick_count = state.tick_count.wrapping_add(1);
+    state.simulated_time += TICK_INTERVAL;
+
+    (out, crashed)
+}
+
+/// Runs the game for one tick, logging if the tick is too long.
+/// Returns what the tile entities sent out, and the ones that panicked along with their panic messages.
+pub fn tick(
+    resource_man: &ResourceManager,
+    state: &mut GameSystemState,
+) -> (Outgoing, Vec<(TileCoord, String)>) {
+    let start = Instant::now();
+    let result = inner_tick(resource_man, state);
+    let finish = Instant::now();
+
+    let tick_time = finish - start;
+
+    if tick_time >= MAX_ALLOWED_TICK_INTERVAL {
+        log::warn!(
+            "Tick took longer than allowed maximum! tick_time: {:?}, maximum: {:?}",
+            tick_time,
+            MAX_ALLOWED_TICK_INTERVAL
+        );
+    }
+
+    result
+}
+
+impl GameSystemState {
+    /// Drops the transaction records that are done animating, and the oldest ones past the limit.
+    /// The edges left without records are dropped too, so they don't pile up as the map changes.
+    ///
+    /// This is done after every tick, so the records stay bounded even when nothing is drawing them.
+    fn trim_transaction_records(&mut self) {
+        let now = self.simulated_time;
+
+        for deque in self.transaction_records.values_mut() {
+            while deque.front().map_or(false, |(time, _)| {
+                now.saturating_sub(*time) >= TRANSACTION_ANIMATION_SPEED
+            }) {
+                deque.pop_front();
+            }
+        }
+
+        let count = self
+            .transaction_records
+            .values()
+            .map(VecDeque::len)
+            .sum::<usize>();
+
+        if count > self.transaction_record_limit {
+            let edges = self.transaction_records.keys().copied().collect::<Vec<_>>();
+
+            // the oldest record left on each edge, so the oldest overall is always on top
+            let mut oldest = edges
+                .iter()
+                .enumerate()
+                .flat_map(|(index, edge)| {
+                    self.transaction_records[edge]
+                        .front()
+                        .map(|(time, _)| Reverse((*time, index)))
+                })
+                .collect::<BinaryHeap<_>>();
+
+            for _ in 0..count - self.transaction_record_limit {
+                let Some(Reverse((_, index))) = oldest.pop() else {
+                    break;
+                };
+
+                let deque = self.transaction_records.get_mut(&edges[index]).unwrap();
+                deque.pop_front();
+
+                if let Some((time, _)) = deque.front() {
+                    oldest.push(Reverse((*time, index)));
+                }
+            }
+        }
+
+        self.transaction_records
+            .retain(|_, deque| !deque.is_empty());
+    }
+}
+
+impl Default for GameSystemState {
+    fn default() -> Self {
+        Self {
+            tick_count: 0,
+            simulated_time: Duration::ZERO,
+            stopped: false,
+
+            map: Map::new_empty("".to_string()),
+            tile_entities: Default::default(),
+            pending: Default::default(),
+
+            history: Default::default(),
+            transaction_records: Default::default(),
+            transaction_record_limit: DEFAULT_TRANSACTION_RECORD_LIMIT,
+            slow_tiles: Default::default(),
+            tile_restarts: Default::default(),
+            quarantined_tiles: Default::default(),
+            last_worked: Default::default(),
+            last_work_event: Default::default(),
+            hash_log: None,
+            last_autosave: Duration::ZERO,
+            clock: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! The changes the player made to the map, kept so they can be undone and redone.
+//!
+//! A step is the list of messages that puts the map back the way it was before a change.
+//! While a step is being replayed, the messages in it record what they replace into a step going the other way,
+//! so undoing a change can be redone, and redoing it can be undone again.
+
+use std::mem;
+
+use arraydeque::{ArrayDeque, Wrapping};
+
+use crate::game::GameSystemMessage;
+
+/// How many steps can be undone, or redone.
+pub const HISTORY_SIZE: usize = 64;
+
+pub type Step = Vec<GameSystemMessage>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Replaying {
+    #[default]
+    None,
+    Undo,
+    Redo,
+    /// several changes being made that are undone together
+    Batch,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: ArrayDeque<Step, HISTORY_SIZE, Wrapping>,
+    redo: ArrayDeque<Step, HISTORY_SIZE, Wrapping>,
+    replaying: Replaying,
+    /// the step being made from what the replayed, or batched, messages replace
+    replayed: Step,
+}
+
+impl History {
+    /// Records the messages that reverse a change. A new change can't be redone over, so it forgets the redo steps.
+    pub fn record(&mut self, step: Step) {
+        match self.replaying {
+            Replaying::None => {
+                self.undo.push_back(step);
+                self.redo.clear();
+            }
+            Replaying::Undo | Replaying::Redo | Replaying::Batch => self.replayed.extend(step),
+        }
+    }
+
+    /// Takes the last step to undo, and starts recording the step that redoes it.
+    /// [`History::finish`] must be called once the step has been replayed.
+    pub fn start_undo(&mut self) -> Option<Step> {
+        let step = self.undo.pop_back()?;
+        self.replaying = Replaying::Undo;
+
+        Some(step)
+    }
+
+    /// Takes the last step to redo, and starts recording the step that undoes it again.
+    /// [`History::finish`] must be called once the step has been replayed.
+    pub fn start_redo(&mut self) -> Option<Step> {
+        let step = self.redo.pop_back()?;
+        self.replaying = Replaying::Redo;
+
+        Some(step)
+    }
+
+    /// Starts recording the changes made from now on into one step, so they are undone together.
+    /// [`History::finish`] must be called once they have been made.
+    pub fn start_batch(&mut self) {
+        self.replaying = Replaying::Batch;
+    }
+
+    /// Stores the step recorded while replaying, or batching.
+    pub fn finish(&mut self) {
+        let mut step = mem::take(&mut self.replayed);
+        // the changes have to be reversed in the opposite order they were made in
+        step.reverse();
+
+        match mem::take(&mut self.replaying) {
+            Replaying::None => {}
+            Replaying::Undo => {
+                self.redo.push_back(step);
+            }
+            Replaying::Redo => {
+                self.undo.push_back(step);
+            }
+            Replaying::Batch => {
+                if !step.is_empty() {
+                    self.undo.push_back(step);
+                    self.redo.clear();
+                }
+            }
+        }
+    }
+
+    /// Whether a step is being undone or redone right now.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.replaying, Replaying::Undo | Replaying::Redo)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.replaying = Replaying::None;
+        self.replayed.clear();
+    }
+}
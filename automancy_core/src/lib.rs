@@ -0,0 +1,175 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use automancy_defs::log;
+use automancy_defs::rendering::Vertex;
+use automancy_resources::types::translate::{Translate, DEFAULT_LANGUAGE};
+use automancy_resources::{ResourceManager, RESOURCES_PATH};
+
+pub mod blueprint;
+pub mod clock;
+pub mod determinism;
+pub mod game;
+pub mod history;
+pub mod map;
+pub mod marker;
+pub mod migration;
+pub mod scenario;
+pub mod simulation;
+pub mod suggestion;
+pub mod tile_entity;
+pub mod zone;
+
+/// Initialize the Resource Manager system, and loads all the resources in all namespaces.
+pub fn load_resources() -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    load_resources_in(DEFAULT_LANGUAGE)
+}
+
+/// Loads all the resources in all namespaces, with the translations in the given language, such as `en_US`,
+/// wherever there are some in it.
+pub fn load_resources_in(language: &str) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    load_resources_with(Path::new(RESOURCES_PATH), language)
+}
+
+/// The namespace of the base game, which is loaded before every other one.
+const BASE_NAMESPACE: &str = "automancy";
+
+/// Loads all the resources in the given namespace folders, such as a set of test fixtures.
+///
+/// The base game is loaded first, then the rest by name, so the IDs get the same numbers no matter what order
+/// the folders are listed in.
+pub fn load_resources_from(dirs: &[&Path]) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    load_namespaces(
+        dirs.iter().map(|dir| dir.to_path_buf()).collect(),
+        DEFAULT_LANGUAGE,
+    )
+}
+
+/// Loads all the resources in all namespaces found in the given folder, with the translations in the given language.
+fn load_resources_with(
+    path: &Path,
+    language: &str,
+) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    load_namespaces(namespace_dirs(path), language)
+}
+
+/// Lists the namespace folders in the given folder.
+fn namespace_dirs(path: &Path) -> Vec<PathBuf> {
+    fs::read_dir(path)
+        .expect("The resources folder doesn't exist- this is very wrong")
+        .flatten()
+        .map(|v| v.path())
+        .collect()
+}
+
+/// Puts the base game first, then the rest by name, which is the order the namespaces are loaded in.
+fn sort_namespaces(dirs: &mut [PathBuf]) {
+    dirs.sort_by_key(|dir| {
+        (
+            dir.file_name() != Some(OsStr::new(BASE_NAMESPACE)),
+            dir.clone(),
+        )
+    });
+}
+
+/// Loads all the resources in the given namespace folders, with the translations in the given language.
+fn load_namespaces(
+    mut dirs: Vec<PathBuf>,
+    language: &str,
+) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
+    let mut resource_man = ResourceManager::new();
+
+    sort_namespaces(&mut dirs);
+
+    dirs.into_iter().for_each(|dir| {
+        let namespace = dir.file_name().unwrap().to_str().unwrap();
+        log::info!("Loading namespace {namespace}...");
+
+        resource_man
+            .load_models(&dir)
+            .expect("Error loading models");
+        #[cfg(feature = "audio")]
+        resource_man.load_audio(&dir).expect("Error loading audio");
+        resource_man
+            .load_ui_sounds(&dir)
+            .expect("Error loading UI sounds");
+        resource_man.load_tiles(&dir).expect("Error loading tiles");
+        resource_man.load_items(&dir).expect("Error loading items");
+        resource_man.load_tags(&dir).expect("Error loading tags");
+        resource_man
+            .load_categories(&dir)
+            .expect("Error loading categories");
+        resource_man
+            .load_scripts(&dir)
+            .expect("Error loading scripts");
+        resource_man
+            .load_translates(&dir, language)
+            .expect("Error loading translates");
+        resource_man
+            .load_shaders(&dir)
+            .expect("Error loading shaders");
+        #[cfg(feature = "gui")]
+        resource_man.load_fonts(&dir).expect("Error loading fonts");
+        resource_man
+            .load_functions(&dir)
+            .expect("Error loading functions");
+        resource_man
+            .load_researches(&dir)
+            .expect("Error loading researches");
+        resource_man
+            .load_map_templates(&dir)
+            .expect("Error loading map templates");
+
+        log::info!("Loaded namespace {namespace}.");
+    });
+
+    resource_man
+        .preprocess_shaders()
+        .expect("Error preprocessing shaders");
+    resource_man.compile_researches();
+
+    let mut translates = Translate::clone(&resource_man.translates());
+    resource_man.fill_missing_translations(&mut translates);
+    resource_man.set_translates(translates);
+
+    #[cfg(feature = "gui")]
+    resource_man.check_font_coverage();
+    resource_man.ordered_tiles();
+    resource_man.ordered_items();
+    resource_man.ordered_categories();
+
+    let (vertices, indices) = resource_man.compile_models();
+
+    resource_man.loaded_ids = resource_man.interner.len();
+    log::info!("Interned {} IDs.", resource_man.loaded_ids);
+
+    (Arc::new(resource_man), vertices, indices)
+}
+
+/// Loads the translations in the given language from all namespaces, and swaps them in for the ones in use.
+/// Nothing else is loaded again, and everything sharing the resources, such as the game, uses them from then on.
+pub fn switch_language(resource_man: &ResourceManager, language: &str) -> anyhow::Result<()> {
+    let mut dirs = namespace_dirs(Path::new(RESOURCES_PATH));
+    sort_namespaces(&mut dirs);
+
+    let mut translates = Translate {
+        language: language.to_string(),
+        ..Default::default()
+    };
+
+    // as when loading, the last namespace with translations is the one used
+    for dir in dirs {
+        if let Some(translate) = resource_man.read_translate(&dir, language)? {
+            translates = translate;
+        }
+    }
+
+    resource_man.fill_missing_translations(&mut translates);
+    resource_man.set_translates(translates);
+    #[cfg(feature = "gui")]
+    resource_man.check_font_coverage();
+
+    Ok(())
+}
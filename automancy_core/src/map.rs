@@ -0,0 +1,1326 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use std::{fs, io, path::PathBuf};
+
+use hashbrown::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use rhai::INT;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use xxhash_rust::xxh3::xxh3_64;
+use zstd::{Decoder, Encoder};
+
+use automancy_defs::coord::{ChunkCoord, PackedCoords, TileCoord};
+use automancy_defs::id::{Id, IdRaw, Interner};
+use automancy_defs::log;
+use automancy_resources::chrono::Local;
+use automancy_resources::data::inventory::{Inventory, InventoryRaw};
+use automancy_resources::data::schema::DataError;
+use automancy_resources::data::{DataMap, DataMapRaw};
+use automancy_resources::ResourceManager;
+
+use crate::marker::Markers;
+use crate::migration::MAP_VERSION;
+use crate::scenario::{Scenario, ScenarioRaw};
+use crate::tile_entity::{TileEntities, TileEntity};
+use crate::zone::Zones;
+
+pub const MAP_PATH: &str = "map";
+pub const MAP_EXT: &str = ".zst";
+pub const INFO_EXT: &str = ".ron";
+/// The folder in a map's folder that each chunk of its tiles is saved to.
+pub const CHUNKS_PATH: &str = "chunks";
+/// Where exported maps are written, and where maps to import are looked for.
+pub const EXPORT_PATH: &str = "exports";
+pub const ARCHIVE_EXT: &str = ".automancy";
+/// The folder in a map's folder that its autosaves are kept in.
+pub const BACKUPS_PATH: &str = "backups";
+/// How many autosaves are kept for each map. Once there are this many, each new one replaces the oldest.
+pub const AUTOSAVE_SLOTS: usize = 5;
+
+pub const MAIN_MENU: &str = ".main_menu";
+/// The name of the player entry controlled by this game instance.
+pub const LOCAL_PLAYER: &str = "local";
+
+const MAP_BUFFER_SIZE: usize = 256 * 1024;
+const ARCHIVE_COMPRESSION_LEVEL: i32 = 19;
+/// Autosaves happen while the game runs, so they are compressed quickly rather than well.
+const BACKUP_COMPRESSION_LEVEL: i32 = 3;
+/// Ends every file written with [`write_checked`], right after the checksum.
+const CHECKSUM_MAGIC: &[u8; 8] = b"AMCSUM01";
+/// What every zstd frame starts with, to tell compressed files from plain ones.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// How long what [`write_checked`] appends is: the length of the data, its checksum, and [`CHECKSUM_MAGIC`].
+const CHECKSUM_TRAILER_SIZE: usize = 8 + 8 + CHECKSUM_MAGIC.len();
+
+/// Compresses the serialized data and writes it to the file, followed by its length and checksum,
+/// so that [`read_checked`] can tell if the file was damaged or cut off.
+///
+/// The file is written next to where it goes first, then moved over it, so a crash halfway through doesn't leave half a file.
+fn write_checked(path: &Path, serialized: &str, level: i32) -> Result<(), MapError> {
+    let mut encoder = Encoder::new(Vec::new(), level)?;
+    encoder.include_checksum(true)?;
+    encoder.write_all(serialized.as_bytes())?;
+    let compressed = encoder.finish()?;
+
+    let temp = path.with_extension("tmp");
+    {
+        let file = File::create(&temp)?;
+        let mut writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, file);
+
+        writer.write_all(&compressed)?;
+        writer.write_all(&(compressed.len() as u64).to_le_bytes())?;
+        writer.write_all(&xxh3_64(&compressed).to_le_bytes())?;
+        writer.write_all(CHECKSUM_MAGIC)?;
+        writer.flush()?;
+    }
+    fs::rename(temp, path)?;
+
+    Ok(())
+}
+
+/// Reads a file written with [`write_checked`], checking it first.
+/// Files from before there were checksums are read without checking, as plain compressed data.
+fn read_checked<T: DeserializeOwned>(path: &Path) -> Result<T, MapError> {
+    decode_checked(path, &fs::read(path)?)
+}
+
+/// Decodes the contents of a file written with [`write_checked`], read from the path.
+fn decode_checked<T: DeserializeOwned>(path: &Path, bytes: &[u8]) -> Result<T, MapError> {
+    let corrupted = || MapError::Corrupted(path.display().to_string());
+
+    let compressed = if let Some(rest) = bytes.strip_suffix(CHECKSUM_MAGIC.as_slice()) {
+        let data_len = rest
+            .len()
+            .checked_sub(CHECKSUM_TRAILER_SIZE - CHECKSUM_MAGIC.len())
+            .ok_or_else(corrupted)?;
+        let (data, trailer) = rest.split_at(data_len);
+
+        let len = u64::from_le_bytes(trailer[0..8].try_into().unwrap());
+        let checksum = u64::from_le_bytes(trailer[8..16].try_into().unwrap());
+
+        if len != data.len() as u64 || checksum != xxh3_64(data) {
+            log::error!("The checksum of {} doesn't match", path.display());
+
+            return Err(corrupted());
+        }
+
+        data
+    } else {
+        bytes
+    };
+
+    let decoder = Decoder::new(compressed)?;
+
+    ron::de::from_reader(decoder).map_err(|err| {
+        log::error!("Serde: {err:?}");
+
+        corrupted()
+    })
+}
+
+/// The tiles of a map, grouped by the chunk they are in, so each chunk can be saved, loaded, and unloaded on its own.
+#[derive(Debug, Clone, Default)]
+pub struct Tiles {
+    chunks: HashMap<ChunkCoord, HashMap<TileCoord, Id>>,
+    len: usize,
+}
+
+impl Tiles {
+    pub fn get(&self, coord: &TileCoord) -> Option<&Id> {
+        self.chunks.get(&coord.chunk())?.get(coord)
+    }
+
+    pub fn contains_key(&self, coord: &TileCoord) -> bool {
+        self.get(coord).is_some()
+    }
+
+    /// Places the tile at the coordinates, returning the one that was there before.
+    pub fn insert(&mut self, coord: TileCoord, id: Id) -> Option<Id> {
+        let old = self
+            .chunks
+            .entry(coord.chunk())
+            .or_default()
+            .insert(coord, id);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, coord: &TileCoord) -> Option<Id> {
+        let chunk = self.chunks.get_mut(&coord.chunk())?;
+        let old = chunk.remove(coord)?;
+
+        if chunk.is_empty() {
+            self.chunks.remove(&coord.chunk());
+        }
+        self.len -= 1;
+
+        Some(old)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TileCoord, &Id)> {
+        self.chunks.values().flatten()
+    }
+
+    /// Gets the chunks that have any tiles in them.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkCoord> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Gets the tiles in a single chunk.
+    pub fn chunk(&self, chunk: ChunkCoord) -> Option<&HashMap<TileCoord, Id>> {
+        self.chunks.get(&chunk)
+    }
+
+    /// Takes all the tiles in a chunk out, returning them.
+    pub fn unload_chunk(&mut self, chunk: ChunkCoord) -> Option<HashMap<TileCoord, Id>> {
+        let tiles = self.chunks.remove(&chunk)?;
+        self.len -= tiles.len();
+
+        Some(tiles)
+    }
+}
+
+/// Contains information about a map.
+#[derive(Debug, Clone, Default)]
+pub struct MapInfo {
+    /// The last save time as a UTC Unix timestamp.
+    pub save_time: Option<SystemTime>,
+    /// The map data.
+    pub data: DataMap,
+    /// The data of each player, keyed by the player's name.
+    pub players: BTreeMap<String, DataMap>,
+    /// The win/lose conditions, if the map is a scenario.
+    pub scenario: Option<Scenario>,
+    /// Whether the map is meant to be used as a starting point for new maps.
+    pub template: bool,
+    /// Whether the map is being edited in the map editor. Lasts only for the current session.
+    pub editor_mode: bool,
+    /// Whether placing tiles takes their cost from the player's inventory, and removing them gives it back.
+    pub survival: bool,
+    /// The regions the player has painted onto the map.
+    pub zones: Zones,
+    /// The points the player has marked on the map.
+    pub markers: Markers,
+    /// When the map was created, if it was made after this was recorded.
+    pub created: Option<SystemTime>,
+    /// When the map was last loaded to be played.
+    pub last_played: Option<SystemTime>,
+    /// How long the map has been played for, counted in ticks.
+    pub playtime: Duration,
+}
+
+impl MapInfo {
+    /// Gets the data of the named player, creating an empty entry if there is none.
+    pub fn player_data_mut(&mut self, name: &str) -> &mut DataMap {
+        self.players.entry(name.to_string()).or_default()
+    }
+
+    /// Converts the info into [`MapInfoRaw`], to be saved along with the given number of tiles.
+    pub fn to_raw(&self, interner: &Interner, tile_count: u64) -> MapInfoRaw {
+        MapInfoRaw {
+            data: self.data.to_raw(interner),
+            players: self
+                .players
+                .iter()
+                .map(|(name, data)| (name.clone(), data.to_raw(interner)))
+                .collect(),
+            scenario: self.scenario.as_ref().map(|v| v.to_raw(interner)),
+            template: self.template,
+            survival: self.survival,
+            zones: self.zones.clone(),
+            markers: self.markers.clone(),
+            created: self.created,
+            last_played: self.last_played,
+            playtime: self.playtime,
+            tile_count,
+            version: MAP_VERSION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapInfoRaw {
+    /// The version of the format the info was saved in.
+    #[serde(default)]
+    pub version: u32,
+    /// The number of saved tiles.
+    #[serde(default)]
+    pub tile_count: u64,
+    #[serde(default)]
+    pub data: DataMapRaw,
+    #[serde(default)]
+    pub players: BTreeMap<String, DataMapRaw>,
+    #[serde(default)]
+    pub scenario: Option<ScenarioRaw>,
+    #[serde(default)]
+    pub template: bool,
+    #[serde(default)]
+    pub survival: bool,
+    #[serde(default)]
+    pub zones: Zones,
+    #[serde(default)]
+    pub markers: Markers,
+    #[serde(default)]
+    pub created: Option<SystemTime>,
+    #[serde(default)]
+    pub last_played: Option<SystemTime>,
+    #[serde(default)]
+    pub playtime: Duration,
+}
+
+impl MapInfoRaw {
+    /// Converts the raw info into [`MapInfo`]. The info should have been migrated to the current version first.
+    pub fn to_info(
+        &self,
+        resource_man: &ResourceManager,
+        save_time: Option<SystemTime>,
+    ) -> MapInfo {
+        MapInfo {
+            save_time,
+            data: self.data.to_data(&resource_man.interner),
+            players: self
+                .players
+                .iter()
+                .map(|(name, data)| (name.clone(), data.to_data(&resource_man.interner)))
+                .collect(),
+            scenario: self
+                .scenario
+                .as_ref()
+                .map(|v| v.to_scenario(&resource_man.interner)),
+            template: self.template,
+            editor_mode: false,
+            survival: self.survival,
+            zones: self.zones.clone(),
+            markers: self.markers.clone(),
+            created: self.created,
+            last_played: self.last_played,
+            playtime: self.playtime,
+        }
+    }
+}
+
+/// A map stores tiles and tile entities to disk.
+#[derive(Debug, Clone)]
+pub struct Map {
+    /// The name of the map. Should be sanitized.
+    pub map_name: String,
+    /// The list of tiles.
+    pub tiles: Tiles,
+    /// The map's info.
+    pub info: Arc<Mutex<MapInfo>>,
+    /// The loose items lying on the ground.
+    pub ground_items: HashMap<TileCoord, Inventory>,
+    /// the hash of what was last written for each chunk, so saving can skip the chunks that haven't changed
+    saved_chunks: HashMap<ChunkCoord, u64>,
+}
+
+/// The tiles of a map, or of a single chunk of it, as they are saved to disk.
+///
+/// The coordinates are written packed, see [`PackedCoords`]. Tiles saved before that are still read.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "MapRawIn")]
+pub struct MapRaw {
+    /// The version of the format the tiles were saved in.
+    pub version: u32,
+    pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+    pub tile_map: HashMap<Id, IdRaw>,
+    #[serde(default)]
+    pub ground_items: Vec<(TileCoord, InventoryRaw)>,
+}
+
+impl MapRaw {
+    pub fn new() -> Self {
+        Self {
+            version: MAP_VERSION,
+            tiles: vec![],
+            tile_map: Default::default(),
+            ground_items: vec![],
+        }
+    }
+
+    /// Adds a tile, and the name of its ID if it's the first of its kind.
+    fn push_tile(&mut self, interner: &Interner, coord: TileCoord, id: Id, data: &DataMap) {
+        self.tile_map
+            .entry(id)
+            .or_insert_with(|| IdRaw::parse(interner.resolve(id).unwrap()));
+
+        self.tiles.push((coord, id, data.to_raw(interner)));
+    }
+
+    /// Adds the tiles and ground items of another part of the map.
+    /// Chunks saved at different times may number the same tile differently, so the other part's IDs are renumbered to match.
+    pub fn merge(&mut self, other: MapRaw) {
+        let mut renumbered = HashMap::new();
+
+        for (id, raw) in other.tile_map {
+            let existing = self
+                .tile_map
+                .iter()
+                .find(|(_, v)| **v == raw)
+                .map(|(id, _)| *id);
+
+            let new = existing.unwrap_or_else(|| {
+                let new = self
+                    .tile_map
+                    .keys()
+                    .max()
+                    .map(|v| Id::from(INT::from(*v) + 1))
+                    .unwrap_or(Id::from(0));
+                self.tile_map.insert(new, raw);
+
+                new
+            });
+
+            renumbered.insert(id, new);
+        }
+
+        self.tiles.extend(
+            other
+                .tiles
+                .into_iter()
+                .flat_map(|(coord, id, data)| Some((coord, *renumbered.get(&id)?, data))),
+        );
+        self.ground_items.extend(other.ground_items);
+    }
+
+    /// Splits the tiles and ground items up by the chunk they are in.
+    fn split_chunks(self) -> HashMap<ChunkCoord, MapRaw> {
+        let mut chunks = HashMap::<ChunkCoord, MapRaw>::new();
+
+        for (coord, id, data) in self.tiles {
+            let Some(raw) = self.tile_map.get(&id) else {
+                continue;
+            };
+
+            let chunk = chunks.entry(coord.chunk()).or_insert_with(MapRaw::new);
+            chunk.tile_map.entry(id).or_insert_with(|| raw.clone());
+            chunk.tiles.push((coord, id, data));
+        }
+
+        for (coord, items) in self.ground_items {
+            chunks
+                .entry(coord.chunk())
+                .or_insert_with(MapRaw::new)
+                .ground_items
+                .push((coord, items));
+        }
+
+        chunks
+    }
+}
+
+impl Default for MapRaw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How [`MapRaw`] is written to disk, with the coordinates of the tiles and ground items packed on their own,
+/// and everything else in the same order.
+#[derive(Serialize)]
+struct MapRawOut<'a> {
+    version: u32,
+    coords: PackedCoords,
+    ids: Vec<Id>,
+    data: Vec<&'a DataMapRaw>,
+    tile_map: &'a HashMap<Id, IdRaw>,
+    ground_coords: PackedCoords,
+    ground_inventories: Vec<&'a InventoryRaw>,
+}
+
+impl Serialize for MapRaw {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // row by row, so the tiles next to each other are next to each other in the list too
+        let mut tiles = self.tiles.iter().collect::<Vec<_>>();
+        tiles.sort_by_key(|(coord, ..)| (coord.y, coord.x));
+
+        let mut ground_items = self.ground_items.iter().collect::<Vec<_>>();
+        ground_items.sort_by_key(|(coord, _)| (coord.y, coord.x));
+
+        MapRawOut {
+            version: self.version,
+            coords: PackedCoords::pack(tiles.iter().map(|(coord, ..)| *coord)),
+            ids: tiles.iter().map(|(_, id, _)| *id).collect(),
+            data: tiles.iter().map(|(.., data)| data).collect(),
+            tile_map: &self.tile_map,
+            ground_coords: PackedCoords::pack(ground_items.iter().map(|(coord, _)| *coord)),
+            ground_inventories: ground_items.iter().map(|(_, items)| items).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// How [`MapRaw`] is read from disk, either with the coordinates packed, or as a list of tiles as it was saved before.
+#[derive(Deserialize)]
+struct MapRawIn {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+    #[serde(default)]
+    coords: PackedCoords,
+    #[serde(default)]
+    ids: Vec<Id>,
+    #[serde(default)]
+    data: Vec<DataMapRaw>,
+    tile_map: HashMap<Id, IdRaw>,
+    #[serde(default)]
+    ground_items: Vec<(TileCoord, InventoryRaw)>,
+    #[serde(default)]
+    ground_coords: PackedCoords,
+    #[serde(default)]
+    ground_inventories: Vec<InventoryRaw>,
+}
+
+impl TryFrom<MapRawIn> for MapRaw {
+    type Error = String;
+
+    fn try_from(value: MapRawIn) -> Result<Self, Self::Error> {
+        let coords = value
+            .coords
+            .unpack()
+            .ok_or("the tile coordinates are cut off")?;
+        if coords.len() != value.ids.len() || coords.len() != value.data.len() {
+            return Err("there are not as many tiles as coordinates".to_string());
+        }
+
+        let ground_coords = value
+            .ground_coords
+            .unpack()
+            .ok_or("the ground item coordinates are cut off")?;
+        if ground_coords.len() != value.ground_inventories.len() {
+            return Err("there are not as many ground items as coordinates".to_string());
+        }
+
+        let mut tiles = value.tiles;
+        tiles.extend(
+            coords
+                .into_iter()
+                .zip(value.ids)
+                .zip(value.data)
+                .map(|((coord, id), data)| (coord, id, data)),
+        );
+
+        let mut ground_items = value.ground_items;
+        ground_items.extend(ground_coords.into_iter().zip(value.ground_inventories));
+
+        Ok(MapRaw {
+            version: value.version,
+            tiles,
+            tile_map: value.tile_map,
+            ground_items,
+        })
+    }
+}
+
+/// A whole map in a single file, so it can be shared without knowing how maps are laid out on disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct MapArchive {
+    /// The name the map had when it was exported, and the name it is imported as if that is free.
+    name: String,
+    info: MapInfoRaw,
+    map: MapRaw,
+}
+
+fn ground_items_to_raw(
+    ground_items: &HashMap<TileCoord, Inventory>,
+    interner: &Interner,
+) -> Vec<(TileCoord, InventoryRaw)> {
+    ground_items
+        .iter()
+        .filter(|(_, items)| items.values().any(|amount| *amount > 0))
+        .map(|(coord, items)| (*coord, items.to_raw(interner)))
+        .collect()
+}
+
+impl Map {
+    /// Creates a new empty map.
+    pub fn new_empty(map_name: String) -> Self {
+        Self {
+            map_name,
+            tiles: Default::default(),
+            info: Arc::new(Mutex::new(MapInfo {
+                created: Some(SystemTime::now()),
+                ..Default::default()
+            })),
+            ground_items: Default::default(),
+            saved_chunks: Default::default(),
+        }
+    }
+
+    /// Gets the path to a map from its name.
+    pub fn path(map_name: &str) -> PathBuf {
+        PathBuf::from(format!("{MAP_PATH}/{map_name}/"))
+    }
+
+    /// Gets the path to the small preview image of a map, from its name.
+    pub fn thumbnail(map_name: &str) -> PathBuf {
+        Map::path(map_name).join("thumbnail.png")
+    }
+
+    /// Gets the path to a map's info from its name.
+    pub fn info(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(format!("info{INFO_EXT}"))
+    }
+
+    /// Gets the path to a map's tiles from its name, for maps saved before the tiles were split into chunks.
+    pub fn legacy_tiles(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(format!("map{MAP_EXT}"))
+    }
+
+    /// Gets the path to the folder a map's chunks are saved in, from its name.
+    pub fn chunks_path(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(CHUNKS_PATH)
+    }
+
+    /// Gets the path to a single chunk of a map's tiles.
+    pub fn chunk_path(map_name: &str, chunk: ChunkCoord) -> PathBuf {
+        Map::chunks_path(map_name).join(format!("{},{}{MAP_EXT}", chunk.q, chunk.r))
+    }
+
+    /// Checks if there is a saved map with this name.
+    pub fn exists(map_name: &str) -> bool {
+        Map::info(map_name).exists()
+            && (Map::chunks_path(map_name).is_dir() || Map::legacy_tiles(map_name).exists())
+    }
+
+    /// Lists the chunks of a map that have been saved.
+    pub fn list_chunks(map_name: &str) -> Vec<ChunkCoord> {
+        let Ok(dir) = fs::read_dir(Map::chunks_path(map_name)) else {
+            return vec![];
+        };
+
+        dir.flatten()
+            .flat_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let (q, r) = name.strip_suffix(MAP_EXT)?.split_once(',')?;
+
+                Some(ChunkCoord {
+                    q: q.parse().ok()?,
+                    r: r.parse().ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Reads a map's info, telling the player if it's damaged.
+    pub fn read_info(
+        resource_man: &ResourceManager,
+        map_name: &str,
+    ) -> Option<(MapInfoRaw, Option<SystemTime>)> {
+        match Map::try_read_info(map_name) {
+            Ok(v) => Some(v),
+            Err(MapError::Io(_)) => None,
+            Err(_) => {
+                resource_man.error_man.push(
+                    (
+                        resource_man.registry.err_ids.invalid_map_data,
+                        vec![map_name.to_string()],
+                    ),
+                    resource_man,
+                );
+
+                None
+            }
+        }
+    }
+
+    /// Reads a map's info.
+    ///
+    /// Info written before it was checked is plain RON, and so is the info of the maps shipped as templates,
+    /// so a file that is neither checked nor compressed is read as it is.
+    fn try_read_info(map_name: &str) -> Result<(MapInfoRaw, Option<SystemTime>), MapError> {
+        let path = Self::info(map_name);
+
+        let bytes = fs::read(&path)?;
+        let time = fs::metadata(&path)
+            .and_then(|v| v.modified().or(v.accessed()))
+            .ok();
+
+        let mut info: MapInfoRaw =
+            if bytes.ends_with(CHECKSUM_MAGIC) || bytes.starts_with(&ZSTD_MAGIC) {
+                decode_checked(&path, &bytes)?
+            } else {
+                ron::de::from_bytes(&bytes).map_err(|err| {
+                    log::error!("Serde: {err:?}");
+
+                    MapError::Corrupted(path.display().to_string())
+                })?
+            };
+
+        info.migrate();
+
+        Ok((info, time))
+    }
+
+    /// Reads all of a map's tiles, from its chunks, or from the single file older maps were saved as.
+    pub fn read_map(map_name: &str) -> Result<MapRaw, MapError> {
+        let legacy = Map::legacy_tiles(map_name);
+        if legacy.exists() {
+            return Map::read_tiles_file(&legacy);
+        }
+
+        let mut map = MapRaw::new();
+
+        for chunk in Map::list_chunks(map_name) {
+            map.merge(Map::read_chunk(map_name, chunk)?);
+        }
+
+        Ok(map)
+    }
+
+    /// Reads a single chunk of a map's tiles, so parts of a map can be loaded when they are needed.
+    pub fn read_chunk(map_name: &str, chunk: ChunkCoord) -> Result<MapRaw, MapError> {
+        Map::read_tiles_file(&Map::chunk_path(map_name, chunk))
+    }
+
+    fn read_tiles_file(path: &Path) -> Result<MapRaw, MapError> {
+        let mut map: MapRaw = read_checked(path)?;
+        map.migrate();
+
+        Ok(map)
+    }
+
+    /// Reads the tiles of a map from disk, without creating any tile entities.
+    pub fn read_tiles(
+        resource_man: &ResourceManager,
+        map_name: &str,
+    ) -> Option<Vec<(TileCoord, Id, DataMap)>> {
+        let map = Map::read_map(map_name)
+            .map_err(|err| log::error!("Could not read the tiles of {map_name}: {err}"))
+            .ok()?;
+
+        Some(
+            map.tiles
+                .into_iter()
+                .flat_map(|(coord, id, data)| {
+                    map.tile_map
+                        .get(&id)
+                        .and_then(|id| resource_man.interner.get(id.to_string()))
+                        .map(|id| (coord, id, data.to_data(&resource_man.interner)))
+                })
+                .collect(),
+        )
+    }
+
+    /// Loads a map from disk.
+    ///
+    /// Every chunk is loaded, as the game runs all the tiles in the map.
+    ///
+    /// A map that doesn't exist yet loads as an empty map. A map that exists but can't be read is an error,
+    /// so that it doesn't get saved over.
+    pub fn load(
+        resource_man: &ResourceManager,
+        map_name: &str,
+    ) -> Result<(Self, TileEntities), MapError> {
+        if !Map::exists(map_name) {
+            return Ok((Map::new_empty(map_name.to_string()), Default::default()));
+        }
+
+        let (info, save_time) = Map::try_read_info(map_name)?;
+        let map_raw = Map::read_map(map_name)?;
+
+        let mut map = Map::new_empty(map_name.to_string());
+        map.info = Arc::new(Mutex::new(info.to_info(resource_man, save_time)));
+
+        let mut tile_entities = TileEntities::default();
+        map.insert_raw(resource_man, &mut tile_entities, map_raw);
+
+        Ok((map, tile_entities))
+    }
+
+    /// Adds the tiles and ground items from disk to the map, creating their tile entities.
+    fn insert_raw(
+        &mut self,
+        resource_man: &ResourceManager,
+        tile_entities: &mut TileEntities,
+        map_raw: MapRaw,
+    ) {
+        for (coord, id, data) in map_raw.tiles.into_iter() {
+            if !coord.is_in_world() {
+                log::warn!("Dropping tile at {coord}, as it is outside of the world");
+                continue;
+            }
+
+            if let Some(id) = map_raw
+                .tile_map
+                .get(&id)
+                .and_then(|id| resource_man.interner.get(id.to_string()))
+            {
+                let mut data = data.to_data(&resource_man.interner);
+                for err in resource_man
+                    .registry
+                    .data_schema
+                    .retain_valid(&resource_man.interner, &mut data)
+                {
+                    log::warn!("Dropping data of the tile at {coord}: {err}");
+                }
+
+                let mut tile_entity = TileEntity::new(id, coord);
+                tile_entity.set_data(data);
+
+                self.tiles.insert(coord, id);
+                tile_entities.insert(tile_entity);
+            }
+        }
+
+        self.ground_items.extend(
+            map_raw
+                .ground_items
+                .iter()
+                .map(|(coord, items)| (*coord, items.to_inventory(&resource_man.interner))),
+        );
+    }
+
+    /// Converts the tiles and ground items into what is saved, split up by chunk.
+    fn chunks_to_raw(
+        &self,
+        interner: &Interner,
+        tile_entities: &TileEntities,
+    ) -> HashMap<ChunkCoord, MapRaw> {
+        let mut chunks = HashMap::<ChunkCoord, MapRaw>::new();
+
+        for (coord, id) in self.tiles.iter() {
+            if let Some(tile_entity) = tile_entities.get(coord) {
+                chunks.entry(coord.chunk()).or_default().push_tile(
+                    interner,
+                    *coord,
+                    *id,
+                    &tile_entity.data(),
+                );
+            }
+        }
+
+        for (coord, items) in ground_items_to_raw(&self.ground_items, interner) {
+            chunks
+                .entry(coord.chunk())
+                .or_default()
+                .ground_items
+                .push((coord, items));
+        }
+
+        chunks
+    }
+
+    /// Saves a map to disk. Only the chunks that changed since the last save are written.
+    ///
+    /// Returns whether it was saved. If it couldn't be, the error is shown to the player.
+    pub async fn save(
+        &mut self,
+        resource_man: &ResourceManager,
+        tile_entities: &TileEntities,
+    ) -> bool {
+        let chunks = self.chunks_to_raw(&resource_man.interner, tile_entities);
+
+        let info = self
+            .info
+            .lock()
+            .await
+            .to_raw(&resource_man.interner, self.tiles.len() as u64);
+
+        if let Err(err) = Map::write_chunks(&self.map_name, &info, chunks, &mut self.saved_chunks) {
+            resource_man.error_man.push(
+                (
+                    resource_man.registry.err_ids.map_save_failed,
+                    vec![self.map_name.clone(), err.to_string()],
+                ),
+                resource_man,
+            );
+
+            return false;
+        }
+
+        true
+    }
+
+    /// Writes the raw info and tiles of a map to disk, replacing what was there.
+    fn write(map_name: &str, info: &MapInfoRaw, map_raw: MapRaw) -> Result<(), MapError> {
+        Map::write_chunks(
+            map_name,
+            info,
+            map_raw.split_chunks(),
+            &mut Default::default(),
+        )
+    }
+
+    /// Writes the raw info and the chunks of a map to disk, and removes the chunks that are no longer there.
+    /// The chunks that are the same as their hash in `saved_chunks` are skipped.
+    fn write_chunks(
+        map_name: &str,
+        info: &MapInfoRaw,
+        chunks: HashMap<ChunkCoord, MapRaw>,
+        saved_chunks: &mut HashMap<ChunkCoord, u64>,
+    ) -> Result<(), MapError> {
+        fs::create_dir_all(Map::chunks_path(map_name))?;
+
+        write_checked(&Self::info(map_name), &ron::ser::to_string(info)?, 0)?;
+
+        for (chunk, map_raw) in &chunks {
+            Map::write_chunk(map_name, *chunk, map_raw, saved_chunks)?;
+        }
+
+        for chunk in Map::list_chunks(map_name) {
+            if !chunks.contains_key(&chunk) {
+                fs::remove_file(Map::chunk_path(map_name, chunk))?;
+                saved_chunks.remove(&chunk);
+            }
+        }
+
+        // the tiles are all in the chunks now
+        let legacy = Map::legacy_tiles(map_name);
+        if legacy.exists() {
+            fs::remove_file(legacy)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single chunk of a map to disk, unless it is the same as when it was last written.
+    fn write_chunk(
+        map_name: &str,
+        chunk: ChunkCoord,
+        map_raw: &MapRaw,
+        saved_chunks: &mut HashMap<ChunkCoord, u64>,
+    ) -> Result<(), MapError> {
+        let serialized = ron::ser::to_string(map_raw)?;
+
+        let mut hasher = DefaultHasher::new();
+        serialized.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if saved_chunks.get(&chunk) == Some(&hash) {
+            return Ok(());
+        }
+
+        write_checked(&Map::chunk_path(map_name, chunk), &serialized, 0)?;
+
+        saved_chunks.insert(chunk, hash);
+
+        Ok(())
+    }
+
+    /// Gets the path a map is exported to by default, from its name.
+    pub fn export_path(map_name: &str) -> PathBuf {
+        PathBuf::from(EXPORT_PATH).join(format!("{map_name}{ARCHIVE_EXT}"))
+    }
+
+    /// Lists the exported maps that can be imported, sorted by name.
+    pub fn list_exports() -> Vec<PathBuf> {
+        let Ok(dir) = fs::read_dir(EXPORT_PATH) else {
+            return vec![];
+        };
+
+        let mut exports = dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.to_str()
+                    .is_some_and(|path| path.ends_with(ARCHIVE_EXT))
+            })
+            .collect::<Vec<_>>();
+        exports.sort();
+
+        exports
+    }
+
+    /// Bundles a saved map's info and tiles into a single compressed file.
+    pub fn export_to_file(
+        resource_man: &ResourceManager,
+        map_name: &str,
+        path: &Path,
+    ) -> Result<(), MapError> {
+        if !Map::exists(map_name) {
+            return Err(MapError::NotFound(map_name.to_string()));
+        }
+
+        let unreadable = || MapError::Unreadable(map_name.to_string());
+
+        let (info, _) = Map::read_info(resource_man, map_name).ok_or_else(unreadable)?;
+        let map = Map::read_map(map_name)?;
+
+        let archive = MapArchive {
+            name: map_name.to_string(),
+            info,
+            map,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = ron::ser::to_string(&archive)?;
+
+        write_checked(path, &serialized, ARCHIVE_COMPRESSION_LEVEL)
+    }
+
+    /// Unpacks a map exported with [`Map::export_to_file`] into a new map, and returns the new map's name.
+    /// If a map with the exported name already exists, a number is added to the name.
+    pub fn import_from_file(path: &Path) -> Result<String, MapError> {
+        let mut archive: MapArchive = read_checked(path)?;
+
+        archive.info.migrate();
+        archive.map.migrate();
+
+        let name = Map::sanitize_name(archive.name);
+        let name = if Map::path(&name).exists() {
+            (2..)
+                .map(|n| format!("{name}_{n}"))
+                .find(|name| !Map::path(name).exists())
+                .unwrap()
+        } else {
+            name
+        };
+
+        Map::write(&name, &archive.info, archive.map)?;
+
+        Ok(name)
+    }
+
+    pub fn backups_path(map_name: &str) -> PathBuf {
+        Map::path(map_name).join(BACKUPS_PATH)
+    }
+
+    /// Lists the autosaves of a map, the newest first.
+    pub fn list_backups(map_name: &str) -> Vec<Backup> {
+        let Ok(dir) = fs::read_dir(Map::backups_path(map_name)) else {
+            return vec![];
+        };
+
+        let mut backups = dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.to_str()
+                    .is_some_and(|path| path.ends_with(ARCHIVE_EXT))
+            })
+            .map(|path| Backup {
+                time: fs::metadata(&path).and_then(|v| v.modified()).ok(),
+                path,
+            })
+            .collect::<Vec<_>>();
+        // the file names are the times they were made, so sorting by name sorts by time
+        backups.sort_by(|a, b| b.path.cmp(&a.path));
+
+        backups
+    }
+
+    /// Copies a saved map into a new autosave, and removes the oldest ones past [`AUTOSAVE_SLOTS`].
+    pub fn write_backup(map_name: &str) -> Result<PathBuf, MapError> {
+        let (info, _) = Map::try_read_info(map_name)?;
+        let map = Map::read_map(map_name)?;
+
+        let archive = MapArchive {
+            name: map_name.to_string(),
+            info,
+            map,
+        };
+
+        fs::create_dir_all(Map::backups_path(map_name))?;
+
+        let path = Map::backups_path(map_name).join(format!(
+            "{}{ARCHIVE_EXT}",
+            Local::now().format("%Y%m%d%H%M%S")
+        ));
+        write_checked(
+            &path,
+            &ron::ser::to_string(&archive)?,
+            BACKUP_COMPRESSION_LEVEL,
+        )?;
+
+        for old in Map::list_backups(map_name).into_iter().skip(AUTOSAVE_SLOTS) {
+            fs::remove_file(old.path)?;
+        }
+
+        Ok(path)
+    }
+
+    /// Replaces a saved map with one of its autosaves. The autosaves themselves are kept.
+    pub fn restore_backup(map_name: &str, backup: &Path) -> Result<(), MapError> {
+        let mut archive: MapArchive = read_checked(backup)?;
+
+        archive.info.migrate();
+        archive.map.migrate();
+
+        Map::write(map_name, &archive.info, archive.map)
+    }
+
+    /// Creates a new map by copying the files of a template map.
+    ///
+    /// The files are copied into a hidden folder first, which is then renamed to the map,
+    /// so a copy that fails halfway doesn't leave part of a map behind.
+    pub fn create_from_template(template: &Path, map_name: &str) -> io::Result<()> {
+        let temp_name = format!(".{map_name}.new");
+        let temp = Map::path(&temp_name);
+
+        if temp.exists() {
+            fs::remove_dir_all(&temp)?;
+        }
+
+        let result = Map::copy_template(template, &temp_name)
+            .and_then(|_| fs::rename(&temp, Map::path(map_name)));
+
+        if result.is_err() {
+            drop(fs::remove_dir_all(&temp));
+        }
+
+        result
+    }
+
+    fn copy_template(template: &Path, map_name: &str) -> io::Result<()> {
+        fs::create_dir_all(Map::path(map_name))?;
+
+        fs::copy(
+            template.join(format!("info{INFO_EXT}")),
+            Map::info(map_name),
+        )?;
+
+        let legacy = template.join(format!("map{MAP_EXT}"));
+        if legacy.exists() {
+            fs::copy(legacy, Map::legacy_tiles(map_name))?;
+        }
+
+        fs::create_dir_all(Map::chunks_path(map_name))?;
+        if let Ok(chunks) = fs::read_dir(template.join(CHUNKS_PATH)) {
+            for entry in chunks.flatten() {
+                fs::copy(
+                    entry.path(),
+                    Map::chunks_path(map_name).join(entry.file_name()),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sanitizes the name to ensure that the map can be used without problems on all platforms. This includes removing leading/trailing whitespace and periods, replacing non-alphanumeric characters, and replacing Windows disallowed names.
+    pub fn sanitize_name(name: String) -> String {
+        if name.is_empty() {
+            return "empty".to_string();
+        }
+        let name = name.trim();
+        let name = name.trim_matches('.');
+        let name = name.replace(|c: char| !c.is_alphanumeric(), "_");
+
+        if WIN_ILLEGAL_NAMES.contains(&name.to_ascii_uppercase().as_str()) {
+            return format!("_{name}");
+        }
+        name
+    }
+}
+
+lazy_static! {
+    static ref WIN_ILLEGAL_NAMES: HashSet<&'static str> = HashSet::from([
+        "CON", "PRN", "AUX", "CLOCK$", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6",
+        "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8",
+        "LPT9",
+    ]);
+}
+
+/// An autosave of a map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the autosave was made.
+    pub time: Option<SystemTime>,
+}
+
+/// Something that went wrong while opening, checking, or writing a map with [`MapFile`].
+#[derive(Debug, Error)]
+pub enum MapError {
+    #[error("the map {0} does not exist")]
+    NotFound(String),
+    #[error("the map {0} could not be read")]
+    Unreadable(String),
+    #[error("the file {0} is damaged or was cut off")]
+    Corrupted(String),
+    #[error("the tile at {0} is outside of the world")]
+    OutsideWorld(TileCoord),
+    #[error("the tile at {0} is not a known tile")]
+    UnknownTile(TileCoord),
+    #[error("the tile at {0} has invalid data: {1}")]
+    InvalidData(TileCoord, DataError),
+    #[error("could not write the map: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not write the map: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+/// A map opened straight from disk, to be looked at or changed without running it, as a save editor would.
+///
+/// Nothing is checked while editing. [`MapFile::validate`] lists what's wrong with the map,
+/// and [`MapFile::write`] refuses to write it back until that list is empty.
+///
+/// ```no_run
+/// # use automancy_core::map::MapFile;
+/// # use automancy_resources::data::Data;
+/// let (resource_man, ..) = automancy_core::load_resources();
+/// let mut map = MapFile::open(&resource_man, "my_factory").unwrap();
+///
+/// // turn every tile in the map to face right
+/// let coords = map.tiles().map(|(coord, ..)| coord).collect::<Vec<_>>();
+/// for coord in coords {
+///     map.data_mut(coord).unwrap().insert(
+///         resource_man.registry.data_ids.target,
+///         Data::Coord(automancy_defs::coord::TileCoord::RIGHT),
+///     );
+/// }
+///
+/// map.write(&resource_man).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MapFile {
+    /// The name of the map, which is also where it is written back to.
+    pub map_name: String,
+    /// The map's info.
+    pub info: MapInfo,
+    /// The loose items lying on the ground.
+    pub ground_items: HashMap<TileCoord, Inventory>,
+    tiles: HashMap<TileCoord, (Id, DataMap)>,
+}
+
+impl MapFile {
+    /// Opens a map from disk.
+    pub fn open(resource_man: &ResourceManager, map_name: &str) -> Result<Self, MapError> {
+        if !Map::exists(map_name) {
+            return Err(MapError::NotFound(map_name.to_string()));
+        }
+
+        let unreadable = || MapError::Unreadable(map_name.to_string());
+
+        let (info, save_time) = Map::read_info(resource_man, map_name).ok_or_else(unreadable)?;
+        let map = Map::read_map(map_name)?;
+
+        let tiles = map
+            .tiles
+            .into_iter()
+            .flat_map(|(coord, id, data)| {
+                let id = map
+                    .tile_map
+                    .get(&id)
+                    .and_then(|id| resource_man.interner.get(id.to_string()));
+
+                if id.is_none() {
+                    log::warn!("Dropping tile at {coord}, as its ID is not known");
+                }
+
+                id.map(|id| (coord, (id, data.to_data(&resource_man.interner))))
+            })
+            .collect();
+
+        Ok(Self {
+            map_name: map_name.to_string(),
+            info: info.to_info(resource_man, save_time),
+            ground_items: map
+                .ground_items
+                .iter()
+                .map(|(coord, items)| (*coord, items.to_inventory(&resource_man.interner)))
+                .collect(),
+            tiles,
+        })
+    }
+
+    /// Gets all the tiles in the map, with their IDs and data. They are in no particular order.
+    pub fn tiles(&self) -> impl Iterator<Item = (TileCoord, Id, &DataMap)> {
+        self.tiles
+            .iter()
+            .map(|(coord, (id, data))| (*coord, *id, data))
+    }
+
+    /// Gets how many tiles are in the map.
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Gets the tile at the given position, and its data.
+    pub fn tile(&self, coord: TileCoord) -> Option<(Id, &DataMap)> {
+        self.tiles.get(&coord).map(|(id, data)| (*id, data))
+    }
+
+    /// Gets the data of the tile at the given position, to be changed.
+    pub fn data_mut(&mut self, coord: TileCoord) -> Option<&mut DataMap> {
+        self.tiles.get_mut(&coord).map(|(_, data)| data)
+    }
+
+    /// Places a tile at the given position, returning the tile that was there before.
+    pub fn set_tile(&mut self, coord: TileCoord, id: Id, data: DataMap) -> Option<(Id, DataMap)> {
+        self.tiles.insert(coord, (id, data))
+    }
+
+    /// Removes the tile at the given position, returning it.
+    pub fn remove_tile(&mut self, coord: TileCoord) -> Option<(Id, DataMap)> {
+        self.tiles.remove(&coord)
+    }
+
+    /// Lists everything that would stop the map from loading properly.
+    pub fn validate(&self, resource_man: &ResourceManager) -> Vec<MapError> {
+        let mut errors = vec![];
+
+        for (coord, (id, data)) in &self.tiles {
+            if !coord.is_in_world() {
+                errors.push(MapError::OutsideWorld(*coord));
+            } else if *id == resource_man.registry.none
+                || !resource_man.registry.tiles.contains_key(id)
+            {
+                errors.push(MapError::UnknownTile(*coord));
+            }
+
+            errors.extend(
+                resource_man
+                    .registry
+                    .data_schema
+                    .validate(&resource_man.interner, data)
+                    .into_iter()
+                    .map(|err| MapError::InvalidData(*coord, err)),
+            );
+        }
+
+        errors
+    }
+
+    /// Checks the map, then writes it back to disk. If it isn't valid, nothing is written,
+    /// and the first problem found is returned.
+    pub fn write(&self, resource_man: &ResourceManager) -> Result<(), MapError> {
+        if let Some(err) = self.validate(resource_man).into_iter().next() {
+            return Err(err);
+        }
+
+        let interner = &resource_man.interner;
+
+        let mut map_raw = MapRaw {
+            ground_items: ground_items_to_raw(&self.ground_items, interner),
+            ..MapRaw::new()
+        };
+
+        for (coord, (id, data)) in &self.tiles {
+            map_raw.push_tile(interner, *coord, *id, data);
+        }
+
+        Map::write(
+            &self.map_name,
+            &self.info.to_raw(interner, self.tiles.len() as u64),
+            map_raw,
+        )
+    }
+}
@@ -0,0 +1,76 @@
+//! Markers are labeled points the player places on the map, to find their way back to places.
+//! They are drawn at the same size on the screen no matter how far the camera is zoomed out.
+
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::coord::TileCoord;
+
+use crate::zone::ZONE_COLORS;
+
+/// The shape a marker is drawn as.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarkerIcon {
+    #[default]
+    Circle,
+    Square,
+    Diamond,
+    Triangle,
+}
+
+impl MarkerIcon {
+    pub const ALL: [MarkerIcon; 4] = [
+        MarkerIcon::Circle,
+        MarkerIcon::Square,
+        MarkerIcon::Diamond,
+        MarkerIcon::Triangle,
+    ];
+}
+
+/// A labeled point on the map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub coord: TileCoord,
+    pub label: String,
+    /// The color of the marker, in sRGB.
+    pub color: [u8; 3],
+    #[serde(default)]
+    pub icon: MarkerIcon,
+}
+
+/// All the markers of a map, in the order they were placed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Markers(pub Vec<Marker>);
+
+impl Markers {
+    /// Places a new marker at the position, and returns its index.
+    pub fn add(&mut self, coord: TileCoord, label: String) -> usize {
+        let color = ZONE_COLORS[self.0.len() % ZONE_COLORS.len()];
+
+        self.0.push(Marker {
+            coord,
+            label,
+            color,
+            icon: MarkerIcon::default(),
+        });
+
+        self.0.len() - 1
+    }
+
+    /// Removes the marker at the index, if there is one.
+    pub fn remove(&mut self, index: usize) -> Option<Marker> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Marker> {
+        self.0.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Marker> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
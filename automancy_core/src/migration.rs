@@ -0,0 +1,100 @@
+//! Upgrades maps saved by older versions of the game, one version of the format at a time.
+//!
+//! When a change to the map format would break existing maps, bump [`MAP_VERSION`],
+//! and add a [`Migration`] from the old version to the end of [`MIGRATIONS`].
+
+use automancy_defs::id::IdRaw;
+use automancy_defs::log;
+
+use crate::map::{MapInfoRaw, MapRaw, LOCAL_PLAYER};
+
+/// The version of the map format this version of the game writes.
+/// Maps saved before the format had versions are version 0.
+pub const MAP_VERSION: u32 = 1;
+
+/// Upgrades a map from one version of the format to the next.
+pub struct Migration {
+    /// The version this upgrades from, to the one right after it.
+    pub from: u32,
+    /// Upgrades the map's info.
+    pub info: fn(&mut MapInfoRaw),
+    /// Upgrades the map's tiles.
+    pub map: fn(&mut MapRaw),
+}
+
+/// Every migration, one for each version before [`MAP_VERSION`], in order.
+pub static MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    info: move_player_data,
+    map: |_| {},
+}];
+
+/// Maps saved before named players existed kept the player's data in the map data,
+/// so it is moved into the [`LOCAL_PLAYER`] entry.
+fn move_player_data(info: &mut MapInfoRaw) {
+    const PLAYER_KEYS: [&str; 4] = [
+        "automancy:player_inventory",
+        "automancy:research_items_filled",
+        "automancy:research_puzzle_completed",
+        "automancy:unlocked_researches",
+    ];
+
+    let keys = info
+        .data
+        .keys()
+        .filter(|key| PLAYER_KEYS.contains(&IdRaw::parse(key).to_string().as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for key in keys {
+        if let Some(value) = info.data.remove(&key) {
+            info.players
+                .entry(LOCAL_PLAYER.to_string())
+                .or_default()
+                .entry(key)
+                .or_insert(value);
+        }
+    }
+}
+
+/// Runs the migrations from `version` onwards on the map data, and gives back the version it is now at.
+fn migrate<T>(
+    what: &str,
+    version: u32,
+    raw: &mut T,
+    step: impl Fn(&Migration) -> fn(&mut T),
+) -> u32 {
+    if version > MAP_VERSION {
+        log::warn!(
+            "The map {what} is from a newer version of the game ({version}, this one is {MAP_VERSION}), and may not load correctly"
+        );
+
+        return version;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|v| v.from >= version) {
+        log::info!(
+            "Upgrading the map {what} from version {} to {}",
+            migration.from,
+            migration.from + 1
+        );
+
+        step(migration)(raw);
+    }
+
+    MAP_VERSION
+}
+
+impl MapInfoRaw {
+    /// Upgrades the info to the current version of the format.
+    pub fn migrate(&mut self) {
+        self.version = migrate("info", self.version, self, |v| v.info);
+    }
+}
+
+impl MapRaw {
+    /// Upgrades the tiles to the current version of the format.
+    pub fn migrate(&mut self) {
+        self.version = migrate("tiles", self.version, self, |v| v.map);
+    }
+}
@@ -0,0 +1,198 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::id::{Id, IdRaw, Interner};
+use automancy_defs::log;
+use automancy_resources::data::stack::ItemAmount;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::game::TPS;
+
+/// How a scenario has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioOutcome {
+    Victory,
+    Defeat,
+}
+
+/// A condition that is checked against the map every tick.
+#[derive(Debug, Clone, Copy)]
+pub enum Condition {
+    /// The player has delivered this many of the item into their inventory.
+    DeliverItems(Id, ItemAmount),
+    /// The player has unlocked the research.
+    ReachResearch(Id),
+    /// The scenario has been running for this many seconds.
+    TimeLimit(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConditionRaw {
+    DeliverItems(IdRaw, ItemAmount),
+    ReachResearch(IdRaw),
+    TimeLimit(u64),
+}
+
+/// The win/lose conditions of a map, and how far it has been played.
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    /// All of these need to be met for the player to win.
+    pub victory: Vec<Condition>,
+    /// Any of these being met makes the player lose.
+    pub defeat: Vec<Condition>,
+    /// The number of ticks the scenario has been running for.
+    pub elapsed_ticks: u64,
+    /// How the scenario has ended, if it has.
+    pub outcome: Option<ScenarioOutcome>,
+    /// Whether the outcome has been shown to the player.
+    pub announced: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScenarioRaw {
+    #[serde(default)]
+    pub victory: Vec<ConditionRaw>,
+    #[serde(default)]
+    pub defeat: Vec<ConditionRaw>,
+    #[serde(default)]
+    pub elapsed_ticks: u64,
+    #[serde(default)]
+    pub outcome: Option<ScenarioOutcome>,
+    #[serde(default)]
+    pub announced: bool,
+}
+
+impl Condition {
+    fn to_raw(self, interner: &Interner) -> Option<ConditionRaw> {
+        Some(match self {
+            Condition::DeliverItems(id, amount) => {
+                ConditionRaw::DeliverItems(interner.resolve(id).map(IdRaw::parse)?, amount)
+            }
+            Condition::ReachResearch(id) => {
+                ConditionRaw::ReachResearch(interner.resolve(id).map(IdRaw::parse)?)
+            }
+            Condition::TimeLimit(seconds) => ConditionRaw::TimeLimit(seconds),
+        })
+    }
+
+    /// Checks if the condition is met by the player.
+    fn is_met(
+        &self,
+        resource_man: &ResourceManager,
+        player_data: &DataMap,
+        elapsed_ticks: u64,
+    ) -> bool {
+        match self {
+            Condition::DeliverItems(id, amount) => {
+                if let Some(Data::Inventory(inventory)) =
+                    player_data.get(&resource_man.registry.data_ids.player_inventory)
+                {
+                    inventory.deref().get(id).copied().unwrap_or(0) >= *amount
+                } else {
+                    false
+                }
+            }
+            Condition::ReachResearch(id) => {
+                if let Some(Data::SetId(unlocked)) =
+                    player_data.get(&resource_man.registry.data_ids.unlocked_researches)
+                {
+                    unlocked.contains(id)
+                } else {
+                    false
+                }
+            }
+            Condition::TimeLimit(seconds) => elapsed_ticks >= seconds * TPS,
+        }
+    }
+}
+
+impl ConditionRaw {
+    /// Converts the raw condition. Logs an error and gives [`None`] if it refers to an ID that isn't known.
+    fn to_condition(&self, interner: &Interner) -> Option<Condition> {
+        let condition = self.try_to_condition(interner);
+
+        if condition.is_none() {
+            log::error!("Dropping the scenario condition {self:?}, as its ID is not known");
+        }
+
+        condition
+    }
+
+    fn try_to_condition(&self, interner: &Interner) -> Option<Condition> {
+        Some(match self {
+            ConditionRaw::DeliverItems(id, amount) => {
+                Condition::DeliverItems(interner.get(id.to_string())?, *amount)
+            }
+            ConditionRaw::ReachResearch(id) => {
+                Condition::ReachResearch(interner.get(id.to_string())?)
+            }
+            ConditionRaw::TimeLimit(seconds) => Condition::TimeLimit(*seconds),
+        })
+    }
+}
+
+impl Scenario {
+    /// Advances the scenario by one tick, and checks if it has ended.
+    pub fn tick(&mut self, resource_man: &ResourceManager, player_data: &DataMap) {
+        if self.outcome.is_some() {
+            return;
+        }
+
+        self.elapsed_ticks += 1;
+
+        if self
+            .defeat
+            .iter()
+            .any(|v| v.is_met(resource_man, player_data, self.elapsed_ticks))
+        {
+            self.outcome = Some(ScenarioOutcome::Defeat);
+        } else if !self.victory.is_empty()
+            && self
+                .victory
+                .iter()
+                .all(|v| v.is_met(resource_man, player_data, self.elapsed_ticks))
+        {
+            self.outcome = Some(ScenarioOutcome::Victory);
+        }
+    }
+
+    pub fn to_raw(&self, interner: &Interner) -> ScenarioRaw {
+        ScenarioRaw {
+            victory: self
+                .victory
+                .iter()
+                .flat_map(|v| v.to_raw(interner))
+                .collect(),
+            defeat: self
+                .defeat
+                .iter()
+                .flat_map(|v| v.to_raw(interner))
+                .collect(),
+            elapsed_ticks: self.elapsed_ticks,
+            outcome: self.outcome,
+            announced: self.announced,
+        }
+    }
+}
+
+impl ScenarioRaw {
+    pub fn to_scenario(&self, interner: &Interner) -> Scenario {
+        Scenario {
+            victory: self
+                .victory
+                .iter()
+                .flat_map(|v| v.to_condition(interner))
+                .collect(),
+            defeat: self
+                .defeat
+                .iter()
+                .flat_map(|v| v.to_condition(interner))
+                .collect(),
+            elapsed_ticks: self.elapsed_ticks,
+            outcome: self.outcome,
+            announced: self.announced,
+        }
+    }
+}
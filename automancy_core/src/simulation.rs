@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use ractor::rpc::CallResult;
+use ractor::{Actor, ActorRef, RpcReplyPort};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemAmount;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::clock::Clock;
+use crate::game::{GameEvent, GameSystem, GameSystemMessage, TileSnapshot, TransactionRecords};
+use crate::tile_entity::TileEntityMsg;
+
+/// Runs the game without a window, ticking it only when asked to, so game logic can be tested.
+///
+/// The tiles are placed as in the map editor, so they don't cost anything.
+///
+/// ```no_run
+/// # use automancy_core::simulation::Simulation;
+/// # use automancy_defs::coord::TileCoord;
+/// # use automancy_resources::data::{Data, DataMap};
+/// let (resource_man, ..) = automancy_core::load_resources();
+/// let data_ids = resource_man.registry.data_ids;
+/// let mut sim = Simulation::new(resource_man).unwrap();
+///
+/// let storage = TileCoord::new(1, 0);
+/// let mut data = DataMap::default();
+/// data.insert(data_ids.item, Data::Id(sim.id("automancy:white_matter")));
+/// data.insert(data_ids.amount, Data::Amount(64));
+///
+/// sim.place(storage, "automancy:small_storage", data);
+/// sim.run_ticks(100);
+///
+/// assert_eq!(sim.item_amount(storage, "automancy:white_matter"), 0);
+/// ```
+pub struct Simulation {
+    pub resource_man: Arc<ResourceManager>,
+    runtime: Runtime,
+    game: ActorRef<GameSystemMessage>,
+    events: UnboundedReceiver<GameEvent>,
+    ticks: usize,
+}
+
+impl Simulation {
+    /// Starts a game with an empty map.
+    pub fn new(resource_man: Arc<ResourceManager>) -> anyhow::Result<Self> {
+        let runtime = Runtime::new()?;
+        let (events_sender, events) = mpsc::unbounded_channel();
+
+        let (game, _handle) = runtime.block_on(Actor::spawn(
+            None,
+            GameSystem {
+                resource_man: resource_man.clone(),
+                events: events_sender,
+                snapshot: TileSnapshot::default(),
+                clock: Clock::default(),
+            },
+            (),
+        ))?;
+
+        Ok(Self {
+            resource_man,
+            runtime,
+            game,
+            events,
+            ticks: 0,
+        })
+    }
+
+    /// Gets the ID with the given name, such as `automancy:small_storage`.
+    ///
+    /// # Panics
+    ///
+    /// If nothing with that name was loaded.
+    pub fn id(&self, name: &str) -> Id {
+        self.resource_man
+            .interner
+            .get(name)
+            .unwrap_or_else(|| panic!("{name} is not a loaded ID"))
+    }
+
+    /// Places a tile, replacing what was there. Its data is added on top of the tile's defaults.
+    pub fn place(&mut self, coord: TileCoord, tile: &str, data: DataMap) -> &mut Self {
+        let id = self.id(tile);
+
+        self.game
+            .send_message(GameSystemMessage::ImportTiles(vec![(coord, id, data)]))
+            .unwrap();
+
+        self
+    }
+
+    /// Places a tile at all the positions as one change, as the player painting with the editor brush would.
+    pub fn place_tiles(
+        &mut self,
+        coords: impl IntoIterator<Item = TileCoord>,
+        tile: &str,
+        data: DataMap,
+    ) -> &mut Self {
+        let id = self.id(tile);
+
+        self.game
+            .send_message(GameSystemMessage::PlaceTiles {
+                tiles: coords
+                    .into_iter()
+                    .map(|coord| (coord, id, data.clone()))
+                    .collect(),
+                record: true,
+            })
+            .unwrap();
+
+        self
+    }
+
+    /// Removes the tile at the position, as the player would.
+    pub fn remove(&mut self, coord: TileCoord) -> &mut Self {
+        self.game
+            .send_message(GameSystemMessage::PlaceTile {
+                coord,
+                id: self.resource_man.registry.none,
+                data: None,
+                record: true,
+                reply: None,
+            })
+            .unwrap();
+
+        self
+    }
+
+    /// Moves the tiles at the positions in the given direction, as the player moving a selection would.
+    pub fn move_tiles(&mut self, tiles: Vec<TileCoord>, direction: TileCoord) -> &mut Self {
+        self.game
+            .send_message(GameSystemMessage::MoveTiles(tiles, direction, true))
+            .unwrap();
+
+        self
+    }
+
+    /// Sets one of the values of a tile's data, or removes it if there is none, as the player configuring the tile would.
+    /// Unlike [`Simulation::set_data`], this can be undone.
+    pub fn configure(&mut self, coord: TileCoord, key: Id, value: Option<Data>) -> &mut Self {
+        self.game
+            .send_message(GameSystemMessage::ConfigureTile {
+                coord,
+                key,
+                value,
+                record: true,
+            })
+            .unwrap();
+
+        self
+    }
+
+    /// Saves the map, and waits for it to be written.
+    pub fn save(&mut self) -> &mut Self {
+        self.call(GameSystemMessage::SaveMap).unwrap();
+
+        self
+    }
+
+    /// Replaces the map with the one saved under the name, or an empty one if there is none.
+    ///
+    /// Panics if the map is there, but can't be loaded.
+    pub fn load(&mut self, map_name: &str) -> &mut Self {
+        let loaded = self
+            .call(|reply| GameSystemMessage::LoadMap(map_name.to_string(), reply))
+            .unwrap();
+        assert!(loaded, "Could not load the map {map_name}");
+
+        self
+    }
+
+    /// Sets one of the values of a tile's data.
+    pub fn set_data(&mut self, coord: TileCoord, key: Id, value: Data) -> &mut Self {
+        self.game
+            .send_message(GameSystemMessage::ForwardMsgToTile(
+                coord,
+                TileEntityMsg::SetDataValue(key, value),
+            ))
+            .unwrap();
+
+        self
+    }
+
+    /// Reverses the last change, as the player undoing it would.
+    pub fn undo(&mut self) -> &mut Self {
+        self.game.send_message(GameSystemMessage::Undo).unwrap();
+
+        self
+    }
+
+    /// Makes the last undone change again.
+    pub fn redo(&mut self) -> &mut Self {
+        self.game.send_message(GameSystemMessage::Redo).unwrap();
+
+        self
+    }
+
+    /// Ticks the game the given number of times, and waits for all the ticks to be done.
+    pub fn run_ticks(&mut self, ticks: usize) -> &mut Self {
+        for _ in 0..ticks {
+            self.game.send_message(GameSystemMessage::Tick).unwrap();
+        }
+        self.ticks += ticks;
+
+        self
+    }
+
+    /// Gets the hash of all the tiles and their data, once everything sent before has been handled.
+    /// Two simulations set up the same way should have the same hash after the same number of ticks.
+    pub fn state_hash(&self) -> u64 {
+        self.call(GameSystemMessage::GetStateHash).unwrap()
+    }
+
+    /// How many ticks have been run so far.
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// Gets the tile at the position, once everything sent before has been handled.
+    pub fn tile(&self, coord: TileCoord) -> Option<Id> {
+        self.call(|reply| GameSystemMessage::GetTile(coord, reply))
+            .unwrap()
+    }
+
+    /// Gets the data of the tile at the position, once everything sent before has been handled.
+    pub fn data(&self, coord: TileCoord) -> Option<DataMap> {
+        let data = self
+            .call(|reply| GameSystemMessage::GetDataInRange {
+                culling_range: TileBounds::new(coord, 1),
+                reply,
+            })
+            .unwrap();
+
+        data.get(&coord).cloned()
+    }
+
+    /// Gets how much of the item is in the buffer of the tile at the position.
+    pub fn item_amount(&self, coord: TileCoord, item: &str) -> ItemAmount {
+        let item = self.id(item);
+
+        match self
+            .data(coord)
+            .and_then(|mut data| data.remove(&self.resource_man.registry.data_ids.buffer))
+        {
+            Some(Data::Inventory(mut inventory)) => inventory.get(item),
+            _ => 0,
+        }
+    }
+
+    /// Sets how many transaction records the game keeps at most.
+    pub fn set_transaction_record_limit(&mut self, limit: usize) -> &mut Self {
+        self.game
+            .send_message(GameSystemMessage::SetTransactionRecordLimit(limit))
+            .unwrap();
+
+        self
+    }
+
+    /// Gets the transactions between tiles that are still being animated, once everything sent before has been handled.
+    pub fn transaction_records(&self) -> TransactionRecords {
+        self.call(GameSystemMessage::GetRecordedTransactions)
+            .unwrap()
+    }
+
+    /// Takes all the events that happened since the last time this was called.
+    pub fn events(&mut self) -> Vec<GameEvent> {
+        let mut events = vec![];
+
+        while let Ok(event) = self.events.try_recv() {
+            events.push(event);
+        }
+
+        events
+    }
+
+    fn call<T: Send + 'static>(
+        &self,
+        msg: impl FnOnce(RpcReplyPort<T>) -> GameSystemMessage,
+    ) -> anyhow::Result<T> {
+        match self
+            .runtime
+            .block_on(self.game.call(msg, None))
+            .map_err(|err| anyhow!("Could not reach the game: {err}"))?
+        {
+            CallResult::Success(result) => Ok(result),
+            _ => Err(anyhow!("The game did not reply")),
+        }
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        self.game.stop(None);
+    }
+}
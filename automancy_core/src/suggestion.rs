@@ -0,0 +1,108 @@
+//! Suggests tiles to place in an empty position, from the tiles around it.
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+/// A tile that fits in an empty position, and the data to place it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub id: Id,
+    pub data: DataMap,
+}
+
+impl Suggestion {
+    fn new(id: Id, target: Option<TileCoord>, resource_man: &ResourceManager) -> Self {
+        let mut data = DataMap::default();
+
+        if let Some(target) = target {
+            data.insert(resource_man.registry.data_ids.target, Data::Coord(target));
+        }
+
+        Self { id, data }
+    }
+}
+
+fn has_flag(resource_man: &ResourceManager, id: Id, flag: Id) -> bool {
+    resource_man
+        .registry
+        .tiles
+        .get(&id)
+        .is_some_and(|tile| tile.data.flag(flag))
+}
+
+/// Suggests tiles for an empty position, the most fitting first.
+///
+/// `neighbors` are the tiles next to the position, with their offset from it. The rules are:
+/// - a connector pointing at the position is continued in the same direction,
+/// - anything else pointing at the position is given a connector to carry on its items, or a storage to put them in,
+/// - a connector pointing away from the position is extended backwards,
+/// - a machine running a script with inputs is given a connector feeding it.
+pub fn suggest_tiles(
+    resource_man: &ResourceManager,
+    neighbors: &[(TileCoord, Id, DataMap)],
+) -> Vec<Suggestion> {
+    let data_ids = &resource_man.registry.data_ids;
+
+    let connector = resource_man
+        .registry
+        .tiles
+        .keys()
+        .filter(|id| has_flag(resource_man, **id, data_ids.connector))
+        .min()
+        .copied();
+    let mut storages = resource_man
+        .registry
+        .tiles
+        .keys()
+        .filter(|id| {
+            has_flag(resource_man, **id, data_ids.storage_takeable)
+                && has_flag(resource_man, **id, data_ids.not_targeted)
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    storages.sort();
+
+    let mut suggestions = vec![];
+    let mut push = |suggestion: Suggestion| {
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    };
+
+    for (offset, id, data) in neighbors {
+        let target = data
+            .get(&data_ids.target)
+            .cloned()
+            .and_then(Data::into_coord);
+        let is_connector = has_flag(resource_man, *id, data_ids.connector);
+
+        if target == Some(-*offset) {
+            if let Some(connector) = connector {
+                push(Suggestion::new(connector, Some(-*offset), resource_man));
+            }
+
+            if !is_connector {
+                for storage in &storages {
+                    push(Suggestion::new(*storage, None, resource_man));
+                }
+            }
+        } else if is_connector && target == Some(*offset) {
+            push(Suggestion::new(*id, Some(*offset), resource_man));
+        } else if let Some(connector) = connector {
+            let needs_inputs = data
+                .get(&data_ids.script)
+                .cloned()
+                .and_then(Data::into_id)
+                .and_then(|script| resource_man.registry.scripts.get(&script))
+                .is_some_and(|script| script.instructions.inputs.is_some());
+
+            if needs_inputs {
+                push(Suggestion::new(connector, Some(*offset), resource_man));
+            }
+        }
+    }
+
+    suggestions
+}
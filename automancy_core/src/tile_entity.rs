@@ -0,0 +1,774 @@
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hashbrown::HashMap;
+use ractor::rpc::CallResult;
+use ractor::{ActorRef, MessagingErr, RpcReplyPort};
+use rand::{thread_rng, RngCore};
+use rhai::{Dynamic, Scope, INT};
+use slotmap::{new_key_type, SlotMap};
+
+use automancy_defs::coord::{ChunkCoord, TileBounds, TileCoord};
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::{ItemAmount, ItemStack};
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::types::function::{ResultType, RhaiDataMap, TransactionResultType};
+use automancy_resources::{rhai_call_options, rhai_log_err, ResourceManager};
+
+use crate::game::{GameSystemMessage, TickUnit, TILE_TICK_BUDGET};
+use crate::tile_entity::TileEntityMsg::*;
+
+/// A tile entity is what allows the tile to take, process, and output resources.
+/// They are all owned, and ticked, by the game.
+#[derive(Debug, Clone)]
+pub struct TileEntity {
+    /// The ID of the tile entity.
+    pub id: Id,
+    /// The coordinates of the tile entity.
+    pub coord: TileCoord,
+
+    /// The data map stored by the tile.
+    data: RhaiDataMap,
+    /// The data as of the last snapshot. It is shared with the snapshots until the data changes.
+    snapshot: Arc<DataMap>,
+
+    /// Rhai scope
+    scope: Option<Scope<'static>>,
+
+    /// The moving average of how long the tile takes to tick.
+    tick_time: Duration,
+    /// Whether the tile has been reported to be going over the tick budget.
+    slow: bool,
+
+    /// Keeps track of how the tile spreads items between its outputs.
+    balancer: Balancer,
+}
+
+new_key_type! {
+    /// The key of a tile entity in [`TileEntities`].
+    pub struct TileKey;
+}
+
+/// All the tile entities in the game, packed together so going over all of them is cheap.
+#[derive(Debug, Default)]
+pub struct TileEntities {
+    entities: SlotMap<TileKey, TileEntity>,
+    keys: HashMap<TileCoord, TileKey>,
+    /// the tile entities in each chunk, for looking them up by area
+    chunks: HashMap<ChunkCoord, Vec<TileKey>>,
+}
+
+impl TileEntities {
+    pub fn get(&self, coord: &TileCoord) -> Option<&TileEntity> {
+        self.keys.get(coord).and_then(|key| self.entities.get(*key))
+    }
+
+    pub fn get_mut(&mut self, coord: &TileCoord) -> Option<&mut TileEntity> {
+        self.keys
+            .get(coord)
+            .and_then(|key| self.entities.get_mut(*key))
+    }
+
+    pub fn contains_key(&self, coord: &TileCoord) -> bool {
+        self.keys.contains_key(coord)
+    }
+
+    /// Inserts the tile entity at its coordinates, returning the one that was there before.
+    pub fn insert(&mut self, tile_entity: TileEntity) -> Option<TileEntity> {
+        let old = self.remove(&tile_entity.coord);
+
+        let coord = tile_entity.coord;
+        let key = self.entities.insert(tile_entity);
+        self.keys.insert(coord, key);
+        self.chunks.entry(coord.chunk()).or_default().push(key);
+
+        old
+    }
+
+    pub fn remove(&mut self, coord: &TileCoord) -> Option<TileEntity> {
+        let key = self.keys.remove(coord)?;
+
+        if let Some(chunk) = self.chunks.get_mut(&coord.chunk()) {
+            chunk.retain(|v| *v != key);
+
+            if chunk.is_empty() {
+                self.chunks.remove(&coord.chunk());
+            }
+        }
+
+        self.entities.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &TileEntity> {
+        self.entities.values()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut TileEntity> {
+        self.entities.values_mut()
+    }
+
+    /// Gets the tile entities within the bounds, only going through the chunks they overlap.
+    pub fn in_range(&self, bounds: TileBounds) -> impl Iterator<Item = &TileEntity> {
+        bounds
+            .chunks()
+            .flat_map(move |chunk| self.chunks.get(&chunk))
+            .flatten()
+            .flat_map(move |key| self.entities.get(*key))
+            .filter(move |tile_entity| bounds.is_in_bounds(*tile_entity.coord))
+    }
+}
+
+/// A handle to a tile entity, for talking to it from outside the game.
+#[derive(Debug, Clone)]
+pub struct TileHandle {
+    coord: TileCoord,
+    game: ActorRef<GameSystemMessage>,
+}
+
+impl TileHandle {
+    pub fn new(coord: TileCoord, game: ActorRef<GameSystemMessage>) -> Self {
+        Self { coord, game }
+    }
+
+    /// Sends a message to the tile entity, without waiting for it to be handled.
+    pub fn send_message(
+        &self,
+        message: TileEntityMsg,
+    ) -> Result<(), MessagingErr<GameSystemMessage>> {
+        self.game
+            .send_message(GameSystemMessage::ForwardMsgToTile(self.coord, message))
+    }
+
+    /// Sets a value in the tile's data, or removes it if there is none, so that the player can undo it.
+    pub fn configure(
+        &self,
+        key: Id,
+        value: Option<Data>,
+    ) -> Result<(), MessagingErr<GameSystemMessage>> {
+        self.game.send_message(GameSystemMessage::ConfigureTile {
+            coord: self.coord,
+            key,
+            value,
+            record: true,
+        })
+    }
+
+    /// Sends a message to the tile entity, and waits for its reply.
+    pub async fn call<T: Send + 'static>(
+        &self,
+        message: impl FnOnce(RpcReplyPort<T>) -> TileEntityMsg,
+        timeout: Option<Duration>,
+    ) -> Result<CallResult<T>, MessagingErr<GameSystemMessage>> {
+        let coord = self.coord;
+
+        self.game
+            .call(
+                |reply| GameSystemMessage::ForwardMsgToTile(coord, message(reply)),
+                timeout,
+            )
+            .await
+    }
+}
+
+/// Everything tile entities sent out while being ticked or handling messages, for the game to deal with.
+#[derive(Debug, Default)]
+pub struct Outgoing {
+    /// the messages for other tile entities
+    pub messages: Vec<(TileCoord, TileEntityMsg)>,
+    /// the transactions to show to the player
+    pub records: Vec<(ItemStack, TileCoord, TileCoord)>,
+    /// the tiles that went over the tick budget, with their average tick time, or back under it
+    pub slow: Vec<(TileCoord, Option<Duration>)>,
+}
+
+impl Outgoing {
+    pub fn append(&mut self, other: Outgoing) {
+        self.messages.extend(other.messages);
+        self.records.extend(other.records);
+        self.slow.extend(other.slow);
+    }
+}
+
+/// Spreads items evenly between a tile's outputs, over many ticks.
+#[derive(Debug, Clone, Default)]
+pub struct Balancer {
+    /// How many items each output got, relative to the one that got the least.
+    sent: HashMap<TileCoord, ItemAmount>,
+    /// Where to start looking from when outputs are tied, so they take turns.
+    next: usize,
+}
+
+impl Balancer {
+    /// Picks which of the outputs the items go to: the one that got the least items so far, taking turns on ties.
+    pub fn pick(&mut self, outputs: &[TileCoord], amount: ItemAmount) -> Option<TileCoord> {
+        if outputs.is_empty() {
+            return None;
+        }
+
+        let highest = outputs
+            .iter()
+            .map(|coord| self.sent.get(coord).cloned().unwrap_or(0))
+            .max()
+            .unwrap_or(0);
+
+        // nothing falls more than one stack behind, so an output that was gone for a while can't hog all the items when it's back
+        for coord in outputs {
+            let sent = self.sent.entry(*coord).or_insert(0);
+            *sent = (*sent).max(highest - amount);
+        }
+
+        let start = self.next % outputs.len();
+        self.next = self.next.wrapping_add(1);
+
+        let picked = (0..outputs.len())
+            .map(|n| outputs[(start + n) % outputs.len()])
+            .min_by_key(|coord| self.sent[coord])?;
+
+        *self.sent.get_mut(&picked).unwrap() += amount;
+
+        let lowest = self.sent.values().cloned().min().unwrap_or(0);
+        self.sent.values_mut().for_each(|sent| *sent -= lowest);
+
+        Some(picked)
+    }
+}
+
+/// The messages a tile sends while handling a batch of transactions,
+/// grouped so that every tile it sends items to only gets one message.
+#[derive(Debug, Default)]
+struct TransactionBatch {
+    /// the items going to each tile, along with where they are from
+    transactions: Vec<(TileCoord, TileCoord, Id, Vec<ItemStack>)>,
+    /// the items consumed, to be reported back to the root of the batch
+    results: Vec<ItemStack>,
+    /// the transactions to show to the player
+    records: Vec<(ItemStack, TileCoord, TileCoord)>,
+}
+
+impl TransactionBatch {
+    fn transaction(
+        &mut self,
+        coord: TileCoord,
+        source_coord: TileCoord,
+        source_id: Id,
+        stack: ItemStack,
+    ) {
+        if let Some((.., stacks)) = self
+            .transactions
+            .iter_mut()
+            .find(|v| v.0 == coord && v.1 == source_coord && v.2 == source_id)
+        {
+            stacks.push(stack);
+        } else {
+            self.transactions
+                .push((coord, source_coord, source_id, vec![stack]));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TileEntityMsg {
+    /// a batch of items sent over from the same tile, in the same tick
+    Transaction {
+        stacks: Vec<ItemStack>,
+        source_coord: TileCoord,
+        source_id: Id,
+        root_coord: TileCoord,
+        root_id: Id,
+        hidden: bool,
+    },
+    /// the items that were consumed out of a batch this tile sent
+    TransactionResult {
+        results: Vec<ItemStack>,
+    },
+    ExtractRequest {
+        requested_from_id: Id,
+        requested_from_coord: TileCoord,
+    },
+    SetData(DataMap),
+    SetDataValue(Id, Data),
+    RemoveData(Id),
+    TakeData(RpcReplyPort<DataMap>),
+    GetData(RpcReplyPort<DataMap>),
+    GetDataValue(Id, RpcReplyPort<Option<Data>>),
+    GetDataWithCoord(RpcReplyPort<(TileCoord, DataMap)>),
+}
+
+impl TileEntity {
+    pub fn new(id: Id, coord: TileCoord) -> Self {
+        Self {
+            id,
+            coord,
+
+            data: Default::default(),
+            snapshot: Default::default(),
+
+            scope: Default::default(),
+
+            tick_time: Default::default(),
+            slow: false,
+
+            balancer: Default::default(),
+        }
+    }
+
+    /// Gets a copy of the data stored by the tile.
+    pub fn data(&self) -> DataMap {
+        self.data.clone().to_data_map()
+    }
+
+    /// Gets the data stored by the tile to put in a snapshot. It is only copied again once it has changed since the last one.
+    pub fn snapshot(&mut self) -> Arc<DataMap> {
+        if !self.data.same_as(&self.snapshot) {
+            self.snapshot = Arc::new(self.data());
+        }
+
+        self.snapshot.clone()
+    }
+
+    /// Gets a single value stored by the tile, without copying all of its data.
+    pub fn get_data_value(&self, id: Id) -> Option<&Data> {
+        self.data.get(id)
+    }
+
+    /// Takes the data stored by the tile, leaving it empty.
+    pub fn take_data(&mut self) -> DataMap {
+        mem::take(&mut self.data).to_data_map()
+    }
+
+    pub fn set_data(&mut self, data: DataMap) {
+        self.data = RhaiDataMap::from_data_map(data);
+    }
+
+    /// Updates the average tick time, and reports to the game when the tile goes over or back under the tick budget.
+    fn track_tick_time(&mut self, out: &mut Outgoing, elapsed: Duration) {
+        self.tick_time = (self.tick_time * 15 + elapsed) / 16;
+
+        let slow = self.tick_time > TILE_TICK_BUDGET;
+
+        if slow != self.slow {
+            self.slow = slow;
+
+            out.slow.push((self.coord, slow.then_some(self.tick_time)));
+        }
+    }
+
+    fn handle_rhai_transaction_result(
+        &mut self,
+        batch: &mut TransactionBatch,
+        stack: ItemStack,
+        source_coord: TileCoord,
+        source_id: Id,
+        result: rhai::Array,
+    ) {
+        if result.is_empty() {
+            return;
+        }
+
+        let ty: TransactionResultType = result[0].clone().cast();
+
+        match ty {
+            TransactionResultType::PassOn => {
+                let coord: TileCoord = result[1].clone().cast();
+
+                batch.transaction(coord, self.coord, self.id, stack);
+                batch.records.push((stack, source_coord, self.coord));
+            }
+            TransactionResultType::Balance => {
+                let outputs = result[1]
+                    .clone()
+                    .try_cast::<rhai::Array>()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|v| v.try_cast::<TileCoord>())
+                    .collect::<Vec<_>>();
+
+                if let Some(coord) = self.balancer.pick(&outputs, stack.amount) {
+                    batch.transaction(coord, self.coord, self.id, stack);
+                    batch.records.push((stack, source_coord, self.coord));
+                }
+            }
+            TransactionResultType::Proxy => {
+                let coord: TileCoord = result[1].clone().cast();
+
+                batch.transaction(coord, source_coord, source_id, stack);
+                batch.records.push((stack, self.coord, coord));
+            }
+            TransactionResultType::Consume => {
+                let consumed: ItemAmount = result[1].clone().cast();
+
+                batch.results.push(ItemStack {
+                    item: stack.item,
+                    amount: consumed,
+                });
+                batch.records.push((stack, source_coord, self.coord));
+            }
+        }
+    }
+
+    /// Sends out everything the tile wanted to send while handling a batch of transactions.
+    fn flush(
+        &self,
+        out: &mut Outgoing,
+        batch: TransactionBatch,
+        root_coord: TileCoord,
+        root_id: Id,
+        hidden: bool,
+    ) {
+        for (coord, source_coord, source_id, stacks) in batch.transactions {
+            out.messages.push((
+                coord,
+                Transaction {
+                    stacks,
+                    source_coord,
+                    source_id,
+                    root_coord,
+                    root_id,
+                    hidden: false,
+                },
+            ));
+        }
+
+        if !batch.results.is_empty() {
+            out.messages.push((
+                root_coord,
+                TransactionResult {
+                    results: batch.results,
+                },
+            ));
+        }
+
+        if !hidden {
+            out.records.extend(batch.records);
+        }
+    }
+
+    fn handle_rhai_result(&self, out: &mut Outgoing, result: rhai::Array) {
+        if result.is_empty() {
+            return;
+        }
+
+        let ty: ResultType = result[0].clone().cast();
+
+        match ty {
+            ResultType::MakeTransaction => {
+                let coord: TileCoord = result[1].clone().cast();
+                let source_id: Id = result[2].clone().cast::<INT>().into();
+                let source_coord: TileCoord = result[3].clone().cast();
+
+                let stacks: Vec<ItemStack> = result[4]
+                    .clone()
+                    .try_cast()
+                    .unwrap_or_else(|| result[4].clone().into_typed_array().unwrap());
+
+                if !stacks.is_empty() {
+                    out.messages.push((
+                        coord,
+                        Transaction {
+                            stacks,
+                            source_coord,
+                            source_id,
+                            root_coord: source_coord,
+                            root_id: source_id,
+                            hidden: false,
+                        },
+                    ));
+                }
+            }
+            ResultType::MakeExtractRequest => {
+                let coord: TileCoord = result[1].clone().cast();
+                let requested_from_id: Id = result[2].clone().cast::<INT>().into();
+                let requested_from_coord: TileCoord = result[3].clone().cast();
+
+                out.messages.push((
+                    coord,
+                    ExtractRequest {
+                        requested_from_id,
+                        requested_from_coord,
+                    },
+                ));
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn transaction(
+        &mut self,
+        resource_man: &ResourceManager,
+        batch: &mut TransactionBatch,
+        stack: ItemStack,
+        source_coord: TileCoord,
+        source_id: Id,
+        root_coord: TileCoord,
+        root_id: Id,
+    ) {
+        let tile = resource_man.registry.tiles.get(&self.id).unwrap();
+
+        // neighbors can only send items in through the sides the player allowed, if they picked any
+        if let Some(Data::VecCoord(sides)) =
+            self.data.get(resource_man.registry.data_ids.input_sides)
+        {
+            if self.coord.neighbors().contains(&source_coord)
+                && !sides.contains(&(source_coord - self.coord))
+            {
+                return;
+            }
+        }
+
+        if let Some((ast, default_scope, function_id)) = tile
+            .function
+            .as_ref()
+            .and_then(|v| resource_man.functions.get(v))
+        {
+            let scope = self
+                .scope
+                .get_or_insert_with(|| default_scope.clone_visible());
+
+            let data = mem::take(&mut self.data);
+            let mut rhai_state = Dynamic::from(data);
+
+            let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+                rhai_call_options(&mut rhai_state),
+                scope,
+                ast,
+                "handle_transaction",
+                (rhai::Map::from([
+                    ("coord".into(), Dynamic::from(self.coord)),
+                    ("id".into(), Dynamic::from_int(self.id.into())),
+                    ("source_coord".into(), Dynamic::from(source_coord)),
+                    ("source_id".into(), Dynamic::from_int(source_id.into())),
+                    ("root_coord".into(), Dynamic::from(root_coord)),
+                    ("root_id".into(), Dynamic::from_int(root_id.into())),
+                    ("random".into(), Dynamic::from_int(random())),
+                    ("stack".into(), Dynamic::from(stack)),
+                ]),),
+            );
+
+            self.data = rhai_state.take().cast::<RhaiDataMap>();
+
+            match result {
+                Ok(result) => {
+                    if let Some(result) = result.try_cast::<rhai::Array>() {
+                        self.handle_rhai_transaction_result(
+                            batch,
+                            stack,
+                            source_coord,
+                            source_id,
+                            result,
+                        );
+                    }
+                }
+                Err(err) => rhai_log_err(function_id, &err),
+            }
+        }
+    }
+
+    /// Runs the tile's tick function once.
+    pub fn tick(
+        &mut self,
+        resource_man: &ResourceManager,
+        _tick_count: TickUnit,
+        out: &mut Outgoing,
+    ) {
+        let tile = resource_man.registry.tiles.get(&self.id).unwrap();
+
+        if let Some((ast, default_scope, function_id)) = tile
+            .function
+            .as_ref()
+            .and_then(|v| resource_man.functions.get(v))
+        {
+            let start = Instant::now();
+
+            let scope = self
+                .scope
+                .get_or_insert_with(|| default_scope.clone_visible());
+
+            let data = mem::take(&mut self.data);
+            let mut rhai_state = Dynamic::from(data);
+
+            let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+                rhai_call_options(&mut rhai_state),
+                scope,
+                ast,
+                "handle_tick",
+                (rhai::Map::from([
+                    ("coord".into(), Dynamic::from(self.coord)),
+                    ("id".into(), Dynamic::from_int(self.id.into())),
+                    ("random".into(), Dynamic::from_int(random())),
+                ]),),
+            );
+
+            self.data = rhai_state.take().cast::<RhaiDataMap>();
+
+            match result {
+                Ok(result) => {
+                    if let Some(result) = result.try_cast::<rhai::Array>() {
+                        self.handle_rhai_result(out, result);
+                    }
+                }
+                Err(err) => {
+                    rhai_log_err(function_id, &err);
+                }
+            }
+
+            self.track_tick_time(out, start.elapsed());
+        }
+    }
+
+    /// Handles a message sent to the tile, by another tile or from outside the game.
+    pub fn handle(
+        &mut self,
+        resource_man: &ResourceManager,
+        message: TileEntityMsg,
+        out: &mut Outgoing,
+    ) {
+        match message {
+            Transaction {
+                stacks,
+                source_coord,
+                source_id,
+                root_coord,
+                root_id,
+                hidden,
+            } => {
+                let mut batch = TransactionBatch::default();
+
+                for stack in stacks {
+                    self.transaction(
+                        resource_man,
+                        &mut batch,
+                        stack,
+                        source_coord,
+                        source_id,
+                        root_coord,
+                        root_id,
+                    );
+                }
+
+                self.flush(out, batch, root_coord, root_id, hidden);
+            }
+            TransactionResult { results } => {
+                let tile = resource_man.registry.tiles.get(&self.id).unwrap();
+
+                if let Some((ast, default_scope, function_id)) = tile
+                    .function
+                    .as_ref()
+                    .and_then(|v| resource_man.functions.get(v))
+                {
+                    let scope = self
+                        .scope
+                        .get_or_insert_with(|| default_scope.clone_visible());
+
+                    let data = mem::take(&mut self.data);
+                    let mut rhai_state = Dynamic::from(data);
+
+                    for result in results {
+                        let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+                            rhai_call_options(&mut rhai_state),
+                            scope,
+                            ast,
+                            "handle_transaction_result",
+                            (rhai::Map::from([
+                                ("coord".into(), Dynamic::from(self.coord)),
+                                ("id".into(), Dynamic::from_int(self.id.into())),
+                                ("random".into(), Dynamic::from_int(random())),
+                                ("transferred".into(), Dynamic::from(result)),
+                            ]),),
+                        );
+
+                        if let Err(err) = result {
+                            rhai_log_err(function_id, &err);
+                        }
+                    }
+
+                    self.data = rhai_state.take().cast::<RhaiDataMap>();
+                }
+            }
+            SetData(data) => {
+                self.set_data(data);
+            }
+            SetDataValue(key, value) => {
+                self.data.set(key, value);
+            }
+            // whoever asked may have given up on waiting by now, which is fine
+            TakeData(reply) => {
+                let _ = reply.send(self.take_data());
+            }
+            GetData(reply) => {
+                let _ = reply.send(self.data());
+            }
+            GetDataValue(key, reply) => {
+                let _ = reply.send(self.data.get(key).cloned());
+            }
+            GetDataWithCoord(reply) => {
+                let _ = reply.send((self.coord, self.data()));
+            }
+            RemoveData(key) => {
+                self.data.remove(key);
+            }
+            ExtractRequest {
+                requested_from_id,
+                requested_from_coord,
+            } => {
+                let tile = resource_man.registry.tiles.get(&self.id).unwrap();
+
+                if let Some((ast, default_scope, function_id)) = tile
+                    .function
+                    .as_ref()
+                    .and_then(|v| resource_man.functions.get(v))
+                {
+                    let scope = self
+                        .scope
+                        .get_or_insert_with(|| default_scope.clone_visible());
+
+                    let data = mem::take(&mut self.data);
+                    let mut rhai_state = Dynamic::from(data);
+
+                    let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+                        rhai_call_options(&mut rhai_state),
+                        scope,
+                        ast,
+                        "handle_extract_request",
+                        (rhai::Map::from([
+                            ("coord".into(), Dynamic::from(self.coord)),
+                            ("id".into(), Dynamic::from_int(self.id.into())),
+                            ("random".into(), Dynamic::from_int(random())),
+                            (
+                                "requested_from_coord".into(),
+                                Dynamic::from(requested_from_coord),
+                            ),
+                            (
+                                "requested_from_id".into(),
+                                Dynamic::from_int(requested_from_id.into()),
+                            ),
+                        ]),),
+                    );
+
+                    self.data = rhai_state.take().cast::<RhaiDataMap>();
+
+                    match result {
+                        Ok(result) => {
+                            if let Some(result) = result.try_cast::<rhai::Array>() {
+                                self.handle_rhai_result(out, result);
+                            }
+                        }
+                        Err(err) => rhai_log_err(function_id, &err),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn random() -> i32 {
+    thread_rng().next_u32() as i32
+}
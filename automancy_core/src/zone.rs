@@ -0,0 +1,126 @@
+//! Zones are named, colored regions the player paints onto the map, to keep a large base organized.
+//! A position can only be in one zone at a time.
+
+use std::collections::BTreeMap;
+
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+
+/// The colors given to new zones, in turn.
+pub const ZONE_COLORS: [[u8; 3]; 8] = [
+    [0x4c, 0xaf, 0x50],
+    [0x21, 0x96, 0xf3],
+    [0xff, 0x98, 0x00],
+    [0x9c, 0x27, 0xb0],
+    [0xf4, 0x43, 0x36],
+    [0x00, 0xbc, 0xd4],
+    [0xff, 0xeb, 0x3b],
+    [0x79, 0x55, 0x48],
+];
+
+/// A named region of the map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    /// The color of the zone's overlay, in sRGB.
+    pub color: [u8; 3],
+    /// The positions painted into the zone.
+    #[serde(default)]
+    pub tiles: HashSet<TileCoord>,
+    /// Whether to warn the player when machines in the zone stall.
+    #[serde(default)]
+    pub alerts: bool,
+}
+
+impl Zone {
+    /// Checks if the position is in the zone.
+    pub fn contains(&self, coord: TileCoord) -> bool {
+        self.tiles.contains(&coord)
+    }
+
+    /// Keeps only what is at positions in the zone.
+    pub fn filter<'a, T: 'a>(
+        &'a self,
+        tiles: impl IntoIterator<Item = (TileCoord, T)> + 'a,
+    ) -> impl Iterator<Item = (TileCoord, T)> + 'a {
+        tiles.into_iter().filter(|(coord, _)| self.contains(*coord))
+    }
+}
+
+/// All the zones of a map, in the order they were made.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Zones(pub Vec<Zone>);
+
+impl Zones {
+    /// Makes a new empty zone, and returns its index.
+    pub fn add(&mut self, name: String) -> usize {
+        let color = ZONE_COLORS[self.0.len() % ZONE_COLORS.len()];
+
+        self.0.push(Zone {
+            name,
+            color,
+            tiles: Default::default(),
+            alerts: false,
+        });
+
+        self.0.len() - 1
+    }
+
+    /// Removes the zone at the index, if there is one.
+    pub fn remove(&mut self, index: usize) -> Option<Zone> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Zone> {
+        self.0.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Zone> {
+        self.0.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Zone> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the index of the zone the position is in.
+    pub fn zone_at(&self, coord: TileCoord) -> Option<usize> {
+        self.0.iter().position(|zone| zone.contains(coord))
+    }
+
+    /// Paints the position into the zone at the index, taking it out of any other zone.
+    pub fn paint(&mut self, index: usize, coord: TileCoord) {
+        if index >= self.0.len() {
+            return;
+        }
+
+        self.erase(coord);
+        self.0[index].tiles.insert(coord);
+    }
+
+    /// Takes the position out of whichever zone it is in.
+    pub fn erase(&mut self, coord: TileCoord) {
+        for zone in &mut self.0 {
+            zone.tiles.remove(&coord);
+        }
+    }
+}
+
+/// What the tiles in a zone, or the whole map, are up to.
+#[derive(Debug, Clone, Default)]
+pub struct ZoneStats {
+    /// How many of each tile there are.
+    pub tiles: BTreeMap<Id, usize>,
+    /// The machines with a script set that haven't worked for a while.
+    pub stalled: Vec<TileCoord>,
+    /// The tiles that have crashed and been restarted, or stopped.
+    pub crashed: Vec<TileCoord>,
+}
@@ -1,12 +1,123 @@
-use egui::Rgba;
-
 use crate::math::Float;
 
+/// A color in linear space, with its color channels premultiplied by its alpha.
+/// It converts into egui's colors with the `window` feature.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rgba([Float; 4]);
+
+impl Rgba {
+    pub const fn from_rgba_premultiplied(r: Float, g: Float, b: Float, a: Float) -> Self {
+        Self([r, g, b, a])
+    }
+
+    pub fn from_rgba_unmultiplied(r: Float, g: Float, b: Float, a: Float) -> Self {
+        Self([r * a, g * a, b * a, a])
+    }
+
+    /// Makes a color from the sRGB color channels, already premultiplied, and a linear alpha.
+    pub fn from_srgba_premultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self([
+            linear_from_gamma(r),
+            linear_from_gamma(g),
+            linear_from_gamma(b),
+            a as Float / 255.0,
+        ])
+    }
+
+    /// Makes a color from the sRGB color channels and a linear alpha.
+    pub fn from_srgba_unmultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self::from_rgba_unmultiplied(
+            linear_from_gamma(r),
+            linear_from_gamma(g),
+            linear_from_gamma(b),
+            a as Float / 255.0,
+        )
+    }
+
+    pub fn r(&self) -> Float {
+        self.0[0]
+    }
+
+    pub fn g(&self) -> Float {
+        self.0[1]
+    }
+
+    pub fn b(&self) -> Float {
+        self.0[2]
+    }
+
+    pub fn a(&self) -> Float {
+        self.0[3]
+    }
+
+    pub fn to_array(&self) -> [Float; 4] {
+        self.0
+    }
+
+    pub fn to_rgba_unmultiplied(&self) -> [Float; 4] {
+        let a = self.a();
+
+        if a == 0.0 {
+            self.0
+        } else {
+            [self.r() / a, self.g() / a, self.b() / a, a]
+        }
+    }
+
+    /// Gets the sRGB color channels and the linear alpha, as [`Rgba::from_srgba_unmultiplied`] takes them.
+    pub fn to_srgba_unmultiplied(&self) -> [u8; 4] {
+        let [r, g, b, a] = self.to_rgba_unmultiplied();
+
+        [
+            gamma_from_linear(r),
+            gamma_from_linear(g),
+            gamma_from_linear(b),
+            (a.clamp(0.0, 1.0) * 255.0 + 0.5) as u8,
+        ]
+    }
+}
+
+/// Converts an sRGB color channel to linear space, the same way egui does.
+fn linear_from_gamma(s: u8) -> Float {
+    if s <= 10 {
+        s as Float / 3294.6
+    } else {
+        ((s as Float + 14.025) / 269.025).powf(2.4)
+    }
+}
+
+/// Converts a linear color channel to sRGB, the same way egui does.
+fn gamma_from_linear(l: Float) -> u8 {
+    if l <= 0.0 {
+        0
+    } else if l <= 0.0031308 {
+        (3294.6 * l + 0.5) as u8
+    } else if l <= 1.0 {
+        (269.025 * l.powf(1.0 / 2.4) - 14.025 + 0.5) as u8
+    } else {
+        255
+    }
+}
+
+#[cfg(feature = "window")]
+impl From<Rgba> for egui::Rgba {
+    fn from(v: Rgba) -> Self {
+        egui::Rgba::from_rgba_premultiplied(v.r(), v.g(), v.b(), v.a())
+    }
+}
+
+#[cfg(feature = "window")]
+impl From<Rgba> for egui::Color32 {
+    fn from(v: Rgba) -> Self {
+        egui::Rgba::from(v).into()
+    }
+}
+
 macro_rules! hex_color {
     ($s:literal) => {{
         let array = color_hex::color_from_hex!($s);
         if array.len() == 3 {
-            egui::Rgba::from_rgba_premultiplied(
+            Rgba::from_rgba_premultiplied(
                 array[0] as f32 / 255.0,
                 array[1] as f32 / 255.0,
                 array[2] as f32 / 255.0,
@@ -14,7 +125,7 @@ macro_rules! hex_color {
             )
         } else {
             #[allow(unconditional_panic)]
-            egui::Rgba::from_rgba_premultiplied(
+            Rgba::from_rgba_premultiplied(
                 array[0] as f32 / 255.0,
                 array[1] as f32 / 255.0,
                 array[2] as f32 / 255.0,
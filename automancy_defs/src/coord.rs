@@ -1,9 +1,12 @@
 use std::cmp::{max, min};
+use std::collections::{HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Deref, Div, Mul, Neg, Sub};
 
+use hexx::algorithms::a_star;
 use hexx::{EdgeDirection, Hex, HexBounds};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// The type of number that will be stored in a tile's coordinates. Should probably be a signed integer.
 pub type TileUnit = i32;
@@ -11,6 +14,13 @@ pub type TileUnit = i32;
 /// The basic Hex Grid coordinate.
 pub type TileHex = Hex;
 
+/// The furthest a tile can be from the origin.
+/// Past this, world positions lose too much float precision for rendering and picking to work.
+pub const MAX_TILE_DISTANCE: u32 = 1 << 14;
+
+/// How many tiles wide and tall a chunk is.
+pub const CHUNK_SIZE: TileUnit = 16;
+
 /// Represents a tile's position.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct TileCoord(TileHex);
@@ -45,7 +55,22 @@ impl TileCoord {
     }
 }
 
+/// The position of a chunk, which groups together [`CHUNK_SIZE`] by [`CHUNK_SIZE`] tiles so they can be looked up by area.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ChunkCoord {
+    pub q: TileUnit,
+    pub r: TileUnit,
+}
+
 impl TileCoord {
+    /// Gets the chunk the tile is in.
+    pub fn chunk(self) -> ChunkCoord {
+        ChunkCoord {
+            q: self.x.div_euclid(CHUNK_SIZE),
+            r: self.y.div_euclid(CHUNK_SIZE),
+        }
+    }
+
     /// Creates a minimal string of the coordinate.
     pub fn to_minimal_string(self) -> String {
         format!("{},{}", self.x, self.y)
@@ -58,6 +83,11 @@ impl TileCoord {
     pub const LEFT: Self = Self(EdgeDirection::POINTY_LEFT.into_hex());
     pub const TOP_LEFT: Self = Self(EdgeDirection::POINTY_TOP_LEFT.into_hex());
 
+    /// Checks if the coordinate is within the playable world.
+    pub fn is_in_world(self) -> bool {
+        self.unsigned_distance_to(Hex::ZERO) <= MAX_TILE_DISTANCE
+    }
+
     /// Creates a list of the neighbors
     pub fn neighbors(self) -> [Self; 6] {
         [
@@ -69,6 +99,32 @@ impl TileCoord {
             self + Self::TOP_LEFT,
         ]
     }
+
+    /// Finds the shortest path from this tile to the destination, both included, only going through the tiles that are free.
+    /// The search never strays more than `detour` tiles further away than the destination is, so it gives up on walled off destinations.
+    pub fn route_to(
+        self,
+        destination: Self,
+        detour: u32,
+        is_free: impl Fn(Self) -> bool,
+    ) -> Option<Vec<Self>> {
+        let max_distance = self.unsigned_distance_to(destination.0) + detour;
+
+        a_star(self.0, destination.0, |_, to| {
+            let to = Self(to);
+
+            if to == destination {
+                return Some(1);
+            }
+
+            (to.is_in_world()
+                && self.unsigned_distance_to(to.0) <= max_distance
+                && destination.unsigned_distance_to(to.0) <= max_distance
+                && is_free(to))
+            .then_some(1)
+        })
+        .map(|path| path.into_iter().map(Self).collect())
+    }
 }
 
 impl Display for TileCoord {
@@ -119,6 +175,235 @@ impl Neg for TileCoord {
     }
 }
 
+/// Shapes and distances on the hex grid.
+impl TileCoord {
+    /// Gets how many steps apart two tiles are.
+    pub fn distance(self, other: Self) -> u32 {
+        self.unsigned_distance_to(other.0)
+    }
+
+    /// Rotates the coordinate around the origin by 60 degrees `times` times, clockwise, the same way [`TileCoord::neighbors`] goes around.
+    /// Negative `times` rotate counter-clockwise.
+    pub fn rotate(self, times: i32) -> Self {
+        let mut v = self;
+
+        for _ in 0..times.rem_euclid(6) {
+            v = TileCoord::new(-v.y, v.x + v.y);
+        }
+
+        v
+    }
+
+    /// Rotates the coordinate around `center`, see [`TileCoord::rotate`].
+    pub fn rotate_around(self, center: Self, times: i32) -> Self {
+        center + (self - center).rotate(times)
+    }
+
+    /// Gets the tiles exactly `radius` steps away, going clockwise from the one to the left of the center.
+    /// A radius of 0 is just the center.
+    pub fn ring(self, radius: u32) -> impl Iterator<Item = Self> {
+        let radius = radius as TileUnit;
+        let mut coord = self + TileCoord::LEFT * radius;
+
+        // walking along each side of the ring, starting with the top left one
+        TileCoord::ZERO
+            .neighbors()
+            .into_iter()
+            .flat_map(move |step| (0..radius.max(1)).map(move |_| step))
+            .take(max(radius * 6, 1) as usize)
+            .map(move |step| {
+                let v = coord;
+                coord = coord + step;
+
+                v
+            })
+    }
+
+    /// Gets the tiles up to `radius` steps away, starting with the center and going outwards ring by ring.
+    pub fn spiral(self, radius: u32) -> impl Iterator<Item = Self> {
+        (0..=radius).flat_map(move |radius| self.ring(radius))
+    }
+
+    /// Gets the tiles up to `radius` steps away, in no particular order. Same as iterating over a [`TileBounds`].
+    pub fn hexagon(self, radius: u32) -> ExactSizeCoordIterator {
+        TileBounds::new(self, radius).into_iter()
+    }
+
+    /// Gets the tiles on the straightest line from this tile to `other`, both included.
+    pub fn line_to(self, other: Self) -> Vec<Self> {
+        let steps = self.distance(other);
+
+        if steps == 0 {
+            return vec![self];
+        }
+
+        // nudged a little, so points exactly between two tiles always round the same way
+        let (ax, ay) = (self.x as f64 + 1e-6, self.y as f64 + 1e-6);
+        let (bx, by) = (other.x as f64 + 1e-6, other.y as f64 + 1e-6);
+
+        (0..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64;
+
+                round_coord(ax + (bx - ax) * t, ay + (by - ay) * t)
+            })
+            .collect()
+    }
+
+    /// Gets every tile reachable from this one by stepping between neighbors that pass `include`, starting with this one,
+    /// nearest first. Stops once `limit` tiles are found, so filling an open area doesn't go on forever.
+    pub fn flood_fill(self, limit: usize, mut include: impl FnMut(Self) -> bool) -> Vec<Self> {
+        let mut found = vec![];
+
+        if limit == 0 || !include(self) {
+            return found;
+        }
+
+        let mut seen = HashSet::from([self]);
+        let mut queue = VecDeque::from([self]);
+
+        while let Some(coord) = queue.pop_front() {
+            found.push(coord);
+
+            if found.len() >= limit {
+                break;
+            }
+
+            for neighbor in coord.neighbors() {
+                if seen.insert(neighbor) && include(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Rounds a fractional axial position to the tile it's in.
+fn round_coord(x: f64, y: f64) -> TileCoord {
+    let z = -x - y;
+
+    let (mut rx, mut ry, rz) = (x.round(), y.round(), z.round());
+    let (dx, dy, dz) = ((rx - x).abs(), (ry - y).abs(), (rz - z).abs());
+
+    if dx > dy && dx > dz {
+        rx = -ry - rz;
+    } else if dy > dz {
+        ry = -rx - rz;
+    }
+
+    TileCoord::new(rx as TileUnit, ry as TileUnit)
+}
+
+/// A list of coordinates packed into bytes, for saving lots of them in little space.
+///
+/// Each coordinate is stored as how far it is from the one before, as two zigzag varints,
+/// so a run of tiles next to each other takes two bytes per tile. Sorting the coordinates first keeps the runs long.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackedCoords(Vec<u8>);
+
+fn write_varint(bytes: &mut Vec<u8>, v: TileUnit) {
+    // zigzag, so small negative numbers are small too
+    let mut v = ((v << 1) ^ (v >> (TileUnit::BITS - 1))) as u32;
+
+    while v >= 0x80 {
+        bytes.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    bytes.push(v as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<TileUnit> {
+    let mut v = 0u32;
+
+    for shift in (0..32).step_by(7) {
+        let byte = bytes.next()?;
+        v |= ((byte & 0x7f) as u32) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(((v >> 1) as TileUnit) ^ -((v & 1) as TileUnit));
+        }
+    }
+
+    None
+}
+
+impl PackedCoords {
+    pub fn pack(coords: impl IntoIterator<Item = TileCoord>) -> Self {
+        let mut bytes = vec![];
+        let mut last = TileCoord::ZERO;
+
+        for coord in coords {
+            write_varint(&mut bytes, coord.x.wrapping_sub(last.x));
+            write_varint(&mut bytes, coord.y.wrapping_sub(last.y));
+            last = coord;
+        }
+
+        Self(bytes)
+    }
+
+    /// Unpacks the coordinates, or returns [`None`] if the bytes were cut off.
+    pub fn unpack(&self) -> Option<Vec<TileCoord>> {
+        let mut bytes = self.0.iter().copied().peekable();
+        let mut coords = vec![];
+        let mut last = TileCoord::ZERO;
+
+        while bytes.peek().is_some() {
+            let x = read_varint(&mut bytes)?;
+            let y = read_varint(&mut bytes)?;
+
+            last = TileCoord::new(last.x.wrapping_add(x), last.y.wrapping_add(y));
+            coords.push(last);
+        }
+
+        Some(coords)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for PackedCoords {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackedCoords {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = PackedCoords;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                f.write_str("packed coordinates")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(PackedCoords(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(PackedCoords(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = vec![];
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+
+                Ok(PackedCoords(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
 /// Represents a tile's position.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TileBounds(HexBounds);
@@ -140,6 +425,15 @@ impl TileBounds {
         let radius = center.unsigned_distance_to(*max) / 2;
         Self::new(center, radius)
     }
+
+    /// Gets all the chunks that could have tiles within the bounds.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkCoord> {
+        let radius = self.radius as TileUnit;
+        let min = TileCoord::new(self.center.x - radius, self.center.y - radius).chunk();
+        let max = TileCoord::new(self.center.x + radius, self.center.y + radius).chunk();
+
+        (min.q..=max.q).flat_map(move |q| (min.r..=max.r).map(move |r| ChunkCoord { q, r }))
+    }
 }
 
 impl FromIterator<TileCoord> for TileBounds {
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use egui::epaint::Shadow;
 use egui::output::OpenUrl;
 use egui::style::{Interaction, Spacing, WidgetVisuals, Widgets};
@@ -13,55 +15,78 @@ pub struct Gui {
     pub context: Context,
     pub state: State,
     pub fonts: FontDefinitions,
+    /// The font currently in use, if one has been set.
+    pub font: Option<String>,
+    /// How many times the font atlas has been rebuilt because the font changed.
+    pub font_rebuilds: usize,
 }
 
+/// Puts the font first in line, after the symbols font.
+///
+/// This rebuilds the font atlas, throwing away every glyph in it, so nothing is done if the font is already in use.
 pub fn set_font(symbols_font: &str, font: &str, gui: &mut Gui) {
-    gui.fonts
-        .families
-        .get_mut(&FontFamily::Proportional)
-        .unwrap()
-        .insert(0, font.to_string());
-    gui.fonts
-        .families
-        .get_mut(&FontFamily::Monospace)
-        .unwrap()
-        .insert(0, font.to_string());
-
-    gui.fonts
-        .families
-        .get_mut(&FontFamily::Proportional)
-        .unwrap()
-        .insert(0, symbols_font.to_string());
-    gui.fonts
-        .families
-        .get_mut(&FontFamily::Monospace)
-        .unwrap()
-        .insert(0, symbols_font.to_string());
+    if gui.font.as_deref() == Some(font) {
+        return;
+    }
+
+    let previous = gui.font.replace(font.to_string());
+
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        let fonts = gui.fonts.families.get_mut(&family).unwrap();
+
+        fonts.retain(|name| name != symbols_font && Some(name) != previous.as_ref());
+        fonts.insert(0, font.to_string());
+        fonts.insert(0, symbols_font.to_string());
+    }
 
     gui.context.set_fonts(gui.fonts.clone());
+    gui.font_rebuilds += 1;
+}
+
+/// The size of body text, in points, that the other text styles are sized against.
+pub const DEFAULT_TEXT_SIZE: f32 = 13.0;
+
+/// The text styles, sized against the size of body text.
+fn text_styles(size: f32) -> BTreeMap<TextStyle, FontId> {
+    let scaled = |v: f32| (v * size / DEFAULT_TEXT_SIZE).round();
+
+    [
+        (
+            TextStyle::Small,
+            FontId::new(scaled(9.0), FontFamily::Proportional),
+        ),
+        (TextStyle::Body, FontId::new(size, FontFamily::Proportional)),
+        (
+            TextStyle::Button,
+            FontId::new(size, FontFamily::Proportional),
+        ),
+        (
+            TextStyle::Heading,
+            FontId::new(scaled(19.0), FontFamily::Proportional),
+        ),
+        (
+            TextStyle::Monospace,
+            FontId::new(size, FontFamily::Monospace),
+        ),
+    ]
+    .into()
+}
+
+/// Sets how text is drawn: the size of body text in points, and whether to snap each glyph to whole pixels.
+///
+/// Snapping keeps text sharp when the screen's scale isn't a whole number, where glyphs would otherwise
+/// land between pixels and be smeared across them.
+/// The glyphs are rasterized without hinting, as the rasterizer egui uses has none.
+pub fn set_text_rendering(context: &Context, size: f32, snap_to_pixels: bool) {
+    context.style_mut(|style| style.text_styles = text_styles(size));
+    context.tessellation_options_mut(|o| o.round_text_to_pixels = snap_to_pixels);
 }
 
 /// Initialize the GUI style.
 fn init_styles(context: &Context) {
     let light = Visuals::light();
     context.set_style(Style {
-        text_styles: [
-            (TextStyle::Small, FontId::new(9.0, FontFamily::Proportional)),
-            (TextStyle::Body, FontId::new(13.0, FontFamily::Proportional)),
-            (
-                TextStyle::Button,
-                FontId::new(13.0, FontFamily::Proportional),
-            ),
-            (
-                TextStyle::Heading,
-                FontId::new(19.0, FontFamily::Proportional),
-            ),
-            (
-                TextStyle::Monospace,
-                FontId::new(13.0, FontFamily::Monospace),
-            ),
-        ]
-        .into(),
+        text_styles: text_styles(DEFAULT_TEXT_SIZE),
         visuals: Visuals {
             window_fill: Color32::from_white_alpha(190),
             panel_fill: Color32::from_white_alpha(190),
@@ -165,6 +190,8 @@ pub fn init_gui(renderer: egui_wgpu::Renderer, window: &Window) -> Gui {
             None,
         ),
         fonts: FontDefinitions::default(),
+        font: None,
+        font_rebuilds: 0,
     }
 }
 
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 use std::hash::Hash;
 
@@ -9,6 +10,10 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use string_interner::backend::StringBackend;
 use string_interner::{StringInterner, Symbol};
 
+/// Interns the names of IDs. The number an ID gets depends on the order the names were interned in,
+/// so only the names are stable between runs of the game, and the numbers should never be saved or sent on their own.
+/// Resources are loaded in a fixed order, so the same set of mods always gives the same numbers,
+/// and saves write the names, so they still load with another set of mods.
 pub type Interner = StringInterner<StringBackend<Id>>;
 
 #[repr(C)]
@@ -40,6 +45,19 @@ impl Symbol for Id {
     }
 }
 
+/// Counts the interned IDs in each namespace. Names without a namespace are counted under an empty one.
+pub fn namespace_counts(interner: &Interner) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+
+    for (_, name) in interner {
+        let namespace = name.split_once(':').map(|(v, _)| v).unwrap_or_default();
+
+        *counts.entry(namespace).or_default() += 1;
+    }
+
+    counts
+}
+
 pub fn id(a: &str, b: &str) -> IdRaw {
     IdRaw(SharedStr::from_ref(a), SharedStr::from_ref(b))
 }
@@ -64,6 +82,11 @@ impl IdRaw {
         interner.get_or_intern(self.to_string())
     }
 
+    /// Gets the namespace the ID is in, which is usually the mod it comes from.
+    pub fn namespace(&self) -> &str {
+        &self.0
+    }
+
     pub fn parse(s: &str) -> IdRaw {
         s.split_once(':')
             .map(|(a, b)| id(a, b))
@@ -10,8 +10,10 @@ pub use string_interner;
 
 pub mod colors;
 pub mod coord;
+#[cfg(feature = "window")]
 pub mod gui;
 pub mod id;
 pub mod math;
 pub mod rendering;
+#[cfg(feature = "window")]
 pub mod window;
@@ -3,7 +3,7 @@
 use std::f64::consts::PI;
 
 use glam::{dvec2, dvec3, dvec4, vec2};
-use hexx::{HexLayout, HexOrientation};
+use hexx::{Hex, HexLayout, HexOrientation};
 
 use crate::coord::{TileBounds, TileCoord};
 
@@ -91,6 +91,14 @@ pub fn matrix(pos: DVec3, aspect: Double) -> DMatrix4 {
     projection * angle * view
 }
 
+/// Converts a tile's position into world coordinates, without losing precision far from the origin.
+pub fn hex_to_world_pos_double(coord: TileCoord) -> DVec2 {
+    let q = HEX_GRID_LAYOUT.hex_to_world_pos(Hex::new(1, 0)).as_dvec2();
+    let r = HEX_GRID_LAYOUT.hex_to_world_pos(Hex::new(0, 1)).as_dvec2();
+
+    q * coord.x as Double + r * coord.y as Double
+}
+
 pub fn lerp_coords_to_pixel(a: TileCoord, b: TileCoord, t: Float) -> Vec2 {
     let a = Vec2::new(a.x as Float, a.y as Float);
     let b = Vec2::new(b.x as Float, b.y as Float);
@@ -1,13 +1,15 @@
+#[cfg(feature = "window")]
 use std::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
-use egui::NumExt;
+#[cfg(feature = "window")]
 use egui_wgpu::wgpu::{
     vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode,
 };
 use glam::{vec3, vec4};
 use gltf::animation::Interpolation;
 use gltf::scene::Transform;
+use hashbrown::HashMap;
 
 use crate::math::{direction_to_angle, Float, Matrix3, Matrix4, Vec2, Vec3, Vec4};
 
@@ -21,7 +23,7 @@ pub fn make_line(a: Vec2, b: Vec2) -> Matrix4 {
 
     Matrix4::from_translation(vec3(mid.x, mid.y, 0.1))
         * Matrix4::from_rotation_z(theta)
-        * Matrix4::from_scale(vec3(d.at_least(0.001), 0.1, LINE_DEPTH))
+        * Matrix4::from_scale(vec3(d.max(0.001), 0.1, LINE_DEPTH))
 }
 
 // vertex
@@ -40,6 +42,7 @@ pub struct Vertex {
 }
 
 impl Vertex {
+    #[cfg(feature = "window")]
     pub fn desc() -> VertexBufferLayout<'static> {
         static ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
             0 => Float32x3,
@@ -178,10 +181,50 @@ impl InstanceData {
 #[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq, Zeroable, Pod)]
 pub struct MatrixData {
     model_matrix: RawMat4,
-    world_matrix: RawMat4,
     normal_matrix: [[Float; 4]; 3], // memory alignment issue, padded to 16 bytes
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialOrd, PartialEq, Zeroable, Pod)]
+pub struct WorldMatrixData {
+    world_matrix: RawMat4,
+}
+
+/// The matrices of a batch of instances, with identical ones stored only once.
+///
+/// Most instances share the same world matrix (the camera's), so those are kept apart from the model matrices.
+#[derive(Debug, Default)]
+pub struct MatrixBuffers {
+    pub matrix_data: Vec<MatrixData>,
+    pub world_matrix_data: Vec<WorldMatrixData>,
+    matrix_indices: HashMap<[u32; 28], u32>,
+    world_matrix_indices: HashMap<[u32; 16], u32>,
+}
+
+impl MatrixBuffers {
+    fn push_matrix_data(&mut self, data: MatrixData) -> u32 {
+        *self
+            .matrix_indices
+            .entry(bytemuck::cast(data))
+            .or_insert_with(|| {
+                self.matrix_data.push(data);
+
+                (self.matrix_data.len() - 1) as u32
+            })
+    }
+
+    fn push_world_matrix_data(&mut self, data: WorldMatrixData) -> u32 {
+        *self
+            .world_matrix_indices
+            .entry(bytemuck::cast(data))
+            .or_insert_with(|| {
+                self.world_matrix_data.push(data);
+
+                (self.world_matrix_data.len() - 1) as u32
+            })
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
 pub struct RawInstanceData {
@@ -189,6 +232,7 @@ pub struct RawInstanceData {
     alpha: Float,
     light_pos: [Float; 4],
     matrix_index: u32,
+    world_matrix_index: u32,
 }
 
 static FIX_COORD: Matrix4 = Matrix4::from_cols(
@@ -199,7 +243,7 @@ static FIX_COORD: Matrix4 = Matrix4::from_cols(
 );
 
 impl RawInstanceData {
-    pub fn from_instance(instance: InstanceData, buffer: &mut Vec<MatrixData>) -> Self {
+    pub fn from_instance(instance: InstanceData, buffers: &mut MatrixBuffers) -> Self {
         let world_matrix = if let Some(world_matrix) = instance.world_matrix {
             FIX_COORD * world_matrix
         } else {
@@ -221,12 +265,11 @@ impl RawInstanceData {
                 inverse_transpose.y_axis.extend(0.0).to_array(),
                 inverse_transpose.z_axis.extend(0.0).to_array(),
             ],
+        };
+        let world_matrix_data = WorldMatrixData {
             world_matrix: world_matrix.to_cols_array_2d(),
         };
 
-        buffer.push(matrix_data);
-        let index = buffer.len() - 1;
-
         Self {
             color_offset: instance.color_offset,
             alpha: instance.alpha,
@@ -236,16 +279,19 @@ impl RawInstanceData {
                 instance.light_pos.z,
                 instance.light_pos.w,
             ],
-            matrix_index: index as u32,
+            matrix_index: buffers.push_matrix_data(matrix_data),
+            world_matrix_index: buffers.push_world_matrix_data(world_matrix_data),
         }
     }
 
+    #[cfg(feature = "window")]
     pub fn desc() -> VertexBufferLayout<'static> {
         static ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
             3 => Float32x4,
             4 => Float32,
             5 => Float32x4,
             6 => Uint32,
+            7 => Uint32,
         ];
 
         VertexBufferLayout {
@@ -82,6 +82,12 @@ pub fn derive_id_reg(item: TokenStream) -> TokenStream {
 
     let name = ast.ident;
 
+    let fields = namespaces
+        .iter()
+        .map(|(field, _)| format!("self.{field},"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
     let items = namespaces
         .into_iter()
         .zip(names)
@@ -97,6 +103,13 @@ pub fn derive_id_reg(item: TokenStream) -> TokenStream {
                     {items}
                 }}
             }}
+
+            /// Every ID in this, in the order they are declared.
+            pub fn all(&self) -> Vec<automancy_defs::id::Id> {{
+                vec![
+                    {fields}
+                ]
+            }}
         }}
         "
     )
@@ -23,12 +23,18 @@ fn parse_meta(attr: &Attribute) -> Vec<String> {
 
 mod cell_getter;
 mod ids;
+mod schema;
 
 #[proc_macro_derive(IdReg, attributes(name, namespace))]
 pub fn derive_id_reg(item: TokenStream) -> TokenStream {
     ids::derive_id_reg(item)
 }
 
+#[proc_macro_derive(SchemaReg, attributes(data))]
+pub fn derive_schema_reg(item: TokenStream) -> TokenStream {
+    schema::derive_schema_reg(item)
+}
+
 #[proc_macro_derive(OptionGetter, attributes(getters))]
 pub fn derive_option_getter(item: TokenStream) -> TokenStream {
     cell_getter::derive_option_getter(item)
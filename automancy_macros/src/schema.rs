@@ -0,0 +1,73 @@
+use proc_macro::TokenStream;
+
+use crate::parse_meta;
+
+const DATA: &str = "data";
+
+/// Generates a `schema` function, listing the kind of data each field's ID holds, and where it is used.
+/// Fields without a `data` attribute are left out.
+///
+/// Only meant to be used inside `automancy_resources`.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(IdReg, SchemaReg)]
+/// pub struct DataIds {
+///     #[data(Bool, Definition)]
+///     pub connector: Id,
+///     #[data(Coord, Entity)]
+///     pub target: Id,
+/// }
+/// ```
+pub fn derive_schema_reg(item: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(item).unwrap();
+
+    let mut items = vec![];
+
+    match ast.data {
+        syn::Data::Struct(data_struct) => match &data_struct.fields {
+            syn::Fields::Named(fields_named) => {
+                for field in fields_named.named.iter() {
+                    let Some(attr) = field.attrs.iter().find(|v| {
+                        v.path()
+                            .get_ident()
+                            .map(|ident| *ident == DATA)
+                            .unwrap_or(false)
+                    }) else {
+                        continue;
+                    };
+
+                    let name = field.ident.clone().unwrap().to_string();
+
+                    let [kind, usage] = parse_meta(attr).try_into().unwrap_or_else(|_| {
+                        panic!("the data attribute of {name} should be a kind and a usage")
+                    });
+
+                    items.push(format!(
+                        "schema.insert(self.{name}, crate::data::schema::DataKind::{kind}, crate::data::schema::DataUsage::{usage});"
+                    ));
+                }
+            }
+            _ => panic!("must be a struct with named fields"),
+        },
+        _ => panic!("must be a struct"),
+    }
+
+    let name = ast.ident;
+    let items = items.join("\n");
+
+    format!(
+        "
+        impl {name} {{
+            pub fn schema(&self) -> crate::data::schema::DataSchema {{
+                let mut schema = crate::data::schema::DataSchema::default();
+                {items}
+                schema
+            }}
+        }}
+        "
+    )
+    .parse()
+    .unwrap()
+}
@@ -2,11 +2,11 @@ use std::any::TypeId;
 use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 
-use egui::Rgba;
 use hashbrown::{HashMap, HashSet};
 use rhai::Dynamic;
 use serde::{Deserialize, Serialize};
 
+use automancy_defs::colors::Rgba;
 use automancy_defs::coord::{TileBounds, TileCoord, TileUnit};
 use automancy_defs::glam::IVec2;
 use automancy_defs::hexx::{Hex, OffsetHexMode};
@@ -17,6 +17,7 @@ use crate::data::stack::ItemAmount;
 
 pub mod inventory;
 pub mod item;
+pub mod schema;
 pub mod stack;
 
 fn offset_to_tile(a: [TileUnit; 2]) -> TileCoord {
@@ -48,6 +49,13 @@ impl Data {
         None
     }
 
+    pub fn into_vec_coord(self) -> Option<Vec<TileCoord>> {
+        if let Self::VecCoord(v) = self {
+            return Some(v);
+        }
+        None
+    }
+
     pub fn into_bool(self) -> Option<bool> {
         if let Self::Bool(v) = self {
             return Some(v);
@@ -202,6 +210,55 @@ impl DataMap {
         self.0
     }
 
+    pub fn get_bool(&self, id: Id) -> Option<bool> {
+        match self.0.get(&id) {
+            Some(Data::Bool(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_id(&self, id: Id) -> Option<Id> {
+        match self.0.get(&id) {
+            Some(Data::Id(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_amount(&self, id: Id) -> Option<ItemAmount> {
+        match self.0.get(&id) {
+            Some(Data::Amount(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_coord(&self, id: Id) -> Option<TileCoord> {
+        match self.0.get(&id) {
+            Some(Data::Coord(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_vec_coord(&self, id: Id) -> Option<&[TileCoord]> {
+        match self.0.get(&id) {
+            Some(Data::VecCoord(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn get_vec_id(&self, id: Id) -> Option<&[Id]> {
+        match self.0.get(&id) {
+            Some(Data::VecId(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Gets whether a flag is set. A flag that isn't in the data is off.
+    ///
+    /// Flags that aren't a [`Data::Bool`] are already reported when they are loaded, see [`schema`].
+    pub fn flag(&self, id: Id) -> bool {
+        self.get_bool(id).unwrap_or(false)
+    }
+
     pub fn to_raw(&self, interner: &Interner) -> DataMapRaw {
         DataMapRaw(
             self.0
@@ -306,6 +363,20 @@ impl From<HashMap<String, DataRaw>> for DataMapRaw {
     }
 }
 
+impl Deref for DataMapRaw {
+    type Target = HashMap<String, DataRaw>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DataMapRaw {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl DataMapRaw {
     pub fn into_inner(self) -> HashMap<String, DataRaw> {
         self.0
@@ -0,0 +1,140 @@
+//! What kind of data each data ID holds, and where it is used.
+//!
+//! The schema is declared on [`DataIds`](crate::registry::DataIds) with `#[data(Kind, Usage)]`,
+//! and data is checked against it when tiles and maps are loaded, so data of the wrong kind is reported
+//! there instead of quietly reading as missing wherever it's used.
+
+use std::fmt::{self, Display, Formatter};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use automancy_defs::id::{Id, Interner};
+
+use crate::data::{Data, DataMap};
+
+/// The kinds of [`Data`], without their values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataKind {
+    Inventory,
+    Coord,
+    VecCoord,
+    TileBounds,
+    Id,
+    Color,
+    VecId,
+    SetId,
+    Amount,
+    Bool,
+    TileMap,
+    MapSetId,
+}
+
+impl Display for DataKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Data {
+    pub fn kind(&self) -> DataKind {
+        match self {
+            Data::Inventory(_) => DataKind::Inventory,
+            Data::Coord(_) => DataKind::Coord,
+            Data::VecCoord(_) => DataKind::VecCoord,
+            Data::TileBounds(_) => DataKind::TileBounds,
+            Data::Id(_) => DataKind::Id,
+            Data::Color(_) => DataKind::Color,
+            Data::VecId(_) => DataKind::VecId,
+            Data::SetId(_) => DataKind::SetId,
+            Data::Amount(_) => DataKind::Amount,
+            Data::Bool(_) => DataKind::Bool,
+            Data::TileMap(_) => DataKind::TileMap,
+            Data::MapSetId(_) => DataKind::MapSetId,
+        }
+    }
+}
+
+/// Where a data ID is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataUsage {
+    /// Set in a tile's definition, and only read by the game.
+    Definition,
+    /// Kept by a tile entity while it runs, and read and written by the game and by scripts.
+    Entity,
+    /// Kept by a player, in the map's info.
+    Player,
+}
+
+/// What kind of data an ID holds, and where it is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSpec {
+    pub kind: DataKind,
+    pub usage: DataUsage,
+}
+
+/// Data that doesn't match the schema.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{key} should be {expected}, but is {found}")]
+pub struct DataError {
+    pub key: String,
+    pub expected: DataKind,
+    pub found: DataKind,
+}
+
+/// The kind of data each known data ID holds. IDs that aren't in it, such as those only used by scripts, can hold anything.
+#[derive(Debug, Clone, Default)]
+pub struct DataSchema(HashMap<Id, DataSpec>);
+
+impl DataSchema {
+    pub fn insert(&mut self, id: Id, kind: DataKind, usage: DataUsage) {
+        self.0.insert(id, DataSpec { kind, usage });
+    }
+
+    pub fn get(&self, id: Id) -> Option<DataSpec> {
+        self.0.get(&id).copied()
+    }
+
+    /// Lists the IDs used in the given place, along with the kind of data they hold.
+    pub fn used_in(&self, usage: DataUsage) -> impl Iterator<Item = (Id, DataKind)> + '_ {
+        self.0
+            .iter()
+            .filter(move |(_, spec)| spec.usage == usage)
+            .map(|(id, spec)| (*id, spec.kind))
+    }
+
+    /// Checks a single value against the schema.
+    pub fn check(&self, interner: &Interner, key: Id, data: &Data) -> Result<(), DataError> {
+        match self.get(key) {
+            Some(spec) if spec.kind != data.kind() => Err(DataError {
+                key: interner.resolve(key).unwrap_or_default().to_string(),
+                expected: spec.kind,
+                found: data.kind(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every value in the data against the schema.
+    pub fn validate(&self, interner: &Interner, data: &DataMap) -> Vec<DataError> {
+        data.iter()
+            .flat_map(|(key, data)| self.check(interner, *key, data).err())
+            .collect()
+    }
+
+    /// Removes the values that don't match the schema, and returns what was wrong with them.
+    pub fn retain_valid(&self, interner: &Interner, data: &mut DataMap) -> Vec<DataError> {
+        let mut errors = vec![];
+
+        data.retain(|key, data| match self.check(interner, *key, data) {
+            Ok(()) => true,
+            Err(err) => {
+                errors.push(err);
+
+                false
+            }
+        });
+
+        errors
+    }
+}
@@ -6,7 +6,8 @@ use automancy_defs::log;
 use crate::{format, ResourceManager};
 
 /// An ErrorManager contains a queue of errors to be displayed.
-#[derive(Default)]
+/// Its clones share the same queue.
+#[derive(Default, Clone)]
 pub struct ErrorManager {
     queue: Arc<RwLock<Vec<GameError>>>,
 }
@@ -16,7 +17,7 @@ pub type GameError = (Id, Vec<String>);
 /// Gets the ID of an error along with its arguments and converts it into a human-readable string.
 pub fn error_to_string((id, args): &GameError, resource_man: &ResourceManager) -> String {
     format(
-        resource_man.translates.error[id].as_str(),
+        resource_man.translates().error[id].as_str(),
         args.iter()
             .map(String::as_str)
             .collect::<Vec<_>>()
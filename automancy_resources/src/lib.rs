@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
@@ -9,23 +10,27 @@ use std::time::SystemTime;
 pub use chrono;
 use chrono::{DateTime, Local};
 use hashbrown::HashMap;
+#[cfg(feature = "audio")]
 pub use kira;
+#[cfg(feature = "audio")]
 use kira::sound::static_sound::StaticSoundData;
-use kira::track::TrackHandle;
 use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
 use thiserror::Error;
 use walkdir::WalkDir;
 
 use automancy_defs::flexstr::SharedStr;
 use automancy_defs::id::{id_static, Id, Interner};
+use automancy_defs::math::Float;
 use automancy_defs::rendering::{Animation, Model};
 use automancy_defs::{id, log};
 
 use crate::error::ErrorManager;
 use crate::registry::{DataIds, ErrorIds, GuiIds, ModelIds, Registry};
+#[cfg(feature = "gui")]
 use crate::types::font::Font;
 use crate::types::model::IndexRange;
-use crate::types::translate::Translate;
+use crate::types::translate::{Language, Translate};
+use crate::types::ui_sound::UiSound;
 
 pub mod data;
 pub mod error;
@@ -40,6 +45,7 @@ mod rhai_functions;
 mod rhai_resources;
 mod rhai_tile;
 
+#[cfg(any(feature = "audio", feature = "gui"))]
 static COULD_NOT_GET_FILE_STEM: &str = "could not get file stem";
 
 #[derive(Error, Debug)]
@@ -68,9 +74,12 @@ pub fn format_time(time: SystemTime, fmt: &str) -> String {
     time.format(fmt).to_string()
 }
 
+/// Lists the files with the extension in the folder and all folders under it.
+/// They are sorted by name, so the IDs in them are interned in the same order on every computer.
 pub fn load_recursively(path: &Path, extension: &OsStr) -> Vec<PathBuf> {
     WalkDir::new(path)
         .follow_links(false)
+        .sort_by_file_name()
         .into_iter()
         .flatten()
         .filter(|v| v.path().extension() == Some(extension))
@@ -78,6 +87,19 @@ pub fn load_recursively(path: &Path, extension: &OsStr) -> Vec<PathBuf> {
         .collect()
 }
 
+/// Lists the files directly in the folder, sorted by name like [`load_recursively`], or nothing if it doesn't exist.
+pub fn list_sorted(path: &Path) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|v| v.path())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    files
+}
+
 pub const RESOURCES_PATH: &str = "resources";
 
 pub const FONT_EXT: [&str; 2] = ["ttf", "otf"];
@@ -98,23 +120,35 @@ pub enum ResourceError {
 /// Represents a resource manager, which contains all resources (apart from maps) loaded from disk dynamically.
 pub struct ResourceManager {
     pub interner: Interner,
-    pub track: TrackHandle,
+    /// How many IDs were interned once everything was loaded, after which no more should be.
+    pub loaded_ids: usize,
     pub error_man: ErrorManager,
     pub engine: Engine,
 
     pub registry: Registry,
 
-    pub translates: Translate,
+    /// the translations in the language picked, which is swapped out when another one is picked, see [`ResourceManager::translates`]
+    translates: RwLock<Arc<Translate>>,
+    /// the languages there are translations for, by their codes
+    pub languages: BTreeMap<String, Language>,
+    #[cfg(feature = "audio")]
     pub audio: HashMap<SharedStr, StaticSoundData>,
+    pub ui_sounds: HashMap<UiSound, SharedStr>,
     pub shaders: HashMap<SharedStr, String>,
+    /// The pieces of shaders that shaders can include, by their namespaced name.
+    pub shader_snippets: HashMap<SharedStr, String>,
     pub functions: HashMap<Id, (AST, Scope<'static>, String)>,
+    #[cfg(feature = "gui")]
     pub fonts: BTreeMap<String, Font>, // yes this does need to be a BTreeMap
+    pub map_templates: BTreeMap<String, PathBuf>,
 
     pub ordered_tiles: Vec<Id>,
     pub ordered_items: Vec<Id>,
     pub ordered_categories: Vec<Id>,
     pub all_models: HashMap<Id, (HashMap<usize, Model>, Vec<Animation>)>,
     pub all_index_ranges: HashMap<Id, HashMap<usize, IndexRange>>,
+    /// The levels of detail of each model that has them, and the distance each is drawn from, nearest first.
+    pub model_lods: HashMap<Id, Vec<(Float, Id)>>,
 }
 
 impl Debug for ResourceManager {
@@ -123,8 +157,14 @@ impl Debug for ResourceManager {
     }
 }
 
+impl Default for ResourceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ResourceManager {
-    pub fn new(track: TrackHandle) -> Self {
+    pub fn new() -> Self {
         let mut interner = Interner::new();
         let none = id::NONE.to_id(&mut interner);
         let any = id_static("automancy", "#any").to_id(&mut interner);
@@ -140,13 +180,14 @@ impl ResourceManager {
         rhai_tile::register_tile_stuff(&mut engine);
 
         let data_ids = DataIds::new(&mut interner);
+        let data_schema = data_ids.schema();
         let model_ids = ModelIds::new(&mut interner);
         let gui_ids = GuiIds::new(&mut interner);
         let err_ids = ErrorIds::new(&mut interner);
 
         Self {
             interner,
-            track,
+            loaded_ids: 0,
             error_man: Default::default(),
             engine,
 
@@ -164,97 +205,128 @@ impl ResourceManager {
                 any,
 
                 data_ids,
+                data_schema,
                 model_ids,
                 gui_ids,
                 err_ids,
             },
 
             translates: Default::default(),
+            languages: Default::default(),
+            #[cfg(feature = "audio")]
             audio: Default::default(),
+            ui_sounds: Default::default(),
             shaders: Default::default(),
+            shader_snippets: Default::default(),
             functions: Default::default(),
+            #[cfg(feature = "gui")]
             fonts: Default::default(),
+            map_templates: Default::default(),
 
             ordered_tiles: vec![],
             ordered_items: vec![],
             ordered_categories: vec![],
             all_index_ranges: Default::default(),
             all_models: Default::default(),
+            model_lods: Default::default(),
         }
     }
 
-    pub fn item_name(&self, id: &Id) -> &str {
-        match self.translates.items.get(id) {
-            Some(name) => name,
-            None => &self.translates.unnamed,
-        }
+    /// Writes the time the way the language does, with the time format from its translations.
+    pub fn time_string(&self, time: SystemTime) -> String {
+        format_time(
+            time,
+            &self.translates().gui[&self.registry.gui_ids.time_fmt],
+        )
     }
 
-    pub fn try_item_name(&self, id: Option<&Id>) -> &str {
+    pub fn item_name(&self, id: &Id) -> String {
+        let translates = self.translates();
+
+        translates
+            .items
+            .get(id)
+            .unwrap_or(&translates.unnamed)
+            .to_string()
+    }
+
+    pub fn try_item_name(&self, id: Option<&Id>) -> String {
         if let Some(id) = id {
             self.item_name(id)
         } else {
-            &self.translates.none
+            self.translates().none.to_string()
         }
     }
 
-    pub fn script_name(&self, id: &Id) -> &str {
-        match self.translates.scripts.get(id) {
-            Some(name) => name,
-            None => &self.translates.unnamed,
-        }
+    pub fn script_name(&self, id: &Id) -> String {
+        let translates = self.translates();
+
+        translates
+            .scripts
+            .get(id)
+            .unwrap_or(&translates.unnamed)
+            .to_string()
     }
 
-    pub fn try_script_name(&self, id: Option<&Id>) -> &str {
+    pub fn try_script_name(&self, id: Option<&Id>) -> String {
         if let Some(id) = id {
             self.item_name(id)
         } else {
-            &self.translates.none
+            self.translates().none.to_string()
         }
     }
 
-    pub fn tile_name(&self, id: &Id) -> &str {
-        match self.translates.tiles.get(id) {
-            Some(name) => name,
-            None => &self.translates.unnamed,
-        }
+    pub fn tile_name(&self, id: &Id) -> String {
+        let translates = self.translates();
+
+        translates
+            .tiles
+            .get(id)
+            .unwrap_or(&translates.unnamed)
+            .to_string()
     }
 
-    pub fn try_tile_name(&self, id: Option<&Id>) -> &str {
+    pub fn try_tile_name(&self, id: Option<&Id>) -> String {
         if let Some(id) = id {
             self.tile_name(id)
         } else {
-            &self.translates.none
+            self.translates().none.to_string()
         }
     }
 
-    pub fn category_name(&self, id: &Id) -> &str {
-        match self.translates.categories.get(id) {
-            Some(name) => name,
-            None => &self.translates.unnamed,
-        }
+    pub fn category_name(&self, id: &Id) -> String {
+        let translates = self.translates();
+
+        translates
+            .categories
+            .get(id)
+            .unwrap_or(&translates.unnamed)
+            .to_string()
     }
 
-    pub fn try_category_name(&self, id: Option<&Id>) -> &str {
+    pub fn try_category_name(&self, id: Option<&Id>) -> String {
         if let Some(id) = id {
             self.category_name(id)
         } else {
-            &self.translates.none
+            self.translates().none.to_string()
         }
     }
 
-    pub fn research_str(&self, id: &Id) -> &str {
-        match self.translates.research.get(id) {
-            Some(name) => name,
-            None => &self.translates.unnamed,
-        }
+    pub fn research_str(&self, id: &Id) -> String {
+        let translates = self.translates();
+
+        translates
+            .research
+            .get(id)
+            .unwrap_or(&translates.unnamed)
+            .to_string()
     }
 
-    pub fn try_research_str(&self, id: Option<&Id>) -> &str {
+    pub fn try_research_str(&self, id: Option<&Id>) -> String {
         if let Some(id) = id {
             self.research_str(id)
         } else {
-            &self.translates.none
+            self.translates().none.to_string()
         }
     }
 }
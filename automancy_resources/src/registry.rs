@@ -1,10 +1,11 @@
 use automancy_defs::graph::graph::NodeIndex;
 use automancy_defs::graph::prelude::StableDiGraph;
 use automancy_defs::id::Id;
-use automancy_macros::IdReg;
+use automancy_macros::{IdReg, SchemaReg};
 use hashbrown::HashMap;
 
 use crate::data::item::Item;
+use crate::data::schema::DataSchema;
 use crate::types::category::Category;
 use crate::types::research::Research;
 use crate::types::script::Script;
@@ -27,35 +28,73 @@ pub struct Registry {
     pub any: Id,
 
     pub data_ids: DataIds,
+    pub data_schema: DataSchema,
     pub model_ids: ModelIds,
     pub gui_ids: GuiIds,
     pub err_ids: ErrorIds,
 }
 
-#[derive(Copy, Clone, IdReg)]
+/// The data IDs the game itself uses. The kind of data each holds is checked on load, see [`DataSchema`].
+#[derive(Copy, Clone, IdReg, SchemaReg)]
 pub struct DataIds {
+    #[data(Id, Entity)]
     pub script: Id,
+    #[data(VecId, Definition)]
     pub scripts: Id,
+    #[data(Inventory, Entity)]
     pub buffer: Id,
+    #[data(Id, Entity)]
     pub item: Id,
+    /// the items the slots of a storage are locked to, besides `item`
+    #[data(VecId, Entity)]
+    pub filters: Id,
+    #[data(Id, Definition)]
     pub item_type: Id,
+    #[data(Amount, Entity)]
     pub amount: Id,
+    #[data(Coord, Entity)]
     pub target: Id,
+    #[data(VecCoord, Entity)]
+    pub input_sides: Id,
+    #[data(Bool, Definition)]
+    pub connector: Id,
+    #[data(Coord, Entity)]
     pub link: Id,
+    #[data(Inventory, Player)]
     pub player_inventory: Id,
+    #[data(SetId, Player)]
     pub research_items_filled: Id,
+    #[data(SetId, Player)]
     pub research_puzzle_completed: Id,
+    #[data(TileMap, Entity)]
     pub tiles: Id,
 
+    #[data(Color, Definition)]
     pub direction_color: Id,
+    #[data(Bool, Definition)]
     pub storage_takeable: Id,
+    #[data(Id, Definition)]
     pub inactive_model: Id,
+    #[data(Bool, Definition)]
     pub not_targeted: Id,
+    #[data(Amount, Definition)]
     pub max_amount: Id,
+    #[data(Bool, Definition)]
     pub linked: Id,
+    #[data(Bool, Definition)]
     pub linking: Id,
+    #[data(Bool, Definition)]
     pub default_tile: Id,
+    #[data(SetId, Player)]
     pub unlocked_researches: Id,
+    #[data(VecId, Player)]
+    pub pinned_scripts: Id,
+    #[data(VecId, Player)]
+    pub pinned_items: Id,
+    /// the tiles in each slot of the hotbar, with `none` in the empty ones
+    #[data(VecId, Player)]
+    pub hotbar: Id,
+    #[data(Id, Definition)]
     pub category: Id,
 }
 
@@ -81,6 +120,7 @@ pub struct GuiIds {
     pub tile_config_script_info: Id,
     pub tile_config_item: Id,
     pub tile_config_target: Id,
+    pub tile_config_sides_tip: Id,
     pub error_popup: Id,
     pub debug_menu: Id,
     pub load_map: Id,
@@ -88,18 +128,150 @@ pub struct GuiIds {
     pub create_map: Id,
     pub invalid_name: Id,
     pub options: Id,
+    pub editor: Id,
+    pub scenario_victory: Id,
+    pub scenario_defeat: Id,
+    pub import_region: Id,
+    pub import_map: Id,
+    pub export_map: Id,
+    pub restore_backup: Id,
+    pub keep_settings: Id,
     pub inventory: Id,
 
     pub inventory_tip: Id,
     pub search_script_tip: Id,
     pub search_item_tip: Id,
 
+    pub caption_alert: Id,
+    pub caption_research_complete: Id,
+    pub caption_tile_crashed: Id,
+
+    pub toast_autosaved: Id,
+    pub toast_research_complete: Id,
+    /// Shown when more machines stall in a zone that has alerts on.
+    pub toast_machines_stalled: Id,
+
+    pub key_main: Id,
+    pub key_alternate: Id,
+    pub key_shift: Id,
+    pub key_control: Id,
+
+    pub hint_place_tile: Id,
+    pub hint_place_continuously: Id,
+    pub hint_set_direction: Id,
+    pub hint_deselect: Id,
+    pub hint_configure: Id,
+    pub hint_link_destination: Id,
+    pub hint_cancel: Id,
+    pub hint_select_group: Id,
+    pub hint_start_move: Id,
+    pub hint_drop_group: Id,
+    pub hint_release_to_cancel: Id,
+    pub hint_undo: Id,
+    pub hint_redo: Id,
+    pub hint_player_menu: Id,
+    pub hint_pause: Id,
+    pub hint_place_region: Id,
+    pub hint_select_puzzle_space: Id,
+    pub hint_pick_puzzle_tile: Id,
+    pub hint_close_menu: Id,
+    pub hint_cheatsheet: Id,
+    pub hint_drop_items: Id,
+    pub hint_pick_up: Id,
+    pub hint_route: Id,
+    pub hint_route_source: Id,
+    pub hint_route_destination: Id,
+    pub hint_copy_blueprint: Id,
+    pub hint_paste_blueprint: Id,
+    pub hint_place_suggestion: Id,
+    pub hint_paint_zone: Id,
+    pub hint_erase_zone: Id,
+
+    pub cheatsheet_title: Id,
+    pub key_category_interface: Id,
+    pub key_category_editing: Id,
+    pub key_category_window: Id,
+    pub key_press_hold: Id,
+    pub key_press_toggle: Id,
+    pub action_escape: Id,
+    pub action_undo: Id,
+    pub action_redo: Id,
+    pub action_debug: Id,
+    pub action_fullscreen: Id,
+    pub action_screenshot: Id,
+    pub action_hide_gui: Id,
+    pub action_player: Id,
+    pub action_cheatsheet: Id,
+    pub action_pick_up: Id,
+    pub action_route: Id,
+    pub action_copy_blueprint: Id,
+    pub action_paste_blueprint: Id,
+    pub action_accept_suggestion: Id,
+    pub action_zones: Id,
+    pub action_statistics: Id,
+    pub action_markers: Id,
+    pub action_hotbar: Id,
+    pub lbl_hotbar_add: Id,
+    pub lbl_hotbar_remove: Id,
+
     pub lbl_amount: Id,
     pub lbl_link_destination: Id,
     pub lbl_maps_loaded: Id,
     pub lbl_pick_another_name: Id,
     pub lbl_delete_map_confirm: Id,
     pub lbl_cannot_place_missing_item: Id,
+    pub lbl_scenario_victory: Id,
+    pub lbl_scenario_defeat: Id,
+    pub lbl_brush_size: Id,
+    pub lbl_map_template: Id,
+    pub lbl_new_from_template: Id,
+    pub lbl_placement_cost: Id,
+    pub lbl_survival: Id,
+    pub lbl_drop_items: Id,
+    pub lbl_item_recipes: Id,
+    pub lbl_recipe_makes: Id,
+    pub lbl_recipe_uses: Id,
+    pub lbl_recipe_inputs: Id,
+    pub lbl_recipe_outputs: Id,
+    pub lbl_recipe_tiles: Id,
+    pub lbl_recipe_research: Id,
+    pub btn_recipes: Id,
+    pub btn_all_recipes: Id,
+    pub search_recipe_tip: Id,
+    pub lbl_storage_capacity: Id,
+    pub lbl_storage_filter: Id,
+    pub lbl_storage_slot_tip: Id,
+    pub lbl_storage_add_filter: Id,
+    pub lbl_export_image_done: Id,
+    pub lbl_screenshot_saved: Id,
+    pub lbl_battery_saver: Id,
+    pub lbl_no_zones: Id,
+    pub lbl_map_exported: Id,
+    pub lbl_import_map_folder: Id,
+    pub lbl_import_blueprint_folder: Id,
+    pub lbl_import_region_center: Id,
+    pub lbl_import_region_radius: Id,
+    pub lbl_import_region_count: Id,
+    pub lbl_zone_alerts: Id,
+    pub lbl_whole_map: Id,
+    pub lbl_stalled_machines: Id,
+    pub lbl_crashed_tiles: Id,
+    pub lbl_no_markers: Id,
+    pub lbl_icon_circle: Id,
+    pub lbl_icon_square: Id,
+    pub lbl_icon_diamond: Id,
+    pub lbl_icon_triangle: Id,
+    pub lbl_no_backups: Id,
+    pub lbl_restore_backup_tip: Id,
+    pub lbl_sort_by: Id,
+    pub lbl_sort_name: Id,
+    pub lbl_sort_last_played: Id,
+    pub lbl_sort_playtime: Id,
+    pub lbl_map_tiles: Id,
+    pub lbl_map_playtime: Id,
+    pub lbl_map_last_played: Id,
+    pub lbl_map_created: Id,
+    pub lbl_keep_settings: Id,
 
     pub btn_confirm: Id,
     pub btn_exit: Id,
@@ -110,13 +282,46 @@ pub struct GuiIds {
     pub btn_fedi: Id,
     pub btn_source: Id,
     pub btn_unpause: Id,
+    pub btn_restart: Id,
+    pub btn_undo: Id,
+    pub btn_reset: Id,
+    pub btn_continue: Id,
+    pub btn_take_all: Id,
+    pub btn_store_all: Id,
+    pub btn_edit: Id,
+    pub btn_create_in_editor: Id,
+    pub btn_edit_data: Id,
+    pub btn_apply: Id,
+    pub btn_import_region: Id,
     pub btn_load: Id,
     pub btn_delete: Id,
     pub btn_new_map: Id,
+    pub btn_export_image: Id,
+    pub btn_pin: Id,
+    pub btn_unpin: Id,
+    pub btn_new_zone: Id,
+    pub btn_paint_zone: Id,
+    pub btn_export: Id,
+    pub btn_import: Id,
+    pub btn_add_marker: Id,
+    pub btn_jump: Id,
+    pub btn_restore_backup: Id,
+    pub btn_restore: Id,
+    pub btn_keep: Id,
+    pub btn_revert: Id,
+    pub btn_back: Id,
 
     pub research_menu_title: Id,
     pub player_inventory_title: Id,
+    pub recipes_title: Id,
     pub research_submit_items: Id,
+    pub export_image_title: Id,
+    pub pinned_title: Id,
+    pub zones_title: Id,
+    pub zone_default_name: Id,
+    pub statistics_title: Id,
+    pub markers_title: Id,
+    pub marker_default_name: Id,
 
     pub time_fmt: Id,
 }
@@ -126,6 +331,30 @@ pub struct GuiIds {
 pub struct ErrorIds {
     /// This error is displayed when the map cannot be read.
     pub invalid_map_data: Id,
+    /// This error is displayed when a map's files are damaged or cut off, and it could not be loaded.
+    pub map_damaged: Id,
     /// This error is displayed when the options cannot be written.
     pub unwritable_options: Id,
+    /// This error is displayed when the tile data written in the map editor cannot be parsed.
+    pub invalid_tile_data: Id,
+    /// This error is displayed when a tile crashes, and has been restarted.
+    pub tile_crashed: Id,
+    /// This error is displayed when a tile has crashed too many times, and has been stopped.
+    pub tile_quarantined: Id,
+    /// This error is displayed when the graphics driver rejects something, such as a broken shader.
+    pub gpu_error: Id,
+    /// This error is displayed when the map image cannot be rendered or saved.
+    pub export_image_failed: Id,
+    /// This error is displayed when the pasted text cannot be read as a blueprint.
+    pub invalid_blueprint: Id,
+    /// This error is displayed when a blueprint file cannot be read as a blueprint.
+    pub invalid_blueprint_file: Id,
+    /// This error is displayed when a map cannot be exported to a file.
+    pub map_export_failed: Id,
+    /// This error is displayed when an exported map file cannot be imported.
+    pub map_import_failed: Id,
+    /// This error is displayed when an autosave cannot be restored.
+    pub map_restore_failed: Id,
+    /// This error is displayed when a map cannot be written to disk.
+    pub map_save_failed: Id,
 }
@@ -1,7 +1,7 @@
 use std::ops::{Add, Neg, Sub};
 
 use hashbrown::HashMap;
-use rhai::{Dynamic, Engine, Module};
+use rhai::{Dynamic, Engine, Module, INT};
 
 use automancy_defs::coord::{TileBounds, TileCoord};
 use automancy_defs::id::Id;
@@ -33,6 +33,23 @@ pub(crate) fn register_coord_stuff(engine: &mut Engine) {
         .register_fn("rotate_right", |n: TileCoord| {
             TileCoord::from(n.clockwise())
         })
+        .register_fn("rotate", |v: TileCoord, times: INT| v.rotate(times as i32))
+        .register_fn(
+            "rotate_around",
+            |v: TileCoord, center: TileCoord, times: INT| v.rotate_around(center, times as i32),
+        )
+        .register_fn("distance", |a: TileCoord, b: TileCoord| {
+            a.distance(b) as INT
+        })
+        .register_fn("ring", |v: TileCoord, radius: INT| {
+            Dynamic::from_iter(v.ring(radius.max(0) as u32))
+        })
+        .register_fn("spiral", |v: TileCoord, radius: INT| {
+            Dynamic::from_iter(v.spiral(radius.max(0) as u32))
+        })
+        .register_fn("line_to", |a: TileCoord, b: TileCoord| {
+            Dynamic::from_iter(a.line_to(b))
+        })
         .register_get("q", |v: &mut TileCoord| v.x)
         .register_get("r", |v: &mut TileCoord| v.y)
         .register_fn("+", TileCoord::add)
@@ -27,11 +27,13 @@ pub(crate) fn register_data_stuff(engine: &mut Engine) {
         .register_fn("add", Inventory::add)
         .register_fn("add", Inventory::add_with_item)
         .register_indexer_get_set(Inventory::get, Inventory::insert)
-        .register_indexer_get_set(Inventory::get_with_item, Inventory::insert_with_item);
+        .register_indexer_get_set(Inventory::get_with_item, Inventory::insert_with_item)
+        .register_get("total", |v: &mut Inventory| v.values().sum::<ItemAmount>());
     engine
         .register_type_with_name::<Id>("Id")
         .register_iterator::<Vec<Id>>()
         .register_iterator::<HashSet<Id>>()
+        .register_fn("contains", |v: &mut Vec<Id>, id: Id| v.contains(&id))
         .register_fn("contains", |v: &mut HashSet<Id>, id: Dynamic| {
             if let Ok(id) = id.as_int() {
                 Dynamic::from_bool(v.contains(&Id::from(id)))
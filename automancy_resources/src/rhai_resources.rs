@@ -33,7 +33,7 @@ pub(crate) fn register_resources(engine: &mut Engine) {
             None => Dynamic::UNIT,
         }
     });
-    engine.register_fn("as_item", |id: INT| {
+    fn as_item(id: Id) -> Dynamic {
         match RESOURCE_MAN
             .read()
             .unwrap()
@@ -41,13 +41,16 @@ pub(crate) fn register_resources(engine: &mut Engine) {
             .unwrap()
             .registry
             .items
-            .get(&Id::from(id))
+            .get(&id)
             .cloned()
         {
             Some(v) => Dynamic::from(v),
             None => Dynamic::UNIT,
         }
-    });
+    }
+
+    engine.register_fn("as_item", |id: INT| as_item(Id::from(id)));
+    engine.register_fn("as_item", as_item);
     engine.register_fn("as_tag", |id: INT| {
         match RESOURCE_MAN
             .read()
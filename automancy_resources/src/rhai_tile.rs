@@ -19,6 +19,7 @@ pub(crate) fn register_tile_stuff(engine: &mut Engine) {
         module
             .set_var("PROXY", TransactionResultType::Proxy)
             .set_var("PASS_ON", TransactionResultType::PassOn)
+            .set_var("BALANCE", TransactionResultType::Balance)
             .set_var("CONSUME", TransactionResultType::Consume);
 
         engine.register_static_module("TransResult", module.into());
@@ -1,49 +1,99 @@
-use crate::{LoadResourceError, ResourceManager, COULD_NOT_GET_FILE_STEM, FONT_EXT};
+use crate::{
+    list_sorted, LoadResourceError, ResourceManager, COULD_NOT_GET_FILE_STEM, FONT_EXT, RON_EXT,
+};
 use automancy_defs::log;
+use hashbrown::HashSet;
+use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::{read_dir, File};
+use std::fs::{read_to_string, File};
 use std::io::Read;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use ttf_parser::Face;
 
+/// What a font says about itself, in a RON file named after it next to it, such as `zpix.ron` for `zpix.ttf`.
+/// All of it is optional, and so is the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FontMetadata {
+    /// the name shown in the font picker, in place of the one in the font file
+    pub name: Option<String>,
+    /// the license the font is under, such as `OFL-1.1`
+    pub license: Option<String>,
+    /// the scripts the font is made for, such as `Latin` or `Han`
+    pub scripts: Vec<String>,
+}
+
 pub struct Font {
     pub name: String,
     pub data: Vec<u8>,
+    /// the namespace the font was loaded from
+    pub namespace: String,
+    pub license: Option<String>,
+    pub scripts: Vec<String>,
+    /// whether the font has a glyph for every letter and digit in the loaded translations,
+    /// which is checked again when another language is picked
+    pub covers_language: AtomicBool,
+}
+
+impl Font {
+    /// Whether the font has a glyph for every one of the characters.
+    pub fn covers(&self, chars: &HashSet<char>) -> bool {
+        let Ok(face) = Face::parse(&self.data, 0) else {
+            return false;
+        };
+
+        chars.iter().all(|c| face.glyph_index(*c).is_some())
+    }
 }
+
 impl ResourceManager {
     pub fn load_fonts(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| LoadResourceError::OsStringError(dir.to_path_buf()))?;
+
         let fonts = dir.join("fonts");
 
-        if let Ok(fonts) = read_dir(fonts) {
-            for file in fonts.into_iter().flatten().map(|v| v.path()).filter(|v| {
-                v.extension()
-                    .and_then(OsStr::to_str)
-                    .is_some_and(|v| FONT_EXT.contains(&v))
-            }) {
-                log::info!("Loading font {file:?}");
-
-                let mut data: Vec<u8> = Vec::new();
-                File::open(&file)?.read_to_end(&mut data)?;
-
-                let file_stem = file
-                    .file_stem()
-                    .ok_or_else(|| {
-                        LoadResourceError::InvalidFileError(file.clone(), COULD_NOT_GET_FILE_STEM)
-                    })? // TODO deupe these
-                    .to_str()
-                    .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?
-                    .to_string();
-
-                let file_name = file
-                    .file_name()
-                    .ok_or_else(|| {
-                        LoadResourceError::InvalidFileError(file.clone(), COULD_NOT_GET_FILE_STEM)
-                    })?
-                    .to_str()
-                    .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?
-                    .to_string();
-
-                let name = Face::parse(&data, 0)?
+        for file in list_sorted(&fonts).into_iter().filter(|v| {
+            v.extension()
+                .and_then(OsStr::to_str)
+                .is_some_and(|v| FONT_EXT.contains(&v))
+        }) {
+            log::info!("Loading font {file:?}");
+
+            let mut data: Vec<u8> = Vec::new();
+            File::open(&file)?.read_to_end(&mut data)?;
+
+            let file_stem = file
+                .file_stem()
+                .ok_or_else(|| {
+                    LoadResourceError::InvalidFileError(file.clone(), COULD_NOT_GET_FILE_STEM)
+                })? // TODO deupe these
+                .to_str()
+                .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?
+                .to_string();
+
+            let file_name = file
+                .file_name()
+                .ok_or_else(|| {
+                    LoadResourceError::InvalidFileError(file.clone(), COULD_NOT_GET_FILE_STEM)
+                })?
+                .to_str()
+                .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?
+                .to_string();
+
+            let metadata_file = file.with_extension(RON_EXT);
+            let metadata = if metadata_file.exists() {
+                ron::from_str::<FontMetadata>(&read_to_string(&metadata_file)?)?
+            } else {
+                FontMetadata::default()
+            };
+
+            let name = match metadata.name {
+                Some(name) => name,
+                None => Face::parse(&data, 0)?
                     .tables()
                     .name
                     .expect("Failed to get name table (likely malformed font file)")
@@ -51,14 +101,52 @@ impl ResourceManager {
                     .into_iter()
                     .filter_map(|n| n.to_string())
                     .find(|n| n.to_lowercase()[..2] == file_stem.to_lowercase()[..2])
-                    .unwrap_or(file_stem);
+                    .unwrap_or(file_stem),
+            };
 
-                log::info!("Loaded font {name} with key {file_name}!");
+            log::info!("Loaded font {name} with key {file_name}!");
 
-                self.fonts.insert(file_name, Font { name, data });
-            }
+            self.fonts.insert(
+                file_name,
+                Font {
+                    name,
+                    data,
+                    namespace: namespace.to_string(),
+                    license: metadata.license,
+                    scripts: metadata.scripts,
+                    covers_language: AtomicBool::new(true),
+                },
+            );
         }
 
         Ok(())
     }
+
+    /// Checks which fonts have glyphs for all the letters and digits in the loaded translations.
+    /// This is done once the translations and the fonts are all loaded, and whenever other translations are swapped in.
+    pub fn check_font_coverage(&self) {
+        let translates = self.translates();
+        let chars = [&translates.none, &translates.unnamed]
+            .into_iter()
+            .chain(translates.items.values())
+            .chain(translates.tiles.values())
+            .chain(translates.categories.values())
+            .chain(translates.scripts.values())
+            .chain(translates.gui.values())
+            .chain(translates.error.values())
+            .chain(translates.research.values())
+            .flat_map(|v| v.chars())
+            // symbols are left out, since the symbols font and egui's own fonts fill in for those
+            .filter(|c| c.is_alphanumeric())
+            .collect::<HashSet<_>>();
+
+        for (key, font) in &self.fonts {
+            let covers = font.covers(&chars);
+            font.covers_language.store(covers, Ordering::Relaxed);
+
+            if !covers {
+                log::info!("Font {key} is missing letters used in the translations");
+            }
+        }
+    }
 }
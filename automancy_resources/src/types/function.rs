@@ -19,6 +19,8 @@ pub enum ResultType {
 #[derive(Debug, Clone, Copy)]
 pub enum TransactionResultType {
     PassOn,
+    /// passes the items on to one of the given tiles, spreading them evenly over time
+    Balance,
     Proxy,
     Consume,
 }
@@ -72,6 +74,11 @@ impl RhaiDataMap {
     pub fn from_data_map(data: DataMap) -> Self {
         Self(data.into_inner())
     }
+
+    /// Checks if the data is the same as the data map, without copying either.
+    pub fn same_as(&self, data: &DataMap) -> bool {
+        self.0 == **data
+    }
 }
 
 impl ResourceManager {
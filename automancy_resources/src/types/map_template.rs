@@ -0,0 +1,45 @@
+use std::ffi::OsStr;
+use std::fs::read_dir;
+use std::path::Path;
+
+use automancy_defs::log;
+
+use crate::{LoadResourceError, ResourceManager, COULD_NOT_GET_FILE_STEM};
+
+impl ResourceManager {
+    /// Finds the prebuilt maps shipped in the resource pack. Each map is a directory in the same format as a saved map,
+    /// and is named after the namespace it is in and its directory, as `namespace:name`.
+    pub fn load_map_templates(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| LoadResourceError::OsStringError(dir.to_path_buf()))?;
+
+        let maps = dir.join("maps");
+
+        if let Ok(maps) = read_dir(maps) {
+            for dir in maps
+                .into_iter()
+                .flatten()
+                .map(|v| v.path())
+                .filter(|v| v.is_dir())
+            {
+                log::info!("Loading map template at {dir:?}");
+
+                let name = dir
+                    .file_name()
+                    .ok_or_else(|| {
+                        LoadResourceError::InvalidFileError(dir.clone(), COULD_NOT_GET_FILE_STEM)
+                    })?
+                    .to_str()
+                    .ok_or_else(|| LoadResourceError::OsStringError(dir.clone()))?
+                    .to_string();
+
+                self.map_templates
+                    .insert(format!("{namespace}:{name}"), dir);
+            }
+        }
+
+        Ok(())
+    }
+}
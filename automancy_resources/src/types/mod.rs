@@ -5,11 +5,14 @@ use serde::{Deserialize, Serialize};
 use automancy_defs::glam::vec3;
 use automancy_defs::math::{z_far, z_near, Float, Matrix4};
 
+#[cfg(feature = "audio")]
 pub mod audio;
 pub mod category;
+#[cfg(feature = "gui")]
 pub mod font;
 pub mod function;
 pub mod item;
+pub mod map_template;
 pub mod model;
 pub mod research;
 pub mod script;
@@ -17,6 +20,7 @@ pub mod shader;
 pub mod tag;
 pub mod tile;
 pub mod translate;
+pub mod ui_sound;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum IconMode {
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use automancy_defs::glam::vec3;
 use automancy_defs::gltf::animation::util::ReadOutputs;
 use automancy_defs::id::{Id, IdRaw};
-use automancy_defs::math::{Matrix4, Quaternion};
+use automancy_defs::math::{Float, Matrix4, Quaternion};
 use automancy_defs::rendering::{Animation, Model, Vertex};
 use automancy_defs::{gltf, log};
 
@@ -24,6 +24,17 @@ pub struct IndexRange {
 pub struct ModelRaw {
     pub id: IdRaw,
     pub file: String,
+    /// Simpler models drawn in place of this one from far away.
+    #[serde(default)]
+    pub lods: Vec<ModelLodRaw>,
+}
+
+/// A level of detail of a model.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelLodRaw {
+    /// How far from the camera the model has to be for this one to be drawn in its place.
+    pub distance: Float,
+    pub model: IdRaw,
 }
 
 impl ResourceManager {
@@ -43,6 +54,19 @@ impl ResourceManager {
         }
     }
 
+    /// Gets the model to draw in place of the given one, from the given distance away from the camera.
+    /// Levels of detail that weren't loaded are skipped.
+    pub fn lod_model(&self, model: Id, distance: Float) -> Id {
+        self.model_lods
+            .get(&model)
+            .and_then(|lods| {
+                lods.iter().rev().find(|(min_distance, lod)| {
+                    distance >= *min_distance && self.all_models.contains_key(lod)
+                })
+            })
+            .map_or(model, |(_, lod)| *lod)
+    }
+
     fn load_model(&mut self, file: &Path) -> anyhow::Result<()> {
         log::info!("Loading model at: {file:?}");
 
@@ -167,8 +191,20 @@ impl ResourceManager {
             }
         }
 
-        self.all_models
-            .insert(model.id.to_id(&mut self.interner), (models, animations));
+        let id = model.id.to_id(&mut self.interner);
+
+        if !model.lods.is_empty() {
+            let mut lods = model
+                .lods
+                .iter()
+                .map(|lod| (lod.distance, lod.model.to_id(&mut self.interner)))
+                .collect::<Vec<_>>();
+            lods.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            self.model_lods.insert(id, lods);
+        }
+
+        self.all_models.insert(id, (models, animations));
 
         Ok(())
     }
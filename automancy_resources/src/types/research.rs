@@ -26,6 +26,8 @@ pub struct ResearchRaw {
     completed_description: IdRaw,
     required_items: Option<Vec<(IdRaw, ItemAmount)>>,
     attached_puzzle: Option<(IdRaw, DataMapRaw)>,
+    #[serde(default)]
+    puzzle_hint_after: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +42,8 @@ pub struct Research {
     pub completed_description: Id,
     pub required_items: Option<Vec<ItemStack>>,
     pub attached_puzzle: Option<(Id, RhaiDataMap)>,
+    /// How many failed attempts at the attached puzzle before a hint is shown. Never shows a hint if None.
+    pub puzzle_hint_after: Option<u32>,
 }
 
 impl ResourceManager {
@@ -84,6 +88,7 @@ impl ResourceManager {
             )
         });
         let icon_mode = research.icon_mode;
+        let puzzle_hint_after = research.puzzle_hint_after;
 
         let index = self.registry.researches.add_node(Research {
             id,
@@ -96,6 +101,7 @@ impl ResourceManager {
             required_items,
             attached_puzzle,
             icon_mode,
+            puzzle_hint_after,
         });
         self.registry.researches_id_map.insert(id, index);
 
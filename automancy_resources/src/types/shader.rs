@@ -1,42 +1,154 @@
 use std::ffi::OsStr;
-use std::fs::{read_dir, read_to_string};
+use std::fs::read_to_string;
 use std::path::Path;
 
-use automancy_defs::flexstr::ToSharedStr;
+use hashbrown::{HashMap, HashSet};
+use thiserror::Error;
+
+use automancy_defs::flexstr::{SharedStr, ToSharedStr};
 use automancy_defs::log;
 
-use crate::{LoadResourceError, ResourceManager, COULD_NOT_GET_FILE_STEM, SHADER_EXT};
+use crate::{list_sorted, LoadResourceError, ResourceManager, COULD_NOT_GET_FILE_STEM, SHADER_EXT};
+
+/// The folder in `shaders` holding the snippets other shaders can include.
+/// The snippets aren't shaders on their own, and are named like IDs, such as `core:fullscreen`.
+const INCLUDE_DIR: &str = "include";
+/// Starts a line that is replaced with the snippet it names, such as `#include "core:fullscreen"`.
+const INCLUDE_DIRECTIVE: &str = "#include";
+
+/// An include that can't be expanded.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ShaderIncludeError {
+    #[error("{shader} includes {snippet}, which doesn't exist")]
+    UnknownSnippet { shader: String, snippet: String },
+    #[error("{shader} includes {snippet} from within itself")]
+    Cycle { shader: String, snippet: String },
+    #[error("{shader} has an include that isn't a quoted name: {line}")]
+    Malformed { shader: String, line: String },
+}
+
+/// Expands the `#include "namespace:name"` lines in the shader with the snippets they name, and the includes in those.
+///
+/// Each snippet ends up in the shader once, however many times it is included, so a snippet can include
+/// whatever it depends on without clashing with the other snippets that do too.
+pub fn preprocess_shader(
+    name: &str,
+    source: &str,
+    snippets: &HashMap<SharedStr, String>,
+) -> Result<String, ShaderIncludeError> {
+    let mut result = String::new();
+
+    expand(
+        name,
+        source,
+        snippets,
+        &mut vec![],
+        &mut HashSet::new(),
+        &mut result,
+    )?;
+
+    Ok(result)
+}
+
+fn expand(
+    shader: &str,
+    source: &str,
+    snippets: &HashMap<SharedStr, String>,
+    stack: &mut Vec<String>,
+    included: &mut HashSet<String>,
+    result: &mut String,
+) -> Result<(), ShaderIncludeError> {
+    for line in source.lines() {
+        let Some(rest) = line.trim().strip_prefix(INCLUDE_DIRECTIVE) else {
+            result.push_str(line);
+            result.push('\n');
+
+            continue;
+        };
+
+        let snippet = rest
+            .trim()
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .ok_or_else(|| ShaderIncludeError::Malformed {
+                shader: shader.to_string(),
+                line: line.to_string(),
+            })?;
+
+        if stack.iter().any(|v| *v == snippet) {
+            return Err(ShaderIncludeError::Cycle {
+                shader: shader.to_string(),
+                snippet: snippet.to_string(),
+            });
+        }
+
+        if !included.insert(snippet.to_string()) {
+            continue;
+        }
+
+        let source = snippets
+            .get(snippet)
+            .ok_or_else(|| ShaderIncludeError::UnknownSnippet {
+                shader: shader.to_string(),
+                snippet: snippet.to_string(),
+            })?;
+
+        stack.push(snippet.to_string());
+        expand(shader, source, snippets, stack, included, result)?;
+        stack.pop();
+    }
+
+    Ok(())
+}
 
 impl ResourceManager {
     pub fn load_shaders(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| LoadResourceError::OsStringError(dir.to_path_buf()))?;
+
         let shaders = dir.join("shaders");
-        if let Ok(shaders) = read_dir(shaders) {
-            for file in shaders
+
+        for (folder, is_snippet) in [(shaders.clone(), false), (shaders.join(INCLUDE_DIR), true)] {
+            for file in list_sorted(&folder)
                 .into_iter()
-                .flatten()
-                .map(|v| v.path())
                 .filter(|v| v.extension() == Some(OsStr::new(SHADER_EXT)))
             {
                 log::info!("Loading shader at {file:?}");
 
                 if let Ok(shader) = read_to_string(&file) {
-                    self.shaders.insert(
-                        file.file_stem()
-                            .ok_or_else(|| {
-                                LoadResourceError::InvalidFileError(
-                                    file.clone(),
-                                    COULD_NOT_GET_FILE_STEM,
-                                )
-                            })?
-                            .to_str()
-                            .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?
-                            .to_shared_str(),
-                        shader,
-                    );
+                    let name = file
+                        .file_stem()
+                        .ok_or_else(|| {
+                            LoadResourceError::InvalidFileError(
+                                file.clone(),
+                                COULD_NOT_GET_FILE_STEM,
+                            )
+                        })?
+                        .to_str()
+                        .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?;
+
+                    if is_snippet {
+                        self.shader_snippets
+                            .insert(format!("{namespace}:{name}").to_shared_str(), shader);
+                    } else {
+                        self.shaders.insert(name.to_shared_str(), shader);
+                    }
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Expands the includes in all the shaders.
+    /// This is done once every namespace is loaded, so shaders can include snippets from any of them.
+    pub fn preprocess_shaders(&mut self) -> Result<(), ShaderIncludeError> {
+        for (name, shader) in self.shaders.iter_mut() {
+            *shader = preprocess_shader(name, shader, &self.shader_snippets)?;
+        }
+
+        Ok(())
+    }
 }
@@ -7,15 +7,42 @@ use serde::{Deserialize, Serialize};
 use automancy_defs::id::{Id, IdRaw};
 use automancy_defs::log;
 
+use crate::data::stack::ItemAmount;
 use crate::data::{DataMap, DataMapRaw};
 use crate::{load_recursively, ResourceManager, RON_EXT};
 
+/// A set of sounds, one of which is picked at random each time it is played.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SoundSet {
+    /// The names of the sounds to pick from.
+    pub sounds: Vec<String>,
+    /// How far the pitch can randomly stray from the original, e.g. 0.1 for up to 10% higher or lower.
+    #[serde(default)]
+    pub pitch_jitter: f64,
+}
+
+/// The sounds a tile makes. Any that aren't set fall back to the default sounds.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TileSounds {
+    #[serde(default)]
+    pub placement: Option<SoundSet>,
+    #[serde(default)]
+    pub removal: Option<SoundSet>,
+    /// Played when the tile does work, such as sending out an item.
+    #[serde(default)]
+    pub work: Option<SoundSet>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TileDefRaw {
     pub id: IdRaw,
     pub function: Option<IdRaw>,
     pub model: IdRaw,
     pub data: DataMapRaw,
+    #[serde(default)]
+    pub sounds: TileSounds,
+    #[serde(default)]
+    pub cost: Vec<(IdRaw, ItemAmount)>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +50,9 @@ pub struct TileDef {
     pub model: Id,
     pub function: Option<Id>,
     pub data: DataMap,
+    pub sounds: TileSounds,
+    /// The items placing the tile takes from the player's inventory, on maps with survival on.
+    pub cost: Vec<(Id, ItemAmount)>,
 }
 
 impl ResourceManager {
@@ -33,8 +63,20 @@ impl ResourceManager {
 
         let id = tile.id.to_id(&mut self.interner);
         let function = tile.function.map(|v| v.to_id(&mut self.interner));
-        let data = tile.data.intern_to_data(&mut self.interner);
+        let mut data = tile.data.intern_to_data(&mut self.interner);
+        for err in self
+            .registry
+            .data_schema
+            .retain_valid(&self.interner, &mut data)
+        {
+            log::warn!("Dropping data of tile at {file:?}: {err}");
+        }
         let model = tile.model.to_id(&mut self.interner);
+        let cost = tile
+            .cost
+            .into_iter()
+            .map(|(id, amount)| (id.to_id(&mut self.interner), amount))
+            .collect();
 
         self.registry.tiles.insert(
             id,
@@ -42,6 +84,8 @@ impl ResourceManager {
                 function,
                 model,
                 data,
+                sounds: tile.sounds,
+                cost,
             },
         );
 
@@ -1,20 +1,115 @@
 use std::ffi::OsStr;
-use std::fs::{read_dir, read_to_string};
+use std::fs::read_to_string;
 use std::path::Path;
+use std::sync::Arc;
 
+use hashbrown::hash_map::Entry;
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
 use automancy_defs::flexstr::{SharedStr, ToSharedStr};
-use automancy_defs::id::{Id, IdRaw};
+use automancy_defs::id::{Id, IdRaw, Interner};
 use automancy_defs::log;
 
-use crate::{ResourceManager, RON_EXT};
+use crate::{list_sorted, LoadResourceError, ResourceManager, RON_EXT};
+
+/// The language used when none is picked, and for the namespaces that don't have the one picked.
+pub const DEFAULT_LANGUAGE: &str = "en_US";
+
+/// A language there are translations for, named after its files, such as `en_US` for `en_US.ron`.
+#[derive(Debug, Clone, Default)]
+pub struct Language {
+    /// the name of the language, in itself
+    pub name: String,
+    /// the namespaces with translations in the language
+    pub namespaces: Vec<String>,
+}
+
+/// How numbers are written in a language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Locale {
+    /// what comes between the whole part and the fraction, such as the `.` in `1.5`
+    pub decimal_separator: char,
+    /// what comes between each group of 3 digits, such as the `,` in `12,345`
+    pub group_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: ',',
+        }
+    }
+}
+
+/// The suffixes for shortened numbers, largest first.
+const COMPACT_SUFFIXES: [(f64, &str); 3] = [(1e9, "G"), (1e6, "M"), (1e3, "k")];
+
+impl Locale {
+    /// Puts the group separators into a run of digits, which may start with a minus.
+    fn group(&self, digits: &str) -> String {
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", digits),
+        };
+
+        let mut result = String::from(sign);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                result.push(self.group_separator);
+            }
+            result.push(digit);
+        }
+
+        result
+    }
+
+    /// Writes a whole number in full, such as an amount of items.
+    pub fn count(&self, value: impl Into<i128>) -> String {
+        self.group(&value.into().to_string())
+    }
+
+    /// Writes a number with the given number of digits after the separator.
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let written = format!("{value:.places$}");
+
+        match written.split_once('.') {
+            Some((whole, fraction)) => {
+                format!("{}{}{fraction}", self.group(whole), self.decimal_separator)
+            }
+            None => self.group(&written),
+        }
+    }
+
+    /// Writes a number shortened with a k, M or G suffix once it gets large, such as `12.5k`,
+    /// for where there is little room, like counts on icons and rates.
+    pub fn compact(&self, value: f64) -> String {
+        let (value, suffix) = COMPACT_SUFFIXES
+            .iter()
+            .find(|(size, _)| value.abs() >= *size)
+            .map_or((value, ""), |(size, suffix)| (value / size, *suffix));
+
+        let written = self.decimal(value, 1);
+        let written = written
+            .strip_suffix('0')
+            .and_then(|v| v.strip_suffix(self.decimal_separator))
+            .unwrap_or(&written);
+
+        format!("{written}{suffix}")
+    }
+}
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct TranslateRaw {
+    /// the name of the language, in itself, as shown in the language picker
+    #[serde(default)]
+    language: String,
     none: String,
     unnamed: String,
+    #[serde(default)]
+    locale: Locale,
     items: HashMap<IdRaw, String>,
     tiles: HashMap<IdRaw, String>,
     categories: HashMap<IdRaw, String>,
@@ -24,10 +119,40 @@ pub struct TranslateRaw {
     research: HashMap<IdRaw, SharedStr>,
 }
 
+impl TranslateRaw {
+    /// Fills in the translations this is missing from the fallback. Gives how many were filled in.
+    fn fill_from(&mut self, fallback: TranslateRaw) -> usize {
+        fn fill<V>(map: &mut HashMap<IdRaw, V>, fallback: HashMap<IdRaw, V>) -> usize {
+            let mut filled = 0;
+
+            for (id, str) in fallback {
+                if let Entry::Vacant(entry) = map.entry(id) {
+                    entry.insert(str);
+                    filled += 1;
+                }
+            }
+
+            filled
+        }
+
+        fill(&mut self.items, fallback.items)
+            + fill(&mut self.tiles, fallback.tiles)
+            + fill(&mut self.categories, fallback.categories)
+            + fill(&mut self.scripts, fallback.scripts)
+            + fill(&mut self.gui, fallback.gui)
+            + fill(&mut self.error, fallback.error)
+            + fill(&mut self.research, fallback.research)
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Translate {
+    /// the code of the language picked, such as `en_US`
+    pub language: String,
     pub none: SharedStr,
     pub unnamed: SharedStr,
+    /// how numbers are written in the language
+    pub locale: Locale,
     pub items: HashMap<Id, SharedStr>,
     pub tiles: HashMap<Id, SharedStr>,
     pub categories: HashMap<Id, SharedStr>,
@@ -37,84 +162,211 @@ pub struct Translate {
     pub research: HashMap<Id, SharedStr>,
 }
 
-impl ResourceManager {
-    fn load_translate(&mut self, file: &Path) -> anyhow::Result<()> {
-        log::info!("Loading translate at: {file:?}");
-
-        let translate: TranslateRaw = ron::from_str(&read_to_string(file)?)?;
-
-        let none = translate.none.to_shared_str();
-        let unnamed = translate.unnamed.to_shared_str();
-
-        let items = translate
-            .items
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let tiles = translate
-            .tiles
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let categories = translate
-            .categories
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let scripts = translate
-            .scripts
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let gui = translate
-            .gui
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let error = translate
-            .error
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-        let research = translate
-            .research
-            .into_iter()
-            .map(|(id, str)| (id.to_id(&mut self.interner), str.into()))
-            .collect();
-
-        self.translates = Translate {
-            none,
-            unnamed,
-            items,
-            tiles,
-            categories,
-            scripts,
-            gui,
-            error,
-            research,
+impl TranslateRaw {
+    /// Turns the IDs into interned ones, leaving out the translations `to_id` gives no ID for.
+    fn into_translate(
+        self,
+        language: &str,
+        mut to_id: impl FnMut(&IdRaw) -> Option<Id>,
+    ) -> Translate {
+        fn convert<V: Into<SharedStr>>(
+            map: HashMap<IdRaw, V>,
+            to_id: &mut impl FnMut(&IdRaw) -> Option<Id>,
+        ) -> HashMap<Id, SharedStr> {
+            map.into_iter()
+                .filter_map(|(id, str)| Some((to_id(&id)?, str.into())))
+                .collect()
+        }
+
+        Translate {
+            language: language.to_string(),
+            none: self.none.to_shared_str(),
+            unnamed: self.unnamed.to_shared_str(),
+            locale: self.locale,
+            items: convert(self.items, &mut to_id),
+            tiles: convert(self.tiles, &mut to_id),
+            categories: convert(self.categories, &mut to_id),
+            scripts: convert(self.scripts, &mut to_id),
+            gui: convert(self.gui, &mut to_id),
+            error: convert(self.error, &mut to_id),
+            research: convert(self.research, &mut to_id),
+        }
+    }
+}
+
+/// Reads the translations of the namespace in every language there are some in, by their codes.
+fn read_translates(dir: &Path) -> anyhow::Result<HashMap<String, TranslateRaw>> {
+    let mut found = HashMap::new();
+
+    for file in list_sorted(&dir.join("translates"))
+        .into_iter()
+        .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
+    {
+        let Some(code) = file.file_stem().and_then(OsStr::to_str) else {
+            continue;
         };
 
+        log::info!("Reading translate at: {file:?}");
+
+        found.insert(code.to_string(), ron::from_str(&read_to_string(&file)?)?);
+    }
+
+    Ok(found)
+}
+
+/// Picks the translations in the language out of the ones found in the namespace, or the ones in
+/// [`DEFAULT_LANGUAGE`] if it doesn't have that one. Anything missing from the language is filled in from the default one.
+fn pick_translate(
+    mut found: HashMap<String, TranslateRaw>,
+    language: &str,
+    namespace: &str,
+) -> Option<TranslateRaw> {
+    let translate = match (found.remove(language), found.remove(DEFAULT_LANGUAGE)) {
+        (Some(mut translate), Some(fallback)) => {
+            let filled = translate.fill_from(fallback);
+
+            if filled > 0 {
+                log::info!(
+                    "{filled} translations are missing from {language} in {namespace}, using {DEFAULT_LANGUAGE} for them"
+                );
+            }
+
+            Some(translate)
+        }
+        (translate, fallback) => translate.or(fallback),
+    };
+
+    if translate.is_none() && !found.is_empty() {
+        log::warn!(
+            "The namespace {namespace} has no translations in {language} or {DEFAULT_LANGUAGE}"
+        );
+    }
+
+    translate
+}
+
+impl ResourceManager {
+    /// The translations in the language picked. These can be swapped for another language's
+    /// with [`ResourceManager::set_translates`] while the resources are shared.
+    pub fn translates(&self) -> Arc<Translate> {
+        self.translates.read().unwrap().clone()
+    }
+
+    /// Swaps in the translations of another language, such as ones loaded by [`ResourceManager::read_translate`].
+    pub fn set_translates(&self, translates: Translate) {
+        *self.translates.write().unwrap() = Arc::new(translates);
+    }
+
+    /// Loads the translations of the namespace in the language, or in [`DEFAULT_LANGUAGE`]
+    /// if the namespace doesn't have that one, and lists the languages it has in [`ResourceManager::languages`].
+    pub fn load_translates(&mut self, dir: &Path, language: &str) -> anyhow::Result<()> {
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| LoadResourceError::OsStringError(dir.to_path_buf()))?;
+
+        let found = read_translates(dir)?;
+
+        for (code, translate) in &found {
+            let listed = self.languages.entry(code.clone()).or_default();
+            if listed.name.is_empty() {
+                listed.name = if translate.language.is_empty() {
+                    code.clone()
+                } else {
+                    translate.language.clone()
+                };
+            }
+            listed.namespaces.push(namespace.to_string());
+        }
+
+        if let Some(translate) = pick_translate(found, language, namespace) {
+            let interner = &mut self.interner;
+            let translate = translate.into_translate(language, |id| Some(id.to_id(interner)));
+
+            *self.translates.get_mut().unwrap() = Arc::new(translate);
+        }
+
         Ok(())
     }
 
-    pub fn load_translates(&mut self, dir: &Path) -> anyhow::Result<()> {
-        let translates = dir.join("translates");
-        let translates = read_dir(translates);
-
-        if let Ok(translates) = translates {
-            for file in translates
-                .into_iter()
-                .flatten()
-                .map(|v| v.path())
-                .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
-            {
-                // TODO language selection
-                if file.file_stem() == Some(OsStr::new("en_US")) {
-                    self.load_translate(&file)?;
+    /// Reads the translations of the namespace in the language, the same way [`ResourceManager::load_translates`] does,
+    /// but without interning anything, so it can be done once the resources are shared.
+    /// Gives nothing if the namespace has no translations.
+    pub fn read_translate(&self, dir: &Path, language: &str) -> anyhow::Result<Option<Translate>> {
+        let namespace = dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| LoadResourceError::OsStringError(dir.to_path_buf()))?;
+
+        Ok(
+            pick_translate(read_translates(dir)?, language, namespace).map(|translate| {
+                translate.into_translate(language, |id| self.interner.get(id.to_string()))
+            }),
+        )
+    }
+
+    /// Gives everything that has no translation in any language its ID as its name, marked so it stands out,
+    /// such as `<automancy:foo>`, so it can still be told apart from everything else.
+    /// This is done once everything is loaded, and to translations loaded afterwards.
+    pub fn fill_missing_translations(&self, translates: &mut Translate) {
+        fn fill(
+            interner: &Interner,
+            map: &mut HashMap<Id, SharedStr>,
+            ids: impl IntoIterator<Item = Id>,
+        ) -> usize {
+            let mut filled = 0;
+
+            for id in ids {
+                if let Entry::Vacant(entry) = map.entry(id) {
+                    let name = interner.resolve(id).unwrap_or("?");
+
+                    entry.insert(format!("<{name}>").into());
+                    filled += 1;
                 }
             }
+
+            filled
         }
 
-        Ok(())
+        let interner = &self.interner;
+        let registry = &self.registry;
+
+        let filled = [
+            (
+                &mut translates.items,
+                registry.items.keys().copied().collect(),
+            ),
+            (
+                &mut translates.tiles,
+                registry.tiles.keys().copied().collect(),
+            ),
+            (
+                &mut translates.categories,
+                registry.categories.keys().copied().collect(),
+            ),
+            (
+                &mut translates.scripts,
+                registry.scripts.keys().copied().collect(),
+            ),
+            (
+                &mut translates.research,
+                registry
+                    .researches
+                    .node_weights()
+                    .flat_map(|v| [v.name, v.description, v.completed_description])
+                    .collect(),
+            ),
+            (&mut translates.gui, registry.gui_ids.all()),
+            (&mut translates.error, registry.err_ids.all()),
+        ]
+        .into_iter()
+        .map(|(map, ids)| fill(interner, map, ids))
+        .sum::<usize>();
+
+        if filled > 0 {
+            log::warn!(
+                "{filled} things have no translation in any language, showing their IDs instead"
+            );
+        }
     }
 }
@@ -0,0 +1,49 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use hashbrown::HashMap;
+#[cfg(feature = "audio")]
+use kira::sound::static_sound::StaticSoundData;
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::flexstr::ToSharedStr;
+use automancy_defs::log;
+
+use crate::ResourceManager;
+
+/// The interface events that can have a sound attached to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UiSound {
+    ButtonHover,
+    ButtonClick,
+    PopupOpen,
+    Error,
+    ResearchComplete,
+}
+
+impl ResourceManager {
+    /// Loads which audio is played for each interface event. Later namespaces override earlier ones.
+    pub fn load_ui_sounds(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let file = dir.join("ui_sounds.ron");
+
+        if file.exists() {
+            log::info!("Loading UI sounds at {file:?}");
+
+            let sounds: HashMap<UiSound, String> = ron::from_str(&read_to_string(file)?)?;
+
+            for (sound, audio) in sounds {
+                self.ui_sounds.insert(sound, audio.to_shared_str());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the audio to play for an interface event, if there is any.
+    #[cfg(feature = "audio")]
+    pub fn ui_sound(&self, sound: UiSound) -> Option<&StaticSoundData> {
+        self.ui_sounds
+            .get(&sound)
+            .and_then(|audio| self.audio.get(audio))
+    }
+}
@@ -0,0 +1,57 @@
+@group(0) @binding(0)
+var frame_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var frame_sampler: sampler;
+
+#include "core:fullscreen"
+
+const LUMA = vec3<f32>(0.299, 0.587, 0.114);
+// how bright a pixel has to be to glow
+const THRESHOLD: f32 = 0.75;
+// how strongly the glow is added back onto the frame
+const INTENSITY: f32 = 0.6;
+
+// keeps only the parts of the frame brighter than the threshold
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(frame_texture, frame_sampler, in.uv).rgb;
+    let luma = dot(color, LUMA);
+
+    let amount = max(luma - THRESHOLD, 0.0) / max(luma, 0.0001);
+
+    return vec4(color * amount, 1.0);
+}
+
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    // the weights of a 9 tap gaussian blur, from the center outwards
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    let texel = direction / vec2<f32>(textureDimensions(frame_texture));
+
+    var result = textureSample(frame_texture, frame_sampler, uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = texel * f32(i);
+
+        result += textureSample(frame_texture, frame_sampler, uv + offset).rgb * weights[i];
+        result += textureSample(frame_texture, frame_sampler, uv - offset).rgb * weights[i];
+    }
+
+    return vec4(result, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_vertical(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2(0.0, 1.0));
+}
+
+// added onto the frame by the blending of the pipeline
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let glow = textureSample(frame_texture, frame_sampler, in.uv).rgb;
+
+    return vec4(glow * INTENSITY, 0.0);
+}
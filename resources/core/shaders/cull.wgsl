@@ -0,0 +1,104 @@
+// culls the instances against the view, copying the ones in view to the instances that are drawn,
+// and counting them in the draws they belong to
+
+struct Uniform {
+    instance_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+}
+
+struct MatrixData {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+}
+
+struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+// the instances are read word by word, as their layout doesn't follow the alignment of WGSL structs
+const INSTANCE_WORDS: u32 = 11u;
+const MATRIX_INDEX_WORD: u32 = 9u;
+const WORLD_MATRIX_INDEX_WORD: u32 = 10u;
+
+// how far any model reaches from its origin, before it is scaled
+const MODEL_RADIUS: f32 = 1.5;
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var<storage, read> matrix_data: array<MatrixData>;
+
+@group(0) @binding(2)
+var<storage, read> world_matrix_data: array<mat4x4<f32>>;
+
+@group(0) @binding(3)
+var<storage, read> instances: array<u32>;
+
+@group(0) @binding(4)
+var<storage, read> draw_indices: array<u32>;
+
+@group(0) @binding(5)
+var<storage, read_write> culled_instances: array<u32>;
+
+@group(0) @binding(6)
+var<storage, read_write> draws: array<DrawIndexedIndirectArgs>;
+
+// whether the sphere is at least partly within the sides of the view.
+// the near and far planes are left out, since the view reaches past the whole visible map anyway
+fn in_view(world_matrix: mat4x4<f32>, center: vec3<f32>, radius: f32) -> bool {
+    // the rows of the matrix, which the planes of the view are made of
+    let m = transpose(world_matrix);
+
+    var planes = array<vec4<f32>, 4>(
+        m[3] + m[0],
+        m[3] - m[0],
+        m[3] + m[1],
+        m[3] - m[1],
+    );
+
+    for (var i = 0; i < 4; i++) {
+        let plane = planes[i];
+
+        if dot(plane.xyz, center) + plane.w < -radius * length(plane.xyz) {
+            return false;
+        }
+    }
+
+    return true;
+}
+
+@compute @workgroup_size(64)
+fn cs_cull(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+
+    if index >= ubo.instance_count {
+        return;
+    }
+
+    let src = index * INSTANCE_WORDS;
+
+    let model_matrix = matrix_data[instances[src + MATRIX_INDEX_WORD]].model_matrix;
+    let world_matrix = world_matrix_data[instances[src + WORLD_MATRIX_INDEX_WORD]];
+
+    let center = model_matrix[3].xyz / model_matrix[3].w;
+    let scale = max(length(model_matrix[0].xyz), max(length(model_matrix[1].xyz), length(model_matrix[2].xyz)));
+
+    if !in_view(world_matrix, center, MODEL_RADIUS * scale) {
+        return;
+    }
+
+    let draw = draw_indices[index];
+    let slot = atomicAdd(&draws[draw].instance_count, 1u);
+    let dst = (draws[draw].first_instance + slot) * INSTANCE_WORDS;
+
+    for (var i = 0u; i < INSTANCE_WORDS; i++) {
+        culled_instances[dst + i] = instances[src + i];
+    }
+}
@@ -1,18 +1,4 @@
-struct Uniform {
-    light_color: vec4<f32>,
-}
-
-struct MatrixData {
-    model_matrix: mat4x4<f32>,
-    world_matrix: mat4x4<f32>,
-    normal_matrix: mat3x3<f32>,
-}
-
-@group(0) @binding(0)
-var<uniform> ubo: Uniform;
-
-@group(0) @binding(1)
-var<storage, read> matrix_data: array<MatrixData>;
+#include "core:lighting"
 
 struct VertexInput {
     @location(0) pos: vec3<f32>,
@@ -25,6 +11,7 @@ struct InstanceInput {
     @location(4) alpha: f32,
     @location(5) light_pos: vec4<f32>,
     @location(6) matrix_index: u32,
+    @location(7) world_matrix_index: u32,
 }
 
 struct VertexOutput {
@@ -45,7 +32,7 @@ fn vs_main(
     let m = matrix_data[instance.matrix_index];
     let model_matrix  = m.model_matrix;
     let normal_matrix = m.normal_matrix;
-    let world_matrix  = m.world_matrix;
+    let world_matrix  = world_matrix_data[instance.world_matrix_index];
 
     let model_pos = model_matrix * vec4(in.pos, 1.0);
 
@@ -65,25 +52,19 @@ struct FragmentOutput {
     @location(2) model: vec4<f32>,
 }
 
-const TINT_DIR = vec3<f32>(-0.348155, 0.348155, 0.870388);
-
 @fragment
 fn fs_main(in: VertexOutput) -> FragmentOutput {
-    let light_pos = in.light_pos.xyz;
-    let light_dir = normalize(light_pos - in.model_pos);
-
-    let diff = pow(max(0.0, dot(light_dir, in.normal)), 0.420) * 0.9;
-    let spec = pow(max(0.0, dot(reflect(-light_dir, in.normal), light_dir)), 2.0) * 0.15;
-    let tint = pow(max(0.0, dot(in.normal, TINT_DIR)), 16.0) * 0.69;
-    let intensity = max(0.25, diff + spec + tint);
-
-    let lighting = ubo.light_color.rgb * max(1.0 - in.light_pos.w, intensity * in.light_pos.w);
-
     var out: FragmentOutput;
 
-    out.color = vec4(in.color.rgb * lighting, in.color.a);
+    out.color = vec4(in.color.rgb * lighting(in.light_pos, in.model_pos, in.normal), in.color.a);
     out.normal = vec4(in.normal, 0.0);
     out.model = vec4(in.model_pos, 0.0);
 
     return out;
-}
\ No newline at end of file
+}
+
+// the tile in the flat color of its outline, for the outline pass to trace around
+@fragment
+fn fs_mask(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(in.color.rgb, 1.0);
+}
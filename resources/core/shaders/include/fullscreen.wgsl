@@ -0,0 +1,27 @@
+// a triangle covering the whole screen, drawn with 3 vertices and no vertex buffer
+
+struct VertexInput {
+    @builtin(vertex_index) idx: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    in: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2(
+         f32((in.idx << 1u) & 2u),
+         f32(in.idx & 2u)
+     );
+
+    out.pos = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2(uv.x, 1.0 - uv.y);
+
+    return out;
+}
@@ -0,0 +1,16 @@
+#include "core:uniform"
+
+const TINT_DIR = vec3<f32>(-0.348155, 0.348155, 0.870388);
+
+// the light reaching a point on a model, from the light at light_pos.xyz
+// light_pos.w is how much the light is used, with the rest lit evenly
+fn lighting(light_pos: vec4<f32>, model_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    let light_dir = normalize(light_pos.xyz - model_pos);
+
+    let diff = pow(max(0.0, dot(light_dir, normal)), 0.420) * 0.9;
+    let spec = pow(max(0.0, dot(reflect(-light_dir, normal), light_dir)), 2.0) * 0.15;
+    let tint = pow(max(0.0, dot(normal, TINT_DIR)), 16.0) * 0.69;
+    let intensity = max(0.25, diff + spec + tint);
+
+    return ubo.light_color.rgb * max(1.0 - light_pos.w, intensity * light_pos.w);
+}
@@ -0,0 +1,19 @@
+// the uniform and the matrices the world is drawn with
+
+struct Uniform {
+    light_color: vec4<f32>,
+}
+
+struct MatrixData {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var<storage, read> matrix_data: array<MatrixData>;
+
+@group(0) @binding(2)
+var<storage, read> world_matrix_data: array<mat4x4<f32>>;
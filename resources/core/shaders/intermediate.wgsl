@@ -3,33 +3,24 @@ var frame_texture: texture_2d<f32>;
 @group(0) @binding(1)
 var frame_sampler: sampler;
 
-struct VertexInput {
-    @builtin(vertex_index) idx: u32,
-}
+#include "core:fullscreen"
 
-struct VertexOutput {
-    @builtin(position) pos: vec4<f32>,
-    @location(0) uv: vec2<f32>,
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(frame_texture, frame_sampler, in.uv);
 }
 
-@vertex
-fn vs_main(
-    in: VertexInput,
-) -> VertexOutput {
-    var out: VertexOutput;
-
-    let uv = vec2(
-         f32((in.idx << 1u) & 2u),
-         f32(in.idx & 2u)
-     );
+fn linear_to_srgb(linear: vec3<f32>) -> vec3<f32> {
+    let cutoff = linear <= vec3(0.0031308);
+    let lower = linear * 12.92;
+    let higher = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
 
-    out.pos = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
-    out.uv = vec2(uv.x, 1.0 - uv.y);
-
-    return out;
+    return select(higher, lower, cutoff);
 }
 
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(frame_texture, frame_sampler, in.uv);
-}
\ No newline at end of file
+fn fs_main_encode_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(frame_texture, frame_sampler, in.uv);
+
+    return vec4(linear_to_srgb(color.rgb), color.a);
+}
@@ -0,0 +1,32 @@
+@group(0) @binding(0)
+var mask_texture: texture_2d<f32>;
+
+#include "core:fullscreen"
+
+// how far the outlines reach out from the tiles, in pixels
+const WIDTH: i32 = 2;
+
+// the color of the outlined tile nearby, over the pixels just outside of the tiles.
+// blended onto the frame by the pipeline
+@fragment
+fn fs_outline(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(mask_texture));
+    let pos = vec2<i32>(in.pos.xy);
+
+    if textureLoad(mask_texture, pos, 0).a > 0.0 {
+        return vec4(0.0);
+    }
+
+    var result = vec4(0.0);
+    for (var y = -WIDTH; y <= WIDTH; y++) {
+        for (var x = -WIDTH; x <= WIDTH; x++) {
+            let sample = textureLoad(mask_texture, clamp(pos + vec2(x, y), vec2(0), size - 1), 0);
+
+            if sample.a > result.a {
+                result = sample;
+            }
+        }
+    }
+
+    return result;
+}
@@ -0,0 +1,179 @@
+use rand::{thread_rng, Rng};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use automancy_defs::id::Id;
+use automancy_defs::log;
+use automancy_resources::kira::sound::PlaybackRate;
+use automancy_resources::types::tile::SoundSet;
+use automancy_resources::types::ui_sound::UiSound;
+use automancy_resources::{format, ResourceManager};
+
+use crate::game::GameEvent;
+use crate::gui::caption::push_caption;
+use crate::gui::sound;
+use crate::gui::toast::push_toast;
+use crate::GameState;
+
+/// The sound played when a tile without its own placement sounds is placed.
+const DEFAULT_PLACEMENT_SOUND: &str = "tile_placement";
+/// The sound played when a tile without its own removal sounds is removed.
+const DEFAULT_REMOVAL_SOUND: &str = "tile_removal";
+
+/// Plays sounds, and shows their captions and toasts, in reaction to what happens in the game.
+pub struct AudioDispatch {
+    sender: UnboundedSender<GameEvent>,
+    events: UnboundedReceiver<GameEvent>,
+}
+
+/// What kind of sound a tile is making.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileSoundKind {
+    Placement,
+    Removal,
+    Work,
+}
+
+impl AudioDispatch {
+    pub fn new(sender: UnboundedSender<GameEvent>, events: UnboundedReceiver<GameEvent>) -> Self {
+        Self { sender, events }
+    }
+
+    /// Sends an event that happened on the client's side, such as in the GUI.
+    pub fn send(&self, event: GameEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Gets the sound set of a tile for the kind of sound, if it has one.
+fn sound_set(resource_man: &ResourceManager, id: Id, kind: TileSoundKind) -> Option<&SoundSet> {
+    let sounds = &resource_man.registry.tiles.get(&id)?.sounds;
+
+    match kind {
+        TileSoundKind::Placement => sounds.placement.as_ref(),
+        TileSoundKind::Removal => sounds.removal.as_ref(),
+        TileSoundKind::Work => sounds.work.as_ref(),
+    }
+}
+
+/// Plays a random sound out of the set, with its pitch randomly shifted.
+fn play_set(state: &mut GameState, set: &SoundSet) {
+    let mut rng = thread_rng();
+
+    if set.sounds.is_empty() {
+        return;
+    }
+    let name = &set.sounds[rng.gen_range(0..set.sounds.len())];
+
+    let Some(sound) = state.resource_man.audio.get(name.as_str()) else {
+        log::warn!("Tile sound {name} does not exist");
+        return;
+    };
+
+    let jitter = set.pitch_jitter.abs();
+    let rate = if jitter > 0.0 {
+        1.0 + rng.gen_range(-jitter..=jitter)
+    } else {
+        1.0
+    };
+
+    state
+        .audio_man
+        .play(&sound.with_modified_settings(|v| v.playback_rate(PlaybackRate::Factor(rate))));
+}
+
+/// Plays the sounds, and shows the captions and toasts, for all the game events that happened since the last call.
+/// At most one sound of each kind is played per call, so that many tiles acting at once don't get too loud.
+pub fn dispatch(state: &mut GameState) {
+    let mut played = Vec::with_capacity(3);
+
+    while let Ok(event) = state.audio_dispatch.events.try_recv() {
+        let (kind, coord, id) = match event {
+            GameEvent::TilePlaced(coord, id) => (TileSoundKind::Placement, coord, id),
+            GameEvent::TileRemoved(coord, id) => (TileSoundKind::Removal, coord, id),
+            GameEvent::TileWorked(coord, id) => (TileSoundKind::Work, coord, id),
+            GameEvent::TileCrashed(_, id) => {
+                let caption = format(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.caption_tile_crashed]
+                        .as_str(),
+                    &[state.resource_man.tile_name(&id).as_str()],
+                );
+                push_caption(state, caption);
+
+                continue;
+            }
+            GameEvent::ResearchComplete(id) => {
+                sound::play(state, UiSound::ResearchComplete);
+
+                let name = state
+                    .resource_man
+                    .get_research(id)
+                    .map(|research| state.resource_man.research_str(&research.name))
+                    .unwrap_or_default();
+                let caption = format(
+                    state.resource_man.translates().gui[&state
+                        .resource_man
+                        .registry
+                        .gui_ids
+                        .caption_research_complete]
+                        .as_str(),
+                    &[name.as_str()],
+                );
+                push_caption(state, caption);
+
+                let toast = format(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.toast_research_complete]
+                        .as_str(),
+                    &[name.as_str()],
+                );
+                push_toast(state, toast);
+
+                continue;
+            }
+            GameEvent::Autosaved => {
+                let toast = state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.toast_autosaved]
+                    .to_string();
+                push_toast(state, toast);
+
+                continue;
+            }
+            GameEvent::Alert => {
+                sound::play(state, UiSound::Error);
+
+                let caption = state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.caption_alert]
+                    .to_string();
+                push_caption(state, caption);
+
+                continue;
+            }
+        };
+
+        if played.contains(&kind) {
+            continue;
+        }
+
+        // only the tiles the player can see make noise while working
+        if kind == TileSoundKind::Work && !state.camera.culling_range.is_in_bounds(*coord) {
+            continue;
+        }
+
+        let resource_man = state.resource_man.clone();
+
+        if let Some(set) = sound_set(&resource_man, id, kind) {
+            play_set(state, set);
+        } else {
+            let default = match kind {
+                TileSoundKind::Placement => DEFAULT_PLACEMENT_SOUND,
+                TileSoundKind::Removal => DEFAULT_REMOVAL_SOUND,
+                TileSoundKind::Work => continue,
+            };
+
+            state.audio_man.play(&resource_man.audio[default]);
+        }
+
+        played.push(kind);
+    }
+}
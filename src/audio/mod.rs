@@ -0,0 +1,288 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait};
+
+use automancy_defs::log;
+use automancy_resources::kira::manager::backend::cpal::{CpalBackend, CpalBackendSettings};
+use automancy_resources::kira::manager::{AudioManager, AudioManagerSettings};
+use automancy_resources::kira::sound::static_sound::StaticSoundData;
+use automancy_resources::kira::track::{TrackBuilder, TrackHandle};
+use automancy_resources::kira::tween::Tween;
+
+pub mod dispatch;
+
+/// How often the list of audio devices is checked for changes.
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+/// The longest the devices go unchecked while there are none, as the wait doubles after every check that finds none.
+const MAX_DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A running audio backend.
+struct Backend {
+    manager: AudioManager,
+    /// The track sound effects are played on.
+    sfx_track: TrackHandle,
+    /// The track interface sounds are played on.
+    ui_track: TrackHandle,
+}
+
+/// The game's audio output. Runs silent if there is no audio device to play to.
+pub struct GameAudio {
+    /// The audio backend, or None if it could not be initialized.
+    backend: Option<Backend>,
+    /// The name of the device the backend is playing to.
+    device: Option<String>,
+    /// The name of the device the player picked, or None to use the system default.
+    preferred_device: Option<String>,
+    /// The output devices, as of the last check.
+    devices: DeviceList,
+    /// Receives the output devices from the device watcher whenever they change.
+    device_updates: Receiver<DeviceList>,
+    sfx_volume: f64,
+    ui_volume: f64,
+}
+
+/// Returns the name of the system's default output device.
+fn default_device_name() -> Option<String> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.name().ok())
+}
+
+/// Returns the names of all output devices.
+fn output_device_names() -> Vec<String> {
+    cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.flat_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// The output devices, as seen by the device watcher.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DeviceList {
+    names: Vec<String>,
+    /// the name of the system's default output device
+    default: Option<String>,
+}
+
+impl DeviceList {
+    fn query() -> Self {
+        Self {
+            names: output_device_names(),
+            default: default_device_name(),
+        }
+    }
+}
+
+/// Starts checking the output devices on a thread of its own, as listing them can be slow,
+/// and sends them out every time they change.
+fn watch_devices(mut last: DeviceList) -> Receiver<DeviceList> {
+    let (sender, receiver) = mpsc::channel();
+
+    let spawned = thread::Builder::new()
+        .name("audio devices".to_string())
+        .spawn(move || {
+            let mut interval = DEVICE_CHECK_INTERVAL;
+
+            loop {
+                thread::sleep(interval);
+
+                let devices = DeviceList::query();
+
+                interval = if devices.names.is_empty() {
+                    (interval * 2).min(MAX_DEVICE_CHECK_INTERVAL)
+                } else {
+                    DEVICE_CHECK_INTERVAL
+                };
+
+                if devices != last {
+                    // the audio is gone, so there's no one left to tell
+                    if sender.send(devices.clone()).is_err() {
+                        break;
+                    }
+
+                    last = devices;
+                }
+            }
+        });
+
+    if let Err(err) = spawned {
+        log::warn!(
+            "Could not start watching the audio devices, changes to them won't be followed: {err}"
+        );
+    }
+
+    receiver
+}
+
+impl GameAudio {
+    /// Initializes the audio backend, falling back to being silent if that fails.
+    pub fn new(preferred_device: Option<String>) -> Self {
+        let devices = DeviceList::query();
+
+        let mut audio = Self {
+            backend: None,
+            device: None,
+            preferred_device,
+            device_updates: watch_devices(devices.clone()),
+            devices,
+            sfx_volume: 1.0,
+            ui_volume: 1.0,
+        };
+
+        audio.retry();
+
+        audio
+    }
+
+    /// Gets the name of the device that should be played to.
+    fn wanted_device(&self) -> Option<String> {
+        self.preferred_device
+            .clone()
+            .filter(|name| self.devices.names.contains(name))
+            .or_else(|| self.devices.default.clone())
+    }
+
+    fn init(device_name: Option<&str>) -> anyhow::Result<Backend> {
+        let device = device_name.and_then(|name| {
+            cpal::default_host()
+                .output_devices()
+                .ok()?
+                .find(|device| device.name().ok().as_deref() == Some(name))
+        });
+
+        let mut manager = AudioManager::<CpalBackend>::new(AudioManagerSettings {
+            backend: CpalBackendSettings {
+                device,
+                ..Default::default()
+            },
+            ..Default::default()
+        })?;
+        let sfx_track = manager.add_sub_track(TrackBuilder::new())?;
+        let ui_track = manager.add_sub_track(TrackBuilder::new())?;
+
+        Ok(Backend {
+            manager,
+            sfx_track,
+            ui_track,
+        })
+    }
+
+    /// Tries to initialize the audio backend again if it isn't running. Returns whether audio is available.
+    pub fn retry(&mut self) -> bool {
+        if self.backend.is_none() {
+            let device = self.wanted_device();
+
+            match Self::init(device.as_deref()) {
+                Ok(backend) => {
+                    self.backend = Some(backend);
+                    self.device = device;
+                    self.set_sfx_volume(self.sfx_volume);
+                    self.set_ui_volume(self.ui_volume);
+
+                    log::info!("Audio backend initialized on device {:?}", self.device);
+                }
+                Err(err) => {
+                    log::warn!(
+                        "Could not initialize the audio backend, the game will be silent: {err}"
+                    );
+                }
+            }
+        }
+
+        self.is_available()
+    }
+
+    /// Restarts the audio backend on the device it should be playing to.
+    fn restart(&mut self) {
+        self.backend = None;
+        self.device = None;
+
+        self.retry();
+    }
+
+    /// Returns whether there is an audio device to play to.
+    pub fn is_available(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Returns the names of all the output devices.
+    pub fn devices(&self) -> &[String] {
+        &self.devices.names
+    }
+
+    /// Switches to playing to the given device, or the system default if None.
+    pub fn set_device(&mut self, preferred_device: Option<String>) {
+        if self.preferred_device != preferred_device {
+            self.preferred_device = preferred_device;
+
+            self.restart();
+        }
+    }
+
+    /// Takes in the changes the device watcher found, such as headphones being unplugged, and switches to the right device.
+    /// Nothing is restarted while there are no devices, so the backend isn't retried over and over.
+    pub fn poll_devices(&mut self) {
+        let Some(devices) = self.device_updates.try_iter().last() else {
+            return;
+        };
+        self.devices = devices;
+
+        if !self.devices.names.is_empty()
+            && (!self.is_available() || self.device != self.wanted_device())
+        {
+            self.restart();
+        }
+    }
+
+    /// Plays a sound effect. Does nothing if audio is unavailable.
+    pub fn play(&mut self, sound: &StaticSoundData) {
+        if let Some(Backend {
+            manager, sfx_track, ..
+        }) = &mut self.backend
+        {
+            if let Err(err) =
+                manager.play(sound.with_modified_settings(|v| v.output_destination(&*sfx_track)))
+            {
+                log::warn!("Could not play sound: {err}");
+            }
+        }
+    }
+
+    /// Plays an interface sound. Does nothing if audio is unavailable.
+    pub fn play_ui(&mut self, sound: &StaticSoundData) {
+        if let Some(Backend {
+            manager, ui_track, ..
+        }) = &mut self.backend
+        {
+            if let Err(err) =
+                manager.play(sound.with_modified_settings(|v| v.output_destination(&*ui_track)))
+            {
+                log::warn!("Could not play sound: {err}");
+            }
+        }
+    }
+
+    /// Sets the volume of the sound effects.
+    pub fn set_sfx_volume(&mut self, volume: f64) {
+        self.sfx_volume = volume;
+
+        if let Some(backend) = &mut self.backend {
+            if let Err(err) = backend.sfx_track.set_volume(volume, Tween::default()) {
+                log::warn!("Could not set the volume: {err}");
+            }
+        }
+    }
+
+    /// Sets the volume of the interface sounds.
+    pub fn set_ui_volume(&mut self, volume: f64) {
+        self.ui_volume = volume;
+
+        if let Some(backend) = &mut self.backend {
+            if let Err(err) = backend.ui_track.set_volume(volume, Tween::default()) {
+                log::warn!("Could not set the volume: {err}");
+            }
+        }
+    }
+}
@@ -3,94 +3,48 @@
 use std::fmt::Write;
 use std::fs::File;
 use std::panic::PanicInfo;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{env, fs, panic};
 
 use color_eyre::config::HookBuilder;
 use egui::{FontData, FontDefinitions};
-use env_logger::Env;
+use env_logger::{Env, Target};
 use num::Zero;
 use ractor::Actor;
 use rfd::{MessageButtons, MessageDialog, MessageLevel};
 use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 use winit::dpi::PhysicalSize;
-use winit::event_loop::{ControlFlow, EventLoop};
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoopBuilder};
 use winit::window::{Fullscreen, Icon, WindowBuilder};
 
+use automancy::audio::dispatch::{self, AudioDispatch};
+use automancy::audio::GameAudio;
 use automancy::camera::Camera;
+use automancy::clock::GameClocks;
+use automancy::determinism::{self, Comparison};
 use automancy::event::{on_event, EventLoopStorage};
-use automancy::game::{load_map, GameSystem, GameSystemMessage, TICK_INTERVAL};
-use automancy::gpu::{init_gpu_resources, Gpu, DEPTH_FORMAT};
+use automancy::game::{load_map, GameSystem, GameSystemMessage, TileSnapshot, TICK_INTERVAL};
+use automancy::gpu::{
+    frame_format, init_gpu_resources, report_uncaptured_errors, Gpu, DEPTH_FORMAT,
+};
+use automancy::gui::popup::revert_unkept_settings;
 use automancy::gui::GuiState;
 use automancy::input::InputHandler;
-use automancy::map::MAIN_MENU;
+use automancy::logs::{self, LogWriter};
+use automancy::map::{Map, MAIN_MENU};
 use automancy::options::Options;
 use automancy::renderer::Renderer;
-use automancy::{GameState, LOGO};
+use automancy::replay::{InputPlayer, InputRecorder, RecordedInput};
+use automancy::{load_resources, load_resources_in, soak, switch_language, GameState, LOGO};
 use automancy_defs::gui::init_gui;
-use automancy_defs::gui::set_font;
-use automancy_defs::math::Double;
-use automancy_defs::rendering::Vertex;
+use automancy_defs::gui::{set_font, set_text_rendering};
 use automancy_defs::{log, window};
-use automancy_resources::kira::manager::{AudioManager, AudioManagerSettings};
-use automancy_resources::kira::track::{TrackBuilder, TrackHandle};
-use automancy_resources::kira::tween::Tween;
-use automancy_resources::{ResourceManager, RESOURCES_PATH, RESOURCE_MAN};
-
-/// Initialize the Resource Manager system, and loads all the resources in all namespaces.
-fn load_resources(track: TrackHandle) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
-    let mut resource_man = ResourceManager::new(track);
-
-    fs::read_dir(RESOURCES_PATH)
-        .expect("The resources folder doesn't exist- this is very wrong")
-        .flatten()
-        .map(|v| v.path())
-        .for_each(|dir| {
-            let namespace = dir.file_name().unwrap().to_str().unwrap();
-            log::info!("Loading namespace {namespace}...");
-
-            resource_man
-                .load_models(&dir)
-                .expect("Error loading models");
-            resource_man.load_audio(&dir).expect("Error loading audio");
-            resource_man.load_tiles(&dir).expect("Error loading tiles");
-            resource_man.load_items(&dir).expect("Error loading items");
-            resource_man.load_tags(&dir).expect("Error loading tags");
-            resource_man
-                .load_categories(&dir)
-                .expect("Error loading categories");
-            resource_man
-                .load_scripts(&dir)
-                .expect("Error loading scripts");
-            resource_man
-                .load_translates(&dir)
-                .expect("Error loading translates");
-            resource_man
-                .load_shaders(&dir)
-                .expect("Error loading shaders");
-            resource_man.load_fonts(&dir).expect("Error loading fonts");
-            resource_man
-                .load_functions(&dir)
-                .expect("Error loading functions");
-            resource_man
-                .load_researches(&dir)
-                .expect("Error loading researches");
-
-            log::info!("Loaded namespace {namespace}.");
-        });
-
-    resource_man.compile_researches();
-    resource_man.ordered_tiles();
-    resource_man.ordered_items();
-    resource_man.ordered_categories();
-
-    let (vertices, indices) = resource_man.compile_models();
-
-    (Arc::new(resource_man), vertices, indices)
-}
+use automancy_resources::RESOURCE_MAN;
 
 static SYMBOLS_FONT: &[u8] = include_bytes!("../../assets/SymbolsNerdFontMono-Regular.ttf");
 static SYMBOLS_FONT_KEY: &str = "SYMBOLS_FONT";
@@ -116,6 +70,11 @@ fn write_msg<P: AsRef<Path>>(buffer: &mut impl Write, file_path: P) -> std::fmt:
         "We have generated a report file at\nfile://{}\n\nSubmit an issue or tag us on Fedi/Discord and include the report as an attachment.\n",
         file_path.as_ref().display(),
     )?;
+    writeln!(
+        buffer,
+        "The game's logs are in\nfile://{}\nand attaching the newest one helps too.\n",
+        logs::logs_folder().display(),
+    )?;
 
     writeln!(buffer, "- Git: https://github.com/automancy/automancy")?;
     writeln!(buffer, "- Fedi(Mastodon): https://gamedev.lgbt/@automancy")?;
@@ -135,8 +94,101 @@ fn write_msg<P: AsRef<Path>>(buffer: &mut impl Write, file_path: P) -> std::fmt:
     Ok(())
 }
 
+/// Gets the values given after a command line flag, such as the two paths in `--compare-hash-logs a b`.
+fn flag_args<'a>(args: &'a [String], flag: &str, count: usize) -> Option<&'a [String]> {
+    let start = args.iter().position(|v| v == flag)? + 1;
+
+    args.get(start..start + count)
+}
+
+/// Runs the determinism audit comparisons asked for on the command line, instead of the game.
+/// Returns whether one was run.
+fn run_comparisons(args: &[String]) -> anyhow::Result<bool> {
+    if let Some([a, b]) = flag_args(args, "--compare-hash-logs", 2) {
+        match determinism::compare_hash_logs(&fs::read_to_string(a)?, &fs::read_to_string(b)?) {
+            Comparison::Same { ticks } => {
+                println!("The runs went the same way for {ticks} ticks.");
+            }
+            Comparison::Diverged { map, tick } => {
+                println!("The runs first went differently on the map {map}, at tick {tick}.");
+            }
+        }
+
+        return Ok(true);
+    }
+
+    if let Some([a, b]) = flag_args(args, "--compare-saves", 2) {
+        let (resource_man, ..) = load_resources();
+
+        let read = |name: &str| {
+            Map::read_tiles(&resource_man, name)
+                .ok_or_else(|| anyhow::anyhow!("Could not read the map {name}"))
+        };
+        let (a, b) = (read(a)?, read(b)?);
+
+        let diff = determinism::diff_tiles(
+            &resource_man.interner,
+            a.iter().map(|(coord, id, data)| (*coord, *id, data)),
+            b.iter().map(|(coord, id, data)| (*coord, *id, data)),
+        );
+
+        if diff.is_empty() {
+            println!("The saves have the same tiles.");
+        } else {
+            println!("The saves differ at {} positions:", diff.len());
+            for coord in diff {
+                println!("  {coord}");
+            }
+        }
+
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Runs the soak test if it was asked for on the command line, instead of the game.
+/// Returns whether it was run, and fails if the test found anything wrong.
+fn run_soak(args: &[String]) -> anyhow::Result<bool> {
+    let Some([minutes]) = flag_args(args, "--soak", 1) else {
+        return Ok(false);
+    };
+    let duration = Duration::from_secs_f64(minutes.parse::<f64>()? * 60.0);
+    let seed = match flag_args(args, "--soak-seed", 1) {
+        Some([seed]) => seed.parse()?,
+        _ => rand::random(),
+    };
+
+    let (resource_man, ..) = load_resources();
+    let report = soak::soak(resource_man, duration, seed)?;
+
+    println!(
+        "Soak test with the seed {seed} took {} steps over {} ticks, with {} tile crashes.",
+        report.steps, report.ticks, report.crashes
+    );
+    for failure in &report.failures {
+        println!("  {failure}");
+    }
+
+    if report.failures.is_empty() {
+        Ok(true)
+    } else {
+        Err(anyhow::anyhow!(
+            "The soak test found {} problems",
+            report.failures.len()
+        ))
+    }
+}
+
 fn main() -> anyhow::Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    env_logger::Builder::from_env(Env::default().default_filter_or("info"))
+        .target(Target::Pipe(Box::new(LogWriter::new())))
+        .init();
+
+    let args = env::args().collect::<Vec<_>>();
+    if run_comparisons(&args)? || run_soak(&args)? {
+        return Ok(());
+    }
 
     {
         let eyre = HookBuilder::blank()
@@ -193,7 +245,7 @@ fn main() -> anyhow::Result<()> {
         }));
     }
 
-    let event_loop = EventLoop::new()?;
+    let event_loop = EventLoopBuilder::<RecordedInput>::with_user_event().build()?;
 
     let mut state = {
         let tokio = Runtime::new().unwrap();
@@ -216,28 +268,30 @@ fn main() -> anyhow::Result<()> {
         let camera = Camera::new(window::window_size_double(&window));
 
         log::info!("Initializing audio backend...");
-        let mut audio_man = AudioManager::new(AudioManagerSettings::default())?;
-        log::info!("Audio backend initialized");
+        let audio_man = GameAudio::new(options.audio.output_device.clone());
 
         log::info!("Loading resources...");
-        let track = audio_man.add_sub_track({
-            let builder = TrackBuilder::new();
-
-            builder
-        })?;
-
-        let (resource_man, vertices, indices) = load_resources(track);
+        let (resource_man, vertices, indices) = load_resources_in(&options.gui.language);
         RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
         log::info!("Loaded resources.");
 
         log::info!("Creating game...");
+        let (events_sender, events) = mpsc::unbounded_channel();
+        let tile_snapshot = TileSnapshot::default();
+        let clocks = GameClocks::default();
         let (game, game_handle) = tokio.block_on(Actor::spawn(
             Some("game".to_string()),
             GameSystem {
                 resource_man: resource_man.clone(),
+                events: events_sender.clone(),
+                snapshot: tile_snapshot.clone(),
+                clock: clocks.simulation.clone(),
             },
             (),
         ))?;
+        if let Some([path]) = flag_args(&args, "--hash-log", 1) {
+            game.send_message(GameSystemMessage::SetHashLog(Some(PathBuf::from(path))))?;
+        }
         {
             let game = game.clone();
             tokio.spawn(async move {
@@ -246,27 +300,32 @@ fn main() -> anyhow::Result<()> {
         }
         log::info!("Game created.");
 
-        let gpu = tokio.block_on(Gpu::new(
-            Arc::new(window),
-            options.graphics.fps_limit == 0.0,
-        ));
+        let gpu = tokio.block_on(Gpu::new(Arc::new(window), options.graphics.vsync));
 
         log::info!("Setting up rendering...");
+        report_uncaptured_errors(&gpu.device, resource_man.clone());
         let (shared_resources, render_resources, global_buffers, gui_resources) =
             init_gpu_resources(
                 &gpu.device,
                 &gpu.queue,
                 &gpu.config,
                 &resource_man,
-                vertices,
-                indices,
+                &vertices,
+                &indices,
+                &|stage| {
+                    log::info!("Setting up {stage}...");
+                    gpu.window
+                        .set_title(&format!("automancy - setting up {stage}..."));
+                },
             );
+        gpu.window.set_title("automancy");
         let global_buffers = Arc::new(global_buffers);
         let renderer = Renderer::new(
             gpu,
             shared_resources,
             render_resources,
             global_buffers.clone(),
+            tile_snapshot,
             &options,
         );
         log::info!("Render setup.");
@@ -275,7 +334,7 @@ fn main() -> anyhow::Result<()> {
         let mut gui = init_gui(
             egui_wgpu::Renderer::new(
                 &renderer.gpu.device,
-                renderer.gpu.config.format,
+                frame_format(&renderer.gpu.config),
                 Some(DEPTH_FORMAT),
                 4,
             ),
@@ -293,6 +352,7 @@ fn main() -> anyhow::Result<()> {
                 .insert(name.to_string(), FontData::from_owned(font.data.clone()));
         }
         set_font(SYMBOLS_FONT_KEY, &options.gui.font, &mut gui);
+        set_text_rendering(&gui.context, options.gui.text_size, options.gui.sharp_text);
         gui.renderer.callback_resources.insert(gui_resources);
         gui.renderer
             .callback_resources
@@ -300,8 +360,7 @@ fn main() -> anyhow::Result<()> {
         gui.renderer.callback_resources.insert(resource_man.clone());
         log::info!("Gui setup.");
 
-        let start_instant = Instant::now();
-        gui.renderer.callback_resources.insert(start_instant);
+        gui.renderer.callback_resources.insert(clocks.ui.clone());
 
         GameState {
             gui_state: GuiState::new(),
@@ -315,9 +374,11 @@ fn main() -> anyhow::Result<()> {
             gui,
             renderer,
             game_handle: Some(game_handle),
-            start_instant,
+            clocks,
             audio_man,
+            audio_dispatch: AudioDispatch::new(events_sender, events),
             puzzle_state: Default::default(),
+            models: Arc::new((vertices, indices)),
         }
     };
 
@@ -333,44 +394,131 @@ fn main() -> anyhow::Result<()> {
         ))
         .unwrap();
 
+    let mut recorder = match flag_args(&args, "--record-input", 1) {
+        Some([path]) => {
+            log::info!("Recording the input to {path}");
+            Some(InputRecorder::create(
+                Path::new(path),
+                state.renderer.gpu.window.inner_size(),
+            )?)
+        }
+        _ => None,
+    };
+    let mut player = match flag_args(&args, "--play-input", 1) {
+        Some([path]) => {
+            log::info!("Playing back the input from {path}");
+            Some(InputPlayer::load(Path::new(path))?)
+        }
+        _ => None,
+    };
+
     event_loop.run(move |event, target| {
         if closed {
             return;
         }
 
-        match on_event(&mut state, target, event) {
-            Ok(to_exit) => {
-                if to_exit {
-                    closed = true;
-                    return;
+        if let (Some(recorder), Event::WindowEvent { event, .. }) = (&mut recorder, &event) {
+            if let Err(err) = recorder.record(event) {
+                log::warn!("Could not record the input: {err}");
+            }
+        }
+
+        // the player's own input would get mixed in with the recording being played back
+        let ignored = player.is_some()
+            && matches!(
+                event,
+                Event::WindowEvent {
+                    event: WindowEvent::CursorMoved { .. }
+                        | WindowEvent::MouseInput { .. }
+                        | WindowEvent::MouseWheel { .. }
+                        | WindowEvent::ModifiersChanged(_)
+                        | WindowEvent::KeyboardInput { .. },
+                    ..
                 }
+            );
+
+        let mut events = if ignored { vec![] } else { vec![event] };
+
+        if let Some(playing) = &mut player {
+            events.extend(playing.due().into_iter().map(Event::UserEvent));
+
+            if playing.is_finished() {
+                log::info!("Finished playing back the input, closing the game");
+                player = None;
+
+                events.push(Event::WindowEvent {
+                    window_id: state.renderer.gpu.window.id(),
+                    event: WindowEvent::CloseRequested,
+                });
             }
-            Err(e) => {
-                log::warn!("Event loop returned error: {e}");
+        }
+
+        for event in events {
+            match on_event(&mut state, target, event) {
+                Ok(to_exit) => {
+                    if to_exit {
+                        closed = true;
+                        return;
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Event loop returned error: {e}");
+                }
             }
         }
 
+        revert_unkept_settings(&mut state);
+
         if !state.options.synced {
+            if state.options.gui.language != state.resource_man.translates().language {
+                log::info!(
+                    "Switching the language to {}...",
+                    state.options.gui.language
+                );
+
+                match switch_language(&state.resource_man, &state.options.gui.language) {
+                    Ok(()) => log::info!("Switched the language."),
+                    Err(err) => log::warn!("Could not switch the language: {err}"),
+                }
+            }
+
             state.gui.context.set_zoom_factor(state.options.gui.scale);
             set_font(SYMBOLS_FONT_KEY, &state.options.gui.font, &mut state.gui);
+            set_text_rendering(
+                &state.gui.context,
+                state.options.gui.text_size,
+                state.options.gui.sharp_text,
+            );
 
             state
                 .audio_man
-                .main_track()
-                .set_volume(state.options.audio.sfx_volume, Tween::default())
-                .unwrap();
-
+                .set_sfx_volume(state.options.audio.sfx_volume);
+            state.audio_man.set_ui_volume(state.options.audio.ui_volume);
             state
-                .renderer
-                .gpu
-                .set_vsync(state.options.graphics.fps_limit == 0.0);
+                .audio_man
+                .set_device(state.options.audio.output_device.clone());
 
-            if state.options.graphics.fps_limit >= 250.0 {
-                state.renderer.fps_limit = Double::INFINITY;
-            } else {
-                state.renderer.fps_limit = state.options.graphics.fps_limit;
+            state.renderer.gpu.set_vsync(state.options.graphics.vsync);
+
+            state.renderer.screenshot_options = state.options.graphics.screenshot.clone();
+
+            state.renderer.fps_limit = state.options.graphics.frame_rate_limit();
+            state.renderer.bloom = state.options.graphics.bloom;
+
+            if let Err(err) = state
+                .game
+                .send_message(GameSystemMessage::SetTransactionRecordLimit(
+                    state.options.memory.max_transaction_records,
+                ))
+            {
+                log::warn!("Could not set the transaction record limit: {err}");
             }
 
+            state
+                .loop_store
+                .quality_governor
+                .set_max(state.options.graphics.quality);
+
             if state.options.graphics.fullscreen {
                 state
                     .renderer
@@ -384,8 +532,14 @@ fn main() -> anyhow::Result<()> {
             state.options.synced = true;
         }
 
-        if !state.renderer.fps_limit.is_zero() {
-            let frame_time = Duration::from_secs_f64(1.0 / state.renderer.fps_limit);
+        state
+            .loop_store
+            .power
+            .update(state.options.graphics.battery_saver);
+        let fps_limit = state.loop_store.power.fps_limit(state.renderer.fps_limit);
+
+        if !fps_limit.is_zero() {
+            let frame_time = Duration::from_secs_f64(1.0 / fps_limit);
 
             if state.loop_store.frame_start.elapsed() > frame_time {
                 state.renderer.gpu.window.request_redraw();
@@ -396,6 +550,9 @@ fn main() -> anyhow::Result<()> {
             target.set_control_flow(ControlFlow::Poll);
         }
 
+        state.audio_man.poll_devices();
+        dispatch::dispatch(&mut state);
+
         let new_elapsed = Instant::now().duration_since(state.loop_store.frame_start);
         state.loop_store.elapsed = new_elapsed;
     })?;
@@ -6,7 +6,9 @@ use automancy_defs::coord::{TileBounds, TileCoord};
 use automancy_defs::glam::{dvec2, dvec3, vec2};
 use automancy_defs::hexx::Hex;
 use automancy_defs::math;
-use automancy_defs::math::{matrix, DMatrix4, DVec2, DVec3, Double, Float, HEX_GRID_LAYOUT};
+use automancy_defs::math::{
+    matrix, DMatrix4, DVec2, DVec3, Double, Float, Vec2, FAR, HEX_GRID_LAYOUT,
+};
 
 use crate::input::InputHandler;
 
@@ -18,6 +20,7 @@ pub struct Camera {
 
     pub culling_range: TileBounds,
     pub pointing_at: TileCoord,
+    origin: TileCoord,
     matrix: DMatrix4,
 }
 
@@ -40,6 +43,7 @@ pub fn fit_pos(DVec3 { x, y, z }: DVec3) -> DVec3 {
 impl Camera {
     pub fn new((width, height): (Double, Double)) -> Self {
         let pos = dvec3(0.0, 0.0, 2.0);
+        let origin = TileCoord::new(0, 0);
         let matrix = matrix(fit_pos(pos), width / height);
 
         Self {
@@ -49,6 +53,7 @@ impl Camera {
 
             culling_range: math::get_culling_range((width, height), fit_pos(pos)),
             pointing_at: TileCoord::new(0, 0),
+            origin,
             matrix,
         }
     }
@@ -58,9 +63,52 @@ impl Camera {
         fit_pos(self.pos)
     }
 
+    /// Returns the tile everything is rendered relative to.
+    /// Rendering around it instead of the world origin keeps the float precision from breaking down far away.
+    pub fn get_origin(&self) -> TileCoord {
+        self.origin
+    }
+
+    /// Returns the position of the camera, relative to the origin tile.
+    pub fn get_relative_pos(&self) -> DVec3 {
+        self.get_pos() - math::hex_to_world_pos_double(self.origin).extend(0.0)
+    }
+
+    /// Returns the world matrix, relative to the origin tile.
     pub fn get_matrix(&self) -> DMatrix4 {
         self.matrix
     }
+
+    /// Converts a tile's position into a render position, relative to the origin tile.
+    pub fn rebase_coord(&self, coord: TileCoord) -> Vec2 {
+        HEX_GRID_LAYOUT.hex_to_world_pos(*(coord - self.origin))
+    }
+
+    /// Converts a world position into a render position, relative to the origin tile.
+    pub fn rebase(&self, pos: DVec2) -> Vec2 {
+        (pos - math::hex_to_world_pos_double(self.origin)).as_vec2()
+    }
+
+    /// Gets where on the screen a tile is drawn, in pixels. Returns None if the tile is behind the camera.
+    pub fn coord_to_screen(
+        &self,
+        coord: TileCoord,
+        (width, height): (Double, Double),
+    ) -> Option<DVec2> {
+        let pos = self.rebase_coord(coord).as_dvec2();
+        let clip = self.matrix * pos.extend(FAR).extend(1.0);
+
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+
+        Some(dvec2(
+            (ndc.x + 1.0) / 2.0 * width,
+            (1.0 - ndc.y) / 2.0 * height,
+        ))
+    }
 }
 
 impl Camera {
@@ -88,6 +136,10 @@ impl Camera {
             }
         }
 
+        if let Some(delta) = input.touch_pan {
+            self.on_moving_main(delta);
+        }
+
         if let Some(delta) = input.scroll {
             self.on_scroll(delta);
         }
@@ -98,9 +150,16 @@ impl Camera {
         let m = elapsed * 100.0;
 
         if self.move_vel.length_squared() > 0.0000001 {
+            let last = self.pos;
+
             self.pos.x += self.move_vel.x * m;
             self.pos.y += self.move_vel.y * m;
 
+            if !self.get_tile_coord().is_in_world() {
+                self.pos = last;
+                self.move_vel = dvec2(0.0, 0.0);
+            }
+
             self.move_vel -= self.move_vel * elapsed.mul(4.0).at_most(0.9);
         }
 
@@ -112,8 +171,30 @@ impl Camera {
             self.scroll_vel -= self.scroll_vel * elapsed.mul(15.0).at_most(0.9);
         }
 
-        self.matrix = matrix(self.get_pos(), width / height);
+        self.update_view((width, height));
+    }
+
+    /// Moves the camera straight to a position, without any easing.
+    pub fn set_pos(&mut self, pos: DVec3, (width, height): (Double, Double)) {
+        self.pos = pos;
+        self.move_vel = dvec2(0.0, 0.0);
+        self.scroll_vel = 0.0;
+
+        self.update_view((width, height));
+    }
+
+    /// Moves the camera straight over a tile, keeping the zoom level.
+    pub fn jump_to(&mut self, coord: TileCoord, (width, height): (Double, Double)) {
+        let pos = math::hex_to_world_pos_double(coord);
+
+        self.set_pos(pos.extend(self.pos.z), (width, height));
+    }
+
+    /// Recalculates everything that depends on the camera's position.
+    fn update_view(&mut self, (width, height): (Double, Double)) {
         self.culling_range = math::get_culling_range((width, height), self.get_pos());
+        self.origin = self.culling_range.center.into();
+        self.matrix = matrix(self.get_relative_pos(), width / height);
     }
 
     /// Called when the camera is scrolled.
@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
@@ -9,8 +10,9 @@ use ractor::rpc::CallResult;
 use ractor::ActorRef;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, WindowEvent};
 use winit::event_loop::EventLoopWindowTarget;
+use winit::keyboard::Key;
 
 use automancy_defs::coord::TileCoord;
 use automancy_defs::hexx::Hex;
@@ -18,15 +20,19 @@ use automancy_defs::id::Id;
 use automancy_defs::{log, math, window};
 use automancy_resources::data::item::Item;
 use automancy_resources::data::{Data, DataMap};
-use automancy_resources::kira::manager::AudioManager;
 use automancy_resources::ResourceManager;
 
-use crate::game::{GameSystemMessage, PlaceTileResponse};
-use crate::gpu::AnimationMap;
-use crate::gui::{Screen, TextField};
-use crate::input::KeyActions;
+use crate::audio::GameAudio;
+use crate::game::GameSystemMessage;
+use crate::gpu::AnimationCache;
+use crate::gui::route::Routing;
+use crate::gui::{route, MapSortOrder, Screen, TextField};
+use crate::input::{GameInputEvent, KeyActions, KeyInput};
 use crate::map::{Map, MapInfo, MapInfoRaw, MAP_PATH};
-use crate::tile_entity::TileEntityMsg;
+use crate::power::PowerState;
+use crate::quality::QualityGovernor;
+use crate::replay::RecordedInput;
+use crate::tile_entity::{TileEntityMsg, TileHandle};
 use crate::{gui, input, GameState};
 
 /// Refreshes the list of maps on the filesystem. Should be done every time the list of maps could have changed (on map creation/delete and on game load).
@@ -41,16 +47,37 @@ pub fn refresh_maps(state: &mut GameState) {
         .flat_map(|map| Map::read_info(&state.resource_man, &map).zip(Some(map)))
         .collect::<Vec<_>>();
 
-    state
+    state.loop_store.map_thumbnails = state
         .loop_store
         .map_infos_cache
-        .sort_by(|a, b| a.1.cmp(&b.1));
-    state.loop_store.map_infos_cache.sort_by(|a, b| {
-        a.0 .1
-            .unwrap_or(SystemTime::UNIX_EPOCH)
-            .cmp(&b.0 .1.unwrap_or(SystemTime::UNIX_EPOCH))
-    });
-    state.loop_store.map_infos_cache.reverse();
+        .iter()
+        .flat_map(|(_, map)| {
+            fs::read(Map::thumbnail(map))
+                .ok()
+                .map(|v| (map.clone(), Arc::from(v)))
+        })
+        .collect();
+
+    sort_maps(state);
+}
+
+/// Sorts the cached list of maps in the order picked in the map menu.
+pub fn sort_maps(state: &mut GameState) {
+    let maps = &mut state.loop_store.map_infos_cache;
+
+    maps.sort_by(|a, b| a.1.cmp(&b.1));
+
+    match state.gui_state.map_sort {
+        MapSortOrder::Name => {}
+        MapSortOrder::LastPlayed => maps.sort_by_key(|((info, save_time), _)| {
+            Reverse(
+                info.last_played
+                    .or(*save_time)
+                    .unwrap_or(SystemTime::UNIX_EPOCH),
+            )
+        }),
+        MapSortOrder::Playtime => maps.sort_by_key(|((info, _), _)| Reverse(info.playtime)),
+    }
 }
 
 /// Stores information that lives for the entire lifetime of the session, and is not dropped at the end of one event cycle or handled elsewhere.
@@ -61,13 +88,19 @@ pub struct EventLoopStorage {
     pub frame_start: Instant,
     /// the elapsed time between each frame
     pub elapsed: Duration,
+    /// picks the quality to draw at, when it's adjusted automatically
+    pub quality_governor: QualityGovernor,
+    /// whether to save power
+    pub power: PowerState,
 
     pub map_infos_cache: Vec<((MapInfoRaw, Option<SystemTime>), String)>,
+    /// the preview images of the maps, for the ones that have one
+    pub map_thumbnails: HashMap<String, Arc<[u8]>>,
     pub map_info: Option<(Arc<Mutex<MapInfo>>, String)>,
 
-    pub config_open_cache: Arc<Mutex<Option<(Id, ActorRef<TileEntityMsg>)>>>,
+    pub config_open_cache: Arc<Mutex<Option<(Id, TileHandle)>>>,
     pub config_open_updating: Arc<AtomicBool>,
-    pub pointing_cache: Arc<Mutex<Option<(Id, ActorRef<TileEntityMsg>)>>>,
+    pub pointing_cache: Arc<Mutex<Option<(Id, TileHandle)>>>,
     pub pointing_updating: Arc<AtomicBool>,
 }
 
@@ -77,8 +110,11 @@ impl EventLoopStorage {
             tag_cache: Default::default(),
             frame_start: Instant::now(),
             elapsed: Default::default(),
+            quality_governor: Default::default(),
+            power: Default::default(),
 
             map_infos_cache: vec![],
+            map_thumbnails: Default::default(),
             map_info: None,
 
             config_open_cache: Arc::new(Default::default()),
@@ -89,10 +125,42 @@ impl EventLoopStorage {
     }
 }
 
+/// Hands a replayed key to the GUI, the way egui-winit would have for a real one.
+fn replay_key_to_gui(
+    state: &mut GameState,
+    key_state: ElementState,
+    key: &Key,
+    repeat: bool,
+    text: Option<&str>,
+) {
+    let name = match key {
+        Key::Named(named) => format!("{named:?}"),
+        Key::Character(c) => c.to_string(),
+        _ => return,
+    };
+    let pressed = key_state == ElementState::Pressed;
+
+    let input = state.gui.state.egui_input_mut();
+
+    if let Some(key) = egui::Key::from_name(&name) {
+        input.events.push(egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat,
+            modifiers: input.modifiers,
+        });
+    }
+
+    if let Some(text) = text.filter(|v| pressed && !v.chars().any(char::is_control)) {
+        input.events.push(egui::Event::Text(text.to_string()));
+    }
+}
+
 pub async fn shutdown_graceful(
     game: &ActorRef<GameSystemMessage>,
     game_handle: &mut Option<JoinHandle<()>>,
-    target: &EventLoopWindowTarget<()>,
+    target: &EventLoopWindowTarget<RecordedInput>,
 ) -> anyhow::Result<bool> {
     game.send_message(GameSystemMessage::StopTicking)?;
 
@@ -107,7 +175,10 @@ pub async fn shutdown_graceful(
     Ok(true)
 }
 
-fn render(state: &mut GameState, target: &EventLoopWindowTarget<()>) -> anyhow::Result<bool> {
+fn render(
+    state: &mut GameState,
+    target: &EventLoopWindowTarget<RecordedInput>,
+) -> anyhow::Result<bool> {
     let mut result = Ok(false);
 
     state.camera.update_pointing_at(
@@ -119,11 +190,36 @@ fn render(state: &mut GameState, target: &EventLoopWindowTarget<()>) -> anyhow::
         state.loop_store.elapsed.as_secs_f64(),
     );
 
+    let quality = if state.options.graphics.adaptive_quality {
+        state
+            .loop_store
+            .quality_governor
+            .record(state.loop_store.elapsed, state.renderer.fps_limit)
+    } else {
+        state.options.graphics.quality
+    };
+    state.renderer.quality = state.loop_store.power.quality(quality);
+
+    // the simulation holds still while paused, and the GUI does too, as well as while in the background
+    let paused = state.gui_state.is_paused();
+    state.clocks.simulation.set_running(!paused);
     state
+        .clocks
+        .ui
+        .set_running(!paused && state.loop_store.power.focused);
+
+    let animation_cache = state
         .gui
         .renderer
         .callback_resources
-        .insert(AnimationMap::new());
+        .entry::<AnimationCache>()
+        .or_insert_with(Default::default);
+    if state.renderer.quality.animate_models() {
+        animation_cache.update(
+            &state.resource_man,
+            state.clocks.simulation.now().as_secs_f32(),
+        );
+    }
 
     state.loop_store.frame_start = Instant::now();
 
@@ -209,7 +305,7 @@ fn render(state: &mut GameState, target: &EventLoopWindowTarget<()>) -> anyhow::
 
         if !matches!(result, Ok(true)) {
             match state.renderer.render(
-                state.start_instant,
+                &state.clocks,
                 &*state.resource_man,
                 &state.tokio,
                 &state.input_handler,
@@ -218,7 +314,7 @@ fn render(state: &mut GameState, target: &EventLoopWindowTarget<()>) -> anyhow::
                 &state.game,
             ) {
                 Ok(_) => {}
-                Err(SurfaceError::Lost) => state.renderer.gpu.resize(
+                Err(SurfaceError::Lost | SurfaceError::Outdated) => state.renderer.gpu.resize(
                     &mut state.renderer.shared_resources,
                     &mut state.renderer.render_resources,
                     state.renderer.gpu.window.inner_size(),
@@ -240,8 +336,8 @@ fn render(state: &mut GameState, target: &EventLoopWindowTarget<()>) -> anyhow::
 
 async fn on_link_tile(
     resource_man: Arc<ResourceManager>,
-    audio_man: &mut AudioManager,
-    pointing_cache: Arc<Mutex<Option<(Id, ActorRef<TileEntityMsg>)>>>,
+    audio_man: &mut GameAudio,
+    pointing_cache: Arc<Mutex<Option<(Id, TileHandle)>>>,
     linking_tile: TileCoord,
 ) {
     let Some((tile, entity)) = pointing_cache.lock().await.clone() else {
@@ -252,13 +348,7 @@ async fn on_link_tile(
         return;
     };
 
-    if tile_def
-        .data
-        .get(&resource_man.registry.data_ids.linked)
-        .cloned()
-        .and_then(Data::into_bool)
-        .unwrap_or(false)
-    {
+    if tile_def.data.flag(resource_man.registry.data_ids.linked) {
         let Ok(CallResult::Success(old)) = entity
             .call(
                 |reply| TileEntityMsg::GetDataValue(resource_man.registry.data_ids.link, reply),
@@ -271,22 +361,20 @@ async fn on_link_tile(
 
         if old.is_some() {
             entity
-                .send_message(TileEntityMsg::RemoveData(
-                    resource_man.registry.data_ids.link,
-                ))
+                .configure(resource_man.registry.data_ids.link, None)
                 .unwrap();
 
-            audio_man.play(resource_man.audio["click"].clone()).unwrap();
+            audio_man.play(&resource_man.audio["click"]);
             // TODO click2
         } else {
             entity
-                .send_message(TileEntityMsg::SetDataValue(
+                .configure(
                     resource_man.registry.data_ids.link,
-                    Data::Coord(linking_tile),
-                ))
+                    Some(Data::Coord(linking_tile)),
+                )
                 .unwrap();
 
-            audio_man.play(resource_man.audio["click"].clone()).unwrap();
+            audio_man.play(&resource_man.audio["click"]);
         }
     }
 }
@@ -294,11 +382,12 @@ async fn on_link_tile(
 /// Triggers every time the event loop is run once.
 pub fn on_event(
     state: &mut GameState,
-    target: &EventLoopWindowTarget<()>,
-    event: Event<()>,
+    target: &EventLoopWindowTarget<RecordedInput>,
+    event: Event<RecordedInput>,
 ) -> anyhow::Result<bool> {
     let mut window_event = None;
     let mut device_event = None;
+    let mut replayed_input = None;
 
     match &event {
         Event::WindowEvent {
@@ -335,9 +424,14 @@ pub fn on_event(
                         &mut state.renderer.render_resources,
                         *size,
                     );
+                    // keep drawing while the window is being dragged, which blocks the event loop on some platforms
+                    state.renderer.gpu.window.request_redraw();
 
                     return Ok(false);
                 }
+                WindowEvent::Focused(focused) => {
+                    state.loop_store.power.focused = *focused;
+                }
                 _ => {}
             }
         }
@@ -346,23 +440,64 @@ pub fn on_event(
             device_event = Some(event);
         }
 
+        Event::UserEvent(input) => {
+            if let Some(event) = input.to_window_event() {
+                let window_id = state.renderer.gpu.window.id();
+
+                return on_event(state, target, Event::WindowEvent { window_id, event });
+            }
+
+            match input {
+                RecordedInput::Resized(size) => {
+                    let _ = state.renderer.gpu.window.request_inner_size(*size);
+                }
+                RecordedInput::Key {
+                    state: key_state,
+                    key,
+                    repeat,
+                    text,
+                } => {
+                    replay_key_to_gui(state, *key_state, key, *repeat, text.as_deref());
+
+                    if !state.gui.context.wants_keyboard_input() {
+                        replayed_input = Some(GameInputEvent::KeyboardEvent {
+                            event: KeyInput {
+                                state: *key_state,
+                                logical_key: key.clone(),
+                                repeat: *repeat,
+                            },
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
         _ => {}
     };
 
-    if window_event.is_some() || device_event.is_some() {
+    if window_event.is_some() || device_event.is_some() || replayed_input.is_some() {
         state.input_handler.reset();
-        state.input_handler.update(input::convert_input(
-            window_event,
-            device_event,
-            window::window_size_double(&state.renderer.gpu.window),
-            1.0, //TODO sensitivity option
-        ));
+        state
+            .input_handler
+            .update(replayed_input.unwrap_or_else(|| {
+                input::convert_input(
+                    window_event,
+                    device_event,
+                    window::window_size_double(&state.renderer.gpu.window),
+                    1.0, //TODO sensitivity option
+                )
+            }));
         state.camera.handle_input(&state.input_handler);
 
         if state.input_handler.key_active(KeyActions::Escape) {
             // one by one
-            if state.gui_state.selected_tile_id.take().is_none()
+            if state.gui_state.dropping_items.take().is_none()
+                && state.gui_state.routing.take().is_none()
+                && state.gui_state.importing_region.take().is_none()
+                && state.gui_state.selected_tile_id.take().is_none()
                 && state.gui_state.linking_tile.take().is_none()
+                && state.gui_state.zoning.take().is_none()
             {
                 if state
                     .gui_state
@@ -372,6 +507,10 @@ pub fn on_event(
                         .tokio
                         .block_on(state.game.call(GameSystemMessage::SaveMap, None))?
                         .unwrap();
+
+                    // the game is drawn without the pause menu, so the next frame shows the map as it was left
+                    state.renderer.thumbnail_request =
+                        state.loop_store.map_info.as_ref().map(|v| v.1.clone());
                 } else {
                     state
                         .gui_state
@@ -380,7 +519,94 @@ pub fn on_event(
             }
         }
 
-        if state.input_handler.main_pressed
+        if state.input_handler.main_pressed && state.gui_state.dropping_items.is_some() {
+            if let Some(stack) = state.gui_state.dropping_items.take() {
+                state.game.send_message(GameSystemMessage::DropItems(
+                    state.camera.pointing_at,
+                    stack,
+                ))?;
+            }
+        } else if state.input_handler.key_active(KeyActions::PickUp)
+            && state.gui_state.screen == Screen::Ingame
+        {
+            state.game.send_message(GameSystemMessage::PickUpItems(
+                state.camera.get_tile_coord(),
+                state.camera.pointing_at,
+            ))?;
+        }
+
+        if state.input_handler.key_active(KeyActions::Route)
+            && state.gui_state.screen == Screen::Ingame
+            && state.gui_state.routing.is_none()
+        {
+            state.gui_state.routing = Some(Routing::PickSource);
+            state.gui_state.route_preview = None;
+            state.gui_state.selected_tile_id = None;
+        }
+
+        if state.input_handler.key_active(KeyActions::AcceptSuggestion)
+            && state.gui_state.screen == Screen::Ingame
+        {
+            if let Some(suggestion) = gui::suggestion::current_suggestion(state).cloned() {
+                state.game.send_message(GameSystemMessage::PlaceTile {
+                    coord: state.camera.pointing_at,
+                    id: suggestion.id,
+                    record: true,
+                    reply: None,
+                    data: Some(suggestion.data),
+                })?;
+
+                state
+                    .audio_man
+                    .play(&state.resource_man.audio["tile_placement"]);
+                state.gui_state.suggestions = None;
+            }
+        }
+
+        if state.input_handler.main_pressed {
+            // the tiles around may be about to change
+            state.gui_state.suggestions = None;
+        }
+
+        if let Some(index) = state.gui_state.zoning {
+            let coord = state.camera.pointing_at;
+
+            if state.gui_state.screen == Screen::Ingame
+                && coord.is_in_world()
+                && (state.input_handler.main_held || state.input_handler.alternate_held)
+            {
+                if let Some((info, _)) = &state.loop_store.map_info {
+                    let mut info = info.blocking_lock();
+
+                    if state.input_handler.main_held {
+                        info.zones.paint(index, coord);
+                    } else {
+                        info.zones.erase(coord);
+                    }
+                }
+            }
+        }
+
+        if state.input_handler.main_pressed && state.gui_state.routing.is_some() {
+            route::on_route_click(state);
+        } else if state.input_handler.main_pressed && state.gui_state.importing_region.is_some() {
+            let offset = state.camera.pointing_at;
+
+            if let Some(tiles) = state.gui_state.importing_region.take() {
+                state.gui_state.importing_conflicts = None;
+
+                state.game.send_message(GameSystemMessage::ImportTiles(
+                    tiles
+                        .into_iter()
+                        .map(|(coord, id, data)| (coord + offset, id, data))
+                        .collect(),
+                ))?;
+
+                state
+                    .audio_man
+                    .play(&state.resource_man.audio["tile_placement"]);
+            }
+        } else if state.input_handler.main_pressed
             || (state.input_handler.shift_held && state.input_handler.main_held)
         {
             if let Some(id) = state.gui_state.selected_tile_id {
@@ -403,34 +629,36 @@ pub fn on_event(
                 }
 
                 if state.gui_state.already_placed_at != Some(state.camera.pointing_at) {
-                    let response = state
-                        .tokio
-                        .block_on(state.game.call(
-                            |reply| GameSystemMessage::PlaceTile {
-                                coord: state.camera.pointing_at,
-                                id,
-                                record: true,
-                                reply: Some(reply),
-                                data: Some(data),
-                            },
-                            None,
-                        ))?
-                        .unwrap();
-
-                    match response {
-                        PlaceTileResponse::Placed => {
+                    let editor_mode = state
+                        .loop_store
+                        .map_info
+                        .as_ref()
+                        .is_some_and(|(info, _)| info.blocking_lock().editor_mode);
+
+                    if editor_mode {
+                        // the whole brush is undone at once, starting from the tile being pointed at
+                        let mut tiles = vec![(state.camera.pointing_at, id, data.clone())];
+                        tiles.extend(
                             state
-                                .audio_man
-                                .play(state.resource_man.audio["tile_placement"].clone())
-                                .unwrap();
-                        }
-                        PlaceTileResponse::Removed => {
-                            state
-                                .audio_man
-                                .play(state.resource_man.audio["tile_removal"].clone())
-                                .unwrap();
-                        }
-                        _ => {}
+                                .camera
+                                .pointing_at
+                                .hexagon(state.gui_state.editor_brush_size)
+                                .filter(|coord| *coord != state.camera.pointing_at)
+                                .map(|coord| (coord, id, data.clone())),
+                        );
+
+                        state.game.send_message(GameSystemMessage::PlaceTiles {
+                            tiles,
+                            record: true,
+                        })?;
+                    } else {
+                        state.game.send_message(GameSystemMessage::PlaceTile {
+                            coord: state.camera.pointing_at,
+                            id,
+                            record: true,
+                            reply: None,
+                            data: Some(data),
+                        })?;
                     }
 
                     state.gui_state.already_placed_at = Some(state.camera.pointing_at)
@@ -438,7 +666,10 @@ pub fn on_event(
             }
         }
 
-        if !state.input_handler.control_held && state.input_handler.alternate_pressed {
+        if !state.input_handler.control_held
+            && state.input_handler.alternate_pressed
+            && state.gui_state.zoning.is_none()
+        {
             if let Some(linking_tile) = state.gui_state.linking_tile {
                 state.tokio.block_on(on_link_tile(
                     state.resource_man.clone(),
@@ -481,15 +712,11 @@ pub fn on_event(
                     }
 
                     state.gui_state.initial_cursor_position = None;
-                    state
-                        .audio_man
-                        .play(state.resource_man.audio["click"].clone())?; // TODO click2
+                    state.audio_man.play(&state.resource_man.audio["click"]); // TODO click2
                 }
             } else if state.input_handler.alternate_pressed {
                 state.gui_state.initial_cursor_position = Some(state.camera.pointing_at);
-                state
-                    .audio_man
-                    .play(state.resource_man.audio["click"].clone())?;
+                state.audio_man.play(&state.resource_man.audio["click"]);
             }
 
             if state.gui_state.initial_cursor_position.is_none() {
@@ -505,6 +732,24 @@ pub fn on_event(
 
         if state.input_handler.control_held && state.input_handler.key_active(KeyActions::Undo) {
             state.game.send_message(GameSystemMessage::Undo)?;
+            state.gui_state.suggestions = None;
+        }
+
+        if state.input_handler.control_held && state.input_handler.key_active(KeyActions::Redo) {
+            state.game.send_message(GameSystemMessage::Redo)?;
+            state.gui_state.suggestions = None;
+        }
+
+        if state.input_handler.control_held && state.gui_state.screen == Screen::Ingame {
+            if state.input_handler.key_active(KeyActions::CopyBlueprint)
+                && !state.gui_state.grouped_tiles.is_empty()
+            {
+                gui::blueprint::copy_blueprint(state);
+            }
+
+            if state.input_handler.key_active(KeyActions::PasteBlueprint) {
+                gui::blueprint::paste_blueprint(state);
+            }
         }
 
         if state.input_handler.key_active(KeyActions::Fullscreen) {
@@ -516,10 +761,7 @@ pub fn on_event(
             if state.input_handler.shift_held
                 && !state.resource_man.registry.tiles[&selected_tile_id]
                     .data
-                    .get(&state.resource_man.registry.data_ids.not_targeted)
-                    .cloned()
-                    .and_then(Data::into_bool)
-                    .unwrap_or(false)
+                    .flag(state.resource_man.registry.data_ids.not_targeted)
             {
                 let hex = math::main_pos_to_fract_hex(
                     window::window_size_double(&state.renderer.gpu.window),
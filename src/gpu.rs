@@ -1,37 +1,46 @@
-use core::slice;
 use std::mem;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use egui_wgpu::wgpu::util::{BufferInitDescriptor, DeviceExt};
 use egui_wgpu::wgpu::{
     AddressMode, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
-    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendState,
-    Buffer, BufferAddress, BufferBindingType, BufferUsages, Color, ColorTargetState, ColorWrites,
-    CompareFunction, DepthStencilState, Device, DeviceDescriptor, Extent3d, Features, FilterMode,
-    FragmentState, FrontFace, Instance, InstanceDescriptor, Limits, MultisampleState,
-    PipelineLayoutDescriptor, PowerPreference, PresentMode, PrimitiveState, PrimitiveTopology,
-    Queue, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, Sampler,
-    SamplerBindingType, SamplerDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource,
-    ShaderStages, SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension,
-    TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
-    TextureViewDimension, VertexState,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, Buffer, BufferAddress, BufferBindingType,
+    BufferDescriptor, BufferSize, BufferUsages, Color, ColorTargetState, ColorWrites,
+    CommandEncoder, CompareFunction, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, DepthStencilState, Device, DeviceDescriptor, ErrorFilter, Extent3d,
+    Features, FilterMode, FragmentState, FrontFace, IndexFormat, Instance, InstanceDescriptor,
+    Limits, LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor, PowerPreference,
+    PresentMode, PrimitiveState, PrimitiveTopology, Queue, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, RequestAdapterOptions, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp,
+    SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState,
 };
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use image::EncodableLayout;
-use wgpu::util::{DrawIndexedIndirectArgs, TextureDataOrder};
+use wgpu::util::{DrawIndexedIndirectArgs, StagingBelt, TextureDataOrder};
 use wgpu::{AdapterInfo, Surface};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
 use automancy_defs::bytemuck;
 use automancy_defs::id::Id;
-use automancy_defs::math::Matrix4;
-use automancy_defs::rendering::{GameUBO, InstanceData, MatrixData, RawInstanceData, Vertex};
+use automancy_defs::log;
+use automancy_defs::math::{Float, Matrix4, Vec3};
+use automancy_defs::rendering::{
+    Animation, GameUBO, InstanceData, MatrixBuffers, MatrixData, RawInstanceData, Vertex,
+    WorldMatrixData,
+};
 use automancy_defs::slice_group_by::GroupBy;
 use automancy_macros::OptionGetter;
+use automancy_resources::types::model::IndexRange;
 use automancy_resources::ResourceManager;
 
+use crate::memory::CacheUsage;
 use crate::SSAO_NOISE_MAP;
 
 pub const GPU_BACKENDS: Backends = Backends::all();
@@ -46,55 +55,208 @@ pub const NORMAL_CLEAR: Color = Color {
 pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 pub const SCREENSHOT_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
 
+/// Gets the format of the textures a frame is drawn onto before it is presented.
+///
+/// It is always sRGB, so the shaders and blending work with linear colors no matter what the surface takes.
+pub fn frame_format(config: &SurfaceConfiguration) -> TextureFormat {
+    config.format.add_srgb_suffix()
+}
+
+/// The current animation matrix of each part of each model, by model and then by the index of the part.
 pub type AnimationMap = HashMap<Id, HashMap<usize, Matrix4>>;
 
+/// Keeps the animations of the models that were drawn across frames, for both the game and the GUI.
+///
+/// The animation tracks of a model are grouped by the part they move only once,
+/// after which only the matrices are worked out again each frame.
+#[derive(Debug, Default)]
+pub struct AnimationCache {
+    /// the indices of the animation tracks of each model, grouped by the part of the model they move
+    tracks: HashMap<Id, Vec<(usize, Vec<usize>)>>,
+    /// the matrices of the animated models, as of the last update
+    pub matrices: AnimationMap,
+}
+
+/// Gets the matrix of the animation at the given time, looping back to the start past its end.
+fn sample_animation(animation: &Animation, elapsed: Float) -> Matrix4 {
+    let last = animation.inputs.last().unwrap();
+    let wrapped = elapsed % last;
+    let index = animation.inputs.partition_point(|v| *v < wrapped);
+
+    animation.outputs[index]
+}
+
+impl AnimationCache {
+    /// How much of the memory the matrices of the animated models are using.
+    pub fn memory_usage(&self) -> CacheUsage {
+        CacheUsage::of::<(usize, Matrix4)>(self.matrices.values().map(HashMap::len).sum())
+    }
+
+    /// Starts keeping track of the model's animation, if it isn't already.
+    pub fn track(&mut self, resource_man: &ResourceManager, model: Id, elapsed: Float) {
+        if self.tracks.contains_key(&model) {
+            return;
+        }
+
+        let Some((_, animations)) = resource_man.all_models.get(&model) else {
+            return;
+        };
+
+        let mut tracks = HashMap::<usize, Vec<usize>>::new();
+        for (index, animation) in animations.iter().enumerate() {
+            tracks.entry(animation.target).or_default().push(index);
+        }
+
+        self.tracks.insert(model, tracks.into_iter().collect());
+        self.update_model(resource_man, model, elapsed);
+    }
+
+    /// Works out the matrices of all the animated models at the given time.
+    pub fn update(&mut self, resource_man: &ResourceManager, elapsed: Float) {
+        let models = self
+            .tracks
+            .iter()
+            .filter(|(_, tracks)| !tracks.is_empty())
+            .map(|(model, _)| *model)
+            .collect::<Vec<_>>();
+
+        for model in models {
+            self.update_model(resource_man, model, elapsed);
+        }
+    }
+
+    fn update_model(&mut self, resource_man: &ResourceManager, model: Id, elapsed: Float) {
+        let (Some(tracks), Some((_, animations))) =
+            (self.tracks.get(&model), resource_man.all_models.get(&model))
+        else {
+            return;
+        };
+
+        if tracks.is_empty() {
+            return;
+        }
+
+        let matrices = self.matrices.entry(model).or_default();
+
+        for (target, indices) in tracks {
+            let matrix = indices.iter().fold(Matrix4::IDENTITY, |acc, index| {
+                acc * sample_animation(&animations[*index], elapsed)
+            });
+
+            matrices.insert(*target, matrix);
+        }
+    }
+}
+
+/// Runs the function with the validation errors it causes caught, and shows them to the player
+/// along with what was being made, instead of letting them take down the game.
+pub fn validated<T>(
+    device: &Device,
+    resource_man: &ResourceManager,
+    what: &str,
+    f: impl FnOnce() -> T,
+) -> T {
+    device.push_error_scope(ErrorFilter::Validation);
+
+    let result = f();
+
+    if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+        resource_man.error_man.push(
+            (
+                resource_man.registry.err_ids.gpu_error,
+                vec![what.to_string(), err.to_string()],
+            ),
+            resource_man,
+        );
+    }
+
+    result
+}
+
+/// Shows the errors not caught by [`validated`] to the player, each one only once, instead of panicking on them.
+pub fn report_uncaptured_errors(device: &Device, resource_man: Arc<ResourceManager>) {
+    let reported = Mutex::new(HashSet::new());
+
+    device.on_uncaptured_error(Box::new(move |err| {
+        let err = err.to_string();
+
+        if reported.lock().unwrap().insert(err.clone()) {
+            resource_man.error_man.push(
+                (
+                    resource_man.registry.err_ids.gpu_error,
+                    vec!["a frame".to_string(), err],
+                ),
+                &resource_man,
+            );
+        }
+    }));
+}
+
+fn create_shader(
+    device: &Device,
+    resource_man: &ResourceManager,
+    name: &str,
+    label: &str,
+) -> ShaderModule {
+    validated(device, resource_man, &format!("the {name} shader"), || {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(label),
+            source: ShaderSource::Wgsl(resource_man.shaders[name].as_str().into()),
+        })
+    })
+}
+
+/// Makes everything needed for rendering, calling `progress` with what is being made at each step.
+///
+/// Building the pipelines can take a while on some drivers, and the version of wgpu in use has no way
+/// to cache them across runs, so the progress is there to show the player the game hasn't hung.
 pub fn init_gpu_resources(
     device: &Device,
     queue: &Queue,
     config: &SurfaceConfiguration,
     resource_man: &ResourceManager,
-    vertices: Vec<Vertex>,
-    indices: Vec<u16>,
+    vertices: &[Vertex],
+    indices: &[u16],
+    progress: &dyn Fn(&str),
 ) -> (
     SharedResources,
     RenderResources,
     GlobalBuffers,
     GuiResources,
 ) {
-    let game_shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: Some("Game Shader"),
-        source: ShaderSource::Wgsl(resource_man.shaders["game"].as_str().into()),
-    });
+    progress("shaders");
 
-    let combine_shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: Some("Combine Shader"),
-        source: ShaderSource::Wgsl(resource_man.shaders["combine"].as_str().into()),
-    });
+    let game_shader = create_shader(device, resource_man, "game", "Game Shader");
 
-    let fxaa_shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: Some("FXAA Shader"),
-        source: ShaderSource::Wgsl(resource_man.shaders["fxaa"].as_str().into()),
-    });
+    let combine_shader = create_shader(device, resource_man, "combine", "Combine Shader");
 
-    let post_processing_shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: Some("Post Processing Shader"),
-        source: ShaderSource::Wgsl(resource_man.shaders["post_processing"].as_str().into()),
-    });
+    let fxaa_shader = create_shader(device, resource_man, "fxaa", "FXAA Shader");
 
-    let intermediate_shader = device.create_shader_module(ShaderModuleDescriptor {
-        label: Some("Intermediate Shader"),
-        source: ShaderSource::Wgsl(resource_man.shaders["intermediate"].as_str().into()),
-    });
+    let post_processing_shader = create_shader(
+        device,
+        resource_man,
+        "post_processing",
+        "Post Processing Shader",
+    );
+
+    let intermediate_shader =
+        create_shader(device, resource_man, "intermediate", "Intermediate Shader");
+
+    let bloom_shader = create_shader(device, resource_man, "bloom", "Bloom Shader");
+
+    let cull_shader = create_shader(device, resource_man, "cull", "Cull Shader");
+
+    let outline_shader = create_shader(device, resource_man, "outline", "Outline Shader");
 
     let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(vertices.as_slice()),
+        contents: bytemuck::cast_slice(vertices),
         usage: BufferUsages::VERTEX,
     });
 
     let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: Some("Index Buffer"),
-        contents: bytemuck::cast_slice(indices.as_slice()),
+        contents: bytemuck::cast_slice(indices),
         usage: BufferUsages::INDEX,
     });
 
@@ -148,7 +310,8 @@ pub fn init_gpu_resources(
         ..Default::default()
     });
 
-    let game_resources = {
+    progress("game pass");
+    let game_resources = validated(device, resource_man, "the game pass", || {
         let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Game Uniform Buffer"),
             contents: bytemuck::cast_slice(&[GameUBO::default()]),
@@ -156,6 +319,8 @@ pub fn init_gpu_resources(
         });
 
         const MATRIX_DATA_SIZE: usize = 65536;
+        // most of the instances share the camera's world matrix
+        const WORLD_MATRIX_DATA_SIZE: usize = 256;
         let matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Game Matrix Data Buffer"),
             contents: &Vec::from_iter(
@@ -163,6 +328,13 @@ pub fn init_gpu_resources(
             ),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
+        let world_matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Game World Matrix Data Buffer"),
+            contents: &Vec::from_iter(
+                (0..(mem::size_of::<WorldMatrixData>() * WORLD_MATRIX_DATA_SIZE)).map(|_| 0),
+            ),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
 
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
@@ -186,6 +358,16 @@ pub fn init_gpu_resources(
                     },
                     count: None,
                 },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
             label: Some("game_bind_group_layout"),
         });
@@ -201,6 +383,10 @@ pub fn init_gpu_resources(
                     binding: 1,
                     resource: matrix_data_buffer.as_entire_binding(),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: world_matrix_data_buffer.as_entire_binding(),
+                },
             ],
             label: Some("game_bind_group"),
         });
@@ -224,7 +410,7 @@ pub fn init_gpu_resources(
                 entry_point: "fs_main",
                 targets: &[
                     Some(ColorTargetState {
-                        format: config.format,
+                        format: frame_format(config),
                         blend: Some(BlendState::ALPHA_BLENDING),
                         write_mask: ColorWrites::ALL,
                     }),
@@ -265,14 +451,16 @@ pub fn init_gpu_resources(
             instance_buffer: device.create_buffer_init(&BufferInitDescriptor {
                 label: None,
                 contents: &[],
-                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                usage: BufferUsages::VERTEX | BufferUsages::STORAGE,
             }),
             indirect_buffer: device.create_buffer_init(&BufferInitDescriptor {
                 label: None,
                 contents: &[],
-                usage: BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+                usage: BufferUsages::INDIRECT | BufferUsages::STORAGE | BufferUsages::COPY_DST,
             }),
+            culling: InstanceCulling::new(device, &cull_shader, "Game"),
             matrix_data_buffer,
+            world_matrix_data_buffer,
             uniform_buffer,
             bind_group,
             pipeline,
@@ -281,9 +469,10 @@ pub fn init_gpu_resources(
             antialiasing_bind_group: None,
             antialiasing_texture: None,
         }
-    };
+    });
 
-    let in_world_item_resources = {
+    progress("in-world item pass");
+    let in_world_item_resources = validated(device, resource_man, "the in-world item pass", || {
         let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("In-world Item Uniform Buffer"),
             contents: bytemuck::cast_slice(&[GameUBO::default()]),
@@ -291,6 +480,7 @@ pub fn init_gpu_resources(
         });
 
         const MATRIX_DATA_SIZE: usize = 2048;
+        const WORLD_MATRIX_DATA_SIZE: usize = 256;
         let matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("In-world Item Matrix Data Buffer"),
             contents: &Vec::from_iter(
@@ -298,6 +488,13 @@ pub fn init_gpu_resources(
             ),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
+        let world_matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("In-world Item World Matrix Data Buffer"),
+            contents: &Vec::from_iter(
+                (0..(mem::size_of::<WorldMatrixData>() * WORLD_MATRIX_DATA_SIZE)).map(|_| 0),
+            ),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
 
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
@@ -321,6 +518,16 @@ pub fn init_gpu_resources(
                     },
                     count: None,
                 },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
             label: Some("in_world_item_bind_group_layout"),
         });
@@ -336,6 +543,10 @@ pub fn init_gpu_resources(
                     binding: 1,
                     resource: matrix_data_buffer.as_entire_binding(),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: world_matrix_data_buffer.as_entire_binding(),
+                },
             ],
             label: Some("in_world_item_bind_group"),
         });
@@ -359,7 +570,7 @@ pub fn init_gpu_resources(
                 entry_point: "fs_main",
                 targets: &[
                     Some(ColorTargetState {
-                        format: config.format,
+                        format: frame_format(config),
                         blend: Some(BlendState::ALPHA_BLENDING),
                         write_mask: ColorWrites::ALL,
                     }),
@@ -400,21 +611,24 @@ pub fn init_gpu_resources(
             instance_buffer: device.create_buffer_init(&BufferInitDescriptor {
                 label: None,
                 contents: &[],
-                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                usage: BufferUsages::VERTEX | BufferUsages::STORAGE,
             }),
             indirect_buffer: device.create_buffer_init(&BufferInitDescriptor {
                 label: None,
                 contents: &[],
-                usage: BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+                usage: BufferUsages::INDIRECT | BufferUsages::STORAGE | BufferUsages::COPY_DST,
             }),
+            culling: InstanceCulling::new(device, &cull_shader, "In-world Item"),
             uniform_buffer,
             matrix_data_buffer,
+            world_matrix_data_buffer,
             bind_group,
             pipeline,
         }
-    };
+    });
 
-    let gui_resources = {
+    progress("GUI pass");
+    let gui_resources = validated(device, resource_man, "the GUI pass", || {
         let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Gui Uniform Buffer"),
             contents: bytemuck::cast_slice(&[GameUBO::default()]),
@@ -422,6 +636,7 @@ pub fn init_gpu_resources(
         });
 
         const MATRIX_DATA_SIZE: usize = 4096;
+        const WORLD_MATRIX_DATA_SIZE: usize = 4096;
         let matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Gui Matrix Data Buffer"),
             contents: &Vec::from_iter(
@@ -429,6 +644,13 @@ pub fn init_gpu_resources(
             ),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
+        let world_matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Gui World Matrix Data Buffer"),
+            contents: &Vec::from_iter(
+                (0..(mem::size_of::<WorldMatrixData>() * WORLD_MATRIX_DATA_SIZE)).map(|_| 0),
+            ),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
 
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
@@ -452,6 +674,16 @@ pub fn init_gpu_resources(
                     },
                     count: None,
                 },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
             label: Some("gui_bind_group_layout"),
         });
@@ -467,6 +699,10 @@ pub fn init_gpu_resources(
                     binding: 1,
                     resource: matrix_data_buffer.as_entire_binding(),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: world_matrix_data_buffer.as_entire_binding(),
+                },
             ],
             label: Some("gui_bind_group"),
         });
@@ -489,7 +725,7 @@ pub fn init_gpu_resources(
                 module: &game_shader,
                 entry_point: "fs_main",
                 targets: &[Some(ColorTargetState {
-                    format: config.format,
+                    format: frame_format(config),
                     blend: Some(BlendState::ALPHA_BLENDING),
                     write_mask: ColorWrites::ALL,
                 })],
@@ -516,6 +752,7 @@ pub fn init_gpu_resources(
         });
 
         GuiResources {
+            uploader: Default::default(),
             instance_buffer: device.create_buffer_init(&BufferInitDescriptor {
                 label: None,
                 contents: &[],
@@ -523,10 +760,11 @@ pub fn init_gpu_resources(
             }),
             uniform_buffer,
             matrix_data_buffer,
+            world_matrix_data_buffer,
             bind_group,
             pipeline,
         }
-    };
+    });
 
     let egui_resources = EguiResources {
         texture: None,
@@ -578,35 +816,38 @@ pub fn init_gpu_resources(
         push_constant_ranges: &[],
     });
 
-    let combine_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-        label: Some("Combine Render Pipeline"),
-        layout: Some(&combine_pipeline_layout),
-        vertex: VertexState {
-            module: &combine_shader,
-            entry_point: "vs_main",
-            buffers: &[],
-        },
-        fragment: Some(FragmentState {
-            module: &combine_shader,
-            entry_point: "fs_main",
-            targets: &[Some(ColorTargetState {
-                format: config.format,
-                blend: None,
-                write_mask: ColorWrites::ALL,
-            })],
-        }),
-        primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
-            front_face: FrontFace::Ccw,
-            ..Default::default()
-        },
-        depth_stencil: None,
-        multisample: MultisampleState {
-            count: 1,
-            mask: !0,
-            alpha_to_coverage_enabled: false,
-        },
-        multiview: None,
+    progress("combine pass");
+    let combine_pipeline = validated(device, resource_man, "the combine pass", || {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Combine Render Pipeline"),
+            layout: Some(&combine_pipeline_layout),
+            vertex: VertexState {
+                module: &combine_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &combine_shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: frame_format(config),
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
     });
 
     let combine_pipeline = Rc::new(combine_pipeline);
@@ -618,7 +859,8 @@ pub fn init_gpu_resources(
         texture: None,
     };
 
-    let antialiasing_resources = {
+    progress("antialiasing pass");
+    let antialiasing_resources = validated(device, resource_man, "the antialiasing pass", || {
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
                 BindGroupLayoutEntry {
@@ -659,7 +901,7 @@ pub fn init_gpu_resources(
                 module: &fxaa_shader,
                 entry_point: "fs_main",
                 targets: &[Some(ColorTargetState {
-                    format: config.format,
+                    format: frame_format(config),
                     blend: None,
                     write_mask: ColorWrites::ALL,
                 })],
@@ -683,9 +925,10 @@ pub fn init_gpu_resources(
             bind_group_layout,
             fxaa_pipeline,
         }
-    };
+    });
 
-    let post_processing_resources = {
+    progress("bloom pass");
+    let bloom_resources = validated(device, resource_man, "the bloom pass", || {
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
                 BindGroupLayoutEntry {
@@ -704,94 +947,257 @@ pub fn init_gpu_resources(
                     ty: BindingType::Sampler(SamplerBindingType::Filtering),
                     count: None,
                 },
-                BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: TextureViewDimension::D2,
-                        sample_type: TextureSampleType::Float { filterable: false },
-                    },
-                    count: None,
+            ],
+            label: Some("bloom_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Bloom Render Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let make_pipeline = |label: &str, entry_point: &str, blend: Option<BlendState>| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &bloom_shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
                 },
-                BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
-                    count: None,
+                fragment: Some(FragmentState {
+                    module: &bloom_shader,
+                    entry_point,
+                    targets: &[Some(ColorTargetState {
+                        format: frame_format(config),
+                        blend,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    front_face: FrontFace::Ccw,
+                    cull_mode: None,
+                    ..Default::default()
                 },
-                BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: TextureViewDimension::D2,
-                        sample_type: TextureSampleType::Float { filterable: false },
-                    },
-                    count: None,
+                depth_stencil: None,
+                multisample: MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
                 },
+                multiview: None,
+            })
+        };
+
+        // the glow is added onto the frame, leaving its alpha as it is
+        let additive = BlendState {
+            color: BlendComponent {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        };
+
+        BloomResources {
+            bright_pipeline: make_pipeline("Bloom Bright Pipeline", "fs_bright", None),
+            blur_horizontal_pipeline: make_pipeline(
+                "Bloom Horizontal Blur Pipeline",
+                "fs_blur_horizontal",
+                None,
+            ),
+            blur_vertical_pipeline: make_pipeline(
+                "Bloom Vertical Blur Pipeline",
+                "fs_blur_vertical",
+                None,
+            ),
+            composite_pipeline: make_pipeline(
+                "Bloom Composite Pipeline",
+                "fs_composite",
+                Some(additive),
+            ),
+            bind_group_layout,
+            bright_bind_group: None,
+            first_texture: None,
+            first_bind_group: None,
+            second_texture: None,
+            second_bind_group: None,
+        }
+    });
+
+    progress("outline pass");
+    let outline_resources = validated(device, resource_man, "the outline pass", || {
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Outline Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[GameUBO::default()]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        // only the tile under the cursor and the selected ones are outlined
+        const MATRIX_DATA_SIZE: usize = 1024;
+        const WORLD_MATRIX_DATA_SIZE: usize = 16;
+        let matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Outline Matrix Data Buffer"),
+            contents: &Vec::from_iter(
+                (0..(mem::size_of::<MatrixData>() * MATRIX_DATA_SIZE)).map(|_| 0),
+            ),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let world_matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Outline World Matrix Data Buffer"),
+            contents: &Vec::from_iter(
+                (0..(mem::size_of::<WorldMatrixData>() * WORLD_MATRIX_DATA_SIZE)).map(|_| 0),
+            ),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
                 BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
                     count: None,
                 },
                 BindGroupLayoutEntry {
-                    binding: 6,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: TextureViewDimension::D2,
-                        sample_type: TextureSampleType::Float { filterable: false },
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
                     },
                     count: None,
                 },
                 BindGroupLayoutEntry {
-                    binding: 7,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
                     count: None,
                 },
-                BindGroupLayoutEntry {
-                    binding: 8,
-                    visibility: ShaderStages::FRAGMENT,
+            ],
+            label: Some("outline_bind_group_layout"),
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: matrix_data_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: world_matrix_data_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("outline_bind_group"),
+        });
+
+        let mask_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Outline Mask Render Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // the tiles are drawn in flat colors with the game shader, for the outlines to be traced around
+        let mask_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Outline Mask Render Pipeline"),
+            layout: Some(&mask_pipeline_layout),
+            vertex: VertexState {
+                module: &game_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), RawInstanceData::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &game_shader,
+                entry_point: "fs_mask",
+                targets: &[Some(ColorTargetState {
+                    format: OUTLINE_MASK_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let composite_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
                     ty: BindingType::Texture {
                         multisampled: false,
                         view_dimension: TextureViewDimension::D2,
                         sample_type: TextureSampleType::Float { filterable: false },
                     },
                     count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 9,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-            ],
-            label: Some("post_processing_bind_group_layout"),
-        });
+                }],
+                label: Some("outline_composite_bind_group_layout"),
+            });
 
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("Post Processing Render Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+        let composite_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Outline Composite Render Pipeline Layout"),
+            bind_group_layouts: &[&composite_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("Post Processing Render Pipeline"),
-            layout: Some(&pipeline_layout),
+        let composite_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Outline Composite Render Pipeline"),
+            layout: Some(&composite_pipeline_layout),
             vertex: VertexState {
-                module: &post_processing_shader,
+                module: &outline_shader,
                 entry_point: "vs_main",
                 buffers: &[],
             },
             fragment: Some(FragmentState {
-                module: &post_processing_shader,
-                entry_point: "fs_main",
+                module: &outline_shader,
+                entry_point: "fs_outline",
                 targets: &[Some(ColorTargetState {
-                    format: config.format,
-                    blend: None,
+                    format: frame_format(config),
+                    // blended over the frame, leaving its alpha as it is
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add,
+                        },
+                        alpha: BlendComponent {
+                            src_factor: BlendFactor::Zero,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
+                        },
+                    }),
                     write_mask: ColorWrites::ALL,
                 })],
             }),
@@ -810,14 +1216,165 @@ pub fn init_gpu_resources(
             multiview: None,
         });
 
-        PostProcessingResources {
-            bind_group_layout,
-            pipeline,
-            ssao_noise_map,
+        OutlineResources {
+            instance_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: &[],
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            }),
+            indirect_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: &[],
+                usage: BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+            }),
+            uniform_buffer,
+            matrix_data_buffer,
+            world_matrix_data_buffer,
+            bind_group,
+            mask_pipeline,
+            composite_bind_group_layout,
+            composite_pipeline,
+            mask_texture: None,
+            composite_bind_group: None,
         }
-    };
+    });
+
+    progress("post processing pass");
+    let post_processing_resources =
+        validated(device, resource_man, "the post processing pass", || {
+            let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 8,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: false },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 9,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                        count: None,
+                    },
+                ],
+                label: Some("post_processing_bind_group_layout"),
+            });
+
+            let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Post Processing Render Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Post Processing Render Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &post_processing_shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &post_processing_shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(ColorTargetState {
+                        format: frame_format(config),
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    front_face: FrontFace::Ccw,
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
 
-    let intermediate_resources = {
+            PostProcessingResources {
+                bind_group_layout,
+                pipeline,
+                ssao_noise_map,
+            }
+        });
+
+    progress("present pass");
+    let intermediate_resources = validated(device, resource_man, "the present pass", || {
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -889,7 +1446,12 @@ pub fn init_gpu_resources(
             },
             fragment: Some(FragmentState {
                 module: &intermediate_shader,
-                entry_point: "fs_main",
+                // a surface that isn't sRGB takes the encoded colors as they are, so they have to be encoded by hand
+                entry_point: if config.format.is_srgb() {
+                    "fs_main"
+                } else {
+                    "fs_main_encode_srgb"
+                },
                 targets: &[Some(ColorTargetState {
                     format: config.format,
                     blend: None,
@@ -916,8 +1478,9 @@ pub fn init_gpu_resources(
             screenshot_pipeline,
             present_pipeline,
             present_bind_group: None,
+            game_bind_group: None,
         }
-    };
+    });
 
     let mut shared = SharedResources {
         game_shader,
@@ -936,178 +1499,816 @@ pub fn init_gpu_resources(
         repeating_sampler,
     };
 
-    let mut render = RenderResources {
-        game_resources,
-        in_world_item_resources,
-        egui_resources,
-        first_combine_resources,
-        antialiasing_resources,
-        post_processing_resources,
-        intermediate_resources,
-    };
+    let mut render = RenderResources {
+        game_resources,
+        in_world_item_resources,
+        egui_resources,
+        first_combine_resources,
+        antialiasing_resources,
+        post_processing_resources,
+        bloom_resources,
+        outline_resources,
+        intermediate_resources,
+    };
+
+    shared.create(device, config, &mut render);
+
+    (
+        shared,
+        render,
+        GlobalBuffers {
+            vertex_buffer,
+            index_buffer,
+        },
+        gui_resources,
+    )
+}
+
+pub fn compile_instances<T: Clone + Send>(
+    resource_man: &ResourceManager,
+    instances: &[(InstanceData, Id, T)],
+    animation_map: &AnimationMap,
+) -> (HashMap<Id, Vec<(usize, RawInstanceData, T)>>, MatrixBuffers) {
+    let mut raw_instances = HashMap::new();
+    let mut matrix_buffers = MatrixBuffers::default();
+
+    instances.binary_group_by_key(|v| v.1).for_each(|group| {
+        let id = group[0].1;
+
+        if let Some((models, ..)) = &resource_man.all_models.get(&id) {
+            let vec = raw_instances
+                .entry(id)
+                .or_insert_with(|| Vec::with_capacity(group.len()));
+
+            for (instance, _, extra) in group.iter() {
+                for model in models.values() {
+                    let mut instance = *instance;
+
+                    let mut matrix = model.matrix;
+                    if let Some(anim) = animation_map
+                        .get(&id)
+                        .and_then(|anim| anim.get(&model.index))
+                    {
+                        matrix *= *anim;
+                    }
+                    instance = instance.add_model_matrix(matrix);
+
+                    vec.push((
+                        model.index,
+                        RawInstanceData::from_instance(instance, &mut matrix_buffers),
+                        extra.clone(),
+                    ));
+                }
+            }
+        }
+    });
+
+    raw_instances
+        .values_mut()
+        .for_each(|v| v.sort_by_key(|v| v.0));
+
+    (raw_instances, matrix_buffers)
+}
+
+/// The instances, their indirect draws, the number of draws, and the matrices the instances point into.
+pub type IndirectInstances<T> = (
+    Vec<RawInstanceData>,
+    HashMap<Id, Vec<(DrawIndexedIndirectArgs, T)>>,
+    u32,
+    MatrixBuffers,
+);
+
+/// Compiles the instances, and makes the indirect draws for them.
+///
+/// When grouping, all the instances of the same mesh are drawn with a single draw, even across different models,
+/// in which case the draw is listed under the first of the models. Otherwise, each instance gets its own draw.
+///
+/// With the position of the camera, relative to the same origin as the instances, each instance is drawn with the
+/// level of detail of its model for how far it is from the camera. Without it, everything is drawn in full detail.
+pub fn indirect_instance<T: Clone + Send + Sync>(
+    resource_man: &ResourceManager,
+    instances: &[(InstanceData, Id, T)],
+    group: bool,
+    animation_map: &AnimationMap,
+    camera_pos: Option<Vec3>,
+) -> IndirectInstances<T> {
+    let lod_instances;
+    let instances = match camera_pos {
+        Some(camera_pos) if !resource_man.model_lods.is_empty() => {
+            let mut swapped = instances
+                .iter()
+                .map(|(instance, id, extra)| {
+                    let distance = instance
+                        .get_model_matrix()
+                        .w_axis
+                        .truncate()
+                        .distance(camera_pos);
+
+                    (
+                        *instance,
+                        resource_man.lod_model(*id, distance),
+                        extra.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            // the instances need to stay sorted by model, to be compiled
+            swapped.sort_by_key(|v| v.1);
+
+            lod_instances = swapped;
+            lod_instances.as_slice()
+        }
+        _ => instances,
+    };
+
+    let (compiled_instances, matrix_buffers) =
+        compile_instances(resource_man, instances, animation_map);
+
+    let mut instances = compiled_instances
+        .into_iter()
+        .flat_map(|(id, instances)| {
+            let index_ranges = &resource_man.all_index_ranges[&id];
+
+            instances
+                .into_iter()
+                .map(move |(index, instance, extra)| (id, index_ranges[&index], instance, extra))
+        })
+        .collect::<Vec<_>>();
+
+    if group {
+        // instances of the same mesh need to be next to each other to be drawn together
+        instances.sort_by_key(|(_, index_range, ..)| (index_range.offset, index_range.size));
+    }
+
+    let mut commands = HashMap::<Id, Vec<_>>::new();
+    let mut draw_count = 0;
+    let mut base_instance = 0;
+
+    let mut add_draw = |draw: &[(Id, IndexRange, RawInstanceData, T)]| {
+        let (id, index_range, _, extra) = &draw[0];
+        let size = draw.len() as u32;
+
+        commands.entry(*id).or_default().push((
+            DrawIndexedIndirectArgs {
+                first_index: index_range.offset,
+                index_count: index_range.size,
+                first_instance: base_instance,
+                instance_count: size,
+                base_vertex: 0,
+            },
+            extra.clone(),
+        ));
+
+        base_instance += size;
+        draw_count += 1;
+    };
+
+    if group {
+        instances
+            .linear_group_by_key(|(_, index_range, ..)| (index_range.offset, index_range.size))
+            .for_each(&mut add_draw);
+    } else {
+        instances.chunks(1).for_each(&mut add_draw);
+    }
+
+    let instances = instances
+        .into_iter()
+        .map(|(_, _, instance, _)| instance)
+        .collect::<Vec<RawInstanceData>>();
+
+    (instances, commands, draw_count, matrix_buffers)
+}
+
+/// How big each chunk of the staging belt is. Uploads bigger than this get a chunk of their own.
+const UPLOAD_CHUNK_SIZE: BufferAddress = 1 << 20;
+
+/// Uploads data to buffers through a staging belt that is reused across frames,
+/// skipping uploads of data that is the same as what was last uploaded to the buffer.
+///
+/// The uploads are recorded into the encoder, so they need to be done before any render pass is started with it.
+/// [`Uploader::finish`] has to be called before the encoder is submitted, and [`Uploader::recall`] after.
+pub struct Uploader {
+    belt: StagingBelt,
+    /// what was last uploaded to each buffer
+    last: HashMap<wgpu::Id<Buffer>, Vec<u8>>,
+}
+
+impl Default for Uploader {
+    fn default() -> Self {
+        Self {
+            belt: StagingBelt::new(UPLOAD_CHUNK_SIZE),
+            last: Default::default(),
+        }
+    }
+}
+
+impl Uploader {
+    /// Uploads the contents to the start of the buffer, making a bigger buffer in its place if it doesn't fit.
+    pub fn upload(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        buffer: &mut Buffer,
+        contents: &[u8],
+    ) {
+        if buffer.size() < contents.len() as BufferAddress {
+            let usage = buffer.usage();
+
+            *buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents,
+                usage,
+            });
+
+            self.remember(buffer, contents);
+        } else {
+            self.write(device, encoder, buffer, contents);
+        }
+    }
+
+    /// Uploads the contents to the start of the buffer, leaving out whatever doesn't fit.
+    pub fn write(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        buffer: &Buffer,
+        contents: &[u8],
+    ) {
+        let contents = &contents[..contents.len().min(buffer.size() as usize)];
+
+        if self
+            .last
+            .get(&buffer.global_id())
+            .is_some_and(|last| last.as_slice() == contents)
+        {
+            return;
+        }
+
+        let Some(size) = BufferSize::new(contents.len() as BufferAddress) else {
+            return;
+        };
+
+        self.belt
+            .write_buffer(encoder, buffer, 0, size, device)
+            .copy_from_slice(contents);
+
+        self.remember(buffer, contents);
+    }
+
+    /// Forgets what was last uploaded to the buffer, so the next upload isn't skipped.
+    /// Needed for buffers the GPU writes to itself.
+    pub fn forget(&mut self, buffer: &Buffer) {
+        self.last.remove(&buffer.global_id());
+    }
+
+    fn remember(&mut self, buffer: &Buffer, contents: &[u8]) {
+        let last = self.last.entry(buffer.global_id()).or_default();
+
+        last.clear();
+        last.extend_from_slice(contents);
+    }
+
+    /// Closes off the uploads made so far. Should be called before submitting the encoder they were recorded into.
+    pub fn finish(&mut self) {
+        self.belt.finish();
+    }
+
+    /// Makes the staging buffers available again once the GPU is done with them. Should be called after submitting.
+    pub fn recall(&mut self) {
+        self.belt.recall();
+    }
+}
+
+/// How many instances each workgroup of the cull shader goes through. Has to match `cs_cull` in `cull.wgsl`.
+const CULL_WORKGROUP_SIZE: u32 = 64;
+
+/// Culls the instances of a world pass against the view on the GPU, so the CPU doesn't need to check each of them every frame.
+///
+/// All the instances are uploaded along with the draw each of them belongs to, and the draws are uploaded with no instances.
+/// The cull shader then copies the instances in view into the instance buffer the pass draws from, counting them in their draws.
+pub struct InstanceCulling {
+    pipeline: ComputePipeline,
+    bind_group_layout: BindGroupLayout,
+    uniform_buffer: Buffer,
+    /// all the instances, before culling
+    input_buffer: Buffer,
+    /// the index of the draw each instance belongs to
+    draw_index_buffer: Buffer,
+}
+
+impl InstanceCulling {
+    pub fn new(device: &Device, shader: &ShaderModule, label: &str) -> Self {
+        let storage = |binding, read_only| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, true),
+                storage(3, true),
+                storage(4, true),
+                storage(5, false),
+                storage(6, false),
+            ],
+            label: Some(&format!("{label} Cull Bind Group Layout")),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some(&format!("{label} Cull Pipeline Layout")),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some(&format!("{label} Cull Pipeline")),
+            layout: Some(&pipeline_layout),
+            module: shader,
+            entry_point: "cs_cull",
+        });
+
+        let storage_buffer = || {
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: &[],
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            })
+        };
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: Some(&format!("{label} Cull Uniform Buffer")),
+                contents: bytemuck::cast_slice(&[0u32; 4]),
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            }),
+            input_buffer: storage_buffer(),
+            draw_index_buffer: storage_buffer(),
+        }
+    }
+
+    /// Uploads the instances and their draws, and culls them into the instance and indirect buffers the pass draws with.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cull(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        uploader: &mut Uploader,
+        (instances, draws): (&[RawInstanceData], &[DrawIndexedIndirectArgs]),
+        (matrix_data_buffer, world_matrix_data_buffer): (&Buffer, &Buffer),
+        instance_buffer: &mut Buffer,
+        indirect_buffer: &mut Buffer,
+    ) {
+        let mut draw_indices = vec![0u32; instances.len()];
+        let mut indirect = vec![];
+
+        for (index, draw) in draws.iter().enumerate() {
+            let first = draw.first_instance as usize;
+
+            draw_indices[first..first + draw.instance_count as usize].fill(index as u32);
+
+            indirect.extend_from_slice(
+                DrawIndexedIndirectArgs {
+                    instance_count: 0,
+                    ..*draw
+                }
+                .as_bytes(),
+            );
+        }
+
+        // the cull shader counts the instances into the draws, so they are never the same as what was uploaded
+        uploader.forget(indirect_buffer);
+        uploader.upload(device, encoder, indirect_buffer, &indirect);
+
+        if instances.is_empty() {
+            return;
+        }
+
+        uploader.upload(
+            device,
+            encoder,
+            &mut self.input_buffer,
+            bytemuck::cast_slice(instances),
+        );
+        uploader.upload(
+            device,
+            encoder,
+            &mut self.draw_index_buffer,
+            bytemuck::cast_slice(&draw_indices),
+        );
+        uploader.write(
+            device,
+            encoder,
+            &self.uniform_buffer,
+            bytemuck::cast_slice(&[instances.len() as u32, 0, 0, 0]),
+        );
+
+        let size = mem::size_of_val(instances) as BufferAddress;
+        if instance_buffer.size() < size {
+            *instance_buffer = device.create_buffer(&BufferDescriptor {
+                label: None,
+                size,
+                usage: instance_buffer.usage(),
+                mapped_at_creation: false,
+            });
+        }
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: matrix_data_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: world_matrix_data_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: self.input_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: self.draw_index_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: indirect_buffer.as_entire_binding(),
+                },
+            ],
+            label: None,
+        });
+
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Cull Pass"),
+            timestamp_writes: None,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups((instances.len() as u32).div_ceil(CULL_WORKGROUP_SIZE), 1, 1);
+    }
+}
+
+/// A texture that render passes hand to each other within a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameResource {
+    Game,
+    Normal,
+    Model,
+    Depth,
+    PostProcessing,
+    Antialiasing,
+    Gui,
+    Combine,
+    /// The window's surface. Passes drawing to it are always run.
+    Surface,
+    /// The copy of the frame taken for a screenshot. Passes drawing to it are always run.
+    Screenshot,
+    /// The scaled down copy of the frame taken as a map's thumbnail. Passes drawing to it are always run.
+    Thumbnail,
+}
+
+impl FrameResource {
+    fn is_output(self) -> bool {
+        matches!(
+            self,
+            FrameResource::Surface | FrameResource::Screenshot | FrameResource::Thumbnail
+        )
+    }
+}
+
+type RecordPass<'a> = Box<dyn FnOnce(&mut CommandEncoder) + 'a>;
 
-    shared.create(device, config, &mut render);
+/// A render pass in a [`FrameGraph`], along with the resources it reads from and draws to.
+pub struct FramePass<'a> {
+    pub name: &'static str,
+    pub inputs: Vec<FrameResource>,
+    pub outputs: Vec<FrameResource>,
+    record: RecordPass<'a>,
+}
 
-    (
-        shared,
-        render,
-        GlobalBuffers {
-            vertex_buffer,
-            index_buffer,
-        },
-        gui_resources,
-    )
+impl<'a> FramePass<'a> {
+    pub fn new(
+        name: &'static str,
+        inputs: &[FrameResource],
+        outputs: &[FrameResource],
+        record: impl FnOnce(&mut CommandEncoder) + 'a,
+    ) -> Self {
+        Self {
+            name,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+            record: Box::new(record),
+        }
+    }
 }
 
-pub fn compile_instances<T: Clone + Send>(
-    resource_man: &ResourceManager,
-    instances: &[(InstanceData, Id, T)],
-    animation_map: &AnimationMap,
-) -> (
-    HashMap<Id, Vec<(usize, RawInstanceData, T)>>,
-    Vec<MatrixData>,
-) {
-    let mut raw_instances = HashMap::new();
-    let mut matrix_data = vec![];
+/// The render passes of a frame, in the order they are run.
+///
+/// Passes can be put in relative to the others by name. When run, passes whose outputs nothing reads are left out.
+#[derive(Default)]
+pub struct FrameGraph<'a> {
+    passes: Vec<FramePass<'a>>,
+}
 
-    instances.binary_group_by_key(|v| v.1).for_each(|group| {
-        let id = group[0].1;
+impl<'a> FrameGraph<'a> {
+    pub fn push(&mut self, pass: FramePass<'a>) {
+        self.passes.push(pass);
+    }
 
-        if let Some((models, ..)) = &resource_man.all_models.get(&id) {
-            let vec = raw_instances
-                .entry(id)
-                .or_insert_with(|| Vec::with_capacity(group.len()));
+    fn position(&self, name: &str) -> Option<usize> {
+        self.passes.iter().position(|pass| pass.name == name)
+    }
 
-            for (instance, _, extra) in group.iter() {
-                for model in models.values() {
-                    let mut instance = *instance;
+    /// Puts the pass in right before the named one, or at the end if there is no such pass.
+    pub fn insert_before(&mut self, name: &str, pass: FramePass<'a>) {
+        let index = self.position(name).unwrap_or(self.passes.len());
 
-                    let mut matrix = model.matrix;
-                    if let Some(anim) = animation_map
-                        .get(&id)
-                        .and_then(|anim| anim.get(&model.index))
-                    {
-                        matrix *= *anim;
-                    }
-                    instance = instance.add_model_matrix(matrix);
+        self.passes.insert(index, pass);
+    }
 
-                    vec.push((
-                        model.index,
-                        RawInstanceData::from_instance(instance, &mut matrix_data),
-                        extra.clone(),
-                    ));
-                }
+    /// Puts the pass in right after the named one, or at the end if there is no such pass.
+    pub fn insert_after(&mut self, name: &str, pass: FramePass<'a>) {
+        let index = self
+            .position(name)
+            .map(|index| index + 1)
+            .unwrap_or(self.passes.len());
+
+        self.passes.insert(index, pass);
+    }
+
+    /// Records the passes that lead to an output into the encoder.
+    pub fn execute(self, encoder: &mut CommandEncoder) {
+        let mut needed = HashSet::new();
+        let mut used = vec![false; self.passes.len()];
+
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            if pass
+                .outputs
+                .iter()
+                .any(|output| output.is_output() || needed.contains(output))
+            {
+                used[index] = true;
+                needed.extend(pass.inputs.iter().copied());
             }
         }
-    });
 
-    raw_instances
-        .values_mut()
-        .for_each(|v| v.sort_by_key(|v| v.0));
+        let mut written = HashSet::new();
 
-    (raw_instances, matrix_data)
-}
+        for (pass, used) in self.passes.into_iter().zip(used) {
+            if !used {
+                continue;
+            }
 
-fn collect_indirect<T: Clone + Send + Sync>(
-    base_instance_counter: &mut u32,
-    draw_count: &mut u32,
-    vec: &mut Vec<(DrawIndexedIndirectArgs, T)>,
-    resource_man: &ResourceManager,
-    id: &Id,
-    instances: &[(usize, RawInstanceData, T)],
-) {
-    let size = instances.len() as u32;
+            for input in &pass.inputs {
+                if !written.contains(input) {
+                    log::warn!(
+                        "Render pass {} reads from {:?} before anything draws to it",
+                        pass.name,
+                        input
+                    );
+                }
+            }
+            written.extend(pass.outputs.iter().copied());
+
+            (pass.record)(encoder);
+        }
+    }
+}
 
-    let index_range = resource_man.all_index_ranges[id][&instances[0].0];
+/// What the world passes need to draw their instances.
+pub struct WorldDraw<'a> {
+    pub pipeline: &'a RenderPipeline,
+    pub bind_group: &'a BindGroup,
+    pub instance_buffer: &'a Buffer,
+    pub indirect_buffer: &'a Buffer,
+    pub draw_count: u32,
+}
 
-    let command = DrawIndexedIndirectArgs {
-        first_index: index_range.offset,
-        index_count: index_range.size,
-        first_instance: *base_instance_counter,
-        instance_count: size,
-        base_vertex: 0,
+/// Draws instances onto the game, normal, model and depth textures, clearing them first if `clear` is set.
+pub fn world_pass(
+    encoder: &mut CommandEncoder,
+    label: &str,
+    shared_resources: &SharedResources,
+    global_buffers: &GlobalBuffers,
+    draw: WorldDraw,
+    clear: bool,
+) {
+    let load = |clear_value: Color| {
+        if clear {
+            LoadOp::Clear(clear_value)
+        } else {
+            LoadOp::Load
+        }
     };
 
-    *base_instance_counter += size;
-    *draw_count += 1;
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[
+            Some(RenderPassColorAttachment {
+                view: &shared_resources.game_texture().1,
+                resolve_target: None,
+                ops: Operations {
+                    load: load(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            }),
+            Some(RenderPassColorAttachment {
+                view: &shared_resources.normal_texture().1,
+                resolve_target: None,
+                ops: Operations {
+                    load: load(NORMAL_CLEAR),
+                    store: StoreOp::Store,
+                },
+            }),
+            Some(RenderPassColorAttachment {
+                view: &shared_resources.model_texture().1,
+                resolve_target: None,
+                ops: Operations {
+                    load: load(Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+            }),
+        ],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+            view: &shared_resources.depth_texture().1,
+            depth_ops: Some(Operations {
+                load: if clear {
+                    LoadOp::Clear(1.0)
+                } else {
+                    LoadOp::Load
+                },
+                store: StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    if draw.draw_count > 0 {
+        pass.set_pipeline(draw.pipeline);
+        pass.set_bind_group(0, draw.bind_group, &[]);
+        pass.set_vertex_buffer(0, global_buffers.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, draw.instance_buffer.slice(..));
+        pass.set_index_buffer(global_buffers.index_buffer.slice(..), IndexFormat::Uint16);
 
-    vec.push((command, instances[0].2.clone()));
+        pass.multi_draw_indexed_indirect(draw.indirect_buffer, 0, draw.draw_count);
+    }
 }
 
-pub fn indirect_instance<T: Clone + Send + Sync>(
-    resource_man: &ResourceManager,
-    instances: &[(InstanceData, Id, T)],
-    group: bool,
-    animation_map: &AnimationMap,
-) -> (
-    Vec<RawInstanceData>,
-    HashMap<Id, Vec<(DrawIndexedIndirectArgs, T)>>,
-    u32,
-    Vec<MatrixData>,
+/// Draws outlines around the silhouettes of the outlined tiles onto the frame, in the colors they were given,
+/// by drawing the tiles into a mask and tracing around it.
+pub fn outline_pass(
+    encoder: &mut CommandEncoder,
+    global_buffers: &GlobalBuffers,
+    resources: &OutlineResources,
+    draw_count: u32,
+    frame: &TextureView,
 ) {
-    let (compiled_instances, matrix_data) =
-        compile_instances(resource_man, instances, animation_map);
+    {
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Outline Mask Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &resources.mask_texture().1,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
 
-    let (_, draw_count, commands) = compiled_instances.iter().fold(
-        (0, 0, HashMap::new()),
-        |(mut base_instance_counter, mut draw_count, mut commands), (id, groups)| {
-            let vec = commands
-                .entry(*id)
-                .or_insert_with(|| Vec::with_capacity(groups.len()));
-
-            if group {
-                for instances in groups.binary_group_by_key(|v| v.0) {
-                    collect_indirect(
-                        &mut base_instance_counter,
-                        &mut draw_count,
-                        vec,
-                        resource_man,
-                        id,
-                        instances,
-                    )
-                }
-            } else {
-                for instances in groups.iter().map(slice::from_ref) {
-                    collect_indirect(
-                        &mut base_instance_counter,
-                        &mut draw_count,
-                        vec,
-                        resource_man,
-                        id,
-                        instances,
-                    )
-                }
-            }
+        pass.set_pipeline(&resources.mask_pipeline);
+        pass.set_bind_group(0, &resources.bind_group, &[]);
+        pass.set_vertex_buffer(0, global_buffers.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, resources.instance_buffer.slice(..));
+        pass.set_index_buffer(global_buffers.index_buffer.slice(..), IndexFormat::Uint16);
 
-            (base_instance_counter, draw_count, commands)
-        },
-    );
+        pass.multi_draw_indexed_indirect(&resources.indirect_buffer, 0, draw_count);
+    }
 
-    let instances = compiled_instances
-        .into_iter()
-        .flat_map(|v| v.1.into_iter().map(|v| v.1))
-        .collect::<Vec<RawInstanceData>>();
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("Outline Composite Pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: frame,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
 
-    (instances, commands, draw_count, matrix_data)
+    pass.set_pipeline(&resources.composite_pipeline);
+    pass.set_bind_group(0, resources.composite_bind_group(), &[]);
+    pass.draw(0..3, 0..1);
 }
 
-pub fn create_or_write_buffer(
-    device: &Device,
-    queue: &Queue,
-    buffer: &mut Buffer,
-    contents: &[u8],
+/// Draws a full-screen triangle with the pipeline into the view, which most of the post-processing passes are.
+pub fn fullscreen_pass(
+    encoder: &mut CommandEncoder,
+    label: &str,
+    view: &TextureView,
+    pipeline: &RenderPipeline,
+    bind_group: &BindGroup,
 ) {
-    if buffer.size() < contents.len() as BufferAddress {
-        let usage = buffer.usage();
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(Color::BLACK),
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
 
-        *buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents,
-            usage,
-        })
-    } else {
-        queue.write_buffer(buffer, 0, contents);
-    }
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.draw(0..3, 0..1);
+}
+
+/// Makes the bright parts of the frame glow, by picking them out into a half-size texture, blurring them,
+/// and adding them back onto the frame.
+pub fn bloom_pass(encoder: &mut CommandEncoder, resources: &BloomResources, frame: &TextureView) {
+    fullscreen_pass(
+        encoder,
+        "Bloom Bright Pass",
+        &resources.first_texture().1,
+        &resources.bright_pipeline,
+        resources.bright_bind_group(),
+    );
+    fullscreen_pass(
+        encoder,
+        "Bloom Horizontal Blur Pass",
+        &resources.second_texture().1,
+        &resources.blur_horizontal_pipeline,
+        resources.first_bind_group(),
+    );
+    fullscreen_pass(
+        encoder,
+        "Bloom Vertical Blur Pass",
+        &resources.first_texture().1,
+        &resources.blur_vertical_pipeline,
+        resources.second_bind_group(),
+    );
+
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("Bloom Composite Pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: frame,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    pass.set_pipeline(&resources.composite_pipeline);
+    pass.set_bind_group(0, resources.first_bind_group(), &[]);
+    pass.draw(0..3, 0..1);
 }
 
 pub fn create_texture_and_view(
@@ -1256,10 +2457,13 @@ pub struct GameDescriptor<'a> {
 
 #[derive(OptionGetter)]
 pub struct GameResources {
+    /// the instances in view, filled in by [`InstanceCulling`]
     pub instance_buffer: Buffer,
     pub indirect_buffer: Buffer,
+    pub culling: InstanceCulling,
     pub uniform_buffer: Buffer,
     pub matrix_data_buffer: Buffer,
+    pub world_matrix_data_buffer: Buffer,
     pub bind_group: BindGroup,
     pub pipeline: RenderPipeline,
     #[getters(get)]
@@ -1306,7 +2510,7 @@ impl GameResources {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1329,7 +2533,7 @@ impl GameResources {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1337,21 +2541,99 @@ impl GameResources {
     }
 }
 
+impl GameResources {
+    /// Uploads the instances made by [`indirect_instance`] and culls them, and gives back how many draws there are.
+    pub fn upload<T>(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        uploader: &mut Uploader,
+        (instances, draws, draw_count, matrices): IndirectInstances<T>,
+    ) -> u32 {
+        if draw_count > 0 {
+            uploader.write(
+                device,
+                encoder,
+                &self.uniform_buffer,
+                bytemuck::cast_slice(&[GameUBO::default()]),
+            );
+            uploader.write(
+                device,
+                encoder,
+                &self.matrix_data_buffer,
+                bytemuck::cast_slice(matrices.matrix_data.as_slice()),
+            );
+            uploader.write(
+                device,
+                encoder,
+                &self.world_matrix_data_buffer,
+                bytemuck::cast_slice(matrices.world_matrix_data.as_slice()),
+            );
+        }
+
+        let draws = draws
+            .into_values()
+            .flatten()
+            .map(|(draw, _)| draw)
+            .collect::<Vec<_>>();
+
+        // the matrices are uploaded first, as the cull shader reads them
+        self.culling.cull(
+            device,
+            encoder,
+            uploader,
+            (instances.as_slice(), draws.as_slice()),
+            (&self.matrix_data_buffer, &self.world_matrix_data_buffer),
+            &mut self.instance_buffer,
+            &mut self.indirect_buffer,
+        );
+
+        draw_count
+    }
+
+    pub fn world_draw(&self, draw_count: u32) -> WorldDraw {
+        WorldDraw {
+            pipeline: &self.pipeline,
+            bind_group: &self.bind_group,
+            instance_buffer: &self.instance_buffer,
+            indirect_buffer: &self.indirect_buffer,
+            draw_count,
+        }
+    }
+}
+
 #[derive(OptionGetter)]
 pub struct InWorldItemResources {
+    /// the instances in view, filled in by [`InstanceCulling`]
     pub instance_buffer: Buffer,
     pub indirect_buffer: Buffer,
+    pub culling: InstanceCulling,
     pub uniform_buffer: Buffer,
     pub matrix_data_buffer: Buffer,
+    pub world_matrix_data_buffer: Buffer,
     pub bind_group: BindGroup,
     pub pipeline: RenderPipeline,
 }
 
+impl InWorldItemResources {
+    pub fn world_draw(&self, draw_count: u32) -> WorldDraw {
+        WorldDraw {
+            pipeline: &self.pipeline,
+            bind_group: &self.bind_group,
+            instance_buffer: &self.instance_buffer,
+            indirect_buffer: &self.indirect_buffer,
+            draw_count,
+        }
+    }
+}
+
 #[derive(OptionGetter)]
 pub struct GuiResources {
+    pub uploader: Uploader,
     pub instance_buffer: Buffer,
     pub uniform_buffer: Buffer,
     pub matrix_data_buffer: Buffer,
+    pub world_matrix_data_buffer: Buffer,
     pub bind_group: BindGroup,
     pub pipeline: RenderPipeline,
 }
@@ -1378,7 +2660,7 @@ impl EguiResources {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1434,7 +2716,7 @@ impl CombineResources {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1463,6 +2745,184 @@ pub struct PostProcessingResources {
     pub ssao_noise_map: Texture,
 }
 
+#[derive(OptionGetter)]
+pub struct BloomResources {
+    pub bind_group_layout: BindGroupLayout,
+    pub bright_pipeline: RenderPipeline,
+    pub blur_horizontal_pipeline: RenderPipeline,
+    pub blur_vertical_pipeline: RenderPipeline,
+    pub composite_pipeline: RenderPipeline,
+    /// Binds the post-processed frame, to pick the bright parts out of.
+    #[getters(get)]
+    bright_bind_group: Option<BindGroup>,
+    /// The two half-size textures the glow is blurred back and forth between.
+    #[getters(get)]
+    first_texture: Option<(Texture, TextureView)>,
+    #[getters(get)]
+    first_bind_group: Option<BindGroup>,
+    #[getters(get)]
+    second_texture: Option<(Texture, TextureView)>,
+    #[getters(get)]
+    second_bind_group: Option<BindGroup>,
+}
+
+impl BloomResources {
+    pub fn create(
+        &mut self,
+        device: &Device,
+        config: &SurfaceConfiguration,
+        shared_descriptor: &SharedDescriptor,
+        frame_texture: &TextureView,
+    ) {
+        let bind = |texture: &TextureView| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("bloom_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(texture),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(shared_descriptor.filtering_sampler),
+                    },
+                ],
+            })
+        };
+        let half_size = || {
+            create_texture_and_view(
+                device,
+                &TextureDescriptor {
+                    label: None,
+                    size: Extent3d {
+                        width: (config.width / 2).max(1),
+                        height: (config.height / 2).max(1),
+                        ..Default::default()
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: frame_format(config),
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        let first_texture = half_size();
+        let second_texture = half_size();
+
+        let bright_bind_group = bind(frame_texture);
+        let first_bind_group = bind(&first_texture.1);
+        let second_bind_group = bind(&second_texture.1);
+
+        self.bright_bind_group = Some(bright_bind_group);
+        self.first_texture = Some(first_texture);
+        self.first_bind_group = Some(first_bind_group);
+        self.second_texture = Some(second_texture);
+        self.second_bind_group = Some(second_bind_group);
+    }
+}
+
+/// The format of the texture the outlined tiles are drawn into, in the colors of their outlines.
+const OUTLINE_MASK_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+#[derive(OptionGetter)]
+pub struct OutlineResources {
+    pub instance_buffer: Buffer,
+    pub indirect_buffer: Buffer,
+    pub uniform_buffer: Buffer,
+    pub matrix_data_buffer: Buffer,
+    pub world_matrix_data_buffer: Buffer,
+    pub bind_group: BindGroup,
+    pub mask_pipeline: RenderPipeline,
+    pub composite_bind_group_layout: BindGroupLayout,
+    pub composite_pipeline: RenderPipeline,
+    /// The outlined tiles in flat colors, with nothing around them.
+    #[getters(get)]
+    mask_texture: Option<(Texture, TextureView)>,
+    #[getters(get)]
+    composite_bind_group: Option<BindGroup>,
+}
+
+impl OutlineResources {
+    pub fn create(&mut self, device: &Device, config: &SurfaceConfiguration) {
+        let mask_texture = create_texture_and_view(
+            device,
+            &TextureDescriptor {
+                label: Some("Outline Mask Texture"),
+                size: Extent3d {
+                    width: config.width,
+                    height: config.height,
+                    ..Default::default()
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: OUTLINE_MASK_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+        );
+
+        self.composite_bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
+            label: Some("outline_composite_bind_group"),
+            layout: &self.composite_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&mask_texture.1),
+            }],
+        }));
+        self.mask_texture = Some(mask_texture);
+    }
+
+    /// Uploads the instances made by [`indirect_instance`], and gives back how many draws there are.
+    pub fn upload<T>(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        uploader: &mut Uploader,
+        (instances, draws, draw_count, matrices): IndirectInstances<T>,
+    ) -> u32 {
+        uploader.upload(
+            device,
+            encoder,
+            &mut self.instance_buffer,
+            bytemuck::cast_slice(instances.as_slice()),
+        );
+        let mut indirect_buffer = vec![];
+        draws
+            .into_values()
+            .flatten()
+            .for_each(|(draw, _)| indirect_buffer.extend_from_slice(draw.as_bytes()));
+        uploader.upload(device, encoder, &mut self.indirect_buffer, &indirect_buffer);
+
+        if draw_count > 0 {
+            uploader.write(
+                device,
+                encoder,
+                &self.uniform_buffer,
+                bytemuck::cast_slice(&[GameUBO::default()]),
+            );
+            uploader.write(
+                device,
+                encoder,
+                &self.matrix_data_buffer,
+                bytemuck::cast_slice(matrices.matrix_data.as_slice()),
+            );
+            uploader.write(
+                device,
+                encoder,
+                &self.world_matrix_data_buffer,
+                bytemuck::cast_slice(matrices.world_matrix_data.as_slice()),
+            );
+        }
+
+        draw_count
+    }
+}
+
 #[derive(OptionGetter)]
 pub struct IntermediateResources {
     pub bind_group_layout: BindGroupLayout,
@@ -1470,6 +2930,9 @@ pub struct IntermediateResources {
     pub present_pipeline: RenderPipeline,
     #[getters(get)]
     present_bind_group: Option<BindGroup>,
+    /// Binds the world alone, without the GUI.
+    #[getters(get)]
+    game_bind_group: Option<BindGroup>,
 }
 
 impl IntermediateResources {
@@ -1478,6 +2941,7 @@ impl IntermediateResources {
         device: &Device,
         shared_descriptor: &SharedDescriptor,
         present_texture: &TextureView,
+        game_texture: &TextureView,
     ) {
         self.present_bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
             label: None,
@@ -1493,6 +2957,20 @@ impl IntermediateResources {
                 },
             ],
         }));
+        self.game_bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(game_texture),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(shared_descriptor.non_filtering_sampler),
+                },
+            ],
+        }));
     }
 }
 
@@ -1534,6 +3012,8 @@ pub struct RenderResources {
 
     pub antialiasing_resources: AntialiasingResources,
     pub post_processing_resources: PostProcessingResources,
+    pub bloom_resources: BloomResources,
+    pub outline_resources: OutlineResources,
     pub intermediate_resources: IntermediateResources,
 }
 
@@ -1558,7 +3038,7 @@ impl SharedResources {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1610,7 +3090,7 @@ impl SharedResources {
                 mip_level_count: 1,
                 sample_count: 4,
                 dimension: TextureDimension::D2,
-                format: config.format,
+                format: frame_format(config),
                 usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
                 view_formats: &[],
             },
@@ -1659,6 +3139,13 @@ impl SharedResources {
             &game_descriptor,
         );
         render_resources.egui_resources.create(device, config);
+        render_resources.bloom_resources.create(
+            device,
+            config,
+            &shared_descriptor,
+            &render_resources.game_resources.post_processing_texture().1,
+        );
+        render_resources.outline_resources.create(device, config);
 
         render_resources.first_combine_resources.create(
             device,
@@ -1671,6 +3158,7 @@ impl SharedResources {
             device,
             &shared_descriptor,
             &render_resources.first_combine_resources.texture().1,
+            &render_resources.game_resources.antialiasing_texture().1,
         );
     }
 }
@@ -1688,6 +3176,21 @@ pub struct Gpu<'a> {
     pub config: SurfaceConfiguration,
 }
 
+/// The features and limits the game needs from the device.
+pub fn device_descriptor() -> DeviceDescriptor<'static> {
+    DeviceDescriptor {
+        required_features: Features::INDIRECT_FIRST_INSTANCE | Features::MULTI_DRAW_INDIRECT,
+        // WebGL doesn't support all of wgpu's features, so if
+        // we're building for the web we'll have to disable some.
+        required_limits: if cfg!(target_arch = "wasm32") {
+            Limits::downlevel_webgl2_defaults()
+        } else {
+            Limits::default()
+        },
+        label: None,
+    }
+}
+
 impl<'a> Gpu<'a> {
     fn pick_present_mode(vsync: bool) -> PresentMode {
         if vsync {
@@ -1706,14 +3209,29 @@ impl<'a> Gpu<'a> {
         }
     }
 
+    /// Whether the window has a different size than what the surface is set up for.
+    pub fn is_outdated(&self) -> bool {
+        let size = self.window.inner_size();
+
+        size.width != self.config.width || size.height != self.config.height
+    }
+
+    /// Reconfigures the surface and remakes the screen-sized textures for the size.
+    /// Does nothing for an empty size, as when the window is minimized.
     pub fn resize(
         &mut self,
         shared_resources: &mut SharedResources,
         render_resources: &mut RenderResources,
         size: PhysicalSize<u32>,
     ) {
-        self.config.width = size.width;
-        self.config.height = size.height;
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let max = self.device.limits().max_texture_dimension_2d;
+
+        self.config.width = size.width.min(max);
+        self.config.height = size.height.min(max);
 
         self.surface.configure(&self.device, &self.config);
         shared_resources.create(&self.device, &self.config, render_resources);
@@ -1741,21 +3259,7 @@ impl<'a> Gpu<'a> {
             .unwrap();
 
         let (device, queue) = adapter
-            .request_device(
-                &DeviceDescriptor {
-                    required_features: Features::INDIRECT_FIRST_INSTANCE
-                        | Features::MULTI_DRAW_INDIRECT,
-                    // WebGL doesn't support all of wgpu's features, so if
-                    // we're building for the web we'll have to disable some.
-                    required_limits: if cfg!(target_arch = "wasm32") {
-                        Limits::downlevel_webgl2_defaults()
-                    } else {
-                        Limits::default()
-                    },
-                    label: None,
-                },
-                None,
-            )
+            .request_device(&device_descriptor(), None)
             .await
             .unwrap();
 
@@ -0,0 +1,166 @@
+//! Short animations played on the GUI, such as the items flying into the inventory when they are taken.
+//!
+//! Animations are started with [`UiAnimations::push`], and dropped once they are done by [`UiAnimations::update`],
+//! which runs once a frame. The ones headed somewhere only known while drawing, like [`UiAnimationKind::FlyTo`],
+//! are drawn by whatever draws that place, and the rest are drawn over everything else by [`UiAnimations::draw`].
+//!
+//! They are timed by the UI clock, so they hold still while the game is paused or in the background.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use egui::{vec2, Align2, Color32, Context, FontId, LayerId, Order, Pos2, Rect, Rounding};
+use hashbrown::HashMap;
+
+use automancy_resources::data::item::Item;
+
+use crate::clock::ClockTime;
+use crate::memory::CacheUsage;
+
+/// How long pop-ups take to rise and fade out.
+pub const POPUP_DURATION: Duration = Duration::from_millis(800);
+/// How far pop-ups rise before they are gone.
+const POPUP_RISE: f32 = 24.0;
+const POPUP_FONT_SIZE: f32 = 14.0;
+/// How long flashes take to fade out.
+pub const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// What animations are grouped by, so they can be found by what draws them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationKey {
+    /// An item taken into the player's inventory, flying to where the inventory shows it.
+    TakeItem(Item),
+    /// Pop-ups and flashes, drawn where they were started.
+    Effect,
+}
+
+#[derive(Debug, Clone)]
+pub enum UiAnimationKind {
+    /// Moves from the rect to wherever it is drawn towards.
+    FlyTo(Rect),
+    /// Text rising up from the position while fading out, such as the amount of items taken.
+    Popup {
+        pos: Pos2,
+        text: String,
+        color: Color32,
+    },
+    /// Fills the rect with the color, fading out.
+    Flash { rect: Rect, color: Color32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct UiAnimation {
+    /// when the animation started, on the UI clock
+    pub start: ClockTime,
+    pub duration: Duration,
+    pub kind: UiAnimationKind,
+}
+
+impl UiAnimation {
+    /// Makes an animation starting at the time on the UI clock.
+    pub fn new(now: ClockTime, duration: Duration, kind: UiAnimationKind) -> Self {
+        Self {
+            start: now,
+            duration,
+            kind,
+        }
+    }
+
+    /// How far along the animation is, from 0 to 1.
+    pub fn progress(&self, now: ClockTime) -> f32 {
+        (now.saturating_sub(self.start).as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    pub fn is_done(&self, now: ClockTime) -> bool {
+        now.saturating_sub(self.start) >= self.duration
+    }
+
+    /// How opaque the animation is as it fades out. It fades slowly at first, then quickly.
+    pub fn opacity(&self, now: ClockTime) -> f32 {
+        let progress = self.progress(now);
+
+        1.0 - progress * progress
+    }
+
+    /// Where a [`UiAnimationKind::FlyTo`] animation is on its way to the target. None for the other kinds.
+    pub fn fly_to(&self, now: ClockTime, target: Rect) -> Option<Rect> {
+        match &self.kind {
+            UiAnimationKind::FlyTo(from) => Some(from.lerp_towards(&target, self.progress(now))),
+            _ => None,
+        }
+    }
+}
+
+/// The animations being played on the GUI.
+#[derive(Debug, Default)]
+pub struct UiAnimations {
+    animations: HashMap<AnimationKey, VecDeque<UiAnimation>>,
+}
+
+impl UiAnimations {
+    /// Starts an animation, dropping the oldest ones with the same key past the limit.
+    pub fn push(&mut self, key: AnimationKey, animation: UiAnimation, limit: usize) {
+        let animations = self.animations.entry(key).or_default();
+
+        animations.push_back(animation);
+        while animations.len() > limit {
+            animations.pop_front();
+        }
+    }
+
+    /// The animations with the key, oldest first.
+    pub fn get(&self, key: AnimationKey) -> impl Iterator<Item = &UiAnimation> {
+        self.animations.get(&key).into_iter().flatten()
+    }
+
+    /// Drops the animations that are done, along with the keys left without any.
+    pub fn update(&mut self, now: ClockTime) {
+        for animations in self.animations.values_mut() {
+            animations.retain(|animation| !animation.is_done(now));
+        }
+
+        self.animations
+            .retain(|_, animations| !animations.is_empty());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    /// How much of the memory the animations are using.
+    pub fn memory_usage(&self) -> CacheUsage {
+        CacheUsage::of::<UiAnimation>(self.animations.values().map(VecDeque::len).sum())
+    }
+
+    /// Draws the pop-ups and flashes over everything else, and keeps redrawing until they are done.
+    pub fn draw(&self, context: &Context, now: ClockTime) {
+        if self.is_empty() {
+            return;
+        }
+
+        let painter =
+            context.layer_painter(LayerId::new(Order::Foreground, "ui_animations".into()));
+
+        for animation in self.animations.values().flatten() {
+            let opacity = animation.opacity(now);
+
+            match &animation.kind {
+                UiAnimationKind::FlyTo(_) => {}
+                UiAnimationKind::Popup { pos, text, color } => {
+                    painter.text(
+                        *pos - vec2(0.0, POPUP_RISE * animation.progress(now)),
+                        Align2::CENTER_BOTTOM,
+                        text,
+                        FontId::proportional(POPUP_FONT_SIZE),
+                        color.gamma_multiply(opacity),
+                    );
+                }
+                UiAnimationKind::Flash { rect, color } => {
+                    painter.rect_filled(*rect, Rounding::ZERO, color.gamma_multiply(opacity));
+                }
+            }
+        }
+
+        context.request_repaint();
+    }
+}
@@ -0,0 +1,59 @@
+use arboard::Clipboard;
+use ractor::rpc::CallResult;
+
+use automancy_defs::log;
+
+use crate::blueprint::Blueprint;
+use crate::game::GameSystemMessage;
+use crate::GameState;
+
+/// Copies the selected tiles to the clipboard as a blueprint string.
+pub fn copy_blueprint(state: &mut GameState) {
+    let coords = state.gui_state.grouped_tiles.iter().cloned().collect();
+
+    let Ok(CallResult::Success(tiles)) = state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetTiles(coords, reply), None),
+    ) else {
+        return;
+    };
+    if tiles.is_empty() {
+        return;
+    }
+
+    let string = Blueprint::new(&state.resource_man, tiles).encode(&state.resource_man);
+
+    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(string)) {
+        Ok(()) => state.audio_man.play(&state.resource_man.audio["click"]),
+        Err(err) => log::error!("Could not copy the blueprint to the clipboard: {err:?}"),
+    }
+}
+
+/// Reads a blueprint string from the clipboard, and starts placing it like an imported region.
+pub fn paste_blueprint(state: &mut GameState) {
+    let text = match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => text,
+        Err(err) => {
+            log::error!("Could not read the clipboard: {err:?}");
+            return;
+        }
+    };
+
+    match Blueprint::decode(&state.resource_man, &text) {
+        Ok(blueprint) => {
+            state.gui_state.importing_region = Some(blueprint.tiles);
+            state.gui_state.importing_conflicts = None;
+            state.gui_state.selected_tile_id = None;
+        }
+        Err(err) => {
+            state.resource_man.error_man.push(
+                (
+                    state.resource_man.registry.err_ids.invalid_blueprint,
+                    vec![err.to_string()],
+                ),
+                &state.resource_man,
+            );
+        }
+    }
+}
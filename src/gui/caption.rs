@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use egui::{vec2, Align2, Area, Frame};
+
+use crate::GameState;
+
+/// How long a caption stays on screen.
+const CAPTION_DURATION: Duration = Duration::from_secs(4);
+/// How long a caption takes to fade out at the end.
+const CAPTION_FADE: Duration = Duration::from_millis(500);
+/// The most captions shown at once. Older ones are dropped first.
+const MAX_CAPTIONS: usize = 5;
+
+/// Shows a caption for a sound, if captions are enabled.
+pub fn push_caption(state: &mut GameState, caption: String) {
+    if !state.options.gui.captions {
+        return;
+    }
+
+    if state.gui_state.captions.len() >= MAX_CAPTIONS {
+        state.gui_state.captions.pop_front();
+    }
+
+    state
+        .gui_state
+        .captions
+        .push_back((Instant::now(), caption));
+}
+
+/// Draws the captions in the bottom right corner of the screen.
+pub fn captions(state: &mut GameState) {
+    state
+        .gui_state
+        .captions
+        .retain(|(added, _)| added.elapsed() < CAPTION_DURATION);
+
+    if state.gui_state.captions.is_empty() {
+        return;
+    }
+
+    Area::new("captions".into())
+        .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -10.0))
+        .interactable(false)
+        .show(&state.gui.context.clone(), |ui| {
+            for (added, caption) in &state.gui_state.captions {
+                let remaining = CAPTION_DURATION.saturating_sub(added.elapsed());
+                let opacity = (remaining.as_secs_f32() / CAPTION_FADE.as_secs_f32()).min(1.0);
+
+                ui.set_opacity(opacity);
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(caption);
+                });
+            }
+        });
+
+    // keep redrawing so the captions fade out even when nothing else happens
+    state.gui.context.request_repaint();
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use egui::{vec2, Align2, Grid, RichText, TextStyle, Window};
+
+use automancy_defs::id::Id;
+use automancy_resources::format;
+
+use crate::input::{binding_name, KeyActions, KeyCategory, PressTypes};
+use crate::GameState;
+
+pub fn category_name(state: &GameState, category: KeyCategory) -> Id {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    match category {
+        KeyCategory::Interface => gui_ids.key_category_interface,
+        KeyCategory::Editing => gui_ids.key_category_editing,
+        KeyCategory::Window => gui_ids.key_category_window,
+    }
+}
+
+pub fn action_name(state: &GameState, action: KeyActions) -> String {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+    let translates = state.resource_man.translates();
+
+    let id = match action {
+        KeyActions::Escape => gui_ids.action_escape,
+        KeyActions::Undo => gui_ids.action_undo,
+        KeyActions::Redo => gui_ids.action_redo,
+        KeyActions::Debug => gui_ids.action_debug,
+        KeyActions::Fullscreen => gui_ids.action_fullscreen,
+        KeyActions::Screenshot => gui_ids.action_screenshot,
+        KeyActions::HideGui => gui_ids.action_hide_gui,
+        KeyActions::Player => gui_ids.action_player,
+        KeyActions::Cheatsheet => gui_ids.action_cheatsheet,
+        KeyActions::PickUp => gui_ids.action_pick_up,
+        KeyActions::Route => gui_ids.action_route,
+        KeyActions::CopyBlueprint => gui_ids.action_copy_blueprint,
+        KeyActions::PasteBlueprint => gui_ids.action_paste_blueprint,
+        KeyActions::AcceptSuggestion => gui_ids.action_accept_suggestion,
+        KeyActions::Zones => gui_ids.action_zones,
+        KeyActions::Statistics => gui_ids.action_statistics,
+        KeyActions::Markers => gui_ids.action_markers,
+        KeyActions::Hotbar(slot) => {
+            return format(
+                translates.gui[&gui_ids.action_hotbar].as_str(),
+                &[&(slot + 1).to_string()],
+            )
+        }
+    };
+
+    translates.gui[&id].to_string()
+}
+
+/// Draws the overlay listing all the key bindings, while its key is held.
+pub fn cheatsheet(state: &mut GameState) {
+    if !state.input_handler.key_active(KeyActions::Cheatsheet) {
+        return;
+    }
+
+    let gui_ids = &state.resource_man.registry.gui_ids;
+    let translates = state.resource_man.translates();
+
+    let mut categories = BTreeMap::<KeyCategory, Vec<_>>::new();
+    for (binding, action) in state.input_handler.bindings() {
+        let mut keys = binding_name(&binding);
+        // these only work together with control
+        if action.action.needs_control() {
+            keys = format!("{}+{keys}", translates.gui[&gui_ids.key_control]);
+        }
+
+        let press = match action.press_type {
+            PressTypes::Tap => None,
+            PressTypes::Hold => Some(gui_ids.key_press_hold),
+            PressTypes::Toggle => Some(gui_ids.key_press_toggle),
+        };
+
+        categories
+            .entry(action.action.category())
+            .or_default()
+            .push((action.action, keys, press));
+    }
+
+    Window::new(translates.gui[&gui_ids.cheatsheet_title].as_str())
+        .id("cheatsheet".into())
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .resizable(false)
+        .collapsible(false)
+        .interactable(false)
+        .show(&state.gui.context.clone(), |ui| {
+            for (category, mut actions) in categories {
+                actions.sort_by_key(|(action, ..)| *action);
+
+                ui.label(
+                    RichText::new(translates.gui[&category_name(state, category)].as_str())
+                        .text_style(TextStyle::Heading),
+                );
+
+                Grid::new(("cheatsheet", category))
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (action, keys, press) in actions {
+                            ui.label(RichText::new(keys).strong().monospace());
+                            ui.horizontal(|ui| {
+                                ui.label(action_name(state, action));
+                                if let Some(press) = press {
+                                    ui.weak(translates.gui[&press].as_str());
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(8.0);
+            }
+        });
+}
@@ -0,0 +1,188 @@
+//! The mouse cursor, which shows what clicking in the world would do.
+//!
+//! At the normal size the system cursors are used. They can't be made any larger, so larger cursors,
+//! for screens with many pixels, are drawn over everything else instead, with the system cursor hidden.
+
+use egui::{vec2, Color32, Context, CursorIcon, LayerId, Order, Pos2, Shape, Stroke};
+use winit::window::CursorIcon as WindowCursorIcon;
+
+use automancy_defs::colors;
+
+use crate::gui::Screen;
+use crate::GameState;
+
+/// What clicking in the world would do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameCursor {
+    Default,
+    /// Picking the other end of a link or a route.
+    Link,
+    /// Moving the selected tiles.
+    Move,
+    /// The selected tile can't be placed there.
+    Forbidden,
+}
+
+impl GameCursor {
+    fn icon(self) -> CursorIcon {
+        match self {
+            GameCursor::Default => CursorIcon::Default,
+            GameCursor::Link => CursorIcon::Alias,
+            GameCursor::Move => CursorIcon::Move,
+            GameCursor::Forbidden => CursorIcon::NotAllowed,
+        }
+    }
+}
+
+/// Gets what clicking in the world would do, from what the player is doing.
+fn game_cursor(state: &GameState) -> GameCursor {
+    if state.gui_state.screen != Screen::Ingame {
+        return GameCursor::Default;
+    }
+
+    if state.gui_state.linking_tile.is_some() || state.gui_state.routing.is_some() {
+        return GameCursor::Link;
+    }
+
+    if state.input_handler.control_held && state.gui_state.initial_cursor_position.is_some() {
+        return GameCursor::Move;
+    }
+
+    let conflicts = state
+        .gui_state
+        .importing_conflicts
+        .as_ref()
+        .is_some_and(|(_, conflicts)| !conflicts.is_empty());
+
+    if state.gui_state.placement_blocked || conflicts {
+        return GameCursor::Forbidden;
+    }
+
+    GameCursor::Default
+}
+
+fn window_icon(icon: CursorIcon) -> WindowCursorIcon {
+    match icon {
+        CursorIcon::PointingHand => WindowCursorIcon::Pointer,
+        CursorIcon::Text => WindowCursorIcon::Text,
+        CursorIcon::Grab => WindowCursorIcon::Grab,
+        CursorIcon::Grabbing => WindowCursorIcon::Grabbing,
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => WindowCursorIcon::NotAllowed,
+        CursorIcon::Help => WindowCursorIcon::Help,
+        CursorIcon::Alias => WindowCursorIcon::Alias,
+        CursorIcon::Move | CursorIcon::AllScroll => WindowCursorIcon::Move,
+        CursorIcon::Crosshair => WindowCursorIcon::Crosshair,
+        CursorIcon::Wait => WindowCursorIcon::Wait,
+        CursorIcon::Progress => WindowCursorIcon::Progress,
+        CursorIcon::ResizeHorizontal | CursorIcon::ResizeColumn => WindowCursorIcon::ColResize,
+        CursorIcon::ResizeVertical | CursorIcon::ResizeRow => WindowCursorIcon::RowResize,
+        CursorIcon::ResizeNeSw => WindowCursorIcon::NeswResize,
+        CursorIcon::ResizeNwSe => WindowCursorIcon::NwseResize,
+        _ => WindowCursorIcon::Default,
+    }
+}
+
+/// Shows the cursor for what the pointer is over. The GUI decides it over its own widgets, and the game elsewhere.
+///
+/// Runs after the rest of the GUI, so the widgets have said which cursor they want.
+pub fn cursor(state: &mut GameState) {
+    let context = state.gui.context.clone();
+
+    let icon = match context.output(|o| o.cursor_icon) {
+        CursorIcon::Default if !context.is_pointer_over_area() => game_cursor(state).icon(),
+        icon => icon,
+    };
+    let scale = state.options.gui.cursor_scale;
+    let drawn = scale > 1.0;
+
+    if state.gui_state.cursor != Some((icon, drawn)) {
+        let window = &state.renderer.gpu.window;
+
+        window.set_cursor_visible(!drawn);
+        window.set_cursor_icon(window_icon(icon));
+
+        state.gui_state.cursor = Some((icon, drawn));
+    }
+
+    if drawn {
+        if let Some(pos) = context.pointer_latest_pos() {
+            draw_cursor(&context, pos, icon, scale);
+        }
+    }
+}
+
+/// Draws an arrow with its tip at the position, with a badge for the game's cursors.
+fn draw_cursor(context: &Context, pos: Pos2, icon: CursorIcon, scale: f32) {
+    let painter = context.layer_painter(LayerId::new(Order::Debug, "cursor".into()));
+
+    // in pixels from the tip, at a scale of 1
+    let at = |x: f32, y: f32| pos + vec2(x, y) * scale;
+    let outline = Stroke::new(scale, Color32::BLACK);
+
+    painter.add(Shape::convex_polygon(
+        vec![at(0.0, 0.0), at(0.0, 15.0), at(10.5, 10.5)],
+        Color32::WHITE,
+        outline,
+    ));
+    painter.add(Shape::convex_polygon(
+        vec![at(4.0, 11.0), at(6.5, 10.2), at(9.5, 16.5), at(7.0, 17.3)],
+        Color32::WHITE,
+        outline,
+    ));
+
+    let center = at(14.0, 14.0);
+    let radius = 5.0 * scale;
+    let mark = Stroke::new(1.5 * scale, Color32::BLACK);
+
+    match icon {
+        CursorIcon::Alias => {
+            painter.circle(center, radius, colors::LIGHT_BLUE, outline);
+            painter.line_segment(
+                [
+                    center - vec2(radius * 0.5, 0.0),
+                    center + vec2(radius * 0.5, 0.0),
+                ],
+                mark,
+            );
+            painter.line_segment(
+                [
+                    center + vec2(0.0, -radius * 0.5),
+                    center + vec2(radius * 0.5, 0.0),
+                ],
+                mark,
+            );
+            painter.line_segment(
+                [
+                    center + vec2(0.0, radius * 0.5),
+                    center + vec2(radius * 0.5, 0.0),
+                ],
+                mark,
+            );
+        }
+        CursorIcon::Move => {
+            painter.circle(center, radius, colors::ORANGE, outline);
+            painter.line_segment(
+                [
+                    center - vec2(radius * 0.6, 0.0),
+                    center + vec2(radius * 0.6, 0.0),
+                ],
+                mark,
+            );
+            painter.line_segment(
+                [
+                    center - vec2(0.0, radius * 0.6),
+                    center + vec2(0.0, radius * 0.6),
+                ],
+                mark,
+            );
+        }
+        CursorIcon::NotAllowed => {
+            let forbidden = Stroke::new(1.5 * scale, colors::RED);
+            let diagonal = vec2(radius, radius) * std::f32::consts::FRAC_1_SQRT_2;
+
+            painter.circle(center, radius, Color32::WHITE, forbidden);
+            painter.line_segment([center - diagonal, center + diagonal], forbidden);
+        }
+        _ => {}
+    }
+}
@@ -1,12 +1,22 @@
 use std::time::SystemTime;
 
-use egui::Window;
+use egui::{Color32, Window};
+use ractor::rpc::CallResult;
 use ron::ser::PrettyConfig;
 
+use automancy_defs::colors;
+use automancy_defs::id::namespace_counts;
+
+use crate::game::{GameSystemMessage, TILE_TICK_BUDGET};
+use crate::gpu::AnimationCache;
+use crate::memory::{format_bytes, process_memory};
 use crate::GameState;
 
+/// How many of the slowest tiles to list.
+const MAX_SLOW_TILES_SHOWN: usize = 10;
+
 /// Draws the debug menu (F3).
-pub fn debugger(state: &GameState) {
+pub fn debugger(state: &mut GameState) {
     let resource_man = &*state.resource_man;
 
     let fps = 1.0 / state.loop_store.elapsed.as_secs_f64();
@@ -18,6 +28,39 @@ pub fn debugger(state: &GameState) {
     let scripts = resource_man.registry.scripts.len();
     let audio = resource_man.audio.len();
     let meshes = resource_man.all_models.len();
+    let categories = resource_man.registry.categories.len();
+    let researches = resource_man.registry.researches.node_count();
+
+    let interned = resource_man.interner.len();
+    let loaded = resource_man.loaded_ids;
+    let namespaces = namespace_counts(&resource_man.interner)
+        .into_iter()
+        .map(|(namespace, count)| format!("{namespace}={count}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let (atlas_size, atlas_fill) = state
+        .gui
+        .context
+        .fonts(|fonts| (fonts.font_image_size(), fonts.font_atlas_fill_ratio()));
+    let font_rebuilds = state.gui.font_rebuilds;
+
+    let process_memory = process_memory().map_or("unknown".to_string(), format_bytes);
+    let mut caches = state.renderer.memory_usage();
+    if let Some(animation_cache) = state
+        .gui
+        .renderer
+        .callback_resources
+        .get::<AnimationCache>()
+    {
+        caches.push(("Animation Matrices", animation_cache.memory_usage()));
+    }
+    caches.push(("UI Animations", state.gui_state.animations.memory_usage()));
+    let caches = caches
+        .into_iter()
+        .map(|(name, usage)| format!("{name}={usage}"))
+        .collect::<Vec<_>>()
+        .join(" ");
 
     let Some((info, map_name)) = &state.loop_store.map_info else {
         return;
@@ -25,19 +68,43 @@ pub fn debugger(state: &GameState) {
 
     let map_info = state.tokio.block_on(info.lock()).clone();
 
+    let slow_tiles = match state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetSlowTiles, None))
+    {
+        Ok(CallResult::Success(slow_tiles)) => slow_tiles,
+        _ => vec![],
+    };
+
     Window::new(
-        resource_man.translates.gui[&resource_man.registry.gui_ids.debug_menu].as_str(),
+        resource_man.translates().gui[&resource_man.registry.gui_ids.debug_menu].as_str(),
     )
         .id("debugger".into())
         .resizable(false)
         .default_width(600.0)
         .show(&state.gui.context.clone(), |ui| {
-            ui.label(format!("FPS: {fps:.1}"));
+            ui.label(format!("FPS: {fps:.1}, Quality: {:?}", state.renderer.quality));
+            ui.checkbox(&mut state.gui_state.layout_inspector, "Layout inspector");
             ui.label(format!("WGPU: {}", ron::ser::to_string_pretty(&state.renderer.gpu.adapter_info, PrettyConfig::default()).unwrap_or("could not format wgpu info".to_string())));
             ui.separator();
             ui.label(format!(
-                "ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} Meshes={meshes}"
+                "ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} Meshes={meshes} Categories={categories} Researches={researches}"
+            ));
+            ui.label(format!("Interner: {interned} IDs ({loaded} when loaded), by namespace: {namespaces}"));
+            // everything should be interned while loading, so IDs made afterwards are likely made over and over by a script
+            if interned > loaded {
+                ui.colored_label(Color32::from(colors::RED), format!("{} IDs were made after loading", interned - loaded));
+            }
+            ui.label(format!(
+                "Font Atlas: {}x{} ({:.1}% full), rebuilt {font_rebuilds} times", atlas_size[0], atlas_size[1], atlas_fill * 100.0
             ));
+            ui.label(format!("Memory: {process_memory}, Caches: {caches}"));
+            ui.separator();
+            ui.label(format!("Slow Tiles (over {TILE_TICK_BUDGET:?} per tick): {}", slow_tiles.len()));
+            for (coord, id, tick_time) in slow_tiles.iter().take(MAX_SLOW_TILES_SHOWN) {
+                ui.label(format!("{} at {coord}: {tick_time:?}", resource_man.tile_name(id)));
+            }
+            ui.separator();
             ui.label(format!("Map \"{map_name}\" ({:?}): {}", map_info.save_time.unwrap_or(SystemTime::UNIX_EPOCH), ron::ser::to_string_pretty(&map_info.data.to_raw(&state.resource_man.interner), PrettyConfig::default()).unwrap_or("could not format map info".to_string())));
         });
 }
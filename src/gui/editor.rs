@@ -0,0 +1,46 @@
+//! The map editor's tools, shown while a map is open in the editor.
+//!
+//! The editor places any tile regardless of research, paints the selected tile over the brush's area,
+//! edits tile data directly, and marks a map as a scenario template. Maps have no terrain or resource
+//! layer to paint, so there is no terrain or resource brush; one would need to be added to the map format first.
+
+use egui::{vec2, Align2, Checkbox, RichText, Slider, Window};
+
+use crate::map::MapInfo;
+use crate::GameState;
+
+/// The largest radius the editor brush can paint in.
+pub const MAX_BRUSH_SIZE: u32 = 8;
+
+/// Draws the map editor tools.
+pub fn editor_ui(state: &mut GameState, info: &mut MapInfo) {
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.editor].as_str(),
+    )
+    .id("map_editor".into())
+    .resizable(false)
+    .collapsible(true)
+    .default_width(200.0)
+    .anchor(Align2::RIGHT_TOP, vec2(-10.0, 10.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_brush_size]
+                    .as_str(),
+            ));
+            ui.add(Slider::new(
+                &mut state.gui_state.editor_brush_size,
+                0..=MAX_BRUSH_SIZE,
+            ));
+        });
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_map_template]
+                    .as_str(),
+            ));
+            ui.add(Checkbox::new(&mut info.template, ""));
+        });
+    });
+}
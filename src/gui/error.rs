@@ -9,7 +9,7 @@ use crate::GameState;
 pub fn error_popup(state: &mut GameState) {
     if let Some(error) = state.resource_man.error_man.peek() {
         Window::new(
-            state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.error_popup]
+            state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.error_popup]
                 .to_string(),
         )
         .id("error_popup".into())
@@ -24,7 +24,7 @@ pub fn error_popup(state: &mut GameState) {
                 ui.horizontal(|ui| {
                     if ui
                         .button(
-                            state.resource_man.translates.gui
+                            state.resource_man.translates().gui
                                 [&state.resource_man.registry.gui_ids.btn_confirm]
                                 .to_string(),
                         )
@@ -0,0 +1,196 @@
+use egui::{Frame, Margin, RichText, TopBottomPanel};
+
+use automancy_defs::id::Id;
+
+use crate::gui::route::Routing;
+use crate::gui::suggestion::current_suggestion;
+use crate::input::{binding_name, KeyActions};
+use crate::GameState;
+
+/// A key, or combination of keys, and what it does right now.
+struct Hint {
+    keys: String,
+    action: Id,
+}
+
+/// Gets the name of the key or mouse button bound to the action, or "?" if there is none.
+fn bound_key(state: &GameState, action: KeyActions) -> String {
+    state
+        .input_handler
+        .binding_for(action)
+        .as_ref()
+        .map(binding_name)
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// Gets the key actions that can be done in the current mode, from the most to least relevant.
+fn current_hints(state: &GameState) -> Vec<Hint> {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+    let translates = state.resource_man.translates();
+
+    let main = translates.gui[&gui_ids.key_main].to_string();
+    let alternate = translates.gui[&gui_ids.key_alternate].to_string();
+    let shift = translates.gui[&gui_ids.key_shift].to_string();
+    let control = translates.gui[&gui_ids.key_control].to_string();
+    let escape = bound_key(state, KeyActions::Escape);
+    let undo = format!("{control}+{}", bound_key(state, KeyActions::Undo));
+
+    let hint = |keys: &str, action: Id| Hint {
+        keys: keys.to_string(),
+        action,
+    };
+
+    if state.gui_state.dropping_items.is_some() {
+        return vec![
+            hint(&main, gui_ids.hint_drop_items),
+            hint(&escape, gui_ids.hint_cancel),
+        ];
+    }
+
+    match state.gui_state.routing {
+        Some(Routing::PickSource) => {
+            return vec![
+                hint(&main, gui_ids.hint_route_source),
+                hint(&escape, gui_ids.hint_cancel),
+            ];
+        }
+        Some(Routing::PickDestination(_)) => {
+            return vec![
+                hint(&main, gui_ids.hint_route_destination),
+                hint(&escape, gui_ids.hint_cancel),
+            ];
+        }
+        None => {}
+    }
+
+    if state.gui_state.zoning.is_some() {
+        return vec![
+            hint(&main, gui_ids.hint_paint_zone),
+            hint(&alternate, gui_ids.hint_erase_zone),
+            hint(&escape, gui_ids.hint_cancel),
+        ];
+    }
+
+    if state.gui_state.importing_region.is_some() {
+        return vec![
+            hint(&main, gui_ids.hint_place_region),
+            hint(&escape, gui_ids.hint_cancel),
+        ];
+    }
+
+    if state.input_handler.key_active(KeyActions::Player) && state.puzzle_state.is_some() {
+        let player = bound_key(state, KeyActions::Player);
+
+        return if state.gui_state.selected_research_puzzle_tile.is_some() {
+            vec![
+                hint(&main, gui_ids.hint_pick_puzzle_tile),
+                hint(&player, gui_ids.hint_close_menu),
+            ]
+        } else {
+            vec![
+                hint(&main, gui_ids.hint_select_puzzle_space),
+                hint(&player, gui_ids.hint_close_menu),
+            ]
+        };
+    }
+
+    if state.gui_state.linking_tile.is_some() {
+        return vec![
+            hint(&alternate, gui_ids.hint_link_destination),
+            hint(&escape, gui_ids.hint_cancel),
+        ];
+    }
+
+    if state.input_handler.control_held {
+        return if state.gui_state.initial_cursor_position.is_some() {
+            vec![
+                hint(&alternate, gui_ids.hint_drop_group),
+                hint(&control, gui_ids.hint_release_to_cancel),
+            ]
+        } else {
+            vec![
+                hint(&control, gui_ids.hint_select_group),
+                hint(&format!("{control}+{alternate}"), gui_ids.hint_start_move),
+                hint(
+                    &format!("{control}+{}", bound_key(state, KeyActions::CopyBlueprint)),
+                    gui_ids.hint_copy_blueprint,
+                ),
+                hint(
+                    &format!("{control}+{}", bound_key(state, KeyActions::PasteBlueprint)),
+                    gui_ids.hint_paste_blueprint,
+                ),
+                hint(&undo, gui_ids.hint_undo),
+                hint(
+                    &format!("{control}+{shift}+{}", bound_key(state, KeyActions::Redo)),
+                    gui_ids.hint_redo,
+                ),
+            ]
+        };
+    }
+
+    if let Some(id) = state.gui_state.selected_tile_id {
+        let mut hints = vec![
+            hint(&main, gui_ids.hint_place_tile),
+            hint(&format!("{shift}+{main}"), gui_ids.hint_place_continuously),
+        ];
+
+        let targeted = !state.resource_man.registry.tiles[&id]
+            .data
+            .flag(state.resource_man.registry.data_ids.not_targeted);
+        if targeted {
+            hints.push(hint(&shift, gui_ids.hint_set_direction));
+        }
+
+        hints.push(hint(&escape, gui_ids.hint_deselect));
+        hints.push(hint(&undo, gui_ids.hint_undo));
+
+        return hints;
+    }
+
+    let mut hints = vec![];
+
+    if current_suggestion(state).is_some() {
+        hints.push(hint(
+            &bound_key(state, KeyActions::AcceptSuggestion),
+            gui_ids.hint_place_suggestion,
+        ));
+    }
+
+    hints.extend([
+        hint(&alternate, gui_ids.hint_configure),
+        hint(&control, gui_ids.hint_select_group),
+        hint(&bound_key(state, KeyActions::PickUp), gui_ids.hint_pick_up),
+        hint(&bound_key(state, KeyActions::Route), gui_ids.hint_route),
+        hint(
+            &bound_key(state, KeyActions::Player),
+            gui_ids.hint_player_menu,
+        ),
+        hint(&undo, gui_ids.hint_undo),
+        hint(&escape, gui_ids.hint_pause),
+        hint(
+            &bound_key(state, KeyActions::Cheatsheet),
+            gui_ids.hint_cheatsheet,
+        ),
+    ]);
+
+    hints
+}
+
+/// Draws the bar at the bottom of the screen showing what the keys currently do.
+pub fn hint_bar(state: &mut GameState) {
+    let hints = current_hints(state);
+
+    TopBottomPanel::bottom("input_hints")
+        .show_separator_line(false)
+        .resizable(false)
+        .frame(Frame::none().inner_margin(Margin::symmetric(10.0, 4.0)))
+        .show(&state.gui.context.clone(), |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for Hint { keys, action } in hints {
+                    ui.label(RichText::new(keys).strong().monospace());
+                    ui.label(state.resource_man.translates().gui[&action].as_str());
+                    ui.add_space(12.0);
+                }
+            });
+        });
+}
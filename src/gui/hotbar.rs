@@ -0,0 +1,169 @@
+//! The hotbar, a row of slots above the tile selection holding the tiles the player uses the most,
+//! so they can be picked with the number keys without going through the categories.
+//!
+//! Tiles are added to and removed from it by right clicking them in the tile selection, and the slots
+//! are kept in the player's data, so they are saved along with the map.
+
+use std::f64::consts::FRAC_PI_4;
+
+use egui::{vec2, Align2, CursorIcon, FontId, Frame, Margin, Sense, TopBottomPanel};
+
+use automancy_defs::glam::{dvec3, vec3};
+use automancy_defs::id::Id;
+use automancy_defs::math;
+use automancy_defs::math::{z_far, z_near, DMatrix4};
+use automancy_defs::rendering::InstanceData;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::gui::{GameEguiCallback, MEDIUM_ICON_SIZE};
+use crate::input::{KeyActions, HOTBAR_SLOTS};
+use crate::GameState;
+
+/// Gets the tiles in each slot of the hotbar, with `None` for the empty ones.
+pub fn hotbar_slots(
+    resource_man: &ResourceManager,
+    game_data: &DataMap,
+) -> [Option<Id>; HOTBAR_SLOTS] {
+    let mut slots = [None; HOTBAR_SLOTS];
+
+    if let Some(Data::VecId(ids)) = game_data.get(&resource_man.registry.data_ids.hotbar) {
+        for (slot, id) in slots.iter_mut().zip(ids) {
+            if resource_man.registry.tiles.contains_key(id) {
+                *slot = Some(*id);
+            }
+        }
+    }
+
+    slots
+}
+
+fn set_hotbar_slots(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    slots: [Option<Id>; HOTBAR_SLOTS],
+) {
+    game_data.insert(
+        resource_man.registry.data_ids.hotbar,
+        Data::VecId(
+            slots
+                .into_iter()
+                .map(|v| v.unwrap_or(resource_man.registry.none))
+                .collect(),
+        ),
+    );
+}
+
+/// Puts the tile in the first empty slot of the hotbar, or takes it out if it is already in there.
+/// Nothing happens if the hotbar is full.
+pub fn toggle_hotbar(resource_man: &ResourceManager, game_data: &mut DataMap, id: Id) {
+    let mut slots = hotbar_slots(resource_man, game_data);
+
+    if let Some(slot) = slots.iter_mut().find(|v| **v == Some(id)) {
+        *slot = None;
+    } else if let Some(slot) = slots.iter_mut().find(|v| v.is_none()) {
+        *slot = Some(id);
+    } else {
+        return;
+    }
+
+    set_hotbar_slots(resource_man, game_data, slots);
+}
+
+/// Draws the hotbar, if anything is in it, and returns the tile picked from it this frame,
+/// either by clicking it or by its key.
+///
+/// Right clicking a slot empties it.
+pub fn hotbar(state: &mut GameState, game_data: &mut DataMap) -> Option<Id> {
+    let mut slots = hotbar_slots(&state.resource_man, game_data);
+
+    let mut picked = slots.iter().enumerate().find_map(|(index, id)| {
+        id.filter(|_| {
+            state
+                .input_handler
+                .key_active(KeyActions::Hotbar(index as u8))
+        })
+    });
+
+    if slots.iter().all(Option::is_none) {
+        return picked;
+    }
+
+    let projection = DMatrix4::perspective_lh(FRAC_PI_4, 1.0, z_near(), z_far())
+        * math::view(dvec3(0.0, 0.0, 2.75));
+    let projection = projection.as_mat4();
+
+    let mut cleared = false;
+
+    TopBottomPanel::bottom("hotbar")
+        .show_separator_line(false)
+        .resizable(false)
+        .frame(
+            Frame::window(&state.gui.context.clone().style())
+                .outer_margin(Margin::symmetric(80.0, 0.0)),
+        )
+        .show(&state.gui.context.clone(), |ui| {
+            ui.horizontal(|ui| {
+                ui.set_height(MEDIUM_ICON_SIZE);
+
+                for (index, slot) in slots.iter_mut().enumerate() {
+                    let size = ui.available_height();
+                    let (ui_id, rect) = ui.allocate_space(vec2(size, size));
+
+                    let response = ui.interact(rect, ui_id, Sense::click());
+
+                    if let Some(id) = *slot {
+                        let response = response
+                            .on_hover_text(state.resource_man.tile_name(&id))
+                            .on_hover_cursor(CursorIcon::Grab);
+
+                        if response.clicked() {
+                            picked = Some(id);
+                        }
+                        if response.secondary_clicked() {
+                            *slot = None;
+                            cleared = true;
+                        }
+
+                        let model = state
+                            .resource_man
+                            .get_model(state.resource_man.registry.tiles[&id].model);
+
+                        ui.painter().add(egui_wgpu::Callback::new_paint_callback(
+                            rect,
+                            GameEguiCallback::new(
+                                InstanceData::default()
+                                    .with_world_matrix(projection)
+                                    .with_light_pos(vec3(0.0, 4.0, 14.0), None),
+                                model,
+                                rect,
+                                ui.ctx().screen_rect(),
+                            ),
+                        ));
+
+                        if state.gui_state.selected_tile_id == Some(id) {
+                            ui.painter().rect_stroke(
+                                rect,
+                                ui.visuals().widgets.active.rounding,
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                    }
+
+                    ui.painter().text(
+                        rect.left_top(),
+                        Align2::LEFT_TOP,
+                        (index + 1).to_string(),
+                        FontId::monospace(10.0),
+                        ui.visuals().weak_text_color(),
+                    );
+                }
+            });
+        });
+
+    if cleared {
+        set_hotbar_slots(&state.resource_man, game_data, slots);
+    }
+
+    picked
+}
@@ -13,7 +13,7 @@ use crate::GameState;
 /// Draws the info GUI.
 pub fn info_ui(state: &mut GameState) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.info].as_str(),
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.info].as_str(),
     )
     .id("info".into())
     .anchor(Align2::RIGHT_TOP, vec2(-10.0, 10.0))
@@ -0,0 +1,87 @@
+//! The layout inspector, turned on from the debug menu, for finding out why widgets end up where they do,
+//! or get cut off.
+//!
+//! While it is on, egui outlines every interactive widget, and the widget under the pointer along with
+//! the regions it was laid out in and what made them grow. Clicking a widget logs what egui knows about it.
+
+use egui::output::OutputEvent;
+use egui::{vec2, Align2, Window};
+
+use automancy_defs::log;
+
+use crate::GameState;
+
+/// Turns egui's layout outlines on or off, and shows what is under the pointer and what was clicked last.
+///
+/// `debug_on_hover` outlines the widget under the pointer even with the inspector off.
+/// Runs after the rest of the GUI, so the clicks on it this frame are seen.
+pub fn layout_inspector(state: &mut GameState, debug_on_hover: bool) {
+    let context = state.gui.context.clone();
+    let inspecting = state.gui_state.layout_inspector;
+
+    let style = context.style();
+    if style.debug.debug_on_hover != (inspecting || debug_on_hover)
+        || style.debug.show_interactive_widgets != inspecting
+    {
+        context.style_mut(|style| {
+            style.debug.debug_on_hover = inspecting || debug_on_hover;
+            style.debug.show_expand_width = inspecting;
+            style.debug.show_expand_height = inspecting;
+            style.debug.show_resize = inspecting;
+            style.debug.show_interactive_widgets = inspecting;
+        });
+    }
+
+    if !inspecting {
+        return;
+    }
+
+    let clicked = context.output(|output| {
+        output.events.iter().rev().find_map(|event| match event {
+            OutputEvent::Clicked(info) => Some(info.clone()),
+            _ => None,
+        })
+    });
+
+    if let Some(info) = clicked {
+        log::info!(
+            "Clicked widget at {:?}: {info:?}",
+            context.pointer_interact_pos()
+        );
+
+        state.gui_state.inspected_widget = Some(info.description());
+    }
+
+    let pointer = context.pointer_latest_pos();
+
+    Window::new("Layout Inspector")
+        .id("layout_inspector".into())
+        .resizable(false)
+        .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -10.0))
+        .show(&context, |ui| {
+            match pointer {
+                Some(pos) => {
+                    ui.label(format!("Pointer: {:.1}, {:.1}", pos.x, pos.y));
+                    ui.label(format!("Layer: {:?}", context.layer_id_at(pos)));
+                }
+                None => {
+                    ui.label("Pointer: outside the window");
+                }
+            }
+            ui.label(format!(
+                "Screen: {:?} at {} pixels per point",
+                context.screen_rect().size(),
+                context.pixels_per_point()
+            ));
+            ui.separator();
+            ui.label(format!(
+                "Last clicked: {}",
+                state
+                    .gui_state
+                    .inspected_widget
+                    .as_deref()
+                    .unwrap_or("nothing yet")
+            ));
+            ui.checkbox(&mut state.gui_state.layout_inspector, "Inspect");
+        });
+}
@@ -34,10 +34,10 @@ pub fn draw_item(
                 ui.label(format!(
                     "{} ({})",
                     resource_man.item_name(&stack.item.id),
-                    stack.amount
+                    resource_man.translates().locale.count(stack.amount)
                 ))
             } else {
-                ui.label(resource_man.item_name(&stack.item.id).to_string())
+                ui.label(resource_man.item_name(&stack.item.id))
             };
 
             icon_response.union(label_response)
@@ -0,0 +1,191 @@
+use egui::{
+    vec2, Align2, Color32, ComboBox, FontId, LayerId, Order, Pos2, Rect, Shape, Stroke, TextEdit,
+    Window,
+};
+
+use automancy_defs::id::Id;
+use automancy_defs::{colors, window};
+use automancy_resources::format;
+
+use crate::map::MapInfo;
+use crate::marker::MarkerIcon;
+use crate::GameState;
+
+/// How big the markers are drawn on the screen, in points. This stays the same at every zoom level.
+const MARKER_SIZE: f32 = 8.0;
+
+fn icon_name(state: &GameState, icon: MarkerIcon) -> Id {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    match icon {
+        MarkerIcon::Circle => gui_ids.lbl_icon_circle,
+        MarkerIcon::Square => gui_ids.lbl_icon_square,
+        MarkerIcon::Diamond => gui_ids.lbl_icon_diamond,
+        MarkerIcon::Triangle => gui_ids.lbl_icon_triangle,
+    }
+}
+
+/// Draws the list of markers, where they can be placed, renamed, recolored, and jumped to.
+pub fn markers_ui(state: &mut GameState, info: &mut MapInfo) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let size = window::window_size_double(&state.renderer.gpu.window);
+
+    let mut remove = None;
+    let mut jump = None;
+
+    Window::new(state.resource_man.translates().gui[&gui_ids.markers_title].as_str())
+        .id("markers".into())
+        .resizable(false)
+        .collapsible(true)
+        .default_width(250.0)
+        .anchor(Align2::CENTER_TOP, vec2(0.0, 10.0))
+        .show(&state.gui.context.clone(), |ui| {
+            let translates = state.resource_man.translates();
+
+            for (index, marker) in info.markers.0.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut marker.color);
+
+                    ComboBox::from_id_source(("marker_icon", index))
+                        .width(80.0)
+                        .selected_text(translates.gui[&icon_name(state, marker.icon)].as_str())
+                        .show_ui(ui, |ui| {
+                            for icon in MarkerIcon::ALL {
+                                ui.selectable_value(
+                                    &mut marker.icon,
+                                    icon,
+                                    translates.gui[&icon_name(state, icon)].as_str(),
+                                );
+                            }
+                        });
+
+                    ui.add(TextEdit::singleline(&mut marker.label).desired_width(100.0));
+
+                    if ui
+                        .small_button(translates.gui[&gui_ids.btn_jump].as_str())
+                        .clicked()
+                    {
+                        jump = Some(marker.coord);
+                    }
+
+                    if ui.small_button("x").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+
+            if info.markers.is_empty() {
+                ui.label(translates.gui[&gui_ids.lbl_no_markers].as_str());
+            }
+
+            ui.separator();
+
+            if ui
+                .button(translates.gui[&gui_ids.btn_add_marker].as_str())
+                .clicked()
+            {
+                let label = format(
+                    translates.gui[&gui_ids.marker_default_name].as_str(),
+                    &[&(info.markers.0.len() + 1).to_string()],
+                );
+
+                info.markers.add(state.camera.get_tile_coord(), label);
+            }
+        });
+
+    if let Some(index) = remove {
+        info.markers.remove(index);
+    }
+
+    if let Some(coord) = jump {
+        state.camera.jump_to(coord, size);
+    }
+}
+
+/// Draws every marker on the screen, over the map but under the windows.
+pub fn draw_markers(state: &GameState, info: &MapInfo) {
+    if info.markers.is_empty() {
+        return;
+    }
+
+    let (width, height) = window::window_size_double(&state.renderer.gpu.window);
+    let pixels_per_point = state.gui.context.pixels_per_point();
+    let screen = state.gui.context.screen_rect();
+
+    let painter = state
+        .gui
+        .context
+        .layer_painter(LayerId::new(Order::Background, "markers".into()));
+    let outline = Stroke::new(1.5, Color32::from(colors::BLACK));
+
+    for marker in info.markers.iter() {
+        let Some(pos) = state.camera.coord_to_screen(marker.coord, (width, height)) else {
+            continue;
+        };
+        let center = Pos2::new(pos.x as f32, pos.y as f32) / pixels_per_point;
+
+        if !screen.expand(MARKER_SIZE * 4.0).contains(center) {
+            continue;
+        }
+
+        let [r, g, b] = marker.color;
+        let color = Color32::from_rgb(r, g, b);
+
+        match marker.icon {
+            MarkerIcon::Circle => {
+                painter.circle(center, MARKER_SIZE, color, outline);
+            }
+            MarkerIcon::Square => {
+                painter.rect(
+                    Rect::from_center_size(center, vec2(MARKER_SIZE, MARKER_SIZE) * 1.8),
+                    0.0,
+                    color,
+                    outline,
+                );
+            }
+            MarkerIcon::Diamond => {
+                painter.add(Shape::convex_polygon(
+                    vec![
+                        center + vec2(0.0, -MARKER_SIZE * 1.2),
+                        center + vec2(MARKER_SIZE * 1.2, 0.0),
+                        center + vec2(0.0, MARKER_SIZE * 1.2),
+                        center + vec2(-MARKER_SIZE * 1.2, 0.0),
+                    ],
+                    color,
+                    outline,
+                ));
+            }
+            MarkerIcon::Triangle => {
+                painter.add(Shape::convex_polygon(
+                    vec![
+                        center + vec2(0.0, -MARKER_SIZE * 1.2),
+                        center + vec2(MARKER_SIZE * 1.1, MARKER_SIZE * 0.8),
+                        center + vec2(-MARKER_SIZE * 1.1, MARKER_SIZE * 0.8),
+                    ],
+                    color,
+                    outline,
+                ));
+            }
+        }
+
+        if !marker.label.is_empty() {
+            let text_pos = center - vec2(0.0, MARKER_SIZE * 1.6);
+
+            // a dark copy of the label behind it keeps it readable over bright tiles
+            painter.text(
+                text_pos + vec2(1.0, 1.0),
+                Align2::CENTER_BOTTOM,
+                &marker.label,
+                FontId::proportional(14.0),
+                Color32::from(colors::BLACK),
+            );
+            painter.text(
+                text_pos,
+                Align2::CENTER_BOTTOM,
+                &marker.label,
+                FontId::proportional(14.0),
+                color,
+            );
+        }
+    }
+}
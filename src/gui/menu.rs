@@ -1,28 +1,47 @@
 use std::borrow::Cow;
 use std::fs;
+use std::sync::atomic::Ordering;
+use std::time::{Instant, SystemTime};
 
 use egui::load::Bytes;
 use egui::{
-    vec2, Align, Align2, Button, Checkbox, ComboBox, Image, ImageSource, RichText, ScrollArea,
-    Slider, TextEdit, TextStyle, Window,
+    vec2, Align, Align2, Button, Checkbox, Color32, ComboBox, Grid, Image, ImageSource, Response,
+    RichText, Rounding, ScrollArea, Slider, Stroke, TextEdit, TextStyle, Ui, Window,
 };
 use winit::event_loop::EventLoopWindowTarget;
 
+use automancy_defs::colors;
 use automancy_defs::gui::HyperlinkWidget;
 use automancy_defs::log;
-use automancy_resources::{format, format_time};
+use automancy_resources::format;
+use automancy_resources::types::font::Font;
 
-use crate::event::{refresh_maps, shutdown_graceful};
-use crate::game::{load_map, GameSystemMessage};
-use crate::gui::{OptionsMenuState, PopupState, Screen, SubState, TextField};
-use crate::map::{Map, MAIN_MENU};
-use crate::options::AAType;
+use crate::event::{refresh_maps, shutdown_graceful, sort_maps};
+use crate::game::{load_map, load_map_in_editor, reload_map, GameSystemMessage};
+use crate::gui::cheatsheet::{action_name, category_name};
+use crate::gui::poster;
+use crate::gui::sound::UiSoundExt;
+use crate::gui::toast::push_toast;
+use crate::gui::{
+    MapSortOrder, OptionsMenuState, PopupState, Rebinding, Screen, SubState, TextField,
+};
+use crate::input::{binding_name, Binding, KeyActions, KeyCategory};
+use crate::logs;
+use crate::map::{Map, MapInfoRaw, MAIN_MENU};
+use crate::options::{AAType, FPS_PRESETS, RISKY_OPTIONS, UNLIMITED_FPS};
+use crate::power::BatterySaver;
+use crate::quality::Quality;
+use crate::replay::RecordedInput;
+use crate::scenario::ScenarioOutcome;
 use crate::{GameState, LOGO, LOGO_PATH, VERSION};
 
+/// How wide the preview images of the maps in the map menu are.
+const THUMBNAIL_WIDTH: f32 = 96.0;
+
 /// Draws the main menu.
 pub fn main_menu(
     state: &mut GameState,
-    target: &EventLoopWindowTarget<()>,
+    target: &EventLoopWindowTarget<RecordedInput>,
 ) -> anyhow::Result<bool> {
     let mut result = Ok(false);
 
@@ -49,7 +68,7 @@ pub fn main_menu(
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_play]
                                         .as_str(),
                                 )
@@ -57,6 +76,7 @@ pub fn main_menu(
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
                         refresh_maps(state);
@@ -67,7 +87,7 @@ pub fn main_menu(
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_options]
                                         .as_str(),
                                 )
@@ -75,6 +95,7 @@ pub fn main_menu(
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
                         state.gui_state.switch_screen(Screen::Options)
@@ -83,7 +104,7 @@ pub fn main_menu(
                     ui.add(HyperlinkWidget::new(
                         Button::new(
                             RichText::new(
-                                state.resource_man.translates.gui
+                                state.resource_man.translates().gui
                                     [&state.resource_man.registry.gui_ids.btn_fedi]
                                     .as_str(),
                             )
@@ -96,7 +117,7 @@ pub fn main_menu(
                     ui.add(HyperlinkWidget::new(
                         Button::new(
                             RichText::new(
-                                state.resource_man.translates.gui
+                                state.resource_man.translates().gui
                                     [&state.resource_man.registry.gui_ids.btn_source]
                                     .as_str(),
                             )
@@ -110,7 +131,7 @@ pub fn main_menu(
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_exit]
                                         .as_str(),
                                 )
@@ -118,6 +139,7 @@ pub fn main_menu(
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
                         result = state.tokio.block_on(shutdown_graceful(
@@ -137,6 +159,15 @@ pub fn main_menu(
 
 /// Draws the pause menu.
 pub fn pause_menu(state: &mut GameState) {
+    let map_name = state.loop_store.map_info.as_ref().map(|v| v.1.clone());
+    let thumbnail = state
+        .renderer
+        .last_thumbnail
+        .try_lock()
+        .ok()
+        .and_then(|v| v.clone())
+        .filter(|v| Some(&v.map_name) == map_name.as_ref());
+
     Window::new("Game Paused")
         .resizable(false)
         .collapsible(false)
@@ -148,11 +179,30 @@ pub fn pause_menu(state: &mut GameState) {
                     .with_cross_align(Align::Center)
                     .with_main_align(Align::Center),
                 |ui| {
+                    if let Some(thumbnail) = thumbnail {
+                        let version = thumbnail
+                            .time
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_millis();
+
+                        ui.add(
+                            Image::new(ImageSource::Bytes {
+                                uri: Cow::Owned(format!(
+                                    "bytes://thumbnail/{}/taken-{version}.png",
+                                    thumbnail.map_name
+                                )),
+                                bytes: Bytes::Shared(thumbnail.png),
+                            })
+                            .max_size(vec2(160.0, 90.0)),
+                        );
+                    }
+
                     if ui
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_unpause]
                                         .as_str(),
                                 )
@@ -160,6 +210,7 @@ pub fn pause_menu(state: &mut GameState) {
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
                         state.gui_state.switch_screen(Screen::Ingame)
@@ -168,7 +219,68 @@ pub fn pause_menu(state: &mut GameState) {
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_restart]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        if state
+                            .tokio
+                            .block_on(reload_map(&state.game, &mut state.loop_store))
+                            .unwrap()
+                        {
+                            state.gui_state.switch_screen(Screen::Ingame)
+                        }
+                    };
+                    if ui
+                        .add(
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_import_region]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        refresh_maps(state);
+
+                        state.gui_state.import_source = None;
+                        state.gui_state.popup = PopupState::ImportRegion;
+                    };
+                    if ui
+                        .add_enabled(
+                            state.gui_state.poster_export.is_none(),
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_export_image]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        poster::export_poster(state);
+                    };
+                    if ui
+                        .add(
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_options]
                                         .as_str(),
                                 )
@@ -176,6 +288,7 @@ pub fn pause_menu(state: &mut GameState) {
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
                         state.gui_state.switch_screen(Screen::Options)
@@ -184,7 +297,7 @@ pub fn pause_menu(state: &mut GameState) {
                         .add(
                             Button::new(
                                 RichText::new(
-                                    state.resource_man.translates.gui
+                                    state.resource_man.translates().gui
                                         [&state.resource_man.registry.gui_ids.btn_exit]
                                         .as_str(),
                                 )
@@ -192,26 +305,120 @@ pub fn pause_menu(state: &mut GameState) {
                             )
                             .min_size(vec2(100.0, 28.0)),
                         )
+                        .hover_sound(state)
                         .clicked()
                     {
-                        state
-                            .tokio
-                            .block_on(state.game.call(GameSystemMessage::SaveMap, None))
-                            .unwrap()
-                            .unwrap();
+                        exit_to_main_menu(state);
+                    };
+                    ui.label(VERSION)
+                },
+            );
+        });
+}
 
-                        state
+/// Saves the current map, and goes back to the main menu.
+fn exit_to_main_menu(state: &mut GameState) {
+    state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::SaveMap, None))
+        .unwrap()
+        .unwrap();
+
+    state
+        .tokio
+        .block_on(load_map(
+            &state.game,
+            &mut state.loop_store,
+            MAIN_MENU.to_string(),
+        ))
+        .unwrap();
+
+    state.gui_state.switch_screen(Screen::MainMenu)
+}
+
+/// Draws the screen shown when a scenario has ended.
+pub fn scenario_end_menu(state: &mut GameState, outcome: ScenarioOutcome) {
+    let (title, description) = match outcome {
+        ScenarioOutcome::Victory => (
+            state.resource_man.registry.gui_ids.scenario_victory,
+            state.resource_man.registry.gui_ids.lbl_scenario_victory,
+        ),
+        ScenarioOutcome::Defeat => (
+            state.resource_man.registry.gui_ids.scenario_defeat,
+            state.resource_man.registry.gui_ids.lbl_scenario_defeat,
+        ),
+    };
+
+    Window::new(state.resource_man.translates().gui[&title].as_str())
+        .resizable(false)
+        .collapsible(false)
+        .default_width(175.0)
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .show(&state.gui.context.clone(), |ui| {
+            ui.with_layout(
+                ui.layout()
+                    .with_cross_align(Align::Center)
+                    .with_main_align(Align::Center),
+                |ui| {
+                    ui.label(state.resource_man.translates().gui[&description].as_str());
+
+                    if ui
+                        .add(
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_continue]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        state.gui_state.switch_screen(Screen::Ingame)
+                    };
+                    if ui
+                        .add(
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_restart]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        if state
                             .tokio
-                            .block_on(load_map(
-                                &state.game,
-                                &mut state.loop_store,
-                                MAIN_MENU.to_string(),
-                            ))
-                            .unwrap();
-
-                        state.gui_state.switch_screen(Screen::MainMenu)
+                            .block_on(reload_map(&state.game, &mut state.loop_store))
+                            .unwrap()
+                        {
+                            state.gui_state.switch_screen(Screen::Ingame)
+                        }
+                    };
+                    if ui
+                        .add(
+                            Button::new(
+                                RichText::new(
+                                    state.resource_man.translates().gui
+                                        [&state.resource_man.registry.gui_ids.btn_exit]
+                                        .as_str(),
+                                )
+                                .heading(),
+                            )
+                            .min_size(vec2(100.0, 28.0)),
+                        )
+                        .hover_sound(state)
+                        .clicked()
+                    {
+                        exit_to_main_menu(state);
                     };
-                    ui.label(VERSION)
                 },
             );
         });
@@ -220,17 +427,46 @@ pub fn pause_menu(state: &mut GameState) {
 /// Draws the map loading menu.
 pub fn map_menu(state: &mut GameState) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.load_map].as_str(),
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.load_map].as_str(),
     )
     .resizable(false)
     .collapsible(false)
     .default_width(600.0)
     .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
     .show(&state.gui.context.clone(), |ui| {
+        ui.horizontal(|ui| {
+            let translates = state.resource_man.translates();
+            let gui_ids = &state.resource_man.registry.gui_ids;
+            let sort_name = |sort| match sort {
+                MapSortOrder::Name => translates.gui[&gui_ids.lbl_sort_name].as_str(),
+                MapSortOrder::LastPlayed => translates.gui[&gui_ids.lbl_sort_last_played].as_str(),
+                MapSortOrder::Playtime => translates.gui[&gui_ids.lbl_sort_playtime].as_str(),
+            };
+
+            let old = state.gui_state.map_sort;
+
+            ui.label(translates.gui[&gui_ids.lbl_sort_by].as_str());
+            ComboBox::from_id_source("map_sort")
+                .selected_text(sort_name(old))
+                .show_ui(ui, |ui| {
+                    for sort in [
+                        MapSortOrder::Name,
+                        MapSortOrder::LastPlayed,
+                        MapSortOrder::Playtime,
+                    ] {
+                        ui.selectable_value(&mut state.gui_state.map_sort, sort, sort_name(sort));
+                    }
+                });
+
+            if state.gui_state.map_sort != old {
+                sort_maps(state);
+            }
+        });
+
         ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
             let mut dirty = false;
 
-            for ((_, save_time), map_name) in state.loop_store.map_infos_cache.clone() {
+            for ((info, save_time), map_name) in state.loop_store.map_infos_cache.clone() {
                 ui.group(|ui| {
                     ui.scope(|ui| {
                         ui.style_mut().override_text_style = Some(TextStyle::Heading);
@@ -270,37 +506,75 @@ pub fn map_menu(state: &mut GameState) {
                     });
 
                     if let Some(save_time) = save_time {
-                        ui.label(format_time(
-                            save_time,
-                            state.resource_man.translates.gui
-                                [&state.resource_man.registry.gui_ids.time_fmt]
-                                .as_str(),
-                        ));
+                        ui.label(state.resource_man.time_string(save_time));
                     }
 
+                    ui.horizontal(|ui| {
+                        if let Some(thumbnail) = state.loop_store.map_thumbnails.get(&map_name) {
+                            let version = save_time
+                                .and_then(|v| v.duration_since(SystemTime::UNIX_EPOCH).ok())
+                                .unwrap_or_default()
+                                .as_secs();
+
+                            ui.add(
+                                Image::new(ImageSource::Bytes {
+                                    // the save time is in the name, so a new thumbnail isn't hidden by the old one being cached
+                                    uri: Cow::Owned(format!(
+                                        "bytes://thumbnail/{map_name}/{version}.png"
+                                    )),
+                                    bytes: Bytes::Shared(thumbnail.clone()),
+                                })
+                                .max_size(vec2(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH * 9.0 / 16.0)),
+                            );
+                        }
+
+                        map_details(state, ui, &info);
+                    });
+
                     ui.horizontal(|ui| {
                         if ui
                             .button(
-                                state.resource_man.translates.gui
+                                state.resource_man.translates().gui
                                     [&state.resource_man.registry.gui_ids.btn_load]
                                     .as_str(),
                             )
                             .clicked()
                         {
-                            state
+                            if state
                                 .tokio
                                 .block_on(load_map(
                                     &state.game,
                                     &mut state.loop_store,
                                     map_name.clone(),
                                 ))
-                                .unwrap();
-
-                            state.gui_state.switch_screen(Screen::Ingame);
+                                .unwrap()
+                            {
+                                state.gui_state.switch_screen(Screen::Ingame);
+                            }
+                        }
+                        if ui
+                            .button(
+                                state.resource_man.translates().gui
+                                    [&state.resource_man.registry.gui_ids.btn_edit]
+                                    .as_str(),
+                            )
+                            .clicked()
+                        {
+                            if state
+                                .tokio
+                                .block_on(load_map_in_editor(
+                                    &state.game,
+                                    &mut state.loop_store,
+                                    map_name.clone(),
+                                ))
+                                .unwrap()
+                            {
+                                state.gui_state.switch_screen(Screen::Ingame);
+                            }
                         }
                         if ui
                             .button(
-                                state.resource_man.translates.gui
+                                state.resource_man.translates().gui
                                     [&state.resource_man.registry.gui_ids.btn_delete]
                                     .as_str(),
                             )
@@ -311,6 +585,45 @@ pub fn map_menu(state: &mut GameState) {
 
                             dirty = true;
                         }
+                        if ui
+                            .button(
+                                state.resource_man.translates().gui
+                                    [&state.resource_man.registry.gui_ids.btn_export]
+                                    .as_str(),
+                            )
+                            .clicked()
+                        {
+                            let path = Map::export_path(&map_name);
+
+                            match Map::export_to_file(&state.resource_man, &map_name, &path) {
+                                Ok(()) => {
+                                    log::info!("Exported map {map_name} to {}", path.display());
+
+                                    state.gui_state.popup = PopupState::MapExported(path);
+                                }
+                                Err(err) => {
+                                    log::error!("Could not export map {map_name}: {err}");
+
+                                    state.resource_man.error_man.push(
+                                        (
+                                            state.resource_man.registry.err_ids.map_export_failed,
+                                            vec![err.to_string()],
+                                        ),
+                                        &state.resource_man,
+                                    );
+                                }
+                            }
+                        }
+                        if ui
+                            .button(
+                                state.resource_man.translates().gui
+                                    [&state.resource_man.registry.gui_ids.btn_restore_backup]
+                                    .as_str(),
+                            )
+                            .clicked()
+                        {
+                            state.gui_state.popup = PopupState::RestoreBackup(map_name.clone());
+                        }
                     });
                 });
             }
@@ -320,7 +633,8 @@ pub fn map_menu(state: &mut GameState) {
             }
         });
         ui.label(format(
-            state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.lbl_maps_loaded]
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_maps_loaded]
                 .as_str(),
             &[state.loop_store.map_infos_cache.len().to_string().as_str()],
         ));
@@ -328,7 +642,7 @@ pub fn map_menu(state: &mut GameState) {
             if ui
                 .button(
                     RichText::new(
-                        state.resource_man.translates.gui
+                        state.resource_man.translates().gui
                             [&state.resource_man.registry.gui_ids.btn_new_map]
                             .as_str(),
                     )
@@ -341,7 +655,20 @@ pub fn map_menu(state: &mut GameState) {
             if ui
                 .button(
                     RichText::new(
-                        state.resource_man.translates.gui
+                        state.resource_man.translates().gui
+                            [&state.resource_man.registry.gui_ids.btn_import]
+                            .as_str(),
+                    )
+                    .heading(),
+                )
+                .clicked()
+            {
+                state.gui_state.popup = PopupState::MapImport
+            }
+            if ui
+                .button(
+                    RichText::new(
+                        state.resource_man.translates().gui
                             [&state.resource_man.registry.gui_ids.btn_cancel]
                             .as_str(),
                     )
@@ -355,16 +682,66 @@ pub fn map_menu(state: &mut GameState) {
     });
 }
 
+/// Lists how big a map is, and when and how long it has been played.
+fn map_details(state: &GameState, ui: &mut Ui, info: &MapInfoRaw) {
+    let translates = state.resource_man.translates();
+    let gui_ids = &state.resource_man.registry.gui_ids;
+    let locale = translates.locale;
+
+    ui.vertical(|ui| {
+        ui.label(format(
+            translates.gui[&gui_ids.lbl_map_tiles].as_str(),
+            &[&locale.count(info.tile_count)],
+        ));
+
+        let minutes = info.playtime.as_secs() / 60;
+        ui.label(format(
+            translates.gui[&gui_ids.lbl_map_playtime].as_str(),
+            &[&format!("{}:{:02}", minutes / 60, minutes % 60)],
+        ));
+
+        if let Some(last_played) = info.last_played {
+            ui.label(format(
+                translates.gui[&gui_ids.lbl_map_last_played].as_str(),
+                &[&state.resource_man.time_string(last_played)],
+            ));
+        }
+
+        if let Some(created) = info.created {
+            ui.label(format(
+                translates.gui[&gui_ids.lbl_map_created].as_str(),
+                &[&state.resource_man.time_string(created)],
+            ));
+        }
+    });
+}
+
 /// Draws the options menu.
 pub fn options_menu(state: &mut GameState) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.options].as_str(),
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.options].as_str(),
     )
     .resizable(false)
     .collapsible(false)
     .default_width(175.0)
     .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
     .show(&state.gui.context.clone(), |ui| {
+        if state.gui_state.options_on_open.is_none() {
+            state.gui_state.options_on_open = Some(state.options.clone());
+        }
+
+        ui.add(
+            TextEdit::singleline(state.gui_state.text_field.get(TextField::OptionsSearch))
+                .hint_text("Search options")
+                .desired_width(f32::INFINITY),
+        );
+        let search = state
+            .gui_state
+            .text_field
+            .get(TextField::OptionsSearch)
+            .trim()
+            .to_lowercase();
+
         ui.horizontal(|ui| {
             ui.vertical(|ui| {
                 if ui
@@ -391,51 +768,243 @@ pub fn options_menu(state: &mut GameState) {
                 {
                     state.gui_state.substate = SubState::Options(OptionsMenuState::Controls)
                 }
+
+                ui.separator();
+
+                if ui
+                    .add(Button::new("Open logs").min_size(vec2(80.0, 24.0)))
+                    .on_hover_text(logs::logs_folder().display().to_string())
+                    .clicked()
+                {
+                    if let Err(err) = logs::open_logs_folder() {
+                        log::warn!("Could not open the logs folder: {err}");
+                        push_toast(state, format!("Could not open the logs folder: {err}"));
+                    }
+                }
             });
 
             ScrollArea::vertical().show(ui, |ui| {
-                if let SubState::Options(menu) = state.gui_state.substate {
+                if !search.is_empty() {
+                    options_search_results(state, ui, &search);
+                } else if let SubState::Options(menu) = state.gui_state.substate {
                     match menu {
                         OptionsMenuState::Graphics => {
                             ui.vertical(|ui| {
-                                ui.label(RichText::new("Graphics").text_style(TextStyle::Heading));
+                                page_heading(state, ui, OptionsMenuState::Graphics);
                                 ui.vertical(|ui| {
-                                    ui.label(RichText::new("Max FPS: "));
+                                    let fps = 1.0 / state.loop_store.elapsed.as_secs_f64();
+
+                                    ui.horizontal(|ui| {
+                                        mark_jump_target(
+                                            &ui.label(RichText::new(format!(
+                                                "Max FPS (now {fps:.0}): "
+                                            ))),
+                                            &mut state.gui_state.options_jump,
+                                            "Max FPS",
+                                        );
+                                        reset_button(state, ui, "Max FPS");
+                                    });
+
+                                    let graphics = &mut state.options.graphics;
+                                    ui.horizontal(|ui| {
+                                        for preset in FPS_PRESETS {
+                                            if ui
+                                                .selectable_label(
+                                                    !graphics.vsync && graphics.fps_limit == preset,
+                                                    preset.to_string(),
+                                                )
+                                                .clicked()
+                                            {
+                                                graphics.fps_limit = preset;
+                                                graphics.vsync = false;
+                                            }
+                                        }
+                                        if ui
+                                            .selectable_label(
+                                                !graphics.vsync
+                                                    && graphics.frame_rate_limit() == 0.0,
+                                                "Unlimited",
+                                            )
+                                            .clicked()
+                                        {
+                                            graphics.fps_limit = UNLIMITED_FPS;
+                                            graphics.vsync = false;
+                                        }
+                                        if ui
+                                            .selectable_label(
+                                                graphics.vsync
+                                                    && graphics.frame_rate_limit() == 0.0,
+                                                "Vsync",
+                                            )
+                                            .clicked()
+                                        {
+                                            graphics.fps_limit = UNLIMITED_FPS;
+                                            graphics.vsync = true;
+                                        }
+                                    });
                                     ui.add(
-                                        Slider::new(
-                                            &mut state.options.graphics.fps_limit,
-                                            0.0..=250.0,
-                                        )
-                                        .step_by(5.0)
-                                        .custom_formatter(
-                                            |n, _| {
-                                                if n == 0.0 {
-                                                    "Vsync".to_string()
-                                                } else if n == 250.0 {
+                                        Slider::new(&mut graphics.fps_limit, 10.0..=UNLIMITED_FPS)
+                                            .step_by(1.0)
+                                            .custom_formatter(|n, _| {
+                                                if n >= UNLIMITED_FPS {
                                                     "Unlimited".to_string()
                                                 } else {
                                                     format!("{}", n)
                                                 }
-                                            },
-                                        ),
-                                    )
+                                            }),
+                                    );
+                                    ui.horizontal(|ui| {
+                                        mark_jump_target(
+                                            &ui.add(Checkbox::new(
+                                                &mut state.options.graphics.vsync,
+                                                "Vsync",
+                                            )),
+                                            &mut state.gui_state.options_jump,
+                                            "Vsync",
+                                        );
+                                        reset_button(state, ui, "Vsync");
+                                    });
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Quality: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Quality",
+                                    );
+                                    ComboBox::from_id_source("quality")
+                                        .selected_text(format!(
+                                            "{:?}",
+                                            state.options.graphics.quality
+                                        ))
+                                        .show_ui(ui, |ui| {
+                                            for quality in Quality::ALL {
+                                                ui.selectable_value(
+                                                    &mut state.options.graphics.quality,
+                                                    quality,
+                                                    format!("{quality:?}"),
+                                                );
+                                            }
+                                        });
+                                    reset_button(state, ui, "Quality");
+                                    mark_jump_target(
+                                        &ui.add(Checkbox::new(
+                                            &mut state.options.graphics.adaptive_quality,
+                                            "Lower when slow",
+                                        )),
+                                        &mut state.gui_state.options_jump,
+                                        "Lower when slow",
+                                    );
+                                    reset_button(state, ui, "Lower when slow");
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Fullscreen: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Battery saver: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Battery saver",
+                                    );
+                                    ComboBox::from_id_source("battery_saver")
+                                        .selected_text(format!(
+                                            "{:?}",
+                                            state.options.graphics.battery_saver
+                                        ))
+                                        .show_ui(ui, |ui| {
+                                            for mode in BatterySaver::ALL {
+                                                ui.selectable_value(
+                                                    &mut state.options.graphics.battery_saver,
+                                                    mode,
+                                                    format!("{mode:?}"),
+                                                );
+                                            }
+                                        });
+                                    reset_button(state, ui, "Battery saver");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Bloom: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Bloom",
+                                    );
+                                    ui.add(Checkbox::new(&mut state.options.graphics.bloom, ""));
+                                    reset_button(state, ui, "Bloom");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Tile outlines: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Tile outlines",
+                                    );
+                                    ui.add(Checkbox::new(
+                                        &mut state.options.graphics.tile_outlines,
+                                        "",
+                                    ));
+                                    reset_button(state, ui, "Tile outlines");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Fullscreen: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Fullscreen",
+                                    );
                                     ui.add(Checkbox::new(
                                         &mut state.options.graphics.fullscreen,
                                         "",
                                     ));
+                                    reset_button(state, ui, "Fullscreen");
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Scale: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Scale: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Scale",
+                                    );
                                     ui.add(
                                         Slider::new(&mut state.options.graphics.scale, 0.5..=4.0)
                                             .step_by(0.5),
-                                    )
+                                    );
+                                    reset_button(state, ui, "Scale");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Screenshots: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Screenshots",
+                                    );
+                                    ui.add(Checkbox::new(
+                                        &mut state.options.graphics.screenshot.include_gui,
+                                        "Include GUI",
+                                    ));
+                                    ui.add(Checkbox::new(
+                                        &mut state.options.graphics.screenshot.clipboard,
+                                        "Copy to clipboard",
+                                    ));
+                                    ui.add(Checkbox::new(
+                                        &mut state.options.graphics.screenshot.file,
+                                        "Save to file",
+                                    ));
+                                    reset_button(state, ui, "Screenshots");
+                                });
+                                ui.add_enabled_ui(state.options.graphics.screenshot.file, |ui| {
+                                    ui.horizontal(|ui| {
+                                        mark_jump_target(
+                                            &ui.label(RichText::new("Screenshot folder: ")),
+                                            &mut state.gui_state.options_jump,
+                                            "Screenshot folder",
+                                        );
+                                        ui.add(
+                                            TextEdit::singleline(
+                                                &mut state.options.graphics.screenshot.directory,
+                                            )
+                                            .desired_width(200.0),
+                                        );
+                                        reset_button(state, ui, "Screenshot folder");
+                                    });
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Antialiasing: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Antialiasing: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Antialiasing",
+                                    );
                                     ComboBox::from_label("")
                                         .selected_text(format!(
                                             "{:?}",
@@ -458,14 +1027,63 @@ pub fn options_menu(state: &mut GameState) {
                                                 "TAA",
                                             );
                                         });
+                                    reset_button(state, ui, "Antialiasing");
                                 })
                             });
                         }
                         OptionsMenuState::Audio => {
                             ui.vertical(|ui| {
-                                ui.label(RichText::new("Audio").text_style(TextStyle::Heading));
+                                page_heading(state, ui, OptionsMenuState::Audio);
+                                if !state.audio_man.is_available() {
+                                    ui.horizontal(|ui| {
+                                        ui.label(
+                                            RichText::new(
+                                                "No audio device found, the game is silent.",
+                                            )
+                                            .color(colors::RED),
+                                        );
+                                        if ui.button("Retry").clicked() {
+                                            state.audio_man.retry();
+                                        }
+                                    });
+                                }
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("SFX Volume: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Output Device: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Output Device",
+                                    );
+                                    ComboBox::from_id_source("output_device")
+                                        .selected_text(
+                                            state
+                                                .options
+                                                .audio
+                                                .output_device
+                                                .as_deref()
+                                                .unwrap_or("System Default"),
+                                        )
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut state.options.audio.output_device,
+                                                None,
+                                                "System Default",
+                                            );
+                                            for device in state.audio_man.devices() {
+                                                ui.selectable_value(
+                                                    &mut state.options.audio.output_device,
+                                                    Some(device.clone()),
+                                                    device,
+                                                );
+                                            }
+                                        });
+                                    reset_button(state, ui, "Output Device");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("SFX Volume: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "SFX Volume",
+                                    );
                                     ui.add(
                                         Slider::new(&mut state.options.audio.sfx_volume, 0.0..=1.0)
                                             .custom_formatter(|n, _| {
@@ -474,10 +1092,32 @@ pub fn options_menu(state: &mut GameState) {
                                                 };
                                                 format!("{}%", (n * 100.0) as usize)
                                             }),
-                                    )
+                                    );
+                                    reset_button(state, ui, "SFX Volume");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("UI Volume: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "UI Volume",
+                                    );
+                                    ui.add(
+                                        Slider::new(&mut state.options.audio.ui_volume, 0.0..=1.0)
+                                            .custom_formatter(|n, _| {
+                                                if n == 0.0 {
+                                                    return "Muted".to_string();
+                                                };
+                                                format!("{}%", (n * 100.0) as usize)
+                                            }),
+                                    );
+                                    reset_button(state, ui, "UI Volume");
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Music Volume: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Music Volume: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Music Volume",
+                                    );
                                     ui.add(
                                         Slider::new(
                                             &mut state.options.audio.music_volume,
@@ -491,22 +1131,89 @@ pub fn options_menu(state: &mut GameState) {
                                                 format!("{}%", (n * 100.0) as usize)
                                             },
                                         ),
-                                    )
+                                    );
+                                    reset_button(state, ui, "Music Volume");
                                 });
                             });
                         }
                         OptionsMenuState::Gui => {
                             ui.vertical(|ui| {
-                                ui.label(RichText::new("GUI").text_style(TextStyle::Heading));
+                                page_heading(state, ui, OptionsMenuState::Gui);
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Font Scale: "));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Language: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Language",
+                                    );
+                                    let current = state.options.gui.language.clone();
+
+                                    ComboBox::from_id_source("language")
+                                        .width(175.0)
+                                        .selected_text(
+                                            state
+                                                .resource_man
+                                                .languages
+                                                .get(&current)
+                                                .map_or(current.as_str(), |v| v.name.as_str()),
+                                        )
+                                        .show_ui(ui, |ui| {
+                                            for (code, language) in &state.resource_man.languages {
+                                                ui.selectable_value(
+                                                    &mut state.options.gui.language,
+                                                    code.to_string(),
+                                                    language.name.as_str(),
+                                                )
+                                                .on_hover_text(format!(
+                                                    "{code} ({})",
+                                                    language.namespaces.join(", ")
+                                                ));
+                                            }
+                                        });
+                                    reset_button(state, ui, "Language");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Font Scale: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Font Scale",
+                                    );
                                     ui.add(
                                         Slider::new(&mut state.options.gui.scale, 0.5..=4.0)
                                             .step_by(0.25),
-                                    )
+                                    );
+                                    reset_button(state, ui, "Font Scale");
                                 });
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new("Font:"));
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Cursor Size: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Cursor Size",
+                                    );
+                                    ui.add(
+                                        Slider::new(&mut state.options.gui.cursor_scale, 1.0..=4.0)
+                                            .step_by(0.5),
+                                    );
+                                    reset_button(state, ui, "Cursor Size");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Button Repeat Rate: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Button Repeat Rate",
+                                    );
+                                    ui.add(
+                                        Slider::new(&mut state.options.gui.repeat_rate, 2.0..=30.0)
+                                            .step_by(1.0)
+                                            .suffix("/s"),
+                                    );
+                                    reset_button(state, ui, "Button Repeat Rate");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Font: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Font",
+                                    );
                                     let current_font = state.options.gui.font.clone();
 
                                     ComboBox::from_label("")
@@ -515,47 +1222,441 @@ pub fn options_menu(state: &mut GameState) {
                                             &state.resource_man.fonts[&current_font].name,
                                         )
                                         .show_ui(ui, |ui| {
-                                            for (key, font) in &state.resource_man.fonts {
+                                            // fonts missing letters of the language are left out,
+                                            // unless they are the one in use
+                                            for (key, font) in
+                                                state.resource_man.fonts.iter().filter(
+                                                    |(key, font)| {
+                                                        font.covers_language.load(Ordering::Relaxed)
+                                                            || **key == current_font
+                                                    },
+                                                )
+                                            {
                                                 ui.selectable_value(
                                                     &mut state.options.gui.font,
                                                     key.to_string(),
                                                     font.name.clone(),
                                                 )
-                                                .on_hover_text(key.to_string());
+                                                .on_hover_text(font_details(key, font));
                                             }
                                         });
+                                    reset_button(state, ui, "Font");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.label(RichText::new("Text Size: ")),
+                                        &mut state.gui_state.options_jump,
+                                        "Text Size",
+                                    );
+                                    ui.add(
+                                        Slider::new(&mut state.options.gui.text_size, 9.0..=24.0)
+                                            .step_by(1.0),
+                                    );
+                                    reset_button(state, ui, "Text Size");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.add(Checkbox::new(
+                                            &mut state.options.gui.sharp_text,
+                                            "Sharp text",
+                                        ))
+                                        .on_hover_text(
+                                            "Snaps text to whole pixels, for screens scaled by fractions",
+                                        ),
+                                        &mut state.gui_state.options_jump,
+                                        "Sharp text",
+                                    );
+                                    reset_button(state, ui, "Sharp text");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.add(Checkbox::new(
+                                            &mut state.options.gui.captions,
+                                            "Show captions for important sounds",
+                                        )),
+                                        &mut state.gui_state.options_jump,
+                                        "Show captions for important sounds",
+                                    );
+                                    reset_button(state, ui, "Show captions for important sounds");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.add(Checkbox::new(
+                                            &mut state.options.gui.tile_suggestions,
+                                            "Suggest tiles next to machines",
+                                        )),
+                                        &mut state.gui_state.options_jump,
+                                        "Suggest tiles next to machines",
+                                    );
+                                    reset_button(state, ui, "Suggest tiles next to machines");
+                                });
+                                ui.horizontal(|ui| {
+                                    mark_jump_target(
+                                        &ui.add(Checkbox::new(
+                                            &mut state.options.gui.show_zones,
+                                            "Show zones on the map",
+                                        )),
+                                        &mut state.gui_state.options_jump,
+                                        "Show zones on the map",
+                                    );
+                                    reset_button(state, ui, "Show zones on the map");
                                 });
                             });
                         }
                         OptionsMenuState::Controls => {
-                            ui.label(RichText::new("Controls").text_style(TextStyle::Heading));
+                            page_heading(state, ui, OptionsMenuState::Controls);
+                            controls_options(state, ui);
                         }
                     }
                 }
             });
         });
 
+        ui.horizontal(|ui| {
+            if ui
+                .add(
+                    Button::new(RichText::new(
+                        state.resource_man.translates().gui
+                            [&state.resource_man.registry.gui_ids.btn_confirm]
+                            .as_str(),
+                    ))
+                    .min_size(vec2(80.0, 24.0)),
+                )
+                .clicked()
+            {
+                if let Some(on_open) = &state.gui_state.options_on_open {
+                    if RISKY_OPTIONS
+                        .iter()
+                        .any(|name| !state.options.is_same(on_open, name))
+                    {
+                        state.gui_state.risky_revert = Some((Instant::now(), on_open.clone()));
+                        state.gui_state.popup = PopupState::KeepSettings;
+                    }
+                }
+
+                save_options(state);
+                state.gui_state.text_field.take(TextField::OptionsSearch);
+                state.gui_state.options_jump = None;
+                state.gui_state.rebinding = None;
+                state.gui_state.options_on_open = None;
+                state.input_handler.stop_capture();
+                state.gui_state.return_screen();
+            }
+
+            let changed = state
+                .gui_state
+                .options_on_open
+                .as_ref()
+                .is_some_and(|v| state.options.differs_from(v));
+
+            if ui
+                .add_enabled(
+                    changed,
+                    Button::new("Undo changes").min_size(vec2(80.0, 24.0)),
+                )
+                .on_hover_text("Puts every setting back to how it was when the options were opened")
+                .clicked()
+            {
+                if let Some(on_open) = state.gui_state.options_on_open.clone() {
+                    state.options.restore(&on_open);
+                    sync_bindings(state);
+                }
+            }
+        });
+    });
+}
+
+/// Saves the options, which also applies them.
+pub fn save_options(state: &mut GameState) {
+    if state.options.save().is_err() {
+        state.resource_man.error_man.push(
+            (
+                state.resource_man.registry.err_ids.unwritable_options,
+                vec![],
+            ),
+            &state.resource_man,
+        );
+    }
+}
+
+/// A button resetting the setting to its default, which is only enabled when it isn't at its default already.
+/// Gives back whether it was clicked.
+fn reset_button(state: &mut GameState, ui: &mut Ui, name: &str) -> bool {
+    let clicked = ui
+        .add_enabled(
+            !state.options.is_default(name),
+            Button::new("Reset").small(),
+        )
+        .on_hover_text("Reset to default")
+        .clicked();
+
+    if clicked {
+        state.options.reset(name);
+    }
+
+    clicked
+}
+
+/// The heading of the page, along with a button resetting every setting on it to its default.
+fn page_heading(state: &mut GameState, ui: &mut Ui, page: OptionsMenuState) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(page.name()).text_style(TextStyle::Heading));
+
+        let names = SEARCHABLE_OPTIONS
+            .iter()
+            .filter(|(v, _)| *v == page)
+            .map(|(_, name)| *name);
+
+        let all_default = names.clone().all(|name| state.options.is_default(name));
+
         if ui
-            .add(
-                Button::new(RichText::new(
-                    state.resource_man.translates.gui
-                        [&state.resource_man.registry.gui_ids.btn_confirm]
-                        .as_str(),
-                ))
-                .min_size(vec2(80.0, 24.0)),
-            )
+            .add_enabled(!all_default, Button::new("Reset page").small())
             .clicked()
         {
-            if state.options.save().is_err() {
-                state.resource_man.error_man.push(
-                    (
-                        state.resource_man.registry.err_ids.unwritable_options,
-                        vec![],
-                    ),
-                    &state.resource_man,
-                );
+            for name in names {
+                state.options.reset(name);
+            }
+            sync_bindings(state);
+        }
+    });
+}
+
+/// Hands the key bindings in the options to the input handler, after they are changed in the options.
+fn sync_bindings(state: &mut GameState) {
+    state.gui_state.rebinding = None;
+    state.input_handler.stop_capture();
+    state.input_handler.key_map = state.options.keymap.clone();
+    state.input_handler.mouse_map = state.options.mouse_map.clone();
+}
+
+/// Lists the key bindings by category. Clicking one waits for a key or mouse button to be pressed, and binds the action to it.
+fn controls_options(state: &mut GameState, ui: &mut Ui) {
+    if let Some(Rebinding::Waiting(action)) = state.gui_state.rebinding {
+        match state.input_handler.take_captured() {
+            Some(binding) => {
+                state.gui_state.rebinding = match state.input_handler.bound_action(&binding) {
+                    Some(other) if other.action != action => Some(Rebinding::Conflict {
+                        action,
+                        binding,
+                        other: other.action,
+                    }),
+                    _ => {
+                        rebind(state, action, binding);
+
+                        None
+                    }
+                };
+            }
+            // escape was pressed, which cancels binding
+            None if !state.input_handler.capturing() => {
+                state.gui_state.rebinding = None;
             }
-            state.gui_state.return_screen();
+            None => {}
+        }
+    }
+
+    ui.horizontal(|ui| {
+        mark_jump_target(
+            &ui.label("Click an action, then press the key or mouse button to bind it to, or escape to cancel."),
+            &mut state.gui_state.options_jump,
+            "Key bindings",
+        );
+        if reset_button(state, ui, "Key bindings") {
+            sync_bindings(state);
         }
     });
+
+    if let Some(Rebinding::Conflict {
+        action,
+        binding,
+        other,
+    }) = state.gui_state.rebinding.clone()
+    {
+        ui.colored_label(
+            colors::RED,
+            format!(
+                "{} is already bound to {}. Swap it with {}?",
+                binding_name(&binding),
+                action_name(state, other),
+                action_name(state, action),
+            ),
+        );
+        ui.horizontal(|ui| {
+            if ui.button("Swap").clicked() {
+                rebind(state, action, binding);
+                state.gui_state.rebinding = None;
+            }
+            if ui.button("Cancel").clicked() {
+                state.gui_state.rebinding = None;
+            }
+        });
+    }
+
+    let control = state.resource_man.translates().gui
+        [&state.resource_man.registry.gui_ids.key_control]
+        .to_string();
+
+    for category in [
+        KeyCategory::Interface,
+        KeyCategory::Editing,
+        KeyCategory::Window,
+    ] {
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new(
+                state.resource_man.translates().gui[&category_name(state, category)].as_str(),
+            )
+            .strong(),
+        );
+
+        Grid::new(("controls", category))
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for action in KeyActions::all().filter(|v| v.category() == category) {
+                    ui.label(action_name(state, action));
+
+                    let waiting = matches!(
+                        state.gui_state.rebinding,
+                        Some(Rebinding::Waiting(v)) if v == action
+                    );
+                    let text = if waiting {
+                        "Press a key...".to_string()
+                    } else {
+                        let name = state
+                            .input_handler
+                            .binding_for(action)
+                            .as_ref()
+                            .map(binding_name)
+                            .unwrap_or_else(|| "?".to_string());
+
+                        // these only work together with control
+                        if action.needs_control() {
+                            format!("{control}+{name}")
+                        } else {
+                            name
+                        }
+                    };
+
+                    if ui
+                        .add(
+                            Button::new(RichText::new(text).monospace())
+                                .selected(waiting)
+                                .min_size(vec2(120.0, 0.0)),
+                        )
+                        .clicked()
+                    {
+                        if waiting {
+                            state.gui_state.rebinding = None;
+                            state.input_handler.stop_capture();
+                        } else {
+                            state.gui_state.rebinding = Some(Rebinding::Waiting(action));
+                            state.input_handler.start_capture();
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Binds the action to the key or mouse button, and keeps the bindings in the options so they are saved.
+fn rebind(state: &mut GameState, action: KeyActions, binding: Binding) {
+    state.input_handler.rebind(action, binding);
+
+    state.options.keymap = state.input_handler.key_map.clone();
+    state.options.mouse_map = state.input_handler.mouse_map.clone();
+}
+
+/// Every setting in the options menu, along with the page it is on, so they can be searched for.
+/// The names are the ones the settings are labelled with.
+const SEARCHABLE_OPTIONS: &[(OptionsMenuState, &str)] = &[
+    (OptionsMenuState::Graphics, "Max FPS"),
+    (OptionsMenuState::Graphics, "Vsync"),
+    (OptionsMenuState::Graphics, "Quality"),
+    (OptionsMenuState::Graphics, "Lower when slow"),
+    (OptionsMenuState::Graphics, "Battery saver"),
+    (OptionsMenuState::Graphics, "Bloom"),
+    (OptionsMenuState::Graphics, "Tile outlines"),
+    (OptionsMenuState::Graphics, "Fullscreen"),
+    (OptionsMenuState::Graphics, "Scale"),
+    (OptionsMenuState::Graphics, "Screenshots"),
+    (OptionsMenuState::Graphics, "Screenshot folder"),
+    (OptionsMenuState::Graphics, "Antialiasing"),
+    (OptionsMenuState::Audio, "Output Device"),
+    (OptionsMenuState::Audio, "SFX Volume"),
+    (OptionsMenuState::Audio, "UI Volume"),
+    (OptionsMenuState::Audio, "Music Volume"),
+    (OptionsMenuState::Gui, "Language"),
+    (OptionsMenuState::Gui, "Font Scale"),
+    (OptionsMenuState::Gui, "Font"),
+    (OptionsMenuState::Gui, "Text Size"),
+    (OptionsMenuState::Gui, "Sharp text"),
+    (OptionsMenuState::Gui, "Cursor Size"),
+    (OptionsMenuState::Gui, "Button Repeat Rate"),
+    (OptionsMenuState::Gui, "Show captions for important sounds"),
+    (OptionsMenuState::Gui, "Suggest tiles next to machines"),
+    (OptionsMenuState::Gui, "Show zones on the map"),
+    (OptionsMenuState::Controls, "Key bindings"),
+];
+
+/// Describes a font for the font picker: its file, where it's from, its license and what scripts it's made for.
+fn font_details(key: &str, font: &Font) -> String {
+    let mut details = format!("{key} ({})", font.namespace);
+
+    if let Some(license) = &font.license {
+        details.push_str(&format!("\nLicense: {license}"));
+    }
+    if !font.scripts.is_empty() {
+        details.push_str(&format!("\nFor: {}", font.scripts.join(", ")));
+    }
+
+    details
+}
+
+/// Lists the settings matching the search on any page. Clicking one goes to its page and points it out.
+fn options_search_results(state: &mut GameState, ui: &mut Ui, search: &str) {
+    let mut found = false;
+
+    for (page, name) in SEARCHABLE_OPTIONS
+        .iter()
+        .filter(|(_, name)| name.to_lowercase().contains(search))
+    {
+        found = true;
+
+        if ui
+            .add(Button::new(format!("{name} ({})", page.name())).frame(false))
+            .clicked()
+        {
+            state.gui_state.substate = SubState::Options(*page);
+            state.gui_state.options_jump = Some((*name, true));
+            state.gui_state.text_field.take(TextField::OptionsSearch);
+        }
+    }
+
+    if !found {
+        ui.label(RichText::new("No options found").color(colors::GRAY));
+    }
+}
+
+/// Outlines the setting if it was jumped to from the options search, scrolling to it the first time it is drawn.
+fn mark_jump_target(response: &Response, jump: &mut Option<(&'static str, bool)>, name: &str) {
+    let Some((target, scroll)) = jump else {
+        return;
+    };
+
+    if *target != name {
+        return;
+    }
+
+    if *scroll {
+        response.scroll_to_me(Some(Align::Center));
+        *scroll = false;
+    }
+
+    response.ctx.layer_painter(response.layer_id).rect_stroke(
+        response.rect.expand(2.0),
+        Rounding::same(2.0),
+        Stroke::new(1.0, Color32::from(colors::ORANGE)),
+    );
 }
@@ -1,15 +1,20 @@
+use std::collections::VecDeque;
 use std::mem;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
+use egui::style::Spacing;
 use egui::{
-    CursorIcon, LayerId, PaintCallbackInfo, Rect, ScrollArea, TextEdit, Ui, Widget, WidgetText,
+    vec2, Align2, Area, CursorIcon, PaintCallbackInfo, Rect, RichText, ScrollArea, TextEdit, Ui,
+    Widget, WidgetText,
 };
 use egui_wgpu::{CallbackResources, CallbackTrait, ScreenDescriptor};
 use enum_map::{enum_map, Enum, EnumMap};
 use fuse_rust::Fuse;
 use hashbrown::{HashMap, HashSet};
 use lazy_static::lazy_static;
+use ractor::rpc::CallResult;
 use tokio::sync::oneshot;
 use wgpu::util::DrawIndexedIndirectArgs;
 use wgpu::{CommandBuffer, CommandEncoder, Device, IndexFormat, Queue, RenderPass};
@@ -19,28 +24,59 @@ use automancy_defs::colors::ColorAdj;
 use automancy_defs::coord::TileCoord;
 use automancy_defs::glam::{dvec2, dvec3, vec3};
 use automancy_defs::id::Id;
-use automancy_defs::math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT};
+use automancy_defs::math::{Float, Matrix4, FAR};
 use automancy_defs::rendering::{make_line, InstanceData};
 use automancy_defs::{bytemuck, colors, math, window};
 use automancy_resources::data::item::Item;
-use automancy_resources::data::Data;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::error::GameError;
 use automancy_resources::ResourceManager;
 
-use crate::game::TAKE_ITEM_ANIMATION_SPEED;
-use crate::gpu::{AnimationMap, GlobalBuffers, GuiResources};
-use crate::input::KeyActions;
-use crate::renderer::try_add_animation;
+use crate::clock::Clock;
+use crate::game::GameSystemMessage;
+use crate::gpu::{AnimationCache, GlobalBuffers, GuiResources};
+use crate::gui::animation::{AnimationKey, UiAnimations};
+use crate::gui::route::Routing;
+use crate::input::{Binding, KeyActions};
+use crate::map::LOCAL_PLAYER;
+use crate::options::Options;
+use crate::replay::RecordedInput;
+use crate::scenario::ScenarioOutcome;
+use crate::suggestion::Suggestion;
+use crate::zone::ZoneStats;
 use crate::{gpu, GameState};
 
+pub mod animation;
+pub mod blueprint;
+pub mod caption;
+pub mod cheatsheet;
+pub mod cursor;
 pub mod debug;
+pub mod editor;
 pub mod error;
+pub mod hint;
+pub mod hotbar;
 pub mod info;
+pub mod inspector;
 pub mod item;
+pub mod marker;
 pub mod menu;
+pub mod pinned;
+pub mod placement;
 pub mod player;
 pub mod popup;
+pub mod poster;
+pub mod recipes;
+pub mod repeat;
+pub mod route;
+pub mod sound;
+pub mod statistics;
+pub mod suggestion;
 pub mod tile_config;
 pub mod tile_selection;
+pub mod toast;
+pub mod zone;
 
 pub const SMALL_ICON_SIZE: Float = 24.0;
 pub const SMALLISH_ICON_SIZE: Float = 36.0;
@@ -54,10 +90,18 @@ pub struct GuiState {
     pub popup: PopupState,
 
     pub debugger_open: bool,
+    /// whether the recipe browser is open, and the item it shows the recipes of, if not all of them
+    pub recipes_open: bool,
+    pub recipe_item: Option<Id>,
+    /// whether the layout inspector is on, and the last widget clicked while it was
+    pub layout_inspector: bool,
+    pub inspected_widget: Option<String>,
 
     pub text_field: TextFieldState,
 
     pub renaming_map: String,
+    /// how the maps in the map menu are sorted
+    pub map_sort: MapSortOrder,
 
     pub tile_selection_category: Option<Id>,
 
@@ -82,6 +126,92 @@ pub struct GuiState {
     pub selected_research: Option<Id>,
     pub selected_research_puzzle_tile: Option<TileCoord>,
     pub research_puzzle_selections: Option<(TileCoord, Vec<Id>)>,
+    pub research_puzzle_progress: ResearchPuzzleProgress,
+
+    /// the radius of the map editor's placement brush
+    pub editor_brush_size: u32,
+    /// the tile that has its data open in the map editor
+    pub editing_tile_data: Option<TileCoord>,
+
+    /// the template the map in the map creation popup will be copied from
+    pub selected_map_template: Option<PathBuf>,
+    /// whether the new map has survival on
+    pub new_map_survival: bool,
+    /// the items the player picked from their inventory to drop on the ground
+    pub dropping_items: Option<ItemStack>,
+
+    /// what the routing tool is waiting for, if it's in use
+    pub routing: Option<Routing>,
+    /// the last route found by the routing tool, from and to where, or None if there was no route
+    pub route_preview: Option<(TileCoord, TileCoord, Option<Vec<TileCoord>>)>,
+
+    /// the tiles of the map or blueprint picked in the import region popup, to pick a region of
+    pub import_source: Option<Vec<(TileCoord, Id, DataMap)>>,
+    /// the center and radius of the region picked from `import_source`
+    pub import_region: (TileCoord, u32),
+    /// the tiles being imported from another map, relative to the cursor
+    pub importing_region: Option<Vec<(TileCoord, Id, DataMap)>>,
+    /// the tiles the import would replace, at the offset they were checked at
+    pub importing_conflicts: Option<(TileCoord, Vec<TileCoord>)>,
+
+    /// the widget the pointer is over this frame, and the last frame, for the hover sound
+    pub hovered_widget: Option<egui::Id>,
+    pub last_hovered_widget: Option<egui::Id>,
+    /// the popup and the error shown last frame, to play a sound when new ones show up
+    pub last_popup: PopupState,
+    pub last_error: Option<GameError>,
+
+    /// the captions being shown, and when they were added
+    pub captions: VecDeque<(Instant, String)>,
+    /// the toasts being shown, and when they were added
+    pub toasts: VecDeque<(Instant, String)>,
+    /// the animations being played on the GUI
+    pub animations: UiAnimations,
+
+    /// the map image being exported in the background, if any
+    pub poster_export: Option<Arc<Mutex<poster::PosterExport>>>,
+
+    /// the tiles suggested for the position they were found for, best first
+    pub suggestions: Option<(TileCoord, Vec<Suggestion>)>,
+
+    /// the index of the zone being painted, if any
+    pub zoning: Option<usize>,
+
+    /// the zone the statistics are shown for, or None for the whole map
+    pub statistics_zone: Option<usize>,
+    /// the statistics last shown, which zone they are for, and when they were fetched
+    pub statistics: Option<(Option<usize>, Instant, ZoneStats)>,
+    /// when the zones with alerts on were last checked, and how many machines were stalled in each
+    pub last_zone_alert_check: Option<Instant>,
+    pub zone_stalled: HashMap<usize, usize>,
+
+    /// the setting jumped to from the options search, and whether it still needs to be scrolled to
+    pub options_jump: Option<(&'static str, bool)>,
+    /// whether the selected tile can't be placed where the player is pointing
+    pub placement_blocked: bool,
+    /// the cursor last shown, and whether it is drawn rather than left to the system
+    pub cursor: Option<(CursorIcon, bool)>,
+    /// the spacing the GUI had before it was made bigger for touch, while it is
+    pub spacing_before_touch: Option<Spacing>,
+    /// the action being bound on the controls page, if any
+    pub rebinding: Option<Rebinding>,
+    /// the options as they were when the options menu was opened, for undoing the changes made since
+    pub options_on_open: Option<Options>,
+    /// when risky settings were last changed, and the options to revert them to if they aren't kept in time
+    pub risky_revert: Option<(Instant, Options)>,
+}
+
+/// Tracks the player's interactions with the currently open research puzzle.
+#[derive(Default)]
+pub struct ResearchPuzzleProgress {
+    /// the previous states of the puzzle tiles, for undoing
+    pub history: Vec<HashMap<TileCoord, Id>>,
+    /// how many placements did not solve the puzzle
+    pub failed_attempts: u32,
+    /// whether the puzzle was changed by a placement since it was last evaluated
+    pub attempted: bool,
+    /// the tile the hint is pointing at
+    pub hint: Option<TileCoord>,
 }
 
 impl GuiState {
@@ -92,6 +222,10 @@ impl GuiState {
             substate: SubState::None,
             popup: PopupState::None,
             debugger_open: false,
+            recipes_open: false,
+            recipe_item: None,
+            layout_inspector: false,
+            inspected_widget: None,
             text_field: Default::default(),
             renaming_map: "".to_string(),
             tile_selection_category: None,
@@ -106,6 +240,40 @@ impl GuiState {
             selected_research: None,
             selected_research_puzzle_tile: None,
             research_puzzle_selections: None,
+            research_puzzle_progress: Default::default(),
+            editor_brush_size: 0,
+            editing_tile_data: None,
+            selected_map_template: None,
+            new_map_survival: false,
+            map_sort: MapSortOrder::LastPlayed,
+            dropping_items: None,
+            routing: None,
+            route_preview: None,
+            import_source: None,
+            import_region: (TileCoord::ZERO, 0),
+            importing_region: None,
+            importing_conflicts: None,
+            hovered_widget: None,
+            last_hovered_widget: None,
+            last_popup: PopupState::None,
+            last_error: None,
+            captions: Default::default(),
+            toasts: Default::default(),
+            animations: Default::default(),
+            poster_export: None,
+            suggestions: None,
+            zoning: None,
+            statistics_zone: None,
+            statistics: None,
+            last_zone_alert_check: None,
+            zone_stalled: Default::default(),
+            options_jump: None,
+            placement_blocked: false,
+            cursor: None,
+            spacing_before_touch: None,
+            rebinding: None,
+            options_on_open: None,
+            risky_revert: None,
         }
     }
 }
@@ -118,6 +286,17 @@ pub enum Screen {
     Options,
     Ingame,
     Paused,
+    ScenarioEnd(ScenarioOutcome),
+}
+
+/// How the maps in the map menu are sorted.
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub enum MapSortOrder {
+    Name,
+    /// The most recently played first.
+    LastPlayed,
+    /// The most played first.
+    Playtime,
 }
 
 #[derive(Eq, PartialEq, Copy, Clone)]
@@ -134,6 +313,32 @@ pub enum OptionsMenuState {
     Controls,
 }
 
+impl OptionsMenuState {
+    /// The name of the page, as shown on its button.
+    pub fn name(self) -> &'static str {
+        match self {
+            OptionsMenuState::Graphics => "Graphics",
+            OptionsMenuState::Audio => "Audio",
+            OptionsMenuState::Gui => "GUI",
+            OptionsMenuState::Controls => "Controls",
+        }
+    }
+}
+
+/// An action being bound to another key or mouse button on the controls page.
+#[derive(Debug, Clone)]
+pub enum Rebinding {
+    /// Waiting for the key or mouse button to be pressed.
+    Waiting(KeyActions),
+    /// The key or mouse button pressed is already bound to another action,
+    /// so the player is asked whether to swap the two.
+    Conflict {
+        action: KeyActions,
+        binding: Binding,
+        other: KeyActions,
+    },
+}
+
 /// The state of popups (which are on top of the main GUI), if any should be displayed.
 #[derive(Eq, PartialEq, Clone)]
 pub enum PopupState {
@@ -141,9 +346,21 @@ pub enum PopupState {
     MapCreate,
     MapDeleteConfirmation(String),
     InvalidName,
+    ImportRegion,
+    MapImport,
+    MapExported(PathBuf),
+    RestoreBackup(String),
+    /// Asks whether to keep the risky settings just applied, reverting them if there is no answer in time.
+    KeepSettings,
 }
 
 impl GuiState {
+    /// Whether the game is paused, which it stays while going through the options from the pause menu.
+    pub fn is_paused(&self) -> bool {
+        self.screen == Screen::Paused
+            || (self.screen == Screen::Options && self.previous == Some(Screen::Paused))
+    }
+
     pub fn return_screen(&mut self) {
         if let Some(prev) = self.previous {
             self.screen = prev;
@@ -181,6 +398,9 @@ pub enum TextField {
     Filter,
     MapRenaming,
     MapName,
+    TileData,
+    OptionsSearch,
+    RecipeSearch,
 }
 
 pub struct TextFieldState {
@@ -195,7 +415,10 @@ impl Default for TextFieldState {
             fields: enum_map! {
                 TextField::Filter => Default::default(),
                 TextField::MapName => Default::default(),
-                TextField::MapRenaming => Default::default()
+                TextField::MapRenaming => Default::default(),
+                TextField::TileData => Default::default(),
+                TextField::OptionsSearch => Default::default(),
+                TextField::RecipeSearch => Default::default()
             },
         }
     }
@@ -211,6 +434,24 @@ impl TextFieldState {
     }
 }
 
+/// Shows an icon in the bottom left corner of the screen while power is being saved.
+fn power_saving_icon(state: &GameState) {
+    if !state.loop_store.power.saving() {
+        return;
+    }
+
+    Area::new("power_saving".into())
+        .anchor(Align2::LEFT_BOTTOM, vec2(10.0, -10.0))
+        .show(&state.gui.context, |ui| {
+            ui.label(RichText::new("\u{f243}").size(20.0))
+                .on_hover_text(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.lbl_battery_saver]
+                        .as_str(),
+                );
+        });
+}
+
 pub fn hover_tip(ui: &mut Ui, info: impl Into<WidgetText>) {
     ui.label("\u{f449}")
         .on_hover_cursor(CursorIcon::Help)
@@ -219,53 +460,27 @@ pub fn hover_tip(ui: &mut Ui, info: impl Into<WidgetText>) {
         });
 }
 
-fn take_item_animation(state: &mut GameState, ui: &mut Ui, item: Item, dst_rect: Rect) {
-    let now = Instant::now();
-
-    let mut to_remove = HashMap::new();
-
-    for (coord, deque) in &state.renderer.take_item_animations {
-        to_remove.insert(
-            *coord,
-            deque
-                .iter()
-                .take_while(|(instant, _)| {
-                    now.duration_since(*instant) >= TAKE_ITEM_ANIMATION_SPEED
-                })
-                .count(),
-        );
-    }
-
-    for (coord, v) in to_remove {
-        for _ in 0..v {
-            state
-                .renderer
-                .take_item_animations
-                .get_mut(&coord)
-                .unwrap()
-                .pop_front();
-        }
-    }
+/// Draws the items taken into the inventory flying to where the inventory shows them.
+fn take_item_animation(state: &GameState, ui: &mut Ui, item: Item, dst_rect: Rect) {
+    let now = state.clocks.ui.now();
 
-    if let Some(animations) = state.renderer.take_item_animations.get(&item) {
-        for (instant, src_rect) in animations {
-            let d = now.duration_since(*instant).as_secs_f32()
-                / TAKE_ITEM_ANIMATION_SPEED.as_secs_f32();
-            let rect = src_rect.lerp_towards(&dst_rect, d);
+    for animation in state.gui_state.animations.get(AnimationKey::TakeItem(item)) {
+        let Some(rect) = animation.fly_to(now, dst_rect) else {
+            continue;
+        };
 
-            ui.ctx()
-                .layer_painter(ui.layer_id())
-                .add(egui_wgpu::Callback::new_paint_callback(
+        ui.ctx()
+            .layer_painter(ui.layer_id())
+            .add(egui_wgpu::Callback::new_paint_callback(
+                rect,
+                GameEguiCallback::new(
+                    InstanceData::default()
+                        .with_world_matrix(math::view(dvec3(0.0, 0.0, 1.0)).as_mat4()),
+                    state.resource_man.get_item_model(item.model),
                     rect,
-                    GameEguiCallback::new(
-                        InstanceData::default()
-                            .with_world_matrix(math::view(dvec3(0.0, 0.0, 1.0)).as_mat4()),
-                        state.resource_man.get_item_model(item.model),
-                        rect,
-                        ui.ctx().screen_rect(),
-                    ),
-                ));
-        }
+                    ui.ctx().screen_rect(),
+                ),
+            ));
     }
 }
 
@@ -378,10 +593,16 @@ impl CallbackTrait for GameEguiCallback {
             .get::<Arc<ResourceManager>>()
             .unwrap()
             .clone();
-        let start_instant = *callback_resources.get::<Instant>().unwrap();
-        let animation_map = callback_resources.get_mut::<AnimationMap>().unwrap();
+        let elapsed = callback_resources
+            .get::<Clock>()
+            .unwrap()
+            .now()
+            .as_secs_f32();
 
-        try_add_animation(&resource_man, start_instant, self.model, animation_map);
+        callback_resources
+            .get_mut::<AnimationCache>()
+            .unwrap()
+            .track(&resource_man, self.model, elapsed);
 
         callback_resources
             .entry::<Vec<(InstanceData, Id, usize)>>()
@@ -394,8 +615,8 @@ impl CallbackTrait for GameEguiCallback {
     fn finish_prepare(
         &self,
         device: &Device,
-        queue: &Queue,
-        _egui_encoder: &mut CommandEncoder,
+        _queue: &Queue,
+        egui_encoder: &mut CommandEncoder,
         callback_resources: &mut CallbackResources,
     ) -> Vec<CommandBuffer> {
         if let Some(mut instances) = callback_resources.remove::<Vec<(InstanceData, Id, usize)>>() {
@@ -406,25 +627,31 @@ impl CallbackTrait for GameEguiCallback {
                 .unwrap()
                 .clone();
 
-            let animation_map = callback_resources.get::<AnimationMap>().unwrap();
+            let animation_map = &callback_resources.get::<AnimationCache>().unwrap().matrices;
 
-            let (instances, draws, _count, matrix_data) =
-                gpu::indirect_instance(&resource_man, &instances, false, animation_map);
+            let (instances, draws, _count, matrices) =
+                gpu::indirect_instance(&resource_man, &instances, false, animation_map, None);
 
             {
                 let gui_resources = callback_resources.get_mut::<GuiResources>().unwrap();
 
-                gpu::create_or_write_buffer(
+                gui_resources.uploader.upload(
                     device,
-                    queue,
+                    egui_encoder,
                     &mut gui_resources.instance_buffer,
                     bytemuck::cast_slice(instances.as_slice()),
                 );
-
-                queue.write_buffer(
+                gui_resources.uploader.write(
+                    device,
+                    egui_encoder,
                     &gui_resources.matrix_data_buffer,
-                    0,
-                    bytemuck::cast_slice(matrix_data.as_slice()),
+                    bytemuck::cast_slice(matrices.matrix_data.as_slice()),
+                );
+                gui_resources.uploader.write(
+                    device,
+                    egui_encoder,
+                    &gui_resources.world_matrix_data_buffer,
+                    bytemuck::cast_slice(matrices.world_matrix_data.as_slice()),
                 );
             }
 
@@ -464,47 +691,129 @@ impl CallbackTrait for GameEguiCallback {
     }
 }
 
+/// How much bigger the widgets are made while a touchscreen is being used, so they are easier to hit with a finger.
+const TOUCH_TARGET_SCALE: f32 = 1.75;
+
+/// Makes the widgets bigger while a touchscreen is being used, and puts them back once the mouse is used again.
+fn touch_targets(state: &mut GameState) {
+    let touch = state.input_handler.touch_active;
+
+    if touch == state.gui_state.spacing_before_touch.is_some() {
+        return;
+    }
+
+    if touch {
+        let before = state.gui.context.style().spacing.clone();
+
+        state.gui.context.style_mut(|style| {
+            style.spacing.interact_size *= TOUCH_TARGET_SCALE;
+            style.spacing.button_padding *= TOUCH_TARGET_SCALE;
+            style.spacing.item_spacing *= TOUCH_TARGET_SCALE;
+            style.spacing.icon_width *= TOUCH_TARGET_SCALE;
+            style.spacing.slider_width *= TOUCH_TARGET_SCALE;
+        });
+        state.gui_state.spacing_before_touch = Some(before);
+    } else if let Some(before) = state.gui_state.spacing_before_touch.take() {
+        state.gui.context.style_mut(|style| style.spacing = before);
+    }
+}
+
 pub fn render_ui(
     state: &mut GameState,
     result: &mut anyhow::Result<bool>,
-    target: &EventLoopWindowTarget<()>,
+    target: &EventLoopWindowTarget<RecordedInput>,
 ) {
-    if state.input_handler.key_active(KeyActions::Debug) {
-        #[cfg(debug_assertions)]
-        state.gui.context.set_debug_on_hover(true);
+    touch_targets(state);
 
+    let debugging = state.input_handler.key_active(KeyActions::Debug);
+    if debugging {
         debug::debugger(state);
-    } else {
-        #[cfg(debug_assertions)]
-        state.gui.context.set_debug_on_hover(false);
     }
 
     if state.gui_state.popup == PopupState::None {
         match state.gui_state.screen {
             Screen::Ingame => {
+                let mut placement = None;
+
+                statistics::check_zone_alerts(state);
+
                 if !state.input_handler.key_active(KeyActions::HideGui) {
                     if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone())
                     {
                         let mut lock = map_info.blocking_lock();
-                        let game_data = &mut lock.data;
+
+                        if let Some(scenario) = &mut lock.scenario {
+                            if let (Some(outcome), false) = (scenario.outcome, scenario.announced) {
+                                scenario.announced = true;
+                                state.gui_state.switch_screen(Screen::ScenarioEnd(outcome));
+                            }
+                        }
+
+                        let editor_mode = lock.editor_mode;
+                        if editor_mode {
+                            editor::editor_ui(state, &mut lock);
+                        }
+
+                        let zones_open = state.input_handler.key_active(KeyActions::Zones);
+                        if zones_open {
+                            zone::zones_ui(state, &mut lock);
+                        } else {
+                            state.gui_state.zoning = None;
+                        }
+                        if zones_open || state.options.gui.show_zones {
+                            zone::zone_overlay(state, &lock);
+                        }
+
+                        if state.input_handler.key_active(KeyActions::Markers) {
+                            marker::markers_ui(state, &mut lock);
+                        }
+                        marker::draw_markers(state, &lock);
+
+                        let survival = lock.survival;
+                        let game_data = lock.player_data_mut(LOCAL_PLAYER);
+
+                        placement = state.gui_state.selected_tile_id.map(|id| {
+                            placement::check_placement(state, game_data, id, editor_mode, survival)
+                        });
+
+                        if placement.is_none() {
+                            suggestion::update_suggestions(state, game_data, editor_mode, survival);
+                        } else {
+                            state.gui_state.suggestions = None;
+                        }
 
                         if state.input_handler.key_active(KeyActions::Player) {
                             player::player(state, game_data);
                         }
+                        recipes::recipes_ui(state);
 
                         // tile_info
                         info::info_ui(state);
 
                         // tile_config
-                        tile_config::tile_config_ui(state, game_data);
+                        tile_config::tile_config_ui(state, game_data, editor_mode);
+
+                        hint::hint_bar(state);
+
+                        power_saving_icon(state);
+
+                        pinned::pinned_ui(state, game_data);
 
                         let (selection_send, selection_recv) = oneshot::channel();
 
                         // tile_selections
-                        tile_selection::tile_selections(state, game_data, selection_send);
+                        tile_selection::tile_selections(
+                            state,
+                            game_data,
+                            editor_mode,
+                            selection_send,
+                        );
 
-                        if let Ok(id) = selection_recv.blocking_recv() {
+                        let hotbar_picked = hotbar::hotbar(state, game_data);
+
+                        if let Some(id) = selection_recv.blocking_recv().ok().or(hotbar_picked) {
                             state.gui_state.already_placed_at = None;
+                            state.gui_state.zoning = None;
 
                             if state.gui_state.selected_tile_id == Some(id) {
                                 state.gui_state.selected_tile_id = None;
@@ -514,47 +823,96 @@ pub fn render_ui(
                         }
                     }
 
+                    // after the map info is unlocked, as the game needs it to count
+                    if state.input_handler.key_active(KeyActions::Statistics) {
+                        statistics::statistics_ui(state);
+                    }
+
                     let cursor_pos = math::screen_to_world(
                         window::window_size_double(&state.renderer.gpu.window),
                         state.input_handler.main_pos,
                         state.camera.get_pos(),
                     );
-                    let cursor_pos = dvec2(cursor_pos.x, cursor_pos.y);
+                    let cursor_pos = state
+                        .camera
+                        .rebase(dvec2(cursor_pos.x, cursor_pos.y))
+                        .as_dvec2();
 
-                    if let Some(tile_def) = state
-                        .gui_state
-                        .selected_tile_id
-                        .and_then(|id| state.resource_man.registry.tiles.get(&id))
-                    {
-                        state.gui.context.layer_painter(LayerId::background()).add(
-                            egui_wgpu::Callback::new_paint_callback(
-                                state.gui.context.screen_rect(),
-                                GameEguiCallback::new(
+                    if let Some(preview) = &placement {
+                        placement::placement_ghost(state, preview);
+                    }
+                    state.gui_state.placement_blocked =
+                        placement.as_ref().is_some_and(|v| !v.valid);
+
+                    suggestion::suggestion_ghost(state);
+
+                    route::route_ghost(state);
+
+                    if let Some(stack) = state.gui_state.dropping_items {
+                        state.renderer.in_world_item_instances.push((
+                            InstanceData::default()
+                                .with_alpha(0.6)
+                                .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
+                                .with_world_matrix(state.camera.get_matrix().as_mat4())
+                                .with_model_matrix(
+                                    Matrix4::from_translation(
+                                        state
+                                            .camera
+                                            .rebase_coord(state.camera.pointing_at)
+                                            .extend(0.05),
+                                    ) * Matrix4::from_scale(vec3(0.4, 0.4, 0.4)),
+                                ),
+                            stack.item.model,
+                        ));
+                    }
+
+                    if let Some(tiles) = &state.gui_state.importing_region {
+                        let offset = state.camera.pointing_at;
+
+                        if state.gui_state.importing_conflicts.as_ref().map(|v| v.0) != Some(offset)
+                        {
+                            let coords = tiles.iter().map(|v| v.0 + offset).collect();
+                            let conflicts =
+                                match state.tokio.block_on(state.game.call(
+                                    |reply| GameSystemMessage::GetOccupied(coords, reply),
+                                    None,
+                                )) {
+                                    Ok(CallResult::Success(conflicts)) => conflicts,
+                                    _ => vec![],
+                                };
+
+                            state.gui_state.importing_conflicts = Some((offset, conflicts));
+                        }
+
+                        for (coord, id, _) in tiles {
+                            if let Some(tile) = state.resource_man.registry.tiles.get(id) {
+                                let pos = state.camera.rebase_coord(*coord + offset);
+
+                                state.renderer.extra_instances.push((
                                     InstanceData::default()
-                                        .with_alpha(0.6)
-                                        .with_light_pos(state.camera.get_pos().as_vec3(), None)
+                                        .with_alpha(0.5)
+                                        .with_light_pos(
+                                            state.camera.get_relative_pos().as_vec3(),
+                                            None,
+                                        )
                                         .with_world_matrix(state.camera.get_matrix().as_mat4())
-                                        .with_model_matrix(Matrix4::from_translation(vec3(
-                                            cursor_pos.x as Float,
-                                            cursor_pos.y as Float,
-                                            FAR as Float,
-                                        ))),
-                                    tile_def.model,
-                                    state.gui.context.screen_rect(),
-                                    state.gui.context.screen_rect(),
-                                ),
-                            ),
-                        );
+                                        .with_model_matrix(Matrix4::from_translation(
+                                            pos.extend(FAR as Float),
+                                        )),
+                                    state.resource_man.get_model(tile.model),
+                                ));
+                            }
+                        }
                     }
 
                     if let Some(coord) = state.gui_state.linking_tile {
                         state.renderer.extra_instances.push((
                             InstanceData::default()
                                 .with_color_offset(colors::RED.to_array())
-                                .with_light_pos(state.camera.get_pos().as_vec3(), None)
+                                .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
                                 .with_world_matrix(state.camera.get_matrix().as_mat4())
                                 .with_model_matrix(make_line(
-                                    HEX_GRID_LAYOUT.hex_to_world_pos(*coord),
+                                    state.camera.rebase_coord(coord),
                                     cursor_pos.as_vec2(),
                                 )),
                             state.resource_man.registry.model_ids.cube1x1,
@@ -569,20 +927,16 @@ pub fn render_ui(
                         if dir != TileCoord::ZERO
                             && !state.resource_man.registry.tiles[&selected_tile_id]
                                 .data
-                                .get(&state.resource_man.registry.data_ids.not_targeted)
-                                .cloned()
-                                .and_then(Data::into_bool)
-                                .unwrap_or(false)
+                                .flag(state.resource_man.registry.data_ids.not_targeted)
                         {
                             state.renderer.extra_instances.push((
                                 InstanceData::default()
                                     .with_color_offset(colors::RED.to_array())
-                                    .with_light_pos(state.camera.get_pos().as_vec3(), None)
+                                    .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
                                     .with_world_matrix(state.camera.get_matrix().as_mat4())
                                     .with_model_matrix(make_line(
-                                        HEX_GRID_LAYOUT.hex_to_world_pos(*state.camera.pointing_at),
-                                        HEX_GRID_LAYOUT
-                                            .hex_to_world_pos(*(state.camera.pointing_at + dir)),
+                                        state.camera.rebase_coord(state.camera.pointing_at),
+                                        state.camera.rebase_coord(state.camera.pointing_at + dir),
                                     )),
                                 state.resource_man.registry.model_ids.cube1x1,
                             ));
@@ -600,6 +954,9 @@ pub fn render_ui(
             Screen::Paused => {
                 menu::pause_menu(state);
             }
+            Screen::ScenarioEnd(outcome) => {
+                menu::scenario_end_menu(state, outcome);
+            }
         }
     }
 
@@ -612,18 +969,53 @@ pub fn render_ui(
         PopupState::InvalidName => {
             popup::invalid_name_popup(state);
         }
+        PopupState::ImportRegion => {
+            popup::import_region_popup(state);
+        }
+        PopupState::MapImport => {
+            popup::map_import_popup(state);
+        }
+        PopupState::MapExported(path) => {
+            popup::map_exported_popup(state, &path);
+        }
+        PopupState::RestoreBackup(map_name) => {
+            popup::restore_backup_popup(state, &map_name);
+        }
+        PopupState::KeepSettings => {
+            popup::keep_settings_popup(state);
+        }
     }
 
-    state
-        .renderer
-        .tile_tints
-        .insert(state.camera.pointing_at, colors::RED.with_alpha(0.2));
+    if state.options.graphics.tile_outlines {
+        state
+            .renderer
+            .tile_outlines
+            .insert(state.camera.pointing_at, colors::RED);
 
-    for coord in &state.gui_state.grouped_tiles {
+        for coord in &state.gui_state.grouped_tiles {
+            state.renderer.tile_outlines.insert(*coord, colors::ORANGE);
+        }
+    } else {
         state
             .renderer
             .tile_tints
-            .insert(*coord, colors::ORANGE.with_alpha(0.4));
+            .insert(state.camera.pointing_at, colors::RED.with_alpha(0.2));
+
+        for coord in &state.gui_state.grouped_tiles {
+            state
+                .renderer
+                .tile_tints
+                .insert(*coord, colors::ORANGE.with_alpha(0.4));
+        }
+    }
+
+    if let Some((_, conflicts)) = &state.gui_state.importing_conflicts {
+        for coord in conflicts {
+            state
+                .renderer
+                .tile_tints
+                .insert(*coord, colors::RED.with_alpha(0.6));
+        }
     }
 
     if state.input_handler.control_held {
@@ -634,11 +1026,11 @@ pub fn render_ui(
                 state.renderer.extra_instances.push((
                     InstanceData::default()
                         .with_color_offset(colors::LIGHT_BLUE.to_array())
-                        .with_light_pos(state.camera.get_pos().as_vec3(), None)
+                        .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
                         .with_world_matrix(state.camera.get_matrix().as_mat4())
                         .with_model_matrix(make_line(
-                            HEX_GRID_LAYOUT.hex_to_world_pos(*start),
-                            HEX_GRID_LAYOUT.hex_to_world_pos(*state.camera.pointing_at),
+                            state.camera.rebase_coord(start),
+                            state.camera.rebase_coord(state.camera.pointing_at),
                         )),
                     state.resource_man.registry.model_ids.cube1x1,
                 ));
@@ -654,5 +1046,26 @@ pub fn render_ui(
         }
     }
 
+    cheatsheet::cheatsheet(state);
+
+    caption::captions(state);
+
+    poster::poster_export_window(state);
+
+    poster::screenshot_notice(state);
+
+    toast::toasts(state);
+
+    let now = state.clocks.ui.now();
+    state.gui_state.animations.update(now);
+    state.gui_state.animations.draw(&state.gui.context, now);
+
     error::error_popup(state);
+
+    sound::frame_sounds(state);
+
+    // debug builds outline the widget under the pointer whenever the debug menu is open
+    inspector::layout_inspector(state, cfg!(debug_assertions) && debugging);
+
+    cursor::cursor(state);
 }
@@ -0,0 +1,166 @@
+use egui::{vec2, Align2, RichText, Ui, Window};
+
+use automancy_defs::colors;
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::{ItemAmount, ItemStack};
+use automancy_resources::data::{Data, DataMap};
+
+use crate::gui::item::draw_item;
+use crate::gui::SMALL_ICON_SIZE;
+use crate::GameState;
+
+/// Gets the IDs pinned under the key in the player's data.
+fn pinned(game_data: &DataMap, key: Id) -> Vec<Id> {
+    game_data
+        .get(&key)
+        .cloned()
+        .and_then(|data| match data {
+            Data::VecId(ids) => Some(ids),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Pins the ID under the key in the player's data, or unpins it if it already is.
+pub fn toggle_pin(game_data: &mut DataMap, key: Id, id: Id) {
+    let mut ids = pinned(game_data, key);
+
+    if let Some(index) = ids.iter().position(|v| *v == id) {
+        ids.remove(index);
+    } else {
+        ids.push(id);
+    }
+
+    game_data.insert(key, Data::VecId(ids));
+}
+
+/// Draws a button that pins or unpins the ID to the HUD.
+pub fn pin_button(state: &GameState, ui: &mut Ui, game_data: &mut DataMap, key: Id, id: Id) {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    let label = if pinned(game_data, key).contains(&id) {
+        gui_ids.btn_unpin
+    } else {
+        gui_ids.btn_pin
+    };
+
+    if ui
+        .small_button(state.resource_man.translates().gui[&label].as_str())
+        .clicked()
+    {
+        toggle_pin(game_data, key, id);
+    }
+}
+
+/// Draws how many of the item the player has, out of how many are needed if that is known.
+fn item_count(state: &GameState, ui: &mut Ui, stack: ItemStack, have: ItemAmount) {
+    ui.horizontal(|ui| {
+        draw_item(&state.resource_man, ui, None, stack, SMALL_ICON_SIZE, false);
+
+        let name = state.resource_man.item_name(&stack.item.id);
+
+        if stack.amount > 0 {
+            let text = RichText::new(format!("{name} {have}/{}", stack.amount));
+
+            ui.label(if have < stack.amount {
+                text.color(colors::RED)
+            } else {
+                text
+            });
+        } else {
+            ui.label(format!("{name} {have}"));
+        }
+    });
+}
+
+/// Draws the pinned recipes and items, with how many of each item the player has.
+pub fn pinned_ui(state: &mut GameState, game_data: &mut DataMap) {
+    let data_ids = state.resource_man.registry.data_ids;
+
+    let scripts = pinned(game_data, data_ids.pinned_scripts);
+    let items = pinned(game_data, data_ids.pinned_items);
+
+    if scripts.is_empty() && items.is_empty() {
+        return;
+    }
+
+    let inventory = match game_data.get(&data_ids.player_inventory) {
+        Some(Data::Inventory(inventory)) => inventory.clone().into_inner(),
+        _ => Default::default(),
+    };
+    let have = |id: &Id| inventory.get(id).copied().unwrap_or(0);
+
+    let mut unpin = None;
+
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.pinned_title]
+            .as_str(),
+    )
+    .id("pinned".into())
+    .resizable(false)
+    .collapsible(true)
+    .auto_sized()
+    .anchor(Align2::LEFT_TOP, vec2(10.0, 10.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.spacing_mut().item_spacing = vec2(4.0, 2.0);
+
+        for id in scripts {
+            let Some(script) = state.resource_man.registry.scripts.get(&id) else {
+                continue;
+            };
+
+            ui.horizontal(|ui| {
+                ui.strong(state.resource_man.script_name(&id));
+
+                if ui.small_button("x").clicked() {
+                    unpin = Some((data_ids.pinned_scripts, id));
+                }
+            });
+
+            for input in script.instructions.inputs.iter().flatten() {
+                item_count(state, ui, *input, have(&input.item.id));
+            }
+            for output in &script.instructions.outputs {
+                ui.horizontal(|ui| {
+                    ui.label("=>");
+                    draw_item(
+                        &state.resource_man,
+                        ui,
+                        None,
+                        *output,
+                        SMALL_ICON_SIZE,
+                        true,
+                    );
+                });
+            }
+
+            ui.separator();
+        }
+
+        for id in items {
+            let Some(item) = state.resource_man.registry.items.get(&id) else {
+                continue;
+            };
+
+            ui.horizontal(|ui| {
+                item_count(
+                    state,
+                    ui,
+                    ItemStack {
+                        item: *item,
+                        amount: 0,
+                    },
+                    have(&id),
+                );
+
+                if ui.small_button("x").clicked() {
+                    unpin = Some((data_ids.pinned_items, id));
+                }
+            });
+        }
+    });
+
+    if let Some((key, id)) = unpin {
+        toggle_pin(game_data, key, id);
+    }
+}
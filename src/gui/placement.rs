@@ -0,0 +1,116 @@
+use std::ops::Deref;
+
+use egui::show_tooltip_at_pointer;
+
+use automancy_defs::colors;
+use automancy_defs::colors::ColorAdj;
+use automancy_defs::id::Id;
+use automancy_defs::math::{Float, Matrix4, FAR};
+use automancy_defs::rendering::InstanceData;
+use automancy_resources::data::stack::ItemAmount;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::format;
+
+use crate::game::try_category;
+use crate::util::is_research_unlocked;
+use crate::GameState;
+
+/// Whether the selected tile can be placed where the player is pointing, and what it would cost.
+#[derive(Debug, Clone)]
+pub struct PlacementPreview {
+    pub id: Id,
+    pub valid: bool,
+    /// the items needed to place the tile, how many of each are needed, and how many the player has
+    pub cost: Vec<(Id, ItemAmount, ItemAmount)>,
+}
+
+/// Checks if the tile can be placed where the player is pointing.
+pub fn check_placement(
+    state: &GameState,
+    game_data: &mut DataMap,
+    id: Id,
+    editor_mode: bool,
+    survival: bool,
+) -> PlacementPreview {
+    let mut valid = state.camera.pointing_at.is_in_world();
+    let mut cost = vec![];
+
+    if !editor_mode {
+        if let Some(research) = state.resource_man.get_research_by_unlock(id) {
+            if !is_research_unlocked(research.id, &state.resource_man, game_data) {
+                valid = false;
+            }
+        }
+
+        try_category(&state.resource_man, id, |item| cost.push((item, 1)));
+
+        if survival {
+            cost.extend(state.resource_man.registry.tiles[&id].cost.iter().cloned());
+        }
+    }
+
+    let cost = cost
+        .into_iter()
+        .map(|(item, amount)| {
+            let have = match game_data.get(&state.resource_man.registry.data_ids.player_inventory) {
+                Some(Data::Inventory(inventory)) => {
+                    inventory.deref().get(&item).copied().unwrap_or(0)
+                }
+                _ => 0,
+            };
+
+            if have < amount {
+                valid = false;
+            }
+
+            (item, amount, have)
+        })
+        .collect();
+
+    PlacementPreview { id, valid, cost }
+}
+
+/// Draws the selected tile on the hovered tile, tinted red if it can't be placed there, along with its cost.
+pub fn placement_ghost(state: &mut GameState, preview: &PlacementPreview) {
+    let Some(tile) = state.resource_man.registry.tiles.get(&preview.id) else {
+        return;
+    };
+
+    let color_offset = if preview.valid {
+        Default::default()
+    } else {
+        colors::RED.with_alpha(0.5).to_array()
+    };
+
+    state.renderer.extra_instances.push((
+        InstanceData::default()
+            .with_alpha(0.6)
+            .with_color_offset(color_offset)
+            .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
+            .with_world_matrix(state.camera.get_matrix().as_mat4())
+            .with_model_matrix(Matrix4::from_translation(
+                state
+                    .camera
+                    .rebase_coord(state.camera.pointing_at)
+                    .extend(FAR as Float),
+            )),
+        state.resource_man.get_model(tile.model),
+    ));
+
+    if !preview.cost.is_empty() && !state.gui.context.is_pointer_over_area() {
+        show_tooltip_at_pointer(&state.gui.context, "placement_cost".into(), |ui| {
+            for (item, amount, have) in &preview.cost {
+                ui.label(format(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.lbl_placement_cost]
+                        .as_str(),
+                    &[
+                        amount.to_string().as_str(),
+                        state.resource_man.item_name(item).as_str(),
+                        have.to_string().as_str(),
+                    ],
+                ));
+            }
+        });
+    }
+}
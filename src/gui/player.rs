@@ -1,7 +1,7 @@
 use std::mem;
 
 use egui::scroll_area::ScrollBarVisibility;
-use egui::{pos2, vec2, Button, Frame, Pos2, Rect, ScrollArea, Sense, Window};
+use egui::{pos2, vec2, Button, Color32, Frame, Pos2, Rect, ScrollArea, Sense, Stroke, Window};
 use rhai::Dynamic;
 
 use automancy_defs::coord::TileCoord;
@@ -18,7 +18,9 @@ use automancy_resources::types::function::RhaiDataMap;
 use automancy_resources::types::IconMode;
 use automancy_resources::{rhai_call_options, rhai_log_err};
 
+use crate::game::GameEvent;
 use crate::gui::item::draw_item;
+use crate::gui::recipes::open_recipes;
 use crate::gui::{
     take_item_animation, GameEguiCallback, MEDIUM_ICON_SIZE, SMALLISH_ICON_SIZE, SMALL_ICON_SIZE,
 };
@@ -35,7 +37,7 @@ const PUZZLE_HEX_GRID_LAYOUT: HexLayout = HexLayout {
 
 pub fn player(state: &mut GameState, game_data: &mut DataMap) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.player_menu]
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.player_menu]
             .as_str(),
     )
         .id("player_menu".into())
@@ -44,11 +46,24 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
         .show(&state.gui.context.clone(), |ui| {
             ui.horizontal_top(|ui| {
                 ui.vertical(|ui| {
-                    ui.heading(
-                        state.resource_man.translates.gui
-                            [&state.resource_man.registry.gui_ids.player_inventory_title]
-                            .as_str(),
-                    );
+                    ui.horizontal(|ui| {
+                        ui.heading(
+                            state.resource_man.translates().gui
+                                [&state.resource_man.registry.gui_ids.player_inventory_title]
+                                .as_str(),
+                        );
+
+                        if ui
+                            .button(
+                                state.resource_man.translates().gui
+                                    [&state.resource_man.registry.gui_ids.btn_recipes]
+                                    .as_str(),
+                            )
+                            .clicked()
+                        {
+                            open_recipes(state, None);
+                        }
+                    });
 
                     if let Some(Data::Inventory(inventory)) =
                         game_data.get(&state.resource_man.registry.data_ids.player_inventory)
@@ -61,18 +76,42 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                 for (id, amount) in inventory.iter() {
                                     if *amount != 0 {
                                         if let Some(item) = state.resource_man.registry.items.get(id) {
-                                            let (dst_rect, _) = draw_item(
+                                            let stack = ItemStack {
+                                                item: *item,
+                                                amount: *amount,
+                                            };
+
+                                            let (dst_rect, response) = draw_item(
                                                 &state.resource_man,
                                                 ui,
                                                 None,
-                                                ItemStack {
-                                                    item: *item,
-                                                    amount: *amount,
-                                                },
+                                                stack,
                                                 MEDIUM_ICON_SIZE,
                                                 true,
                                             );
 
+                                            let response = response.on_hover_text(format!(
+                                                "{}\n{}",
+                                                state.resource_man.translates().gui[&state
+                                                    .resource_man
+                                                    .registry
+                                                    .gui_ids
+                                                    .lbl_drop_items],
+                                                state.resource_man.translates().gui[&state
+                                                    .resource_man
+                                                    .registry
+                                                    .gui_ids
+                                                    .lbl_item_recipes],
+                                            ));
+
+                                            if response.secondary_clicked() {
+                                                state.gui_state.dropping_items = Some(stack);
+                                                state.gui_state.selected_tile_id = None;
+                                            }
+                                            if response.clicked() {
+                                                open_recipes(state, Some(*id));
+                                            }
+
                                             take_item_animation(state, ui, *item, dst_rect);
                                         }
                                     }
@@ -85,7 +124,7 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
 
                 ui.vertical(|ui| {
                     ui.heading(
-                        state.resource_man.translates.gui
+                        state.resource_man.translates().gui
                             [&state.resource_man.registry.gui_ids.research_menu_title]
                             .as_str(),
                     );
@@ -184,6 +223,7 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                                 state.gui_state.selected_research_puzzle_tile = None;
                                                 state.gui_state.research_puzzle_selections = None;
                                                 state.puzzle_state = None; // TODO have a better save system for this
+                                                state.gui_state.research_puzzle_progress = Default::default();
                                             };
                                         }
                                     });
@@ -242,7 +282,7 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                                 .add_enabled(
                                                     !already_filled,
                                                     Button::new(
-                                                        state.resource_man.translates.gui[&state
+                                                        state.resource_man.translates().gui[&state
                                                             .resource_man
                                                             .registry
                                                             .gui_ids
@@ -380,6 +420,13 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
 
                                             match result {
                                                 Ok(result) => {
+                                                    let progress = &mut state.gui_state.research_puzzle_progress;
+
+                                                    if !result && progress.attempted {
+                                                        progress.failed_attempts += 1;
+                                                    }
+                                                    progress.attempted = false;
+
                                                     if result {
                                                         if let Data::SetId(set) = game_data
                                                             .entry(
@@ -399,6 +446,35 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                             }
                                         }
 
+                                        let progress = &mut state.gui_state.research_puzzle_progress;
+
+                                        if progress.hint.is_none()
+                                            && research
+                                                .puzzle_hint_after
+                                                .is_some_and(|n| progress.failed_attempts >= n)
+                                            && ast.iter_functions().any(|f| f.name == "hint")
+                                        {
+                                            let mut rhai_state = Dynamic::from(mem::take(&mut puzzle_state.0));
+
+                                            let result =
+                                                state.resource_man.engine.call_fn_with_options::<Dynamic>(
+                                                    rhai_call_options(&mut rhai_state),
+                                                    &mut scope,
+                                                    ast,
+                                                    "hint",
+                                                    (
+                                                        Dynamic::from(setup.clone()),
+                                                    ),
+                                                );
+
+                                            puzzle_state.0 = rhai_state.take().cast::<RhaiDataMap>();
+
+                                            match result {
+                                                Ok(result) => progress.hint = result.try_cast::<TileCoord>(),
+                                                Err(err) => rhai_log_err(function_id, &err),
+                                            }
+                                        }
+
                                         if let Some(selected) =
                                             state.gui_state.selected_research_puzzle_tile
                                         {
@@ -467,6 +543,14 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
 
                                                                 ui.allocate_rect(rect, Sense::hover());
 
+                                                                if state.gui_state.research_puzzle_progress.hint == Some(*coord) {
+                                                                    ui.painter().circle_stroke(
+                                                                        rect.center(),
+                                                                        rect.width() / 2.0,
+                                                                        Stroke::new(2.0, Color32::YELLOW),
+                                                                    );
+                                                                }
+
                                                                 ui.painter().add(
                                                                     egui_wgpu::Callback::new_paint_callback(
                                                                         rect,
@@ -570,6 +654,11 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                                         }
 
                                                         if let Some((selected, id)) = select_result {
+                                                            let progress = &mut state.gui_state.research_puzzle_progress;
+                                                            progress.history.push(tiles.clone());
+                                                            progress.attempted = true;
+                                                            progress.hint = None;
+
                                                             tiles.insert(selected, id);
 
                                                             state
@@ -593,6 +682,49 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                                         }
                                                     }
                                                 });
+
+                                            let mut reset = false;
+
+                                            ui.horizontal(|ui| {
+                                                if ui
+                                                    .add_enabled(
+                                                        !state.gui_state.research_puzzle_progress.history.is_empty(),
+                                                        Button::new(
+                                                            state.resource_man.translates().gui
+                                                                [&state.resource_man.registry.gui_ids.btn_undo]
+                                                                .as_str(),
+                                                        ),
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    let progress = &mut state.gui_state.research_puzzle_progress;
+
+                                                    if let Some(tiles) = progress.history.pop() {
+                                                        data.set(state.resource_man.registry.data_ids.tiles, Data::TileMap(tiles));
+                                                        progress.hint = None;
+                                                        *dirty = true;
+                                                    }
+                                                }
+
+                                                if ui
+                                                    .button(
+                                                        state.resource_man.translates().gui
+                                                            [&state.resource_man.registry.gui_ids.btn_reset]
+                                                            .as_str(),
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    reset = true;
+                                                }
+                                            });
+
+                                            if reset {
+                                                state.puzzle_state = None;
+                                                state.gui_state.selected_research_puzzle_tile = None;
+                                                state.gui_state.research_puzzle_selections = None;
+                                                state.gui_state.research_puzzle_progress.history.clear();
+                                                state.gui_state.research_puzzle_progress.hint = None;
+                                            }
                                         }
                                     }
                                 }
@@ -677,6 +809,10 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                                     set.insert(research.id);
                                 }
 
+                                state
+                                    .audio_dispatch
+                                    .send(GameEvent::ResearchComplete(research.id));
+
                                 state.gui_state.selected_research_puzzle_tile = None;
                                 state.gui_state.research_puzzle_selections = None;
                             }
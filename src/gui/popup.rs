@@ -1,18 +1,27 @@
 use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
-use egui::{vec2, Align2, Window};
+use egui::{vec2, Align2, Button, Checkbox, ComboBox, DragValue, ScrollArea, Slider, Ui, Window};
 
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::id::Id;
 use automancy_defs::log;
+use automancy_resources::data::DataMap;
+use automancy_resources::format;
 
+use crate::blueprint::Blueprint;
 use crate::event::refresh_maps;
-use crate::game::load_map;
+use crate::game::{load_map, load_map_in_editor};
+use crate::gui::menu::save_options;
 use crate::gui::{PopupState, Screen, TextField};
-use crate::map::Map;
+use crate::map::{Map, EXPORT_PATH};
+use crate::options::RISKY_OPTIONS;
 use crate::GameState;
 
 pub fn invalid_name_popup(state: &mut GameState) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.invalid_name]
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.invalid_name]
             .as_str(),
     )
     .id("invalid_map_name_popup".into())
@@ -22,13 +31,14 @@ pub fn invalid_name_popup(state: &mut GameState) {
     .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
     .show(&state.gui.context.clone(), |ui| {
         ui.label(
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.lbl_pick_another_name]
                 .as_str(),
         );
         if ui
             .button(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.btn_confirm]
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_confirm]
                     .as_str(),
             )
             .clicked()
@@ -42,7 +52,8 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
     let mut dirty = false;
 
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.delete_map].as_str(),
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.delete_map]
+            .as_str(),
     )
     .id("map_delete_popup".into())
     .resizable(false)
@@ -51,13 +62,14 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
     .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
     .show(&state.gui.context.clone(), |ui| {
         ui.label(
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.lbl_delete_map_confirm]
                 .as_str(),
         );
         if ui
             .button(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.btn_confirm]
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_confirm]
                     .as_str(),
             )
             .clicked()
@@ -69,7 +81,8 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
         }
         if ui
             .button(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.btn_cancel]
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
                     .as_str(),
             )
             .clicked()
@@ -83,10 +96,50 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
     }
 }
 
+/// Creates the map named in the map creation popup, from the selected template if there is one, and enters it.
+fn create_map(state: &mut GameState, in_editor: bool) {
+    let name = Map::sanitize_name(state.gui_state.text_field.get(TextField::MapName).clone());
+
+    if let Some(template) = state.gui_state.selected_map_template.take() {
+        if Map::path(&name).exists() || Map::create_from_template(&template, &name).is_err() {
+            state.gui_state.popup = PopupState::InvalidName;
+            return;
+        }
+    }
+
+    let loaded = if in_editor {
+        state
+            .tokio
+            .block_on(load_map_in_editor(&state.game, &mut state.loop_store, name))
+            .unwrap()
+    } else {
+        state
+            .tokio
+            .block_on(load_map(&state.game, &mut state.loop_store, name))
+            .unwrap()
+    };
+
+    if !loaded {
+        return;
+    }
+
+    if let Some((info, _)) = &state.loop_store.map_info {
+        let mut info = info.blocking_lock();
+        // the new map is not a template itself, even when it's copied from one
+        info.template = false;
+        info.survival = state.gui_state.new_map_survival;
+    }
+
+    state.gui_state.text_field.get(TextField::MapName).clear();
+    state.gui_state.popup = PopupState::None;
+    state.gui_state.switch_screen(Screen::Ingame);
+}
+
 /// Draws the map creation popup.
 pub fn map_create_popup(state: &mut GameState) {
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.create_map].as_str(),
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.create_map]
+            .as_str(),
     )
     .id("map_create_popup".into())
     .resizable(false)
@@ -98,33 +151,561 @@ pub fn map_create_popup(state: &mut GameState) {
             ui.label("Name:"); //TODO add this to translation
             ui.text_edit_singleline(state.gui_state.text_field.get(TextField::MapName));
         });
+        ui.horizontal(|ui| {
+            ui.label(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_new_from_template]
+                    .as_str(),
+            );
+
+            let templates = state
+                .resource_man
+                .map_templates
+                .iter()
+                .map(|(name, path)| (name.clone(), path.clone()))
+                .chain(
+                    state
+                        .loop_store
+                        .map_infos_cache
+                        .iter()
+                        .filter(|((info, _), _)| info.template)
+                        .map(|(_, name)| (name.clone(), Map::path(name))),
+                )
+                .collect::<Vec<_>>();
+
+            let none = state.resource_man.translates().none.clone();
+            let selected = templates
+                .iter()
+                .find(|(_, path)| Some(path) == state.gui_state.selected_map_template.as_ref())
+                .map(|(name, _)| name.as_str())
+                .unwrap_or(none.as_str());
+
+            ComboBox::from_id_source("map_template")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut state.gui_state.selected_map_template,
+                        None,
+                        state.resource_man.translates().none.as_str(),
+                    );
+
+                    for (name, path) in &templates {
+                        ui.selectable_value(
+                            &mut state.gui_state.selected_map_template,
+                            Some(path.clone()),
+                            name,
+                        );
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_survival]
+                    .as_str(),
+            );
+            ui.add(Checkbox::new(&mut state.gui_state.new_map_survival, ""));
+        });
         if ui
             .button(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.btn_confirm]
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_confirm]
                     .as_str(),
             )
             .clicked()
         {
-            let name =
-                Map::sanitize_name(state.gui_state.text_field.get(TextField::MapName).clone());
+            create_map(state, false);
+        }
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_create_in_editor]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            create_map(state, true);
+        }
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.popup = PopupState::None;
+            state.gui_state.selected_map_template = None;
+        }
+    });
+}
+
+/// Starts picking a region of the tiles to import, around their center and covering all of them.
+fn pick_import_source(state: &mut GameState, tiles: Vec<(TileCoord, Id, DataMap)>) {
+    let center = TileCoord::from(TileBounds::from_iter(tiles.iter().map(|v| v.0)).center);
+    let radius = tiles
+        .iter()
+        .map(|v| v.0.distance(center))
+        .max()
+        .unwrap_or(0);
 
-            state
-                .tokio
-                .block_on(load_map(&state.game, &mut state.loop_store, name))
-                .unwrap();
+    state.gui_state.import_region = (center, radius);
+    state.gui_state.import_source = Some(tiles);
+}
+
+/// Lists the maps and blueprint files a region can be imported from.
+fn import_source_list(state: &mut GameState, ui: &mut Ui) {
+    ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+        let current = state.loop_store.map_info.as_ref().map(|v| v.1.clone());
+
+        for (_, map_name) in state.loop_store.map_infos_cache.clone() {
+            if Some(&map_name) == current.as_ref() {
+                continue;
+            }
+
+            if ui.button(map_name.as_str()).clicked() {
+                if let Some(tiles) = Map::read_tiles(&state.resource_man, &map_name)
+                    .filter(|tiles| !tiles.is_empty())
+                {
+                    pick_import_source(state, tiles);
+                }
+            }
+        }
+
+        for path in Blueprint::list_files() {
+            let Some(name) = path.file_name().and_then(|v| v.to_str()) else {
+                continue;
+            };
+
+            if ui.button(name).clicked() {
+                match Blueprint::read_file(&state.resource_man, &path) {
+                    Ok(blueprint) => pick_import_source(state, blueprint.tiles),
+                    Err(err) => {
+                        log::error!("Could not import {}: {err}", path.display());
+
+                        state.resource_man.error_man.push(
+                            (
+                                state.resource_man.registry.err_ids.invalid_blueprint_file,
+                                vec![name.to_string(), err.to_string()],
+                            ),
+                            &state.resource_man,
+                        );
+                    }
+                }
+            }
+        }
+    });
+
+    ui.label(format(
+        state.resource_man.translates().gui[&state
+            .resource_man
+            .registry
+            .gui_ids
+            .lbl_import_blueprint_folder]
+            .as_str(),
+        &[EXPORT_PATH],
+    ));
+}
+
+/// Picks the region of the source to import, and starts placing it.
+fn import_region_picker(state: &mut GameState, ui: &mut Ui) {
+    let Some(tiles) = &state.gui_state.import_source else {
+        return;
+    };
+
+    let (center, mut radius) = state.gui_state.import_region;
+    let (mut q, mut r) = (center.x, center.y);
+    let max_radius = tiles
+        .iter()
+        .map(|v| v.0.distance(center))
+        .max()
+        .unwrap_or(0);
+
+    ui.horizontal(|ui| {
+        ui.label(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_import_region_center]
+                .as_str(),
+        );
+        ui.add(DragValue::new(&mut q).prefix("q: "));
+        ui.add(DragValue::new(&mut r).prefix("r: "));
+    });
+    ui.horizontal(|ui| {
+        ui.label(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_import_region_radius]
+                .as_str(),
+        );
+        ui.add(Slider::new(&mut radius, 0..=max_radius));
+    });
+
+    let center = TileCoord::new(q, r);
+    let count = tiles
+        .iter()
+        .filter(|v| v.0.distance(center) <= radius)
+        .count();
+
+    ui.label(format(
+        state.resource_man.translates().gui
+            [&state.resource_man.registry.gui_ids.lbl_import_region_count]
+            .as_str(),
+        &[&count.to_string()],
+    ));
+
+    state.gui_state.import_region = (center, radius);
+
+    ui.horizontal(|ui| {
+        if ui
+            .button(
+                state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.btn_back]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.import_source = None;
+        }
+
+        if ui
+            .add_enabled(
+                count > 0,
+                Button::new(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.btn_import]
+                        .as_str(),
+                ),
+            )
+            .clicked()
+        {
+            if let Some(tiles) = state.gui_state.import_source.take() {
+                // the region is placed centered on the cursor
+                state.gui_state.importing_region = Some(
+                    tiles
+                        .into_iter()
+                        .filter(|v| v.0.distance(center) <= radius)
+                        .map(|(coord, id, data)| (coord - center, id, data))
+                        .collect(),
+                );
+                state.gui_state.importing_conflicts = None;
+            }
 
-            state.gui_state.text_field.get(TextField::MapName).clear();
             state.gui_state.popup = PopupState::None;
             state.gui_state.switch_screen(Screen::Ingame);
         }
+    });
+}
+
+/// Draws the popup for picking a map or blueprint file to import tiles from, and then which region of its tiles.
+pub fn import_region_popup(state: &mut GameState) {
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.import_region]
+            .as_str(),
+    )
+    .id("import_region_popup".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&state.gui.context.clone(), |ui| {
+        if state.gui_state.import_source.is_some() {
+            import_region_picker(state, ui);
+        } else {
+            import_source_list(state, ui);
+        }
+
         if ui
             .button(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.btn_cancel]
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
                     .as_str(),
             )
             .clicked()
         {
+            state.gui_state.import_source = None;
             state.gui_state.popup = PopupState::None
         }
     });
 }
+
+/// Lists the exported maps in the exports folder, to import one as a new map.
+pub fn map_import_popup(state: &mut GameState) {
+    let mut dirty = false;
+
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.import_map]
+            .as_str(),
+    )
+    .id("map_import_popup".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.label(format(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_import_map_folder]
+                .as_str(),
+            &[EXPORT_PATH],
+        ));
+
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for path in Map::list_exports() {
+                let Some(name) = path.file_name().and_then(|v| v.to_str()) else {
+                    continue;
+                };
+
+                if ui.button(name).clicked() {
+                    match Map::import_from_file(&path) {
+                        Ok(map_name) => {
+                            log::info!("Imported {} as map {map_name}", path.display());
+
+                            dirty = true;
+                            state.gui_state.popup = PopupState::None;
+                        }
+                        Err(err) => {
+                            log::error!("Could not import {}: {err}", path.display());
+
+                            state.resource_man.error_man.push(
+                                (
+                                    state.resource_man.registry.err_ids.map_import_failed,
+                                    vec![err.to_string()],
+                                ),
+                                &state.resource_man,
+                            );
+                        }
+                    }
+                }
+            }
+        });
+
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.popup = PopupState::None
+        }
+    });
+
+    if dirty {
+        refresh_maps(state);
+    }
+}
+
+/// Tells the player where the map was exported to.
+pub fn map_exported_popup(state: &mut GameState, path: &Path) {
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.export_map]
+            .as_str(),
+    )
+    .id("map_exported_popup".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.label(format(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_map_exported]
+                .as_str(),
+            &[&path.display().to_string()],
+        ));
+
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_confirm]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.popup = PopupState::None
+        }
+    });
+}
+
+/// Lists the autosaves of a map, newest first, so the map can be rolled back to one of them.
+pub fn restore_backup_popup(state: &mut GameState, map_name: &str) {
+    let mut dirty = false;
+
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.restore_backup]
+            .as_str(),
+    )
+    .id("restore_backup_popup".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.heading(map_name);
+        ui.label(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_restore_backup_tip]
+                .as_str(),
+        );
+
+        let backups = Map::list_backups(map_name);
+
+        if backups.is_empty() {
+            ui.label(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_no_backups]
+                    .as_str(),
+            );
+        }
+
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for backup in backups {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        backup
+                            .time
+                            .map(|time| state.resource_man.time_string(time))
+                            .unwrap_or_else(|| backup.path.display().to_string()),
+                    );
+
+                    if ui
+                        .button(
+                            state.resource_man.translates().gui
+                                [&state.resource_man.registry.gui_ids.btn_restore]
+                                .as_str(),
+                        )
+                        .clicked()
+                    {
+                        match Map::restore_backup(map_name, &backup.path) {
+                            Ok(()) => {
+                                log::info!(
+                                    "Restored map {map_name} from {}",
+                                    backup.path.display()
+                                );
+
+                                dirty = true;
+                                state.gui_state.popup = PopupState::None;
+                            }
+                            Err(err) => {
+                                log::error!("Could not restore {}: {err}", backup.path.display());
+
+                                state.resource_man.error_man.push(
+                                    (
+                                        state.resource_man.registry.err_ids.map_restore_failed,
+                                        vec![err.to_string()],
+                                    ),
+                                    &state.resource_man,
+                                );
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.popup = PopupState::None
+        }
+    });
+
+    if dirty {
+        refresh_maps(state);
+    }
+}
+
+/// How long the risky settings are kept without being confirmed, before they are reverted.
+const KEEP_SETTINGS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Asks whether to keep the risky settings just applied. The countdown is checked by [`revert_unkept_settings`].
+pub fn keep_settings_popup(state: &mut GameState) {
+    let Some((applied, _)) = &state.gui_state.risky_revert else {
+        state.gui_state.popup = PopupState::None;
+
+        return;
+    };
+
+    let remaining = KEEP_SETTINGS_TIMEOUT.saturating_sub(applied.elapsed());
+    let mut keep = None;
+
+    Window::new(
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.keep_settings]
+            .as_str(),
+    )
+    .id("keep_settings_popup".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+    .show(&state.gui.context.clone(), |ui| {
+        ui.label(format(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_keep_settings]
+                .as_str(),
+            &[&remaining.as_secs_f32().ceil().to_string()],
+        ));
+        ui.horizontal(|ui| {
+            if ui
+                .button(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.btn_keep]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                keep = Some(true);
+            }
+            if ui
+                .button(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.btn_revert]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                keep = Some(false);
+            }
+        });
+    });
+
+    let Some(keep) = keep else {
+        state.gui.context.request_repaint();
+
+        return;
+    };
+
+    answer_keep_settings(state, keep);
+}
+
+/// Reverts the risky settings once they have gone too long without being kept.
+/// This is called from the event loop rather than the popup, so the countdown is in real time,
+/// and keeps going even when the window can't be seen or used.
+pub fn revert_unkept_settings(state: &mut GameState) {
+    if state
+        .gui_state
+        .risky_revert
+        .as_ref()
+        .is_some_and(|(applied, _)| applied.elapsed() >= KEEP_SETTINGS_TIMEOUT)
+    {
+        answer_keep_settings(state, false);
+    }
+}
+
+fn answer_keep_settings(state: &mut GameState, keep: bool) {
+    if let Some((_, previous)) = state.gui_state.risky_revert.take() {
+        if !keep {
+            for name in RISKY_OPTIONS {
+                state.options.copy_from(&previous, name);
+            }
+
+            save_options(state);
+        }
+    }
+
+    state.gui_state.popup = PopupState::None;
+}
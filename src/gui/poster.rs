@@ -0,0 +1,162 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use egui::{vec2, Align2, ProgressBar, Window};
+
+use automancy_defs::log;
+use automancy_resources::format;
+
+use crate::game::GameSystemMessage;
+use crate::gui::toast::push_toast;
+use crate::headless;
+use crate::renderer::save_screenshot;
+use crate::GameState;
+
+/// The size of each piece of the poster. Larger pieces need fewer renders, but more video memory.
+const PIECE_SIZE: (u32, u32) = (1024, 1024);
+
+/// How far along exporting the map image is.
+#[derive(Debug, Clone)]
+pub enum PosterExport {
+    /// how many pieces have been rendered, and how many there are
+    Rendering(usize, usize),
+    Done(PathBuf),
+    Failed,
+}
+
+/// Saves the current map, and starts rendering it into an image in the background.
+pub fn export_poster(state: &mut GameState) {
+    if state.gui_state.poster_export.is_some() {
+        return;
+    }
+
+    let Some(map_name) = state.loop_store.map_info.as_ref().map(|v| v.1.clone()) else {
+        return;
+    };
+
+    state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::SaveMap, None))
+        .unwrap()
+        .unwrap();
+
+    let export = Arc::new(Mutex::new(PosterExport::Rendering(0, 0)));
+    state.gui_state.poster_export = Some(export.clone());
+
+    let resource_man = state.resource_man.clone();
+    let models = state.models.clone();
+    let directory = state.options.graphics.screenshot.directory.clone();
+
+    thread::spawn(move || {
+        let (vertices, indices) = models.as_ref();
+
+        let result = headless::render_map_poster(
+            &resource_man,
+            vertices,
+            indices,
+            &map_name,
+            PIECE_SIZE,
+            &|done, total| {
+                *export.lock().unwrap() = PosterExport::Rendering(done, total);
+            },
+        )
+        .and_then(|poster| save_screenshot(&poster, &directory, "poster-"));
+
+        *export.lock().unwrap() = match result {
+            Ok(path) => PosterExport::Done(path),
+            Err(err) => {
+                log::error!("Could not export the map image: {err:?}");
+                resource_man.error_man.push(
+                    (
+                        resource_man.registry.err_ids.export_image_failed,
+                        vec![err.to_string()],
+                    ),
+                    &resource_man,
+                );
+
+                PosterExport::Failed
+            }
+        };
+    });
+}
+
+/// Draws the progress of the map image export, if there is one.
+pub fn poster_export_window(state: &mut GameState) {
+    let Some(export) = state.gui_state.poster_export.clone() else {
+        return;
+    };
+    let export = export.lock().unwrap().clone();
+
+    if let PosterExport::Failed = export {
+        state.gui_state.poster_export = None;
+
+        return;
+    }
+
+    Window::new(
+        state.resource_man.translates().gui
+            [&state.resource_man.registry.gui_ids.export_image_title]
+            .as_str(),
+    )
+    .id("poster_export".into())
+    .resizable(false)
+    .collapsible(false)
+    .default_width(250.0)
+    .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -10.0))
+    .show(&state.gui.context.clone(), |ui| match export {
+        PosterExport::Rendering(done, total) => {
+            let progress = if total == 0 {
+                0.0
+            } else {
+                done as f32 / total as f32
+            };
+
+            ui.add(ProgressBar::new(progress).text(format!("{done}/{total}")));
+        }
+        PosterExport::Done(path) => {
+            ui.label(format(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.lbl_export_image_done]
+                    .as_str(),
+                &[&path.display().to_string()],
+            ));
+
+            if ui
+                .button(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.btn_confirm]
+                        .as_str(),
+                )
+                .clicked()
+            {
+                state.gui_state.poster_export = None;
+            }
+        }
+        PosterExport::Failed => {}
+    });
+
+    // keep redrawing so the progress moves even when nothing else happens
+    state.gui.context.request_repaint();
+}
+
+/// Shows a toast for where the last screenshot was saved, once it has been.
+pub fn screenshot_notice(state: &mut GameState) {
+    let Some(path) = state
+        .renderer
+        .saved_screenshot
+        .try_lock()
+        .ok()
+        .and_then(|mut v| v.take())
+    else {
+        return;
+    };
+
+    let text = format(
+        state.resource_man.translates().gui
+            [&state.resource_man.registry.gui_ids.lbl_screenshot_saved]
+            .as_str(),
+        &[&path.display().to_string()],
+    );
+    push_toast(state, text);
+}
@@ -0,0 +1,234 @@
+//! The recipe browser, listing every script with what goes in, what comes out, which tiles run it,
+//! and which research unlocks it.
+//!
+//! It is opened from the player menu, and from the items in the player's inventory, in which case it shows
+//! what makes that item and what uses it.
+
+use egui::{vec2, Align2, ScrollArea, TextEdit, Ui, Widget, Window};
+
+use automancy_defs::id::Id;
+use automancy_resources::data::stack::ItemStack;
+use automancy_resources::data::Data;
+use automancy_resources::types::script::Script;
+use automancy_resources::{format, ResourceManager};
+
+use crate::gui::item::draw_item;
+use crate::gui::{TextField, SMALL_ICON_SIZE};
+use crate::GameState;
+
+/// How far the search can be from a name and still match, from 0 for an exact match to 1 for anything.
+const SEARCH_THRESHOLD: f64 = 0.4;
+
+/// Opens the recipe browser, showing what makes and uses the item if there is one, or every recipe otherwise.
+pub fn open_recipes(state: &mut GameState, item: Option<Id>) {
+    state.gui_state.recipes_open = true;
+    state.gui_state.recipe_item = item;
+}
+
+/// The tiles that can run the script.
+fn tiles_running(resource_man: &ResourceManager, script: Id) -> Vec<Id> {
+    resource_man
+        .ordered_tiles
+        .iter()
+        .filter(|id| {
+            resource_man.registry.tiles[*id]
+                .data
+                .get(&resource_man.registry.data_ids.scripts)
+                .is_some_and(|v| matches!(v, Data::VecId(scripts) if scripts.contains(&script)))
+        })
+        .copied()
+        .collect()
+}
+
+/// The names of the research unlocking the script, or the tiles running it if the script doesn't need any itself.
+fn unlocked_by(resource_man: &ResourceManager, script: Id, tiles: &[Id]) -> Vec<String> {
+    if let Some(research) = resource_man.get_research_by_unlock(script) {
+        return vec![resource_man.research_str(&research.name)];
+    }
+
+    let mut names = tiles
+        .iter()
+        .flat_map(|id| resource_man.get_research_by_unlock(*id))
+        .map(|research| resource_man.research_str(&research.name))
+        .collect::<Vec<_>>();
+    names.dedup();
+
+    names
+}
+
+/// Draws a row of items after the label. Clicking one shows what makes and uses it.
+fn item_row(state: &mut GameState, ui: &mut Ui, label: Id, stacks: &[ItemStack]) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(state.resource_man.translates().gui[&label].as_str());
+
+        for stack in stacks {
+            let (_, response) =
+                draw_item(&state.resource_man, ui, None, *stack, SMALL_ICON_SIZE, true);
+
+            if response.clicked() {
+                state.gui_state.recipe_item = Some(stack.item.id);
+            }
+        }
+    });
+}
+
+/// Draws one recipe.
+fn recipe(state: &mut GameState, ui: &mut Ui, script: &Script) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    ui.group(|ui| {
+        ui.set_width(ui.available_width());
+
+        ui.strong(state.resource_man.script_name(&script.id));
+
+        if let Some(inputs) = &script.instructions.inputs {
+            item_row(state, ui, gui_ids.lbl_recipe_inputs, inputs);
+        }
+        item_row(
+            state,
+            ui,
+            gui_ids.lbl_recipe_outputs,
+            &script.instructions.outputs,
+        );
+
+        let tiles = tiles_running(&state.resource_man, script.id);
+        if !tiles.is_empty() {
+            let names = tiles
+                .iter()
+                .map(|id| state.resource_man.tile_name(id))
+                .collect::<Vec<_>>();
+
+            ui.label(format(
+                state.resource_man.translates().gui[&gui_ids.lbl_recipe_tiles].as_str(),
+                &[&names.join(", ")],
+            ));
+        }
+
+        let research = unlocked_by(&state.resource_man, script.id, &tiles);
+        if !research.is_empty() {
+            ui.label(format(
+                state.resource_man.translates().gui[&gui_ids.lbl_recipe_research].as_str(),
+                &[&research.join(", ")],
+            ));
+        }
+    });
+}
+
+/// Draws the recipe browser, if it is open.
+pub fn recipes_ui(state: &mut GameState) {
+    if !state.gui_state.recipes_open {
+        return;
+    }
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let mut open = true;
+
+    let mut scripts = state
+        .resource_man
+        .registry
+        .scripts
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    scripts.sort_by(|a, b| {
+        state
+            .resource_man
+            .script_name(&a.id)
+            .cmp(&state.resource_man.script_name(&b.id))
+    });
+
+    Window::new(state.resource_man.translates().gui[&gui_ids.recipes_title].as_str())
+        .id("recipes".into())
+        .open(&mut open)
+        .resizable(false)
+        .default_width(320.0)
+        .pivot(Align2::RIGHT_TOP)
+        .default_pos(state.gui.context.screen_rect().right_top() + vec2(-10.0, 10.0))
+        .show(&state.gui.context.clone(), |ui| {
+            if let Some(item) = state.gui_state.recipe_item {
+                let name = state.resource_man.item_name(&item);
+
+                ui.horizontal(|ui| {
+                    ui.heading(name.as_str());
+
+                    if ui
+                        .button(
+                            state.resource_man.translates().gui[&gui_ids.btn_all_recipes].as_str(),
+                        )
+                        .clicked()
+                    {
+                        state.gui_state.recipe_item = None;
+                    }
+                });
+
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (label, uses) in [
+                        (gui_ids.lbl_recipe_makes, false),
+                        (gui_ids.lbl_recipe_uses, true),
+                    ] {
+                        ui.label(format(
+                            state.resource_man.translates().gui[&label].as_str(),
+                            &[&name],
+                        ));
+
+                        for script in scripts.iter().filter(|script| {
+                            let stacks = if uses {
+                                script.instructions.inputs.as_deref().unwrap_or_default()
+                            } else {
+                                script.instructions.outputs.as_slice()
+                            };
+
+                            stacks.iter().any(|stack| stack.item.id == item)
+                        }) {
+                            recipe(state, ui, script);
+                        }
+
+                        ui.add_space(8.0);
+                    }
+                });
+            } else {
+                TextEdit::singleline(state.gui_state.text_field.get(TextField::RecipeSearch))
+                    .hint_text(
+                        state.resource_man.translates().gui[&gui_ids.search_recipe_tip].as_str(),
+                    )
+                    .ui(ui);
+
+                let text = state
+                    .gui_state
+                    .text_field
+                    .get(TextField::RecipeSearch)
+                    .clone();
+
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for script in &scripts {
+                        // recipes match by their own name, or the name of anything going in or out
+                        let matches = text.is_empty()
+                            || script
+                                .instructions
+                                .inputs
+                                .iter()
+                                .flatten()
+                                .chain(&script.instructions.outputs)
+                                .map(|stack| state.resource_man.item_name(&stack.item.id))
+                                .chain([state.resource_man.script_name(&script.id)])
+                                .any(|name| {
+                                    state
+                                        .gui_state
+                                        .text_field
+                                        .fuse
+                                        .search_text_in_string(&text, &name)
+                                        .is_some_and(|v| v.score <= SEARCH_THRESHOLD)
+                                });
+
+                        if matches {
+                            recipe(state, ui, script);
+                        }
+                    }
+                });
+            }
+        });
+
+    if !open {
+        state.gui_state.recipes_open = false;
+    }
+}
@@ -0,0 +1,81 @@
+//! Buttons that keep going while they are held, for stepping numbers without clicking over and over.
+//!
+//! A held button first waits a moment, so a single click is a single step, then repeats at the rate
+//! set in the options. The longer it is held, the bigger each step gets, so large numbers are reached quickly.
+
+use egui::{Button, Ui, WidgetText};
+
+/// How long a button has to be held before it starts repeating, in seconds.
+const REPEAT_DELAY: f64 = 0.4;
+/// How many repeats it takes for the steps to get 10 times bigger.
+const ACCELERATE_EVERY: u32 = 15;
+/// How many times the steps can get 10 times bigger.
+const MAX_ACCELERATION: u32 = 3;
+
+#[derive(Debug, Clone, Copy)]
+struct Held {
+    /// when the button was pressed, on the GUI's clock
+    since: f64,
+    /// how many times it has repeated since
+    repeats: u32,
+}
+
+/// How big the step is on the nth repeat.
+fn step_size(repeat: u32) -> i64 {
+    10i64.pow((repeat / ACCELERATE_EVERY).min(MAX_ACCELERATION))
+}
+
+/// A button that steps once when pressed, then again and again while it is held, `rate` times a second.
+///
+/// Returns how far to step this frame, which is 0 when it isn't pressed.
+pub fn repeat_button(ui: &mut Ui, text: impl Into<WidgetText>, rate: f32) -> i64 {
+    let response = ui.add(Button::new(text));
+    let id = response.id;
+
+    if !response.is_pointer_button_down_on() {
+        ui.data_mut(|data| data.remove::<Held>(id));
+
+        return 0;
+    }
+
+    // keeps the frames coming while it is held, even without the pointer moving
+    ui.ctx().request_repaint();
+
+    let now = ui.input(|input| input.time);
+
+    let Some(held) = ui.data(|data| data.get_temp::<Held>(id)) else {
+        ui.data_mut(|data| {
+            data.insert_temp(
+                id,
+                Held {
+                    since: now,
+                    repeats: 0,
+                },
+            )
+        });
+
+        return 1;
+    };
+
+    let holding = now - held.since - REPEAT_DELAY;
+    if holding < 0.0 {
+        return 0;
+    }
+
+    let due = (holding * rate.max(1.0) as f64) as u32 + 1;
+    if due <= held.repeats {
+        return 0;
+    }
+
+    ui.data_mut(|data| {
+        data.insert_temp(
+            id,
+            Held {
+                repeats: due,
+                ..held
+            },
+        )
+    });
+
+    (held.repeats + 1..=due).map(step_size).sum()
+}
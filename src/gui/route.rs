@@ -0,0 +1,191 @@
+use ractor::rpc::CallResult;
+
+use automancy_defs::colors;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::dvec2;
+use automancy_defs::id::Id;
+use automancy_defs::math::{Float, Matrix4, FAR};
+use automancy_defs::rendering::{make_line, InstanceData};
+use automancy_defs::{math, window};
+use automancy_resources::data::{Data, DataMap};
+
+use crate::game::GameSystemMessage;
+use crate::tile_entity::TileEntityMsg;
+use crate::GameState;
+
+/// What the routing tool is waiting for the player to click on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Routing {
+    PickSource,
+    PickDestination(TileCoord),
+}
+
+/// Gets the tile the routing tool builds routes out of.
+pub fn connector_tile(state: &GameState) -> Option<Id> {
+    state
+        .resource_man
+        .registry
+        .tiles
+        .iter()
+        .filter(|(_, tile)| {
+            tile.data
+                .flag(state.resource_man.registry.data_ids.connector)
+        })
+        .map(|(id, _)| *id)
+        .min()
+}
+
+/// Gets the route from the source to the tile the player is pointing at, asking the game only when either end changed.
+fn route_to_pointer(state: &mut GameState, from: TileCoord) -> Option<Vec<TileCoord>> {
+    let to = state.camera.pointing_at;
+
+    match &state.gui_state.route_preview {
+        Some((cached_from, cached_to, route)) if *cached_from == from && *cached_to == to => {
+            route.clone()
+        }
+        _ => {
+            let route = match state.tokio.block_on(state.game.call(
+                |reply| GameSystemMessage::FindRoute { from, to, reply },
+                None,
+            )) {
+                Ok(CallResult::Success(route)) => route,
+                _ => None,
+            };
+
+            state.gui_state.route_preview = Some((from, to, route.clone()));
+
+            route
+        }
+    }
+}
+
+/// Draws the connectors the route would be made of, or a red line if there is no route to where the player is pointing.
+pub fn route_ghost(state: &mut GameState) {
+    let Some(Routing::PickDestination(from)) = state.gui_state.routing else {
+        return;
+    };
+    let Some(tile) =
+        connector_tile(state).and_then(|id| state.resource_man.registry.tiles.get(&id))
+    else {
+        return;
+    };
+    let model = state.resource_man.get_model(tile.model);
+
+    let Some(route) = route_to_pointer(state, from) else {
+        let cursor_pos = math::screen_to_world(
+            window::window_size_double(&state.renderer.gpu.window),
+            state.input_handler.main_pos,
+            state.camera.get_pos(),
+        );
+        let cursor_pos = state.camera.rebase(dvec2(cursor_pos.x, cursor_pos.y));
+
+        state.renderer.extra_instances.push((
+            InstanceData::default()
+                .with_color_offset(colors::RED.to_array())
+                .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
+                .with_world_matrix(state.camera.get_matrix().as_mat4())
+                .with_model_matrix(make_line(state.camera.rebase_coord(from), cursor_pos)),
+            state.resource_man.registry.model_ids.cube1x1,
+        ));
+
+        return;
+    };
+
+    for coord in connectors(&route) {
+        state.renderer.extra_instances.push((
+            InstanceData::default()
+                .with_alpha(0.5)
+                .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
+                .with_world_matrix(state.camera.get_matrix().as_mat4())
+                .with_model_matrix(Matrix4::from_translation(
+                    state.camera.rebase_coord(*coord).extend(FAR as Float),
+                )),
+            model,
+        ));
+    }
+}
+
+/// Gets the part of the route that needs connectors, which is everything but the two ends.
+fn connectors(route: &[TileCoord]) -> &[TileCoord] {
+    if route.len() < 2 {
+        return &[];
+    }
+
+    &route[1..route.len() - 1]
+}
+
+/// Moves the routing tool along after the player clicked: the first click picks the source,
+/// and the second one places the route to the destination.
+pub fn on_route_click(state: &mut GameState) {
+    match state.gui_state.routing {
+        Some(Routing::PickSource) => {
+            state.gui_state.routing = Some(Routing::PickDestination(state.camera.pointing_at));
+        }
+        Some(Routing::PickDestination(from)) => {
+            let Some(route) = route_to_pointer(state, from) else {
+                return;
+            };
+            let Some(connector) = connector_tile(state) else {
+                return;
+            };
+
+            state.gui_state.routing = None;
+            state.gui_state.route_preview = None;
+
+            // the whole route is undone at once
+            let tiles = connectors(&route)
+                .iter()
+                .enumerate()
+                .map(|(n, coord)| {
+                    let mut data = DataMap::default();
+                    data.insert(
+                        state.resource_man.registry.data_ids.target,
+                        Data::Coord(route[n + 2] - *coord),
+                    );
+
+                    (*coord, connector, data)
+                })
+                .collect();
+
+            state
+                .game
+                .send_message(GameSystemMessage::PlaceTiles {
+                    tiles,
+                    record: true,
+                })
+                .unwrap();
+
+            // point the source at the start of the route, if it can be pointed anywhere
+            let source = match state.tokio.block_on(
+                state
+                    .game
+                    .call(|reply| GameSystemMessage::GetTile(from, reply), None),
+            ) {
+                Ok(CallResult::Success(source)) => source,
+                _ => None,
+            };
+
+            let targeted = source
+                .and_then(|id| state.resource_man.registry.tiles.get(&id))
+                .is_some_and(|tile| {
+                    !tile
+                        .data
+                        .flag(state.resource_man.registry.data_ids.not_targeted)
+                });
+
+            if targeted && route.len() >= 2 {
+                state
+                    .game
+                    .send_message(GameSystemMessage::ForwardMsgToTile(
+                        from,
+                        TileEntityMsg::SetDataValue(
+                            state.resource_man.registry.data_ids.target,
+                            Data::Coord(route[1] - from),
+                        ),
+                    ))
+                    .unwrap();
+            }
+        }
+        None => {}
+    }
+}
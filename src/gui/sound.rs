@@ -0,0 +1,63 @@
+use egui::output::OutputEvent;
+use egui::Response;
+
+use automancy_resources::types::ui_sound::UiSound;
+
+use crate::game::GameEvent;
+use crate::gui::PopupState;
+use crate::GameState;
+
+/// Plays an interface sound, if the resource packs have one for it.
+pub fn play(state: &mut GameState, sound: UiSound) {
+    if let Some(audio) = state.resource_man.ui_sound(sound) {
+        state.audio_man.play_ui(audio);
+    }
+}
+
+pub trait UiSoundExt {
+    /// Plays the hover sound when the pointer starts hovering over the widget.
+    fn hover_sound(self, state: &mut GameState) -> Self;
+}
+
+impl UiSoundExt for Response {
+    fn hover_sound(self, state: &mut GameState) -> Self {
+        if self.hovered() {
+            if state.gui_state.last_hovered_widget != Some(self.id) {
+                play(state, UiSound::ButtonHover);
+            }
+
+            state.gui_state.hovered_widget = Some(self.id);
+        }
+
+        self
+    }
+}
+
+/// Plays the sounds for the interface events that happened this frame.
+pub fn frame_sounds(state: &mut GameState) {
+    let clicked = state.gui.context.output(|output| {
+        output
+            .events
+            .iter()
+            .any(|event| matches!(event, OutputEvent::Clicked(_)))
+    });
+
+    if clicked {
+        play(state, UiSound::ButtonClick);
+    }
+
+    if state.gui_state.popup != PopupState::None
+        && state.gui_state.popup != state.gui_state.last_popup
+    {
+        play(state, UiSound::PopupOpen);
+    }
+    state.gui_state.last_popup = state.gui_state.popup.clone();
+
+    let error = state.resource_man.error_man.peek();
+    if error.is_some() && error != state.gui_state.last_error {
+        state.audio_dispatch.send(GameEvent::Alert);
+    }
+    state.gui_state.last_error = error;
+
+    state.gui_state.last_hovered_widget = state.gui_state.hovered_widget.take();
+}
@@ -0,0 +1,175 @@
+use std::time::{Duration, Instant};
+
+use egui::{vec2, Align2, ComboBox, ScrollArea, Window};
+use ractor::rpc::CallResult;
+
+use automancy_resources::format;
+
+use crate::game::{GameEvent, GameSystemMessage};
+use crate::gui::toast::push_toast;
+use crate::zone::ZoneStats;
+use crate::GameState;
+
+/// How often the statistics window asks the game for new numbers.
+const STATISTICS_REFRESH: Duration = Duration::from_secs(1);
+/// How often the zones with alerts on are checked for stalled machines.
+const ZONE_ALERT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Gets the names of the current map's zones, and whether their alerts are on.
+fn zone_names(state: &GameState) -> Vec<(String, bool)> {
+    state
+        .loop_store
+        .map_info
+        .as_ref()
+        .map(|(info, _)| {
+            info.blocking_lock()
+                .zones
+                .iter()
+                .map(|zone| (zone.name.clone(), zone.alerts))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn zone_stats(state: &GameState, zone: Option<usize>) -> ZoneStats {
+    match state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetZoneStats(zone, reply), None),
+    ) {
+        Ok(CallResult::Success(stats)) => stats,
+        _ => Default::default(),
+    }
+}
+
+/// Draws the statistics of the whole map, or of a single zone.
+///
+/// This asks the game for the numbers, so it must not be called while the map info is locked.
+pub fn statistics_ui(state: &mut GameState) {
+    let zones = zone_names(state);
+
+    if state
+        .gui_state
+        .statistics_zone
+        .is_some_and(|v| v >= zones.len())
+    {
+        state.gui_state.statistics_zone = None;
+    }
+
+    let refresh = match &state.gui_state.statistics {
+        Some((zone, instant, _)) => {
+            *zone != state.gui_state.statistics_zone || instant.elapsed() > STATISTICS_REFRESH
+        }
+        None => true,
+    };
+    if refresh {
+        let stats = zone_stats(state, state.gui_state.statistics_zone);
+
+        state.gui_state.statistics = Some((state.gui_state.statistics_zone, Instant::now(), stats));
+    }
+
+    let Some((_, _, stats)) = state.gui_state.statistics.clone() else {
+        return;
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let translates = state.resource_man.translates();
+    let whole_map = translates.gui[&gui_ids.lbl_whole_map].as_str();
+
+    Window::new(translates.gui[&gui_ids.statistics_title].as_str())
+        .id("statistics".into())
+        .resizable(false)
+        .collapsible(true)
+        .default_width(250.0)
+        .anchor(Align2::LEFT_BOTTOM, vec2(10.0, -40.0))
+        .show(&state.gui.context.clone(), |ui| {
+            let selected = state
+                .gui_state
+                .statistics_zone
+                .and_then(|index| zones.get(index))
+                .map(|(name, _)| name.as_str())
+                .unwrap_or(whole_map);
+
+            ComboBox::from_id_source("statistics_zone")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut state.gui_state.statistics_zone, None, whole_map);
+
+                    for (index, (name, _)) in zones.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut state.gui_state.statistics_zone,
+                            Some(index),
+                            name.as_str(),
+                        );
+                    }
+                });
+
+            ui.separator();
+
+            ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                let mut tiles = stats.tiles.iter().collect::<Vec<_>>();
+                tiles.sort_by(|a, b| b.1.cmp(a.1));
+
+                for (id, count) in tiles {
+                    ui.horizontal(|ui| {
+                        ui.label(state.resource_man.tile_name(id));
+                        ui.label(state.resource_man.translates().locale.count(*count as u64));
+                    });
+                }
+            });
+
+            ui.separator();
+
+            ui.label(format(
+                translates.gui[&gui_ids.lbl_stalled_machines].as_str(),
+                &[&translates.locale.count(stats.stalled.len() as u64)],
+            ));
+            ui.label(format(
+                translates.gui[&gui_ids.lbl_crashed_tiles].as_str(),
+                &[&translates.locale.count(stats.crashed.len() as u64)],
+            ));
+        });
+}
+
+/// Warns the player when more machines stall in a zone that has alerts on.
+///
+/// This asks the game for the numbers, so it must not be called while the map info is locked.
+pub fn check_zone_alerts(state: &mut GameState) {
+    if state
+        .gui_state
+        .last_zone_alert_check
+        .is_some_and(|v| v.elapsed() < ZONE_ALERT_INTERVAL)
+    {
+        return;
+    }
+    state.gui_state.last_zone_alert_check = Some(Instant::now());
+
+    let zones = zone_names(state);
+    state
+        .gui_state
+        .zone_stalled
+        .retain(|index, _| zones.get(*index).is_some_and(|(_, alerts)| *alerts));
+
+    for (index, (name, _)) in zones.iter().enumerate().filter(|(_, (_, alerts))| *alerts) {
+        let stalled = zone_stats(state, Some(index)).stalled.len();
+        let before = state
+            .gui_state
+            .zone_stalled
+            .insert(index, stalled)
+            .unwrap_or(0);
+
+        if stalled > before {
+            let toast = format(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.toast_machines_stalled]
+                    .as_str(),
+                &[
+                    &state.resource_man.translates().locale.count(stalled as u64),
+                    name,
+                ],
+            );
+            push_toast(state, toast);
+            state.audio_dispatch.send(GameEvent::Alert);
+        }
+    }
+}
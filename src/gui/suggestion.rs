@@ -0,0 +1,93 @@
+use ractor::rpc::CallResult;
+
+use automancy_defs::math::{Float, Matrix4, FAR};
+use automancy_defs::rendering::InstanceData;
+use automancy_resources::data::DataMap;
+
+use crate::game::GameSystemMessage;
+use crate::gui::placement::check_placement;
+use crate::suggestion::{suggest_tiles, Suggestion};
+use crate::GameState;
+
+/// Finds the tiles to suggest where the player is pointing, asking the game only when the pointer moved to another tile.
+pub fn update_suggestions(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    editor_mode: bool,
+    survival: bool,
+) {
+    let coord = state.camera.pointing_at;
+
+    if !state.options.gui.tile_suggestions || !coord.is_in_world() {
+        state.gui_state.suggestions = None;
+        return;
+    }
+
+    if state.gui_state.suggestions.as_ref().map(|v| v.0) == Some(coord) {
+        return;
+    }
+
+    let mut coords = coord.neighbors().to_vec();
+    coords.push(coord);
+
+    let tiles = match state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetTiles(coords, reply), None),
+    ) {
+        Ok(CallResult::Success(tiles)) => tiles,
+        _ => vec![],
+    };
+
+    let suggestions = if tiles.iter().any(|(v, ..)| *v == coord) {
+        vec![]
+    } else {
+        let neighbors = tiles
+            .into_iter()
+            .map(|(v, id, data)| (v - coord, id, data))
+            .collect::<Vec<_>>();
+
+        suggest_tiles(&state.resource_man, &neighbors)
+            .into_iter()
+            .filter(|suggestion| {
+                check_placement(state, game_data, suggestion.id, editor_mode, survival).valid
+            })
+            .collect()
+    };
+
+    state.gui_state.suggestions = Some((coord, suggestions));
+}
+
+/// Gets the best tile to suggest where the player is pointing, if there is one.
+pub fn current_suggestion(state: &GameState) -> Option<&Suggestion> {
+    state
+        .gui_state
+        .suggestions
+        .as_ref()
+        .filter(|(coord, _)| *coord == state.camera.pointing_at)
+        .and_then(|(_, suggestions)| suggestions.first())
+}
+
+/// Draws the suggested tile faintly where the player is pointing.
+pub fn suggestion_ghost(state: &mut GameState) {
+    let Some(model) = current_suggestion(state)
+        .and_then(|suggestion| state.resource_man.registry.tiles.get(&suggestion.id))
+        .map(|tile| state.resource_man.get_model(tile.model))
+    else {
+        return;
+    };
+
+    state.renderer.extra_instances.push((
+        InstanceData::default()
+            .with_alpha(0.3)
+            .with_light_pos(state.camera.get_relative_pos().as_vec3(), None)
+            .with_world_matrix(state.camera.get_matrix().as_mat4())
+            .with_model_matrix(Matrix4::from_translation(
+                state
+                    .camera
+                    .rebase_coord(state.camera.pointing_at)
+                    .extend(FAR as Float),
+            )),
+        model,
+    ));
+}
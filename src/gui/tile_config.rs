@@ -1,102 +1,171 @@
-use std::time::Instant;
+use std::ops::Deref;
 
 use egui::Frame;
-use egui::{vec2, DragValue, Margin, Ui, Window};
+use egui::{
+    vec2, Button, Color32, DragValue, Grid, Margin, Pos2, ProgressBar, Rect, Sense, Stroke,
+    TextEdit, Ui, Window,
+};
 use ractor::rpc::CallResult;
-use ractor::ActorRef;
+use ron::ser::PrettyConfig;
 
+use automancy_defs::colors;
 use automancy_defs::coord::TileCoord;
 use automancy_defs::id::Id;
 use automancy_defs::math::Float;
 use automancy_resources::data::inventory::Inventory;
-use automancy_resources::data::stack::ItemStack;
-use automancy_resources::data::{Data, DataMap};
+use automancy_resources::data::stack::{ItemAmount, ItemStack};
+use automancy_resources::data::{Data, DataMap, DataMapRaw};
+use automancy_resources::format;
 use automancy_resources::types::tile::TileDef;
 
+use crate::game::TAKE_ITEM_ANIMATION_SPEED;
+use crate::gui::animation::{
+    AnimationKey, UiAnimation, UiAnimationKind, FLASH_DURATION, POPUP_DURATION,
+};
 use crate::gui::item::draw_item;
+use crate::gui::pinned::pin_button;
+use crate::gui::repeat::repeat_button;
 use crate::gui::{hover_tip, searchable_id, TextField, MEDIUM_ICON_SIZE, SMALL_ICON_SIZE};
-use crate::tile_entity::TileEntityMsg;
+use crate::tile_entity::{TileEntityMsg, TileHandle};
 use crate::GameState;
 
-/// Draws the direction selector.
-pub fn add_direction(ui: &mut Ui, target_coord: &mut Option<TileCoord>, n: u8) {
-    let coord = match n {
-        0 => Some(TileCoord::TOP_RIGHT),
-        1 => Some(TileCoord::RIGHT),
-        2 => Some(TileCoord::BOTTOM_RIGHT),
-        3 => Some(TileCoord::BOTTOM_LEFT),
-        4 => Some(TileCoord::LEFT),
-        5 => Some(TileCoord::TOP_LEFT),
-        _ => None,
-    };
+/// How many item slots are in a row of the storage panel.
+const STORAGE_COLUMNS: usize = 2;
+
+/// The directions of the edges of the side selector, clockwise from the top right.
+const SIDES: [TileCoord; 6] = [
+    TileCoord::TOP_RIGHT,
+    TileCoord::RIGHT,
+    TileCoord::BOTTOM_RIGHT,
+    TileCoord::BOTTOM_LEFT,
+    TileCoord::LEFT,
+    TileCoord::TOP_LEFT,
+];
+
+/// Gets the corner of the hexagon at the start of the nth edge, going clockwise from the top.
+fn hex_corner(center: Pos2, radius: Float, n: usize) -> Pos2 {
+    let angle = (-90.0 + 60.0 * n as Float).to_radians();
+
+    center + vec2(angle.cos(), angle.sin()) * radius
+}
+
+/// Draws the side selector, a hexagon with clickable edges.
+/// Clicking an edge makes the tile output there, and right clicking it toggles whether the tile accepts input from it.
+/// Clicking the middle clears the output.
+fn side_selector(ui: &mut Ui, target: &mut Option<TileCoord>, inputs: &mut Vec<TileCoord>) {
+    const RADIUS: Float = 40.0;
+
+    let (rect, response) = ui.allocate_exact_size(vec2(RADIUS, RADIUS) * 2.2, Sense::click());
+    let center = rect.center();
 
-    ui.selectable_value(
-        target_coord,
-        coord,
-        match n {
-            0 => "\u{f46c}",
-            1 => "\u{f432}",
-            2 => "\u{f43e}",
-            3 => "\u{f424}",
-            4 => "\u{f434}",
-            5 => "\u{f45c}",
-            _ => "",
+    let hovered = response.hover_pos().map(|pos| {
+        let offset = pos - center;
+
+        if offset.length() < RADIUS * 0.4 {
+            None
+        } else {
+            let angle = offset.y.atan2(offset.x).to_degrees() + 90.0;
+
+            Some(SIDES[(angle.rem_euclid(360.0) / 60.0) as usize % 6])
+        }
+    });
+
+    if let Some(hovered) = hovered {
+        if response.clicked() {
+            *target = hovered;
+        }
+
+        if let Some(side) = hovered {
+            if response.secondary_clicked() {
+                if let Some(idx) = inputs.iter().position(|v| *v == side) {
+                    inputs.remove(idx);
+                } else {
+                    inputs.push(side);
+                }
+            }
+        }
+    }
+
+    let visuals = ui.visuals().clone();
+    let painter = ui.painter_at(rect);
+
+    painter.circle_filled(
+        center,
+        RADIUS * 0.4,
+        if hovered == Some(None) {
+            visuals.widgets.hovered.bg_fill
+        } else {
+            visuals.widgets.inactive.bg_fill
         },
     );
+
+    for (n, side) in SIDES.into_iter().enumerate() {
+        let color = if *target == Some(side) {
+            Color32::from(colors::ORANGE)
+        } else if inputs.contains(&side) {
+            Color32::from(colors::LIGHT_BLUE)
+        } else if hovered == Some(Some(side)) {
+            visuals.widgets.hovered.fg_stroke.color
+        } else {
+            visuals.widgets.inactive.fg_stroke.color
+        };
+
+        painter.line_segment(
+            [
+                hex_corner(center, RADIUS, n),
+                hex_corner(center, RADIUS, n + 1),
+            ],
+            Stroke::new(6.0, color),
+        );
+    }
 }
 
-fn config_target(
-    state: &GameState,
-    ui: &mut Ui,
-    data: &DataMap,
-    tile_entity: ActorRef<TileEntityMsg>,
-) {
+fn config_target(state: &GameState, ui: &mut Ui, data: &DataMap, tile_entity: TileHandle) {
     let current_target_coord = data
         .get(&state.resource_man.registry.data_ids.target)
         .cloned()
         .and_then(Data::into_coord);
+    let current_inputs = data
+        .get(&state.resource_man.registry.data_ids.input_sides)
+        .cloned()
+        .and_then(Data::into_vec_coord)
+        .unwrap_or_default();
 
     let mut new_target_coord = current_target_coord;
-    ui.label(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.tile_config_target]
-            .as_str(),
-    );
+    let mut new_inputs = current_inputs.clone();
 
-    ui.vertical(|ui| {
-        ui.horizontal(|ui| {
-            ui.add_space(15.0);
-            add_direction(ui, &mut new_target_coord, 5);
-            add_direction(ui, &mut new_target_coord, 0);
-        });
-
-        ui.horizontal(|ui| {
-            add_direction(ui, &mut new_target_coord, 4);
-            ui.selectable_value(&mut new_target_coord, None, "\u{f467}");
-            add_direction(ui, &mut new_target_coord, 1);
-        });
-
-        ui.horizontal(|ui| {
-            ui.add_space(15.0);
-            add_direction(ui, &mut new_target_coord, 3);
-            add_direction(ui, &mut new_target_coord, 2);
-        });
+    ui.horizontal(|ui| {
+        ui.label(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.tile_config_target]
+                .as_str(),
+        );
+        hover_tip(
+            ui,
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.tile_config_sides_tip]
+                .as_str(),
+        );
     });
 
+    side_selector(ui, &mut new_target_coord, &mut new_inputs);
+
     if new_target_coord != current_target_coord {
-        if let Some(target_coord) = new_target_coord {
-            tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
-                    state.resource_man.registry.data_ids.target,
-                    Data::Coord(target_coord),
-                ))
-                .unwrap();
-        } else {
-            tile_entity
-                .send_message(TileEntityMsg::RemoveData(
-                    state.resource_man.registry.data_ids.target,
-                ))
-                .unwrap();
-        }
+        tile_entity
+            .configure(
+                state.resource_man.registry.data_ids.target,
+                new_target_coord.map(Data::Coord),
+            )
+            .unwrap();
+    }
+
+    if new_inputs != current_inputs {
+        tile_entity
+            .configure(
+                state.resource_man.registry.data_ids.input_sides,
+                (!new_inputs.is_empty()).then_some(Data::VecCoord(new_inputs)),
+            )
+            .unwrap();
     }
 }
 
@@ -105,7 +174,7 @@ fn config_linking(state: &mut GameState, ui: &mut Ui, config_open: TileCoord) {
 
     if ui
         .button(
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.btn_link_network]
                 .to_string(),
         )
@@ -115,7 +184,7 @@ fn config_linking(state: &mut GameState, ui: &mut Ui, config_open: TileCoord) {
     };
 
     ui.label(
-        state.resource_man.translates.gui
+        state.resource_man.translates().gui
             [&state.resource_man.registry.gui_ids.lbl_link_destination]
             .to_string(),
     );
@@ -125,7 +194,7 @@ fn config_amount(
     state: &mut GameState,
     ui: &mut Ui,
     data: &DataMap,
-    tile_entity: ActorRef<TileEntityMsg>,
+    tile_entity: TileHandle,
     tile_info: &TileDef,
 ) {
     let Some(Data::Amount(max_amount)) = tile_info
@@ -145,34 +214,117 @@ fn config_amount(
     };
 
     let mut new_amount = current_amount;
+    let rate = state.options.gui.repeat_rate;
 
-    ui.add(
-        DragValue::new(&mut new_amount)
-            .clamp_range(0..=max_amount)
-            .speed(1.0)
-            .prefix(
-                state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.lbl_amount]
-                    .to_string(),
-            ),
-    );
+    ui.horizontal(|ui| {
+        let down = repeat_button(ui, "-", rate);
+
+        ui.add(
+            DragValue::new(&mut new_amount)
+                .clamp_range(0..=max_amount)
+                .speed(1.0)
+                .prefix(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.lbl_amount]
+                        .to_string(),
+                ),
+        );
+
+        let up = repeat_button(ui, "+", rate);
+
+        new_amount = (new_amount as i64 + up - down).clamp(0, max_amount as i64) as ItemAmount;
+    });
 
     if new_amount != current_amount {
         tile_entity
-            .send_message(TileEntityMsg::SetDataValue(
+            .configure(
                 state.resource_man.registry.data_ids.amount,
-                Data::Amount(new_amount),
-            ))
+                Some(Data::Amount(new_amount)),
+            )
             .unwrap();
     }
 }
 
-fn takeable_item(
+/// Moves the items from the buffer into the player's inventory, and plays the take animation.
+fn take_from_buffer(
+    state: &mut GameState,
+    buffer: &mut Inventory,
+    inventory: &mut Inventory,
+    id: Id,
+    amount: ItemAmount,
+    rect: Rect,
+) -> bool {
+    let Some(amount) = buffer.take(id, amount) else {
+        return false;
+    };
+
+    inventory.add(id, amount);
+
+    let limit = state.options.memory.max_take_item_animations;
+    let now = state.clocks.ui.now();
+    let locale = state.resource_man.translates().locale;
+    let animations = &mut state.gui_state.animations;
+
+    animations.push(
+        AnimationKey::TakeItem(state.resource_man.registry.items[&id]),
+        UiAnimation::new(now, TAKE_ITEM_ANIMATION_SPEED, UiAnimationKind::FlyTo(rect)),
+        limit,
+    );
+    animations.push(
+        AnimationKey::Effect,
+        UiAnimation::new(
+            now,
+            FLASH_DURATION,
+            UiAnimationKind::Flash {
+                rect,
+                color: Color32::from(colors::WHITE).gamma_multiply(0.3),
+            },
+        ),
+        limit,
+    );
+    animations.push(
+        AnimationKey::Effect,
+        UiAnimation::new(
+            now,
+            POPUP_DURATION,
+            UiAnimationKind::Popup {
+                pos: rect.center_top(),
+                text: format!("+{}", locale.compact(amount as f64)),
+                color: Color32::from(colors::WHITE),
+            },
+        ),
+        limit,
+    );
+
+    true
+}
+
+/// Draws the storage panel, with how full the storage is, the stored items, and buttons to move items to and from the player's inventory.
+fn config_storage(
     state: &mut GameState,
     ui: &mut Ui,
     game_data: &mut DataMap,
-    mut buffer: Inventory,
-    tile_entity: ActorRef<TileEntityMsg>,
+    data: &DataMap,
+    tile_entity: TileHandle,
+    tile_info: &TileDef,
 ) {
+    let mut buffer = match data.get(&state.resource_man.registry.data_ids.buffer) {
+        Some(Data::Inventory(buffer)) => buffer.clone(),
+        _ => Inventory::default(),
+    };
+    let filter = data
+        .get(&state.resource_man.registry.data_ids.item)
+        .cloned()
+        .and_then(Data::into_id);
+    let mut filters = match data.get(&state.resource_man.registry.data_ids.filters) {
+        Some(Data::VecId(filters)) => filters.clone(),
+        _ => Vec::new(),
+    };
+    let capacity = match data.get(&state.resource_man.registry.data_ids.amount) {
+        Some(Data::Amount(amount)) => *amount,
+        _ => 0,
+    };
+
     let Data::Inventory(inventory) = game_data
         .entry(state.resource_man.registry.data_ids.player_inventory)
         .or_insert_with(|| Data::Inventory(Default::default()))
@@ -180,32 +332,187 @@ fn takeable_item(
         return;
     };
 
+    let stored = buffer.values().sum::<ItemAmount>();
+    let translates = state.resource_man.translates();
+
+    ui.add(
+        ProgressBar::new(if capacity > 0 {
+            stored as f32 / capacity as f32
+        } else {
+            0.0
+        })
+        .text(format(
+            translates.gui[&state.resource_man.registry.gui_ids.lbl_storage_capacity].as_str(),
+            &[
+                translates.locale.count(stored).as_str(),
+                translates.locale.count(capacity).as_str(),
+            ],
+        )),
+    );
+
+    // everything the storage accepts, in slot order
+    let accepted = filter
+        .into_iter()
+        .chain(filters.iter().copied().filter(|id| Some(*id) != filter))
+        .collect::<Vec<_>>();
+
     let mut dirty = false;
+    let mut filters_dirty = false;
+    // the locked slots come first, even when they're empty, so it's clear what the storage accepts
+    let mut slots = accepted
+        .iter()
+        .map(|id| (*id, buffer.deref().get(id).copied().unwrap_or(0)))
+        .collect::<Vec<_>>();
+    slots.extend(
+        buffer
+            .iter()
+            .filter(|(id, amount)| **amount > 0 && !accepted.contains(id))
+            .map(|(id, amount)| (*id, *amount)),
+    );
 
-    for (id, amount) in buffer.clone().into_inner() {
-        let item = *state.resource_man.registry.items.get(&id).unwrap();
+    Grid::new("storage_slots")
+        .num_columns(STORAGE_COLUMNS)
+        .show(ui, |ui| {
+            for (n, (id, amount)) in slots.into_iter().enumerate() {
+                let Some(item) = state.resource_man.registry.items.get(&id).copied() else {
+                    continue;
+                };
 
-        let (rect, response) = draw_item(
-            &state.resource_man,
-            ui,
-            None,
-            ItemStack { item, amount },
-            MEDIUM_ICON_SIZE,
-            true,
-        );
+                let (rect, response) = draw_item(
+                    &state.resource_man,
+                    ui,
+                    None,
+                    ItemStack { item, amount },
+                    MEDIUM_ICON_SIZE,
+                    true,
+                );
 
-        if response.clicked() {
-            if let Some(amount) = buffer.take(id, amount) {
-                dirty = true;
-                inventory.add(id, amount);
-                state
-                    .renderer
-                    .take_item_animations
-                    .entry(item)
-                    .or_default()
-                    .push_back((Instant::now(), rect));
+                let response = if accepted.contains(&id) {
+                    response.on_hover_text(
+                        state.resource_man.translates().gui
+                            [&state.resource_man.registry.gui_ids.lbl_storage_filter]
+                            .as_str(),
+                    )
+                } else {
+                    response
+                }
+                .on_hover_text(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.lbl_storage_slot_tip]
+                        .as_str(),
+                );
+
+                if response.clicked() && amount > 0 {
+                    dirty |= take_from_buffer(state, &mut buffer, inventory, id, amount, rect);
+                }
+
+                // the configured item is unlocked by picking another one, so only the slot filters toggle here
+                if response.secondary_clicked() && Some(id) != filter {
+                    if let Some(index) = filters.iter().position(|v| *v == id) {
+                        filters.remove(index);
+                    } else {
+                        filters.push(id);
+                    }
+
+                    filters_dirty = true;
+                }
+
+                if (n + 1) % STORAGE_COLUMNS == 0 {
+                    ui.end_row();
+                }
+            }
+        });
+
+    ui.horizontal(|ui| {
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_take_all]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            let rect = ui.min_rect();
+
+            for (id, amount) in buffer.clone().into_inner() {
+                dirty |= take_from_buffer(state, &mut buffer, inventory, id, amount, rect);
             }
         }
+
+        let mut space = capacity.saturating_sub(stored);
+        if ui
+            .add_enabled(
+                !accepted.is_empty() && space > 0,
+                Button::new(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.btn_store_all]
+                        .as_str(),
+                ),
+            )
+            .clicked()
+        {
+            for id in &accepted {
+                let amount = inventory.get(*id).min(space);
+
+                if let Some(amount) = inventory.take(*id, amount) {
+                    buffer.add(*id, amount);
+                    space -= amount;
+                    dirty = true;
+                }
+            }
+        }
+    });
+
+    if let Some(Data::Id(item_type)) = tile_info
+        .data
+        .get(&state.resource_man.registry.data_ids.item_type)
+    {
+        let items = state
+            .resource_man
+            .get_items(*item_type, &mut state.loop_store.tag_cache)
+            .iter()
+            .map(|item| item.id)
+            .filter(|id| !accepted.contains(id))
+            .collect::<Vec<_>>();
+
+        ui.collapsing(
+            state.resource_man.translates().gui
+                [&state.resource_man.registry.gui_ids.lbl_storage_add_filter]
+                .as_str(),
+            |ui| {
+                let mut new_filter = None;
+
+                searchable_id(
+                    state,
+                    ui,
+                    items.as_slice(),
+                    &mut new_filter,
+                    TextField::Filter,
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.search_item_tip]
+                        .to_string(),
+                    &|state, id| state.resource_man.item_name(id),
+                    &|state, ui, id| {
+                        draw_item(
+                            &state.resource_man,
+                            ui,
+                            None,
+                            ItemStack {
+                                item: state.resource_man.registry.items[id],
+                                amount: 0,
+                            },
+                            SMALL_ICON_SIZE,
+                            false,
+                        );
+                    },
+                );
+
+                if let Some(id) = new_filter {
+                    filters.push(id);
+                    filters_dirty = true;
+                }
+            },
+        );
     }
 
     if dirty {
@@ -216,14 +523,24 @@ fn takeable_item(
             ))
             .unwrap();
     }
+
+    if filters_dirty {
+        tile_entity
+            .configure(
+                state.resource_man.registry.data_ids.filters,
+                (!filters.is_empty()).then_some(Data::VecId(filters)),
+            )
+            .unwrap();
+    }
 }
 
 fn config_item(
     state: &mut GameState,
     ui: &mut Ui,
+    game_data: &mut DataMap,
     data: &DataMap,
     item_type: Id,
-    tile_entity: ActorRef<TileEntityMsg>,
+    tile_entity: TileHandle,
     tile_info: &TileDef,
 ) {
     let current_item = data
@@ -241,12 +558,22 @@ fn config_item(
 
     ui.horizontal(|ui| {
         ui.label(
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.tile_config_item]
                 .as_str(),
         );
 
         config_amount(state, ui, data, tile_entity.clone(), tile_info);
+
+        if let Some(item) = current_item {
+            pin_button(
+                state,
+                ui,
+                game_data,
+                state.resource_man.registry.data_ids.pinned_items,
+                item,
+            );
+        }
     });
 
     if let Some(stack) = current_item
@@ -262,9 +589,9 @@ fn config_item(
         items.as_slice(),
         &mut new_item,
         TextField::Filter,
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.search_item_tip]
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.search_item_tip]
             .to_string(),
-        &|state, id| state.resource_man.item_name(id).to_string(),
+        &|state, id| state.resource_man.item_name(id),
         &|state, ui, id| {
             draw_item(
                 &state.resource_man,
@@ -283,10 +610,10 @@ fn config_item(
     if new_item != current_item {
         if let Some(item) = new_item {
             tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
+                .configure(
                     state.resource_man.registry.data_ids.item,
-                    Data::Id(item),
-                ))
+                    Some(Data::Id(item)),
+                )
                 .unwrap();
             tile_entity
                 .send_message(TileEntityMsg::RemoveData(
@@ -334,9 +661,10 @@ fn draw_script_info(state: &mut GameState, ui: &mut Ui, script: Option<Id>) {
 fn config_script(
     state: &mut GameState,
     ui: &mut Ui,
+    game_data: &mut DataMap,
     data: &DataMap,
     scripts: &[Id],
-    tile_entity: ActorRef<TileEntityMsg>,
+    tile_entity: TileHandle,
 ) {
     let current_script = data
         .get(&state.resource_man.registry.data_ids.script)
@@ -345,16 +673,26 @@ fn config_script(
 
     ui.horizontal(|ui| {
         ui.label(
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.tile_config_script]
                 .as_str(),
         );
         hover_tip(
             ui,
-            state.resource_man.translates.gui
+            state.resource_man.translates().gui
                 [&state.resource_man.registry.gui_ids.tile_config_script_info]
                 .as_str(),
         );
+
+        if let Some(script) = current_script {
+            pin_button(
+                state,
+                ui,
+                game_data,
+                state.resource_man.registry.data_ids.pinned_scripts,
+                script,
+            );
+        }
     });
 
     draw_script_info(state, ui, current_script);
@@ -367,9 +705,9 @@ fn config_script(
         scripts,
         &mut new_script,
         TextField::Filter,
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.search_script_tip]
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.search_script_tip]
             .to_string(),
-        &|state, id| state.resource_man.script_name(id).to_string(),
+        &|state, id| state.resource_man.script_name(id),
         &|state, ui, id| {
             if let Some(stacks) = state
                 .resource_man
@@ -395,10 +733,10 @@ fn config_script(
     if new_script != current_script {
         if let Some(script) = new_script {
             tile_entity
-                .send_message(TileEntityMsg::SetDataValue(
+                .configure(
                     state.resource_man.registry.data_ids.script,
-                    Data::Id(script),
-                ))
+                    Some(Data::Id(script)),
+                )
                 .unwrap();
             tile_entity
                 .send_message(TileEntityMsg::RemoveData(
@@ -409,8 +747,84 @@ fn config_script(
     }
 }
 
+/// Draws the raw data editor, used by the map editor.
+fn config_data(
+    state: &mut GameState,
+    ui: &mut Ui,
+    data: &DataMap,
+    config_open_at: TileCoord,
+    tile_entity: TileHandle,
+) {
+    if state.gui_state.editing_tile_data != Some(config_open_at) {
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_edit_data]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            *state.gui_state.text_field.get(TextField::TileData) = ron::ser::to_string_pretty(
+                &data.to_raw(&state.resource_man.interner),
+                PrettyConfig::default(),
+            )
+            .unwrap_or_default();
+            state.gui_state.editing_tile_data = Some(config_open_at);
+        }
+
+        return;
+    }
+
+    ui.add(
+        TextEdit::multiline(state.gui_state.text_field.get(TextField::TileData))
+            .code_editor()
+            .desired_rows(8),
+    );
+
+    ui.horizontal(|ui| {
+        if ui
+            .button(
+                state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.btn_apply]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            match ron::from_str::<DataMapRaw>(state.gui_state.text_field.get(TextField::TileData)) {
+                Ok(raw) => {
+                    tile_entity
+                        .send_message(TileEntityMsg::SetData(
+                            raw.to_data(&state.resource_man.interner),
+                        ))
+                        .unwrap();
+
+                    state.gui_state.editing_tile_data = None;
+                }
+                Err(err) => {
+                    state.resource_man.error_man.push(
+                        (
+                            state.resource_man.registry.err_ids.invalid_tile_data,
+                            vec![err.to_string()],
+                        ),
+                        &state.resource_man,
+                    );
+                }
+            }
+        }
+        if ui
+            .button(
+                state.resource_man.translates().gui
+                    [&state.resource_man.registry.gui_ids.btn_cancel]
+                    .as_str(),
+            )
+            .clicked()
+        {
+            state.gui_state.editing_tile_data = None;
+        }
+    });
+}
+
 /// Draws the tile configuration menu.
-pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
+pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap, editor_mode: bool) {
     let Some(config_open_at) = state.gui_state.config_open_at else {
         return;
     };
@@ -427,7 +841,7 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
     };
 
     Window::new(
-        state.resource_man.translates.gui[&state.resource_man.registry.gui_ids.tile_config]
+        state.resource_man.translates().gui[&state.resource_man.registry.gui_ids.tile_config]
             .to_string(),
     )
     .id("tile_config".into())
@@ -454,41 +868,33 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
-                config_script(state, ui, &data, scripts, entity.clone());
+                config_script(state, ui, game_data, &data, scripts, entity.clone());
             });
             ui.add_space(MARGIN);
         }
 
         if tile_info
             .data
-            .get(&state.resource_man.registry.data_ids.storage_takeable)
-            .cloned()
-            .and_then(Data::into_bool)
-            .unwrap_or(false)
+            .flag(state.resource_man.registry.data_ids.storage_takeable)
         {
-            if let Some(Data::Inventory(buffer)) = data
-                .get(&state.resource_man.registry.data_ids.buffer)
-                .cloned()
-            {
-                ui.add_space(MARGIN);
-                ui.horizontal(|ui| {
-                    ui.label(
-                        state.resource_man.translates.gui
-                            [&state.resource_man.registry.gui_ids.inventory]
-                            .as_str(),
-                    );
-                    hover_tip(
-                        ui,
-                        state.resource_man.translates.gui
-                            [&state.resource_man.registry.gui_ids.inventory_tip]
-                            .as_str(),
-                    );
-                });
-                ui.group(|ui| {
-                    takeable_item(state, ui, game_data, buffer, entity.clone());
-                });
-                ui.add_space(MARGIN);
-            }
+            ui.add_space(MARGIN);
+            ui.horizontal(|ui| {
+                ui.label(
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.inventory]
+                        .as_str(),
+                );
+                hover_tip(
+                    ui,
+                    state.resource_man.translates().gui
+                        [&state.resource_man.registry.gui_ids.inventory_tip]
+                        .as_str(),
+                );
+            });
+            ui.group(|ui| {
+                config_storage(state, ui, game_data, &data, entity.clone(), &tile_info);
+            });
+            ui.add_space(MARGIN);
         }
 
         if let Some(Data::Id(item_type)) = tile_info
@@ -498,17 +904,22 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
-                config_item(state, ui, &data, item_type, entity.clone(), &tile_info);
+                config_item(
+                    state,
+                    ui,
+                    game_data,
+                    &data,
+                    item_type,
+                    entity.clone(),
+                    &tile_info,
+                );
             });
             ui.add_space(MARGIN);
         }
 
         if !tile_info
             .data
-            .get(&state.resource_man.registry.data_ids.not_targeted)
-            .cloned()
-            .and_then(Data::into_bool)
-            .unwrap_or(false)
+            .flag(state.resource_man.registry.data_ids.not_targeted)
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
@@ -519,10 +930,7 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
 
         if tile_info
             .data
-            .get(&state.resource_man.registry.data_ids.linking)
-            .cloned()
-            .and_then(Data::into_bool)
-            .unwrap_or(false)
+            .flag(state.resource_man.registry.data_ids.linking)
         {
             ui.add_space(MARGIN);
             ui.vertical(|ui| {
@@ -530,5 +938,13 @@ pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
             });
             ui.add_space(MARGIN);
         }
+
+        if editor_mode {
+            ui.add_space(MARGIN);
+            ui.vertical(|ui| {
+                config_data(state, ui, &data, config_open_at, entity.clone());
+            });
+            ui.add_space(MARGIN);
+        }
     });
 }
@@ -12,7 +12,7 @@ use automancy_defs::{colors, math};
 use automancy_resources::data::{Data, DataMap};
 use automancy_resources::format;
 
-use crate::gui::{GameEguiCallback, LARGE_ICON_SIZE, MEDIUM_ICON_SIZE};
+use crate::gui::{hotbar, GameEguiCallback, LARGE_ICON_SIZE, MEDIUM_ICON_SIZE};
 use crate::util::is_research_unlocked;
 use crate::GameState;
 
@@ -47,6 +47,7 @@ fn draw_tile_selection(
     state: &mut GameState,
     ui: &mut Ui,
     game_data: &mut DataMap,
+    editor_mode: bool,
     selection_send: &mut Option<oneshot::Sender<Id>>,
     current_category: Option<Id>,
 ) {
@@ -55,7 +56,9 @@ fn draw_tile_selection(
         * math::view(dvec3(0.0, 0.0, 2.75));
     let projection = projection.as_mat4();
 
-    let has_item = if let Some(category) = current_category {
+    let has_item = if editor_mode {
+        true
+    } else if let Some(category) = current_category {
         has_category_item(state, game_data, category)
     } else {
         true
@@ -71,15 +74,11 @@ fn draw_tile_selection(
             }
         }
 
-        let is_default_tile = match state.resource_man.registry.tiles[id]
+        let is_default_tile = state.resource_man.registry.tiles[id]
             .data
-            .get(&state.resource_man.registry.data_ids.default_tile)
-        {
-            Some(Data::Bool(v)) => *v,
-            _ => false,
-        };
+            .flag(state.resource_man.registry.data_ids.default_tile);
 
-        if !is_default_tile {
+        if !(is_default_tile || editor_mode) {
             if let Some(research) = state.resource_man.get_research_by_unlock(*id) {
                 if !is_research_unlocked(research.id, &state.resource_man, game_data) {
                     continue;
@@ -92,9 +91,17 @@ fn draw_tile_selection(
 
         let (ui_id, rect) = ui.allocate_space(vec2(size, size));
 
+        let hotbar_hint =
+            if hotbar::hotbar_slots(&state.resource_man, game_data).contains(&Some(*id)) {
+                state.resource_man.registry.gui_ids.lbl_hotbar_remove
+            } else {
+                state.resource_man.registry.gui_ids.lbl_hotbar_add
+            };
+
         let response = ui
             .interact(rect, ui_id, Sense::click())
             .on_hover_text(state.resource_man.tile_name(id))
+            .on_hover_text(state.resource_man.translates().gui[&hotbar_hint].as_str())
             .on_hover_cursor(CursorIcon::Grab);
 
         let response = if !(is_default_tile || has_item) {
@@ -103,13 +110,13 @@ fn draw_tile_selection(
             {
                 response
                     .on_hover_text(format(
-                        state.resource_man.translates.gui[&state
+                        state.resource_man.translates().gui[&state
                             .resource_man
                             .registry
                             .gui_ids
                             .lbl_cannot_place_missing_item]
                             .as_str(),
-                        &[state.resource_man.item_name(&item)],
+                        &[state.resource_man.item_name(&item).as_str()],
                     ))
                     .on_hover_cursor(CursorIcon::NotAllowed)
             } else {
@@ -124,6 +131,9 @@ fn draw_tile_selection(
                 send.send(*id).unwrap();
             }
         }
+        if response.secondary_clicked() {
+            hotbar::toggle_hotbar(&state.resource_man, game_data, *id);
+        }
 
         let rotate = Matrix4::from_rotation_x(tile_hover_z_angle(ui, &response));
 
@@ -153,6 +163,7 @@ fn draw_tile_selection(
 pub fn tile_selections(
     state: &mut GameState,
     game_data: &mut DataMap,
+    editor_mode: bool,
     selection_send: oneshot::Sender<Id>,
 ) {
     let projection = DMatrix4::perspective_lh(FRAC_PI_4, 1.0, z_near(), z_far())
@@ -175,6 +186,7 @@ pub fn tile_selections(
                             state,
                             ui,
                             game_data,
+                            editor_mode,
                             &mut Some(selection_send),
                             state.gui_state.tile_selection_category,
                         );
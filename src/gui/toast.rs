@@ -0,0 +1,61 @@
+//! Short notices about things the player may want to know but doesn't have to act on,
+//! such as an autosave finishing, shown in the top right corner for a few seconds.
+//!
+//! Anything with access to the game state can show one with [`push_toast`]. Things happening in the game system
+//! are sent over as [`crate::game::GameEvent`]s first, and shown by [`crate::audio::dispatch::dispatch`].
+
+use std::time::{Duration, Instant};
+
+use egui::{vec2, Align2, Area, Frame};
+
+use crate::GameState;
+
+/// How long a toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+/// How long a toast takes to fade out at the end.
+const TOAST_FADE: Duration = Duration::from_millis(600);
+/// The most toasts shown at once. Older ones are dropped first.
+const MAX_TOASTS: usize = 4;
+
+/// Shows a toast. The same text isn't shown twice at once; the toast already showing it starts over instead.
+pub fn push_toast(state: &mut GameState, text: String) {
+    let toasts = &mut state.gui_state.toasts;
+
+    toasts.retain(|(_, v)| *v != text);
+
+    if toasts.len() >= MAX_TOASTS {
+        toasts.pop_front();
+    }
+
+    toasts.push_back((Instant::now(), text));
+}
+
+/// Draws the toasts in the top right corner of the screen, newest at the bottom.
+pub fn toasts(state: &mut GameState) {
+    state
+        .gui_state
+        .toasts
+        .retain(|(added, _)| added.elapsed() < TOAST_DURATION);
+
+    if state.gui_state.toasts.is_empty() {
+        return;
+    }
+
+    Area::new("toasts".into())
+        .anchor(Align2::RIGHT_TOP, vec2(-10.0, 10.0))
+        .interactable(false)
+        .show(&state.gui.context.clone(), |ui| {
+            for (added, text) in &state.gui_state.toasts {
+                let remaining = TOAST_DURATION.saturating_sub(added.elapsed());
+                let opacity = (remaining.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+
+                ui.set_opacity(opacity);
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(text);
+                });
+            }
+        });
+
+    // keep redrawing so the toasts fade out even when nothing else happens
+    state.gui.context.request_repaint();
+}
@@ -0,0 +1,101 @@
+use egui::{vec2, Align2, TextEdit, Window};
+
+use automancy_defs::colors::{ColorAdj, Rgba};
+use automancy_resources::format;
+
+use crate::map::MapInfo;
+use crate::GameState;
+
+/// How see-through the zone overlay is.
+const ZONE_OVERLAY_ALPHA: f32 = 0.3;
+
+/// Draws the list of zones, where they can be made, renamed, recolored, and picked to be painted.
+pub fn zones_ui(state: &mut GameState, info: &mut MapInfo) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let translates = state.resource_man.translates();
+
+    let mut remove = None;
+
+    Window::new(translates.gui[&gui_ids.zones_title].as_str())
+        .id("zones".into())
+        .resizable(false)
+        .collapsible(true)
+        .default_width(250.0)
+        .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -40.0))
+        .show(&state.gui.context.clone(), |ui| {
+            for (index, zone) in info.zones.0.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut zone.color);
+                    ui.add(TextEdit::singleline(&mut zone.name).desired_width(100.0));
+
+                    let painting = state.gui_state.zoning == Some(index);
+                    if ui
+                        .selectable_label(
+                            painting,
+                            translates.gui[&gui_ids.btn_paint_zone].as_str(),
+                        )
+                        .clicked()
+                    {
+                        if painting {
+                            state.gui_state.zoning = None;
+                        } else {
+                            state.gui_state.zoning = Some(index);
+                            state.gui_state.selected_tile_id = None;
+                        }
+                    }
+
+                    ui.label(zone.tiles.len().to_string());
+
+                    ui.checkbox(
+                        &mut zone.alerts,
+                        translates.gui[&gui_ids.lbl_zone_alerts].as_str(),
+                    );
+
+                    if ui.small_button("x").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+
+            if info.zones.is_empty() {
+                ui.label(translates.gui[&gui_ids.lbl_no_zones].as_str());
+            }
+
+            ui.separator();
+
+            if ui
+                .button(translates.gui[&gui_ids.btn_new_zone].as_str())
+                .clicked()
+            {
+                let name = format(
+                    translates.gui[&gui_ids.zone_default_name].as_str(),
+                    &[&(info.zones.0.len() + 1).to_string()],
+                );
+
+                state.gui_state.zoning = Some(info.zones.add(name));
+                state.gui_state.selected_tile_id = None;
+            }
+        });
+
+    if let Some(index) = remove {
+        info.zones.remove(index);
+
+        state.gui_state.zoning = match state.gui_state.zoning {
+            Some(zoning) if zoning == index => None,
+            Some(zoning) if zoning > index => Some(zoning - 1),
+            zoning => zoning,
+        };
+    }
+}
+
+/// Tints every painted position with the color of its zone.
+pub fn zone_overlay(state: &mut GameState, info: &MapInfo) {
+    for zone in info.zones.iter() {
+        let [r, g, b] = zone.color;
+        let color = Rgba::from_srgba_unmultiplied(r, g, b, 255).with_alpha(ZONE_OVERLAY_ALPHA);
+
+        for coord in &zone.tiles {
+            state.renderer.tile_tints.insert(*coord, color);
+        }
+    }
+}
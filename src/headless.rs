@@ -0,0 +1,512 @@
+use std::sync::mpsc;
+
+use anyhow::{anyhow, bail};
+use egui_wgpu::wgpu::{
+    Adapter, Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
+    CompositeAlphaMode, Device, Extent3d, ImageCopyBuffer, ImageDataLayout, Instance,
+    InstanceDescriptor, Maintain, MapMode, PowerPreference, PresentMode, Queue,
+    RequestAdapterOptions, SurfaceConfiguration, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureViewDescriptor, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+use hashbrown::HashMap;
+use image::{imageops, RgbaImage};
+
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::{dvec2, dvec3};
+use automancy_defs::id::Id;
+use automancy_defs::log;
+use automancy_defs::math::{self, DVec3, Double, Float, Matrix4, FAR};
+use automancy_defs::rendering::{InstanceData, Vertex};
+use automancy_resources::data::DataMap;
+use automancy_resources::ResourceManager;
+
+use crate::camera::{fit_z, Camera};
+use crate::game::RenderUnit;
+use crate::gpu::{
+    self, device_descriptor, init_gpu_resources, AnimationMap, FrameGraph, FramePass,
+    FrameResource, GlobalBuffers, RenderResources, SharedResources, Uploader, GPU_BACKENDS,
+    SCREENSHOT_FORMAT,
+};
+use crate::map::Map;
+use crate::renderer::place_unit;
+
+/// The zoom level posters are rendered at. The camera looks straight down from here.
+pub const POSTER_ZOOM: Double = 1.0;
+/// The largest a poster can be on either side, in pixels.
+pub const POSTER_MAX_SIZE: u32 = 16384;
+
+/// Finds an adapter to render with, without needing a surface.
+fn request_adapter() -> Option<Adapter> {
+    let instance = Instance::new(InstanceDescriptor {
+        backends: GPU_BACKENDS,
+        ..Default::default()
+    });
+
+    pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+        power_preference: PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    }))
+}
+
+/// Checks whether there is an adapter to render headlessly with. Machines without a GPU, such as CI runners, may not have one.
+pub fn has_adapter() -> bool {
+    request_adapter().is_some()
+}
+
+/// Renders tiles offscreen, without a window, and reads the frames back as images.
+///
+/// Only the tiles are drawn; the GUI, items and overlays are left out, and nothing is animated,
+/// so the same tiles and camera always give the same image.
+pub struct HeadlessRenderer {
+    device: Device,
+    queue: Queue,
+    shared_resources: SharedResources,
+    render_resources: RenderResources,
+    global_buffers: GlobalBuffers,
+    texture: Texture,
+    buffer: Buffer,
+    size: (u32, u32),
+}
+
+impl HeadlessRenderer {
+    /// Sets up a renderer that draws images of the given size.
+    pub fn new(
+        resource_man: &ResourceManager,
+        vertices: &[Vertex],
+        indices: &[u16],
+        (width, height): (u32, u32),
+    ) -> anyhow::Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("Cannot render an image of size {width}x{height}");
+        }
+
+        let adapter =
+            request_adapter().ok_or_else(|| anyhow!("No graphics adapter is available"))?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&device_descriptor(), None))?;
+
+        // there is no surface, but the resources are only made from its format and size
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: PresentMode::Fifo,
+            alpha_mode: CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let (shared_resources, render_resources, global_buffers, _) = init_gpu_resources(
+            &device,
+            &queue,
+            &config,
+            resource_man,
+            vertices,
+            indices,
+            &|stage| log::info!("Setting up {stage} for headless rendering..."),
+        );
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Headless Output Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: SCREENSHOT_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Headless Output Buffer"),
+            size: ((width * 4).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT) * height)
+                as BufferAddress,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            shared_resources,
+            render_resources,
+            global_buffers,
+            texture,
+            buffer,
+            size: (width, height),
+        })
+    }
+
+    /// The size of the images this renders.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Renders the tiles with the camera placed at `camera_pos`, the same way as in game, with `z` being the zoom level.
+    pub fn render(
+        &mut self,
+        resource_man: &ResourceManager,
+        tiles: &[(TileCoord, Id, DataMap)],
+        camera_pos: DVec3,
+    ) -> anyhow::Result<RgbaImage> {
+        let (width, height) = self.size;
+
+        let size = (width as Double, height as Double);
+        let mut camera = Camera::new(size);
+        camera.set_pos(camera_pos, size);
+
+        let culling_range = camera.culling_range;
+        let none = resource_man.registry.tiles[&resource_man.registry.none].model;
+
+        let mut units = HashMap::<TileCoord, RenderUnit>::new();
+        for (coord, id, data) in tiles {
+            if !culling_range.is_in_bounds(**coord) {
+                continue;
+            }
+            let Some(tile) = resource_man.registry.tiles.get(id) else {
+                continue;
+            };
+
+            let mut unit = RenderUnit {
+                instance: InstanceData::default(),
+                tile_id: *id,
+                model: resource_man.get_model(tile.model),
+                crashed: false,
+            };
+            place_unit(resource_man, &camera, *coord, &mut unit, Some(data));
+
+            units.insert(*coord, unit);
+        }
+
+        for hex in culling_range.all_coords() {
+            let coord = TileCoord::from(hex);
+
+            if coord.is_in_world() && !units.contains_key(&coord) {
+                units.insert(
+                    coord,
+                    RenderUnit {
+                        instance: InstanceData::default().with_model_matrix(
+                            Matrix4::from_translation(
+                                camera.rebase_coord(coord).extend(FAR as Float),
+                            ),
+                        ),
+                        tile_id: none,
+                        model: none,
+                        crashed: false,
+                    },
+                );
+            }
+        }
+
+        let camera_pos_float = camera.get_relative_pos().as_vec3();
+        let world_matrix = camera.get_matrix().as_mat4();
+
+        let mut game_instances = units
+            .into_values()
+            .map(
+                |RenderUnit {
+                     instance, model, ..
+                 }| {
+                    (
+                        instance
+                            .with_light_pos(camera_pos_float, None)
+                            .with_world_matrix(world_matrix),
+                        model,
+                        (),
+                    )
+                },
+            )
+            .collect::<Vec<(InstanceData, Id, ())>>();
+        game_instances.sort_by_key(|(_, model, _)| *model);
+
+        let game_instances = gpu::indirect_instance(
+            resource_man,
+            &game_instances,
+            true,
+            &AnimationMap::default(),
+            Some(camera_pos_float),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Headless Render Encoder"),
+            });
+        let mut uploader = Uploader::default();
+
+        let game_draw_count = self.render_resources.game_resources.upload(
+            &self.device,
+            &mut encoder,
+            &mut uploader,
+            game_instances,
+        );
+
+        let texture_width = width * 4;
+        let padded_width = texture_width.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        {
+            let shared_resources = &self.shared_resources;
+            let render_resources = &self.render_resources;
+            let global_buffers = &self.global_buffers;
+            let texture = &self.texture;
+            let buffer = &self.buffer;
+
+            let mut graph = FrameGraph::default();
+
+            graph.push(FramePass::new(
+                "game",
+                &[],
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                move |encoder| {
+                    gpu::world_pass(
+                        encoder,
+                        "Headless Game Render Pass",
+                        shared_resources,
+                        global_buffers,
+                        render_resources.game_resources.world_draw(game_draw_count),
+                        true,
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "post_processing",
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                &[FrameResource::PostProcessing],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Headless Post Processing Render Pass",
+                        &render_resources.game_resources.post_processing_texture().1,
+                        &render_resources.post_processing_resources.pipeline,
+                        render_resources.game_resources.post_processing_bind_group(),
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "antialiasing",
+                &[FrameResource::PostProcessing],
+                &[FrameResource::Antialiasing],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Headless Antialiasing Render Pass",
+                        &render_resources.game_resources.antialiasing_texture().1,
+                        &render_resources.antialiasing_resources.fxaa_pipeline,
+                        render_resources.game_resources.antialiasing_bind_group(),
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "screenshot",
+                &[FrameResource::Antialiasing],
+                &[FrameResource::Screenshot],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Headless Output Pass",
+                        &texture.create_view(&TextureViewDescriptor::default()),
+                        &render_resources.intermediate_resources.screenshot_pipeline,
+                        render_resources.intermediate_resources.game_bind_group(),
+                    );
+
+                    encoder.copy_texture_to_buffer(
+                        texture.as_image_copy(),
+                        ImageCopyBuffer {
+                            buffer,
+                            layout: ImageDataLayout {
+                                offset: 0,
+                                bytes_per_row: Some(padded_width),
+                                rows_per_image: Some(height),
+                            },
+                        },
+                        texture.size(),
+                    );
+                },
+            ));
+
+            graph.execute(&mut encoder);
+        }
+
+        uploader.finish();
+        self.queue.submit([encoder.finish()]);
+        uploader.recall();
+
+        let (tx, rx) = mpsc::channel();
+        self.buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+        self.device.poll(Maintain::Wait);
+        rx.recv()??;
+
+        let image = {
+            let data = self.buffer.slice(..).get_mapped_range();
+            let mut result = Vec::with_capacity((texture_width * height) as usize);
+            for chunk in data.chunks_exact(padded_width as usize) {
+                for pixel in chunk[..texture_width as usize].chunks_exact(4) {
+                    result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+                }
+            }
+
+            RgbaImage::from_vec(width, height, result)
+        };
+        self.buffer.unmap();
+
+        image.ok_or_else(|| anyhow!("The frame read back was the wrong size"))
+    }
+
+    /// Renders every tile into one large image, by rendering it piece by piece at [`POSTER_ZOOM`] and stitching the pieces together.
+    ///
+    /// `progress` is called with how many of the pieces are done, and how many there are.
+    pub fn render_poster(
+        &mut self,
+        resource_man: &ResourceManager,
+        tiles: &[(TileCoord, Id, DataMap)],
+        progress: &dyn Fn(usize, usize),
+    ) -> anyhow::Result<RgbaImage> {
+        if tiles.is_empty() {
+            bail!("There are no tiles to render");
+        }
+
+        let (width, height) = self.size;
+        let size = (width as Double, height as Double);
+
+        // where the corner of the screen is, relative to the camera. the signs say which way the screen faces
+        let corner =
+            math::normalized_to_world(size, dvec2(1.0, 1.0), dvec3(0.0, 0.0, fit_z(POSTER_ZOOM)));
+        let (ppu_x, ppu_y) = (
+            width as Double / (corner.x.abs() * 2.0),
+            height as Double / (corner.y.abs() * 2.0),
+        );
+
+        let padding = math::hex_to_world_pos_double(TileCoord::new(1, 0)).length();
+        let (mut min, mut max) = (
+            dvec2(Double::MAX, Double::MAX),
+            dvec2(Double::MIN, Double::MIN),
+        );
+        for (coord, ..) in tiles {
+            let pos = math::hex_to_world_pos_double(*coord);
+
+            min = min.min(pos);
+            max = max.max(pos);
+        }
+        let (min, max) = (min - padding, max + padding);
+
+        let poster_width = ((max.x - min.x) * ppu_x).ceil() as u32;
+        let poster_height = ((max.y - min.y) * ppu_y).ceil() as u32;
+        if poster_width > POSTER_MAX_SIZE || poster_height > POSTER_MAX_SIZE {
+            bail!(
+                "The poster would be {poster_width}x{poster_height}, larger than {POSTER_MAX_SIZE}x{POSTER_MAX_SIZE}"
+            );
+        }
+
+        let columns = poster_width.div_ceil(width);
+        let rows = poster_height.div_ceil(height);
+        let total = (columns * rows) as usize;
+
+        let left = if corner.x > 0.0 { min.x } else { max.x };
+        let top = if corner.y > 0.0 { max.y } else { min.y };
+
+        let mut poster = RgbaImage::new(poster_width, poster_height);
+        progress(0, total);
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = (column * width) as Double + width as Double / 2.0;
+                let y = (row * height) as Double + height as Double / 2.0;
+
+                let camera_pos = dvec3(
+                    left + corner.x.signum() * x / ppu_x,
+                    top - corner.y.signum() * y / ppu_y,
+                    POSTER_ZOOM,
+                );
+                let piece = self.render(resource_man, tiles, camera_pos)?;
+
+                imageops::replace(
+                    &mut poster,
+                    &piece,
+                    (column * width) as i64,
+                    (row * height) as i64,
+                );
+
+                progress((row * columns + column + 1) as usize, total);
+            }
+        }
+
+        Ok(poster)
+    }
+}
+
+/// Renders a map offscreen, without a window, and reads the frame back as an image.
+///
+/// See [`HeadlessRenderer::render`].
+pub fn render_map_to_image(
+    resource_man: &ResourceManager,
+    vertices: &[Vertex],
+    indices: &[u16],
+    map_name: &str,
+    camera_pos: DVec3,
+    size: (u32, u32),
+) -> anyhow::Result<RgbaImage> {
+    let Some(tiles) = Map::read_tiles(resource_man, map_name) else {
+        bail!("Could not read the map {map_name}");
+    };
+
+    render_tiles_to_image(resource_man, vertices, indices, &tiles, camera_pos, size)
+}
+
+/// Renders the tiles offscreen, as [`render_map_to_image`] does for a map on disk.
+pub fn render_tiles_to_image(
+    resource_man: &ResourceManager,
+    vertices: &[Vertex],
+    indices: &[u16],
+    tiles: &[(TileCoord, Id, DataMap)],
+    camera_pos: DVec3,
+    size: (u32, u32),
+) -> anyhow::Result<RgbaImage> {
+    HeadlessRenderer::new(resource_man, vertices, indices, size)?.render(
+        resource_man,
+        tiles,
+        camera_pos,
+    )
+}
+
+/// Renders a whole map offscreen as a poster. See [`HeadlessRenderer::render_poster`].
+pub fn render_map_poster(
+    resource_man: &ResourceManager,
+    vertices: &[Vertex],
+    indices: &[u16],
+    map_name: &str,
+    piece_size: (u32, u32),
+    progress: &dyn Fn(usize, usize),
+) -> anyhow::Result<RgbaImage> {
+    let Some(tiles) = Map::read_tiles(resource_man, map_name) else {
+        bail!("Could not read the map {map_name}");
+    };
+
+    HeadlessRenderer::new(resource_man, vertices, indices, piece_size)?.render_poster(
+        resource_man,
+        &tiles,
+        progress,
+    )
+}
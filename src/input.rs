@@ -1,10 +1,13 @@
 use std::mem;
+use std::time::{Duration, Instant};
 
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
 use winit::event::ElementState::{Pressed, Released};
 use winit::event::{
-    DeviceEvent, ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, WindowEvent,
+    DeviceEvent, ElementState, Modifiers, MouseButton, MouseScrollDelta, Touch, TouchPhase,
+    WindowEvent,
 };
 use winit::keyboard::{Key, NamedKey, SmolStr};
 
@@ -15,23 +18,131 @@ use crate::options::Options;
 
 pub static DEFAULT_KEYMAP: &[(Key, KeyAction)] = &[
     (Key::Character(SmolStr::new_inline("z")), actions::UNDO),
+    // shift turns z into Z, so this is control+shift+z
+    (Key::Character(SmolStr::new_inline("Z")), actions::REDO),
     (Key::Character(SmolStr::new_inline("e")), actions::PLAYER),
     (Key::Named(NamedKey::Escape), actions::ESCAPE),
     (Key::Named(NamedKey::F1), actions::HIDE_GUI),
     (Key::Named(NamedKey::F2), actions::SCREENSHOT),
     (Key::Named(NamedKey::F3), actions::DEBUG),
     (Key::Named(NamedKey::F11), actions::FULLSCREEN),
+    (Key::Named(NamedKey::Tab), actions::CHEATSHEET),
+    (Key::Character(SmolStr::new_inline("f")), actions::PICK_UP),
+    (Key::Character(SmolStr::new_inline("r")), actions::ROUTE),
+    (
+        Key::Character(SmolStr::new_inline("c")),
+        actions::COPY_BLUEPRINT,
+    ),
+    (
+        Key::Character(SmolStr::new_inline("v")),
+        actions::PASTE_BLUEPRINT,
+    ),
+    (
+        Key::Character(SmolStr::new_inline("q")),
+        actions::ACCEPT_SUGGESTION,
+    ),
+    (Key::Character(SmolStr::new_inline("g")), actions::ZONES),
+    (
+        Key::Character(SmolStr::new_inline("t")),
+        actions::STATISTICS,
+    ),
+    (Key::Character(SmolStr::new_inline("m")), actions::MARKERS),
+    (Key::Character(SmolStr::new_inline("1")), actions::HOTBAR[0]),
+    (Key::Character(SmolStr::new_inline("2")), actions::HOTBAR[1]),
+    (Key::Character(SmolStr::new_inline("3")), actions::HOTBAR[2]),
+    (Key::Character(SmolStr::new_inline("4")), actions::HOTBAR[3]),
+    (Key::Character(SmolStr::new_inline("5")), actions::HOTBAR[4]),
+    (Key::Character(SmolStr::new_inline("6")), actions::HOTBAR[5]),
+    (Key::Character(SmolStr::new_inline("7")), actions::HOTBAR[6]),
+    (Key::Character(SmolStr::new_inline("8")), actions::HOTBAR[7]),
+    (Key::Character(SmolStr::new_inline("9")), actions::HOTBAR[8]),
 ];
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+/// How many slots the hotbar has, each with its own key.
+pub const HOTBAR_SLOTS: usize = 9;
+
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, EnumIter,
+)]
 pub enum KeyActions {
     Escape,
     Undo,
+    Redo,
     Debug,
     Fullscreen,
     Screenshot,
     HideGui,
     Player,
+    Cheatsheet,
+    PickUp,
+    Route,
+    CopyBlueprint,
+    PasteBlueprint,
+    AcceptSuggestion,
+    Zones,
+    Statistics,
+    Markers,
+    /// Selects the tile in the slot of the hotbar, counted from 0.
+    Hotbar(u8),
+}
+
+/// The groups the key actions are listed under.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum KeyCategory {
+    Interface,
+    Editing,
+    Window,
+}
+
+impl KeyActions {
+    /// Every action, with one for each slot of the hotbar.
+    pub fn all() -> impl Iterator<Item = KeyActions> {
+        KeyActions::iter().flat_map(|action| match action {
+            KeyActions::Hotbar(_) => (0..HOTBAR_SLOTS as u8).map(KeyActions::Hotbar).collect(),
+            action => vec![action],
+        })
+    }
+
+    /// Whether the action only happens while control is held.
+    pub fn needs_control(self) -> bool {
+        matches!(
+            self,
+            KeyActions::Undo
+                | KeyActions::Redo
+                | KeyActions::CopyBlueprint
+                | KeyActions::PasteBlueprint
+        )
+    }
+
+    pub fn category(self) -> KeyCategory {
+        match self {
+            KeyActions::Escape
+            | KeyActions::Debug
+            | KeyActions::HideGui
+            | KeyActions::Player
+            | KeyActions::Cheatsheet
+            | KeyActions::Zones
+            | KeyActions::Statistics
+            | KeyActions::Markers => KeyCategory::Interface,
+            KeyActions::Undo
+            | KeyActions::Redo
+            | KeyActions::PickUp
+            | KeyActions::Route
+            | KeyActions::CopyBlueprint
+            | KeyActions::PasteBlueprint
+            | KeyActions::AcceptSuggestion
+            | KeyActions::Hotbar(_) => KeyCategory::Editing,
+            KeyActions::Fullscreen | KeyActions::Screenshot => KeyCategory::Window,
+        }
+    }
+}
+
+/// A key or mouse button an action can be bound to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Key),
+    /// Only the mouse buttons other than the left, right and middle ones, which already do other things.
+    Mouse(MouseButton),
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -48,7 +159,7 @@ pub struct KeyAction {
 }
 
 pub mod actions {
-    use super::{KeyAction, KeyActions, PressTypes};
+    use super::{KeyAction, KeyActions, PressTypes, HOTBAR_SLOTS};
 
     pub static ESCAPE: KeyAction = KeyAction {
         action: KeyActions::Escape,
@@ -58,6 +169,10 @@ pub mod actions {
         action: KeyActions::Undo,
         press_type: PressTypes::Tap,
     };
+    pub static REDO: KeyAction = KeyAction {
+        action: KeyActions::Redo,
+        press_type: PressTypes::Tap,
+    };
     pub static DEBUG: KeyAction = KeyAction {
         action: KeyActions::Debug,
         press_type: PressTypes::Toggle,
@@ -78,9 +193,74 @@ pub mod actions {
         action: KeyActions::Player,
         press_type: PressTypes::Toggle,
     };
+    pub static CHEATSHEET: KeyAction = KeyAction {
+        action: KeyActions::Cheatsheet,
+        press_type: PressTypes::Hold,
+    };
+    pub static PICK_UP: KeyAction = KeyAction {
+        action: KeyActions::PickUp,
+        press_type: PressTypes::Tap,
+    };
+    pub static ROUTE: KeyAction = KeyAction {
+        action: KeyActions::Route,
+        press_type: PressTypes::Tap,
+    };
+    pub static COPY_BLUEPRINT: KeyAction = KeyAction {
+        action: KeyActions::CopyBlueprint,
+        press_type: PressTypes::Tap,
+    };
+    pub static PASTE_BLUEPRINT: KeyAction = KeyAction {
+        action: KeyActions::PasteBlueprint,
+        press_type: PressTypes::Tap,
+    };
+    pub static ACCEPT_SUGGESTION: KeyAction = KeyAction {
+        action: KeyActions::AcceptSuggestion,
+        press_type: PressTypes::Tap,
+    };
+    pub static ZONES: KeyAction = KeyAction {
+        action: KeyActions::Zones,
+        press_type: PressTypes::Toggle,
+    };
+    pub static STATISTICS: KeyAction = KeyAction {
+        action: KeyActions::Statistics,
+        press_type: PressTypes::Toggle,
+    };
+    pub static MARKERS: KeyAction = KeyAction {
+        action: KeyActions::Markers,
+        press_type: PressTypes::Toggle,
+    };
+
+    const fn hotbar(slot: u8) -> KeyAction {
+        KeyAction {
+            action: KeyActions::Hotbar(slot),
+            press_type: PressTypes::Tap,
+        }
+    }
+
+    pub static HOTBAR: [KeyAction; HOTBAR_SLOTS] = [
+        hotbar(0),
+        hotbar(1),
+        hotbar(2),
+        hotbar(3),
+        hotbar(4),
+        hotbar(5),
+        hotbar(6),
+        hotbar(7),
+        hotbar(8),
+    ];
 }
 
+/// How long a finger has to stay down without moving for it to count as the alternate button, such as for opening
+/// the tile config, rather than the main one.
+pub const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+/// How far a finger can move, in pixels, before it counts as panning rather than a tap or a long press.
+const TAP_SLOP: Double = 12.0;
+/// How far apart two fingers have to move, in pixels, to zoom as much as one step of the mouse wheel.
+const PINCH_STEP: Double = 60.0;
+
 /// The various controls of the game.
+/// `ExtraMouseEvent` is for the mouse buttons other than the left, right and middle ones,
+/// and `Touch` is for a finger on a touchscreen, given by its ID.
 #[derive(Debug, Clone)]
 pub enum GameInputEvent {
     None,
@@ -96,7 +276,18 @@ pub enum GameInputEvent {
     ExitPressed,
     ExitReleased,
     ModifierChanged { modifier: Modifiers },
-    KeyboardEvent { event: KeyEvent },
+    KeyboardEvent { event: KeyInput },
+    ExtraMouseEvent(ElementState, MouseButton),
+    Touch(u64, TouchPhase, DVec2),
+}
+
+/// A key being pressed or released. Unlike winit's [`winit::event::KeyEvent`], this can be made outside winit,
+/// such as when replaying recorded input.
+#[derive(Debug, Clone)]
+pub struct KeyInput {
+    pub state: ElementState,
+    pub logical_key: Key,
+    pub repeat: bool,
 }
 
 pub fn convert_input(
@@ -151,7 +342,7 @@ pub fn convert_input(
                             TertiaryReleased
                         }
                     }
-                    _ => {}
+                    button => result = ExtraMouseEvent(*state, *button),
                 };
             }
             WindowEvent::ModifiersChanged(modifier) => {
@@ -166,9 +357,19 @@ pub fn convert_input(
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 result = KeyboardEvent {
-                    event: event.clone(),
+                    event: KeyInput {
+                        state: event.state,
+                        logical_key: event.logical_key.clone(),
+                        repeat: event.repeat,
+                    },
                 }
             }
+            WindowEvent::Touch(Touch {
+                id,
+                phase,
+                location,
+                ..
+            }) => result = Touch(*id, *phase, dvec2(location.x, location.y)),
             _ => {}
         }
     }
@@ -203,10 +404,26 @@ pub struct InputHandler {
     pub alternate_pressed: bool,
     pub tertiary_pressed: bool,
 
+    /// how far the map was dragged with one finger
+    pub touch_pan: Option<DVec2>,
+    /// whether the last input came from a touchscreen rather than the mouse
+    pub touch_active: bool,
+    /// the fingers that are down, and where
+    touches: HashMap<u64, DVec2>,
+    /// when and where the only finger down was put down, until it moves too far to be a tap or a long press
+    touch_press: Option<(Instant, DVec2)>,
+    /// how far apart the two fingers down were last
+    pinch_distance: Option<Double>,
+
     pub key_map: HashMap<Key, KeyAction>,
+    pub mouse_map: HashMap<MouseButton, KeyAction>,
     pub key_states: HashSet<KeyActions>,
 
     to_clear: Vec<KeyAction>,
+
+    /// Whether the next key or mouse button pressed is captured to be bound, instead of doing its action.
+    capturing: bool,
+    captured: Option<Binding>,
 }
 
 impl InputHandler {
@@ -227,10 +444,20 @@ impl InputHandler {
             alternate_pressed: false,
             tertiary_pressed: false,
 
+            touch_pan: None,
+            touch_active: false,
+            touches: Default::default(),
+            touch_press: None,
+            pinch_distance: None,
+
             key_map: options.keymap.clone(),
+            mouse_map: options.mouse_map.clone(),
             key_states: Default::default(),
 
             to_clear: Default::default(),
+
+            capturing: false,
+            captured: None,
         }
     }
 
@@ -241,6 +468,7 @@ impl InputHandler {
 
         self.main_move = None;
         self.scroll = None;
+        self.touch_pan = None;
 
         for v in mem::take(&mut self.to_clear) {
             self.key_states.remove(&v.action);
@@ -257,10 +485,12 @@ impl InputHandler {
             }
             GameInputEvent::MouseWheel { delta } => {
                 self.scroll = Some(delta);
+                self.touch_active = false;
             }
             GameInputEvent::MainPressed => {
                 self.main_pressed = true;
                 self.main_held = true;
+                self.touch_active = false;
             }
             GameInputEvent::MainReleased => {
                 self.main_held = false;
@@ -291,19 +521,126 @@ impl InputHandler {
                 }
             }
             GameInputEvent::KeyboardEvent {
-                event: KeyEvent {
-                    state, logical_key, ..
-                },
+                event:
+                    KeyInput {
+                        state,
+                        logical_key,
+                        repeat,
+                    },
             } => {
-                self.handle_key(state, logical_key);
+                // the modifiers are left alone, since some actions need them held along with their key
+                let modifier = matches!(
+                    logical_key,
+                    Key::Named(
+                        NamedKey::Control | NamedKey::Shift | NamedKey::Alt | NamedKey::Super
+                    )
+                );
+
+                if self.capturing && state == Pressed && logical_key == Key::Named(NamedKey::Escape)
+                {
+                    // escape cancels binding, rather than being bound
+                    self.stop_capture();
+                } else if self.capturing && state == Pressed && !repeat && !modifier {
+                    self.capture(Binding::Key(logical_key));
+                } else {
+                    self.handle_key(state, logical_key);
+                }
+            }
+            GameInputEvent::Touch(id, phase, pos) => {
+                self.handle_touch(id, phase, pos);
+            }
+            GameInputEvent::ExtraMouseEvent(state, button) => {
+                if self.capturing && state == Pressed {
+                    self.capture(Binding::Mouse(button));
+                } else if let Some(action) = self.mouse_map.get(&button).copied() {
+                    self.handle_action(state, action);
+                }
             }
             _ => {}
         }
     }
 
+    /// Handles a finger on a touchscreen. One finger points like the mouse does, and drags the map around
+    /// once it moves. Lifting it taps the main button, or the alternate one if it was held down long enough.
+    /// Two fingers zoom by pinching.
+    fn handle_touch(&mut self, id: u64, phase: TouchPhase, pos: DVec2) {
+        self.touch_active = true;
+
+        match phase {
+            TouchPhase::Started => {
+                self.touches.insert(id, pos);
+
+                if self.touches.len() == 1 {
+                    self.main_pos = pos;
+                    self.touch_press = Some((Instant::now(), pos));
+                } else {
+                    // a second finger turns it into a pinch
+                    self.touch_press = None;
+                }
+            }
+            TouchPhase::Moved => {
+                let Some(last) = self.touches.insert(id, pos) else {
+                    return;
+                };
+
+                match self.touches.len() {
+                    1 => {
+                        self.main_pos = pos;
+
+                        if self
+                            .touch_press
+                            .is_some_and(|(_, start)| pos.distance(start) > TAP_SLOP)
+                        {
+                            self.touch_press = None;
+                        }
+
+                        if self.touch_press.is_none() {
+                            let delta = pos - last;
+
+                            // the map follows the finger, so the camera goes the other way
+                            self.touch_pan = Some(dvec2(-delta.x, delta.y));
+                        }
+                    }
+                    2 => {
+                        let mut fingers = self.touches.values();
+                        let (Some(a), Some(b)) = (fingers.next(), fingers.next()) else {
+                            return;
+                        };
+                        let distance = a.distance(*b);
+
+                        if let Some(last) = self.pinch_distance {
+                            self.scroll = Some(dvec2(0.0, (distance - last) / PINCH_STEP));
+                        }
+
+                        self.pinch_distance = Some(distance);
+                    }
+                    _ => {}
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+                self.pinch_distance = None;
+
+                if let (TouchPhase::Ended, Some((start, _))) = (phase, self.touch_press.take()) {
+                    if start.elapsed() >= LONG_PRESS_DURATION {
+                        self.alternate_pressed = true;
+                    } else {
+                        self.main_pressed = true;
+                    }
+                }
+            }
+        }
+    }
+
     pub fn handle_key(&mut self, state: ElementState, key: Key) -> Option<()> {
         let action = *self.key_map.get(&key)?;
 
+        self.handle_action(state, action);
+
+        Some(())
+    }
+
+    fn handle_action(&mut self, state: ElementState, action: KeyAction) {
         match action.press_type {
             PressTypes::Tap => match state {
                 Pressed => {
@@ -333,11 +670,120 @@ impl InputHandler {
                 Released => {}
             },
         }
-
-        Some(())
     }
 
     pub fn key_active(&self, action: KeyActions) -> bool {
         self.key_states.contains(&action)
     }
+
+    /// All the bindings, along with the actions they are bound to.
+    pub fn bindings(&self) -> impl Iterator<Item = (Binding, KeyAction)> + '_ {
+        self.key_map
+            .iter()
+            .map(|(key, action)| (Binding::Key(key.clone()), *action))
+            .chain(
+                self.mouse_map
+                    .iter()
+                    .map(|(button, action)| (Binding::Mouse(*button), *action)),
+            )
+    }
+
+    /// Gets the key or mouse button bound to the action, if there is one.
+    pub fn binding_for(&self, action: KeyActions) -> Option<Binding> {
+        self.bindings()
+            .find(|(_, v)| v.action == action)
+            .map(|(binding, _)| binding)
+    }
+
+    /// Gets the action the key or mouse button is bound to, if there is one.
+    pub fn bound_action(&self, binding: &Binding) -> Option<KeyAction> {
+        match binding {
+            Binding::Key(key) => self.key_map.get(key).copied(),
+            Binding::Mouse(button) => self.mouse_map.get(button).copied(),
+        }
+    }
+
+    fn unbind(&mut self, binding: &Binding) -> Option<KeyAction> {
+        match binding {
+            Binding::Key(key) => self.key_map.remove(key),
+            Binding::Mouse(button) => self.mouse_map.remove(button),
+        }
+    }
+
+    fn bind(&mut self, binding: Binding, action: KeyAction) {
+        match binding {
+            Binding::Key(key) => self.key_map.insert(key, action),
+            Binding::Mouse(button) => self.mouse_map.insert(button, action),
+        };
+    }
+
+    /// Binds the action to the key or mouse button. If another action was bound to it,
+    /// that action takes the old binding of this one instead, so every action stays bound.
+    pub fn rebind(&mut self, action: KeyActions, binding: Binding) {
+        let old = self.binding_for(action);
+        let key_action = old.as_ref().and_then(|old| self.unbind(old)).or_else(|| {
+            DEFAULT_KEYMAP
+                .iter()
+                .find(|(_, v)| v.action == action)
+                .map(|(_, v)| *v)
+        });
+        let Some(key_action) = key_action else {
+            return;
+        };
+
+        if let Some(other) = self.unbind(&binding) {
+            if let Some(old) = old {
+                self.bind(old, other);
+            }
+        }
+
+        self.bind(binding, key_action);
+    }
+
+    /// Starts capturing the next key or mouse button pressed, which is then given by [`InputHandler::take_captured`].
+    pub fn start_capture(&mut self) {
+        self.capturing = true;
+        self.captured = None;
+    }
+
+    /// Whether the next key or mouse button pressed will be captured. This stops once one is,
+    /// or when escape is pressed to cancel.
+    pub fn capturing(&self) -> bool {
+        self.capturing
+    }
+
+    pub fn stop_capture(&mut self) {
+        self.capturing = false;
+        self.captured = None;
+    }
+
+    fn capture(&mut self, binding: Binding) {
+        self.capturing = false;
+        self.captured = Some(binding);
+    }
+
+    /// Takes the key or mouse button captured since [`InputHandler::start_capture`], if one was pressed yet.
+    pub fn take_captured(&mut self) -> Option<Binding> {
+        self.captured.take()
+    }
+}
+
+/// Gets the name of a key, as it should be shown to the player.
+pub fn key_name(key: &Key) -> String {
+    match key {
+        Key::Character(c) => c.to_uppercase(),
+        Key::Named(named) => format!("{named:?}"),
+        _ => "?".to_string(),
+    }
+}
+
+/// Gets the name of a key or mouse button, as it should be shown to the player.
+pub fn binding_name(binding: &Binding) -> String {
+    match binding {
+        Binding::Key(key) => key_name(key),
+        Binding::Mouse(MouseButton::Back) => "Mouse Back".to_string(),
+        Binding::Mouse(MouseButton::Forward) => "Mouse Forward".to_string(),
+        Binding::Mouse(MouseButton::Other(n)) => format!("Mouse {n}"),
+        Binding::Mouse(button) => format!("Mouse {button:?}"),
+    }
 }
@@ -1,16 +1,18 @@
 use std::sync::Arc;
-use std::time::Instant;
 
 use ractor::ActorRef;
 use tokio::runtime::Runtime;
 use tokio::task::JoinHandle;
 
 use automancy_defs::gui::Gui;
-use automancy_resources::kira::manager::AudioManager;
+use automancy_defs::rendering::Vertex;
 use automancy_resources::types::function::RhaiDataMap;
 use automancy_resources::ResourceManager;
 
+use crate::audio::dispatch::AudioDispatch;
+use crate::audio::GameAudio;
 use crate::camera::Camera;
+use crate::clock::GameClocks;
 use crate::event::EventLoopStorage;
 use crate::game::GameSystemMessage;
 use crate::gui::GuiState;
@@ -23,18 +25,29 @@ pub static LOGO_PATH: &str = "assets/logo.png";
 pub static LOGO: &[u8] = include_bytes!("assets/logo.png");
 pub static SSAO_NOISE_MAP: &[u8] = include_bytes!("assets/noise_map.png");
 
+pub mod audio;
 pub mod camera;
 pub mod event;
 pub mod game;
 pub mod gpu;
 pub mod gui;
+pub mod headless;
 pub mod input;
-pub mod map;
+pub mod logs;
+pub mod memory;
 pub mod options;
+pub mod power;
+pub mod quality;
 pub mod renderer;
-pub mod tile_entity;
+pub mod replay;
+pub mod soak;
 pub mod util;
 
+pub use automancy_core::{
+    blueprint, clock, determinism, history, load_resources, load_resources_from, load_resources_in,
+    map, marker, migration, scenario, simulation, suggestion, switch_language, tile_entity, zone,
+};
+
 pub struct GameState {
     pub gui_state: GuiState,
     pub options: Options,
@@ -45,9 +58,13 @@ pub struct GameState {
     pub tokio: Runtime,
     pub game: ActorRef<GameSystemMessage>,
     pub gui: Gui,
-    pub audio_man: AudioManager,
-    pub start_instant: Instant,
+    pub audio_man: GameAudio,
+    pub audio_dispatch: AudioDispatch,
+    /// the clocks the simulation and the GUI are timed by
+    pub clocks: GameClocks,
     pub renderer: Renderer<'static>,
     pub game_handle: Option<JoinHandle<()>>,
     pub puzzle_state: Option<(RhaiDataMap, bool)>,
+    /// the vertices and indices of all the models, for setting up offscreen renders
+    pub models: Arc<(Vec<Vertex>, Vec<u16>)>,
 }
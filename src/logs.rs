@@ -0,0 +1,120 @@
+//! The log file, written next to the maps and the options as well as to stderr, so there is always a log to attach
+//! to a bug report.
+//!
+//! Every launch starts a new file, and the logs of the last few launches are kept, numbered from the newest.
+//! A log that grows too large is moved aside the same way, so a long session can't fill up the disk.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::util;
+
+/// The folder the logs are written to.
+pub const LOGS_PATH: &str = "logs";
+/// How many logs are kept, including the one being written.
+const KEEP_LOGS: usize = 5;
+/// How large a log can get before it is moved aside, in bytes.
+const MAX_LOG_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Gets the path to a log, where 0 is the one being written and the rest count back from it.
+pub fn log_path(index: usize) -> PathBuf {
+    if index == 0 {
+        Path::new(LOGS_PATH).join("automancy.log")
+    } else {
+        Path::new(LOGS_PATH).join(format!("automancy.{index}.log"))
+    }
+}
+
+/// Moves every log one number back, dropping the oldest, so a new one can be started.
+fn rotate() -> io::Result<()> {
+    let oldest = log_path(KEEP_LOGS - 1);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+
+    for index in (0..KEEP_LOGS - 1).rev() {
+        let path = log_path(index);
+
+        if path.exists() {
+            fs::rename(path, log_path(index + 1))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts a new log, keeping the old ones.
+fn start_log() -> io::Result<File> {
+    fs::create_dir_all(LOGS_PATH)?;
+    rotate()?;
+
+    File::create(log_path(0))
+}
+
+/// Writes the log to stderr and to the log file. If the file can't be written, only stderr is.
+pub struct LogWriter {
+    file: Option<File>,
+    written: u64,
+}
+
+impl LogWriter {
+    /// Starts a new log file.
+    pub fn new() -> Self {
+        let file = match start_log() {
+            Ok(file) => Some(file),
+            Err(err) => {
+                eprintln!("Could not start the log file, only logging to stderr: {err}");
+                None
+            }
+        };
+
+        Self { file, written: 0 }
+    }
+}
+
+impl Default for LogWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+
+        if self.written + buf.len() as u64 > MAX_LOG_SIZE {
+            self.file = start_log().ok();
+            self.written = 0;
+        }
+
+        if let Some(file) = &mut self.file {
+            if file.write_all(buf).is_err() {
+                self.file = None;
+            }
+            self.written += buf.len() as u64;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+
+        io::stderr().flush()
+    }
+}
+
+/// The full path to the logs folder, for telling the player where to find it.
+pub fn logs_folder() -> PathBuf {
+    fs::canonicalize(LOGS_PATH).unwrap_or_else(|_| PathBuf::from(LOGS_PATH))
+}
+
+/// Opens the logs folder in the system's file manager.
+pub fn open_logs_folder() -> io::Result<()> {
+    fs::create_dir_all(LOGS_PATH)?;
+
+    util::open_folder(&logs_folder())
+}
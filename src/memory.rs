@@ -0,0 +1,70 @@
+//! Keeps track of how much memory the game uses, and how much of it is taken up by each of its caches.
+
+use std::fmt::{self, Display, Formatter};
+use std::mem::size_of;
+
+/// How many entries a cache holds, and about how much memory they take up.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheUsage {
+    pub entries: usize,
+    /// The size of the entries themselves. Whatever they point to on the heap isn't counted.
+    pub bytes: usize,
+}
+
+impl CacheUsage {
+    /// The usage of a cache holding the given number of entries of type T.
+    pub fn of<T>(entries: usize) -> Self {
+        Self {
+            entries,
+            bytes: entries * size_of::<T>(),
+        }
+    }
+}
+
+impl Display for CacheUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.entries, format_bytes(self.bytes as u64))
+    }
+}
+
+/// Formats a size in bytes with the largest unit that keeps it above 1.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// How much memory the game is using, in bytes, as its resident set size.
+/// This can only be told on Linux, and is None everywhere else.
+#[cfg(target_os = "linux")]
+pub fn process_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn process_memory() -> Option<u64> {
+    None
+}
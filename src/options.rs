@@ -3,21 +3,33 @@ use std::io::{BufReader, Write};
 
 use enum_ordinalize::Ordinalize;
 use hashbrown::HashMap;
+use lazy_static::lazy_static;
 use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
 use winit::keyboard::Key;
 
+use automancy_defs::gui::DEFAULT_TEXT_SIZE;
 use automancy_defs::log;
 use automancy_defs::math::{Double, Float};
+use automancy_resources::types::translate::DEFAULT_LANGUAGE;
 
+use crate::game::DEFAULT_TRANSACTION_RECORD_LIMIT;
 use crate::input::{KeyAction, DEFAULT_KEYMAP};
+use crate::power::BatterySaver;
+use crate::quality::Quality;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Options {
     pub graphics: GraphicsOptions,
     pub audio: AudioOptions,
     pub gui: GuiOptions,
+    #[serde(default)]
+    pub memory: MemoryOptions,
     pub keymap: HashMap<Key, KeyAction>,
+    /// The actions bound to mouse buttons rather than keys.
+    #[serde(default)]
+    pub mouse_map: HashMap<MouseButton, KeyAction>,
     pub synced: bool,
 }
 
@@ -27,7 +39,9 @@ impl Default for Options {
             graphics: Default::default(),
             audio: Default::default(),
             gui: Default::default(),
+            memory: Default::default(),
             keymap: DEFAULT_KEYMAP.iter().cloned().collect(),
+            mouse_map: Default::default(),
             synced: false,
         }
     }
@@ -49,9 +63,11 @@ impl Options {
 
         let mut this: Options = ron::de::from_reader(reader).unwrap_or_default();
 
-        if this.keymap.len() != DEFAULT_KEYMAP.len() {
+        // each action is bound to exactly one key or mouse button
+        if this.keymap.len() + this.mouse_map.len() != DEFAULT_KEYMAP.len() {
             // TODO show a popup warning the player
             this.keymap = DEFAULT_KEYMAP.iter().cloned().collect();
+            this.mouse_map.clear();
         }
 
         this.save()?;
@@ -79,6 +95,113 @@ impl Options {
     }
 }
 
+/// A setting that can be reset on its own, named the same as it is labelled with in the options menu.
+pub struct OptionEntry {
+    pub name: &'static str,
+    /// Whether the setting is the same in both options.
+    pub is_same: fn(&Options, &Options) -> bool,
+    /// Sets the setting in the first options to what it is in the second.
+    pub copy: fn(&mut Options, &Options),
+}
+
+/// Makes an [`OptionEntry`] for the fields, which together make up the setting.
+macro_rules! option_entry {
+    ($name:literal, $($($field:ident).+),+) => {
+        OptionEntry {
+            name: $name,
+            is_same: |a, b| $(a.$($field).+ == b.$($field).+)&&+,
+            copy: |a, b| {
+                $(a.$($field).+ = Clone::clone(&b.$($field).+);)+
+            },
+        }
+    };
+}
+
+/// Every setting in the options menu, so each can be reset to its default, or back to what it was before.
+pub static OPTION_ENTRIES: &[OptionEntry] = &[
+    option_entry!("Max FPS", graphics.fps_limit),
+    option_entry!("Vsync", graphics.vsync),
+    option_entry!("Quality", graphics.quality),
+    option_entry!("Lower when slow", graphics.adaptive_quality),
+    option_entry!("Battery saver", graphics.battery_saver),
+    option_entry!("Bloom", graphics.bloom),
+    option_entry!("Tile outlines", graphics.tile_outlines),
+    option_entry!("Fullscreen", graphics.fullscreen),
+    option_entry!("Scale", graphics.scale),
+    option_entry!(
+        "Screenshots",
+        graphics.screenshot.include_gui,
+        graphics.screenshot.clipboard,
+        graphics.screenshot.file
+    ),
+    option_entry!("Screenshot folder", graphics.screenshot.directory),
+    option_entry!("Antialiasing", graphics.anti_aliasing),
+    option_entry!("Output Device", audio.output_device),
+    option_entry!("SFX Volume", audio.sfx_volume),
+    option_entry!("UI Volume", audio.ui_volume),
+    option_entry!("Music Volume", audio.music_volume),
+    option_entry!("Language", gui.language),
+    option_entry!("Font Scale", gui.scale),
+    option_entry!("Font", gui.font),
+    option_entry!("Text Size", gui.text_size),
+    option_entry!("Sharp text", gui.sharp_text),
+    option_entry!("Show captions for important sounds", gui.captions),
+    option_entry!("Suggest tiles next to machines", gui.tile_suggestions),
+    option_entry!("Show zones on the map", gui.show_zones),
+    option_entry!("Cursor Size", gui.cursor_scale),
+    option_entry!("Button Repeat Rate", gui.repeat_rate),
+    option_entry!("Key bindings", keymap, mouse_map),
+];
+
+/// The settings that can leave the window black or unusable with some hardware, so changing them has to be
+/// confirmed afterwards, or they are reverted.
+pub static RISKY_OPTIONS: &[&str] = &["Fullscreen", "Vsync", "Scale"];
+
+lazy_static! {
+    /// The options every setting is reset to.
+    pub static ref DEFAULT_OPTIONS: Options = Options::default();
+}
+
+impl Options {
+    fn entry(name: &str) -> Option<&'static OptionEntry> {
+        OPTION_ENTRIES.iter().find(|v| v.name == name)
+    }
+
+    /// Whether the setting with the name is the same as in the other options.
+    pub fn is_same(&self, other: &Options, name: &str) -> bool {
+        Self::entry(name).map_or(true, |entry| (entry.is_same)(self, other))
+    }
+
+    /// Sets the setting with the name to what it is in the other options.
+    pub fn copy_from(&mut self, other: &Options, name: &str) {
+        if let Some(entry) = Self::entry(name) {
+            (entry.copy)(self, other);
+        }
+    }
+
+    /// Whether any of the settings differ from the other options.
+    pub fn differs_from(&self, other: &Options) -> bool {
+        OPTION_ENTRIES
+            .iter()
+            .any(|entry| !(entry.is_same)(self, other))
+    }
+
+    /// Sets every setting to what it is in the other options.
+    pub fn restore(&mut self, other: &Options) {
+        for entry in OPTION_ENTRIES {
+            (entry.copy)(self, other);
+        }
+    }
+
+    pub fn is_default(&self, name: &str) -> bool {
+        self.is_same(&DEFAULT_OPTIONS, name)
+    }
+
+    pub fn reset(&mut self, name: &str) {
+        self.copy_from(&DEFAULT_OPTIONS, name);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Ordinalize)]
 pub enum AAType {
     None,
@@ -86,21 +209,146 @@ pub enum AAType {
     TAA,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// The FPS limit at and above which the frame rate isn't limited.
+pub const UNLIMITED_FPS: Double = 250.0;
+/// The FPS limits that can be picked with a single click.
+pub const FPS_PRESETS: [Double; 4] = [30.0, 60.0, 120.0, 144.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedGraphicsOptions")]
 pub struct GraphicsOptions {
+    /// The most frames drawn each second. At 0 or [`UNLIMITED_FPS`], there is no limit.
     pub fps_limit: Double,
+    /// Whether to wait for the screen to refresh before showing each frame.
+    pub vsync: bool,
     pub fullscreen: bool,
     pub scale: Float,
     pub anti_aliasing: AAType,
+    #[serde(default)]
+    pub screenshot: ScreenshotOptions,
+    /// How much is drawn each frame, or the most when it's adjusted automatically.
+    #[serde(default)]
+    pub quality: Quality,
+    /// Whether to lower the quality when frames take too long, and raise it again when they don't.
+    #[serde(default)]
+    pub adaptive_quality: bool,
+    /// When to draw less often and draw less, to save power.
+    #[serde(default)]
+    pub battery_saver: BatterySaver,
+    /// Whether the bright parts of the world glow.
+    #[serde(default)]
+    pub bloom: bool,
+    /// Whether the tile under the cursor and the selected tiles are outlined, rather than tinted.
+    #[serde(default = "default_tile_outlines")]
+    pub tile_outlines: bool,
 }
 
 impl Default for GraphicsOptions {
     fn default() -> Self {
         Self {
-            fps_limit: 0.0,
+            fps_limit: UNLIMITED_FPS,
+            vsync: true,
             fullscreen: false,
             scale: 1.0,
             anti_aliasing: AAType::FXAA,
+            screenshot: Default::default(),
+            quality: Default::default(),
+            adaptive_quality: false,
+            battery_saver: Default::default(),
+            bloom: false,
+            tile_outlines: default_tile_outlines(),
+        }
+    }
+}
+
+/// [`GraphicsOptions`] as they are saved, which may be from before vsync could be set on its own.
+#[derive(Deserialize)]
+struct SavedGraphicsOptions {
+    fps_limit: Double,
+    #[serde(default)]
+    vsync: Option<bool>,
+    fullscreen: bool,
+    scale: Float,
+    anti_aliasing: AAType,
+    #[serde(default)]
+    screenshot: ScreenshotOptions,
+    #[serde(default)]
+    quality: Quality,
+    #[serde(default)]
+    adaptive_quality: bool,
+    #[serde(default)]
+    battery_saver: BatterySaver,
+    #[serde(default)]
+    bloom: bool,
+    #[serde(default = "default_tile_outlines")]
+    tile_outlines: bool,
+}
+
+impl From<SavedGraphicsOptions> for GraphicsOptions {
+    fn from(saved: SavedGraphicsOptions) -> Self {
+        // before vsync could be set on its own, an FPS limit of 0 meant vsync, and any other limit meant none
+        let (fps_limit, vsync) = match saved.vsync {
+            Some(vsync) => (saved.fps_limit, vsync),
+            None if saved.fps_limit == 0.0 => (UNLIMITED_FPS, true),
+            None => (saved.fps_limit, false),
+        };
+
+        Self {
+            fps_limit,
+            vsync,
+            fullscreen: saved.fullscreen,
+            scale: saved.scale,
+            anti_aliasing: saved.anti_aliasing,
+            screenshot: saved.screenshot,
+            quality: saved.quality,
+            adaptive_quality: saved.adaptive_quality,
+            battery_saver: saved.battery_saver,
+            bloom: saved.bloom,
+            tile_outlines: saved.tile_outlines,
+        }
+    }
+}
+
+/// What goes into a screenshot, and where it ends up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotOptions {
+    /// Whether the GUI is in the screenshot, or only the world.
+    pub include_gui: bool,
+    /// Whether to copy the screenshot to the clipboard.
+    pub clipboard: bool,
+    /// Whether to save the screenshot as a PNG in the screenshots folder.
+    pub file: bool,
+    /// The folder screenshots are saved in.
+    #[serde(default = "default_screenshot_directory")]
+    pub directory: String,
+}
+
+fn default_tile_outlines() -> bool {
+    true
+}
+
+impl GraphicsOptions {
+    /// The most frames to draw each second, or 0 for no limit.
+    pub fn frame_rate_limit(&self) -> Double {
+        if self.fps_limit >= UNLIMITED_FPS {
+            0.0
+        } else {
+            self.fps_limit
+        }
+    }
+}
+
+fn default_screenshot_directory() -> String {
+    "screenshots".to_string()
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> Self {
+        Self {
+            include_gui: true,
+            clipboard: true,
+            file: false,
+            directory: default_screenshot_directory(),
         }
     }
 }
@@ -109,6 +357,54 @@ impl Default for GraphicsOptions {
 pub struct GuiOptions {
     pub scale: f32,
     pub font: String,
+    /// Whether to show captions for important sounds.
+    #[serde(default)]
+    pub captions: bool,
+    /// Whether to suggest tiles for empty positions next to machines.
+    #[serde(default)]
+    pub tile_suggestions: bool,
+    /// Whether to show the zones on the map even when the zones window is closed.
+    #[serde(default = "default_show_zones")]
+    pub show_zones: bool,
+    /// How big the cursor is. Above 1, it is drawn by the game, as the system cursors can't be scaled.
+    #[serde(default = "default_cursor_scale")]
+    pub cursor_scale: f32,
+    /// How many times a second held buttons repeat, such as the ones stepping amounts.
+    #[serde(default = "default_repeat_rate")]
+    pub repeat_rate: f32,
+    /// The size of body text in points. The other text is sized against it.
+    #[serde(default = "default_text_size")]
+    pub text_size: f32,
+    /// Whether to snap text to whole pixels, which keeps it sharp on screens scaled by fractions.
+    #[serde(default = "default_sharp_text")]
+    pub sharp_text: bool,
+    /// The language of the translations, such as `en_US`.
+    #[serde(default = "default_language")]
+    pub language: String,
+}
+
+fn default_show_zones() -> bool {
+    true
+}
+
+fn default_cursor_scale() -> f32 {
+    1.0
+}
+
+fn default_repeat_rate() -> f32 {
+    10.0
+}
+
+fn default_text_size() -> f32 {
+    DEFAULT_TEXT_SIZE
+}
+
+fn default_sharp_text() -> bool {
+    true
+}
+
+fn default_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
 }
 
 impl Default for GuiOptions {
@@ -116,6 +412,14 @@ impl Default for GuiOptions {
         Self {
             scale: 1.0,
             font: "iosevka-extended.ttf".to_string(),
+            captions: false,
+            tile_suggestions: false,
+            show_zones: true,
+            cursor_scale: default_cursor_scale(),
+            repeat_rate: default_repeat_rate(),
+            text_size: default_text_size(),
+            sharp_text: default_sharp_text(),
+            language: default_language(),
         }
     }
 }
@@ -123,6 +427,15 @@ impl Default for GuiOptions {
 pub struct AudioOptions {
     pub sfx_volume: f64,
     pub music_volume: f64,
+    #[serde(default = "default_volume")]
+    pub ui_volume: f64,
+    /// The name of the device to play audio to, or None to use the system default.
+    #[serde(default)]
+    pub output_device: Option<String>,
+}
+
+fn default_volume() -> f64 {
+    0.5
 }
 
 impl Default for AudioOptions {
@@ -130,6 +443,26 @@ impl Default for AudioOptions {
         Self {
             sfx_volume: 0.5,
             music_volume: 0.5,
+            ui_volume: default_volume(),
+            output_device: None,
+        }
+    }
+}
+
+/// How much the caches keep at most, so they can't grow without bound. The oldest entries are dropped past it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MemoryOptions {
+    /// How many transactions between tiles are kept to be drawn.
+    pub max_transaction_records: usize,
+    /// How many GUI animations of each kind are played at once, such as the items flying into the inventory.
+    pub max_take_item_animations: usize,
+}
+
+impl Default for MemoryOptions {
+    fn default() -> Self {
+        Self {
+            max_transaction_records: DEFAULT_TRANSACTION_RECORD_LIMIT,
+            max_take_item_animations: 32,
         }
     }
 }
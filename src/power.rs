@@ -0,0 +1,143 @@
+//! Saves power by drawing less often and drawing less, when running on a battery or in the background.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::math::Double;
+
+use crate::quality::Quality;
+
+/// The most frames drawn each second while saving power.
+pub const SAVER_FPS: Double = 30.0;
+/// The most frames drawn each second while the window isn't focused, unless the battery saver is off.
+pub const BACKGROUND_FPS: Double = 10.0;
+/// The highest quality drawn at while saving power.
+pub const SAVER_QUALITY: Quality = Quality::Low;
+/// How often to check whether the computer is running on its battery.
+const BATTERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When to save power.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BatterySaver {
+    Off,
+    /// Only while running on a battery.
+    #[default]
+    Auto,
+    On,
+}
+
+impl BatterySaver {
+    pub const ALL: [BatterySaver; 3] = [BatterySaver::Off, BatterySaver::Auto, BatterySaver::On];
+}
+
+/// Whether the computer is running on its battery, as opposed to being plugged in.
+/// This can only be told on Linux, and is assumed to be false everywhere else.
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> bool {
+    use std::fs;
+
+    let Ok(dir) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+
+    let mut discharging = false;
+
+    for supply in dir.flatten().map(|v| v.path()) {
+        let read = |name: &str| {
+            fs::read_to_string(supply.join(name))
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        match read("type").as_str() {
+            "Mains" if read("online") == "1" => return false,
+            "Battery" => discharging |= read("status") == "Discharging",
+            _ => {}
+        }
+    }
+
+    discharging
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn on_battery() -> bool {
+    false
+}
+
+/// Keeps track of whether to save power.
+#[derive(Debug)]
+pub struct PowerState {
+    /// Whether the window is focused.
+    pub focused: bool,
+    mode: BatterySaver,
+    on_battery: bool,
+    last_check: Option<Instant>,
+    saving: bool,
+}
+
+impl Default for PowerState {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            mode: BatterySaver::default(),
+            on_battery: false,
+            last_check: None,
+            saving: false,
+        }
+    }
+}
+
+impl PowerState {
+    /// Works out whether to save power, checking the battery every so often.
+    pub fn update(&mut self, mode: BatterySaver) {
+        self.mode = mode;
+        self.saving = match mode {
+            BatterySaver::Off => false,
+            BatterySaver::On => true,
+            BatterySaver::Auto => {
+                if self
+                    .last_check
+                    .map_or(true, |v| v.elapsed() >= BATTERY_CHECK_INTERVAL)
+                {
+                    self.on_battery = on_battery();
+                    self.last_check = Some(Instant::now());
+                }
+
+                self.on_battery
+            }
+        };
+    }
+
+    /// Whether power is being saved.
+    pub fn saving(&self) -> bool {
+        self.saving
+    }
+
+    /// Lowers the frame rate limit while saving power, or in the background unless the battery saver is off.
+    /// 0 means no limit.
+    pub fn fps_limit(&self, limit: Double) -> Double {
+        let cap = if !self.focused && self.mode != BatterySaver::Off {
+            BACKGROUND_FPS
+        } else if self.saving {
+            SAVER_FPS
+        } else {
+            return limit;
+        };
+
+        if limit > 0.0 {
+            limit.min(cap)
+        } else {
+            cap
+        }
+    }
+
+    /// Lowers the quality while saving power.
+    pub fn quality(&self, quality: Quality) -> Quality {
+        if self.saving {
+            quality.min(SAVER_QUALITY)
+        } else {
+            quality
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Lowers the quality of the rendering when frames take longer than the target frame rate allows,
+//! and raises it again once there is time to spare, for computers that can't always keep up, like laptops.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use automancy_defs::math::Double;
+
+/// The frame rate to keep up with when the frame rate isn't limited.
+const DEFAULT_TARGET_FPS: Double = 60.0;
+/// How much of the frame time the new frame counts for in the average.
+const SMOOTHING: Double = 0.05;
+/// The quality is lowered when the average frame takes longer than this share of the frame time.
+const STEP_DOWN_AT: Double = 0.9;
+/// The quality is raised when the average frame takes less than this share of the frame time.
+const STEP_UP_AT: Double = 0.5;
+/// How long to wait after changing the quality before changing it again, so the average can settle.
+const COOLDOWN: Duration = Duration::from_secs(3);
+
+/// How much is drawn each frame.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Quality {
+    /// The models are still, and the items moving between machines aren't drawn.
+    Low,
+    /// The models are still.
+    Medium,
+    #[default]
+    High,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 3] = [Quality::Low, Quality::Medium, Quality::High];
+
+    /// Whether the models play their animations.
+    pub fn animate_models(self) -> bool {
+        self >= Quality::High
+    }
+
+    /// Whether the items moving between machines are drawn.
+    pub fn draw_transactions(self) -> bool {
+        self >= Quality::Medium
+    }
+
+    fn lower(self) -> Option<Quality> {
+        match self {
+            Quality::Low => None,
+            Quality::Medium => Some(Quality::Low),
+            Quality::High => Some(Quality::Medium),
+        }
+    }
+
+    fn higher(self) -> Option<Quality> {
+        match self {
+            Quality::Low => Some(Quality::Medium),
+            Quality::Medium => Some(Quality::High),
+            Quality::High => None,
+        }
+    }
+}
+
+/// Watches how long frames take, and picks the quality to draw them at.
+#[derive(Debug, Clone)]
+pub struct QualityGovernor {
+    quality: Quality,
+    /// The highest quality the governor picks, set in the options.
+    max: Quality,
+    /// The average time a frame takes, in seconds.
+    average: Double,
+    last_change: Instant,
+}
+
+impl Default for QualityGovernor {
+    fn default() -> Self {
+        Self::new(Quality::default())
+    }
+}
+
+impl QualityGovernor {
+    pub fn new(max: Quality) -> Self {
+        Self {
+            quality: max,
+            max,
+            average: 0.0,
+            last_change: Instant::now(),
+        }
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    /// Changes the highest quality to pick, and starts over from it.
+    pub fn set_max(&mut self, max: Quality) {
+        if self.max != max {
+            *self = Self::new(max);
+        }
+    }
+
+    /// Takes how long a frame took, and the frame rate to keep up with, or 0 if it isn't limited,
+    /// and gives back the quality to draw the next frame at.
+    pub fn record(&mut self, frame_time: Duration, target_fps: Double) -> Quality {
+        let target_fps = if target_fps > 0.0 {
+            target_fps
+        } else {
+            DEFAULT_TARGET_FPS
+        };
+        let budget = 1.0 / target_fps;
+
+        self.average = if self.average == 0.0 {
+            frame_time.as_secs_f64()
+        } else {
+            self.average * (1.0 - SMOOTHING) + frame_time.as_secs_f64() * SMOOTHING
+        };
+
+        if self.last_change.elapsed() < COOLDOWN {
+            return self.quality;
+        }
+
+        let next = if self.average > budget * STEP_DOWN_AT {
+            self.quality.lower()
+        } else if self.average < budget * STEP_UP_AT {
+            self.quality.higher().filter(|v| *v <= self.max)
+        } else {
+            None
+        };
+
+        if let Some(next) = next {
+            self.quality = next;
+            self.last_change = Instant::now();
+        }
+
+        self.quality
+    }
+}
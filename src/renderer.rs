@@ -1,74 +1,248 @@
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::f32::consts::FRAC_PI_6;
+use std::fs;
+use std::io::Cursor;
 use std::mem;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, SystemTime};
 
 use arboard::{Clipboard, ImageData};
-use egui::{Rect, Rgba};
 use egui_wgpu::wgpu::{
-    BufferAddress, BufferDescriptor, BufferUsages, Color, CommandEncoderDescriptor,
-    ImageCopyBuffer, ImageDataLayout, IndexFormat, LoadOp, Maintain, MapMode, Operations,
+    BufferAddress, BufferDescriptor, BufferUsages, Color, CommandEncoderDescriptor, Extent3d,
+    ImageCopyBuffer, ImageDataLayout, LoadOp, Maintain, MapMode, Operations,
     RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
     SurfaceError, TextureDescriptor, TextureDimension, TextureUsages, TextureViewDescriptor,
     COPY_BUFFER_ALIGNMENT, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
 use egui_wgpu::ScreenDescriptor;
 use hashbrown::HashMap;
-use image::{EncodableLayout, RgbaImage};
+use image::{EncodableLayout, ImageFormat, RgbaImage};
 use num::PrimInt;
 use ractor::ActorRef;
 use tokio::runtime::Runtime;
 use tokio::sync::{oneshot, Mutex};
 use wgpu::StoreOp;
 
+use automancy_defs::colors::Rgba;
 use automancy_defs::coord::TileCoord;
 use automancy_defs::glam::vec3;
 use automancy_defs::gui::Gui;
 use automancy_defs::id::Id;
 use automancy_defs::math::{
-    direction_to_angle, lerp_coords_to_pixel, Double, Float, Matrix4, FAR, HEX_GRID_LAYOUT, SQRT_3,
+    direction_to_angle, lerp_coords_to_pixel, Double, Float, Matrix4, Vec3, FAR, HEX_GRID_LAYOUT,
+    SQRT_3,
 };
 use automancy_defs::rendering::{make_line, GameUBO, InstanceData, LINE_DEPTH};
-use automancy_defs::slice_group_by::GroupBy;
-use automancy_defs::{bytemuck, colors, math};
-use automancy_resources::data::item::Item;
+use automancy_defs::{bytemuck, colors, log, math};
 use automancy_resources::data::{Data, DataMap};
 use automancy_resources::ResourceManager;
 
 use crate::camera::Camera;
+use crate::clock::GameClocks;
 use crate::game::{
-    GameSystemMessage, RenderUnit, TransactionRecord, TransactionRecords,
+    GameSystemMessage, RenderUnit, TileSnapshot, TransactionRecord, TransactionRecords,
     TRANSACTION_ANIMATION_SPEED,
 };
 use crate::gpu::{
-    AnimationMap, GlobalBuffers, Gpu, RenderResources, SharedResources, NORMAL_CLEAR,
-    SCREENSHOT_FORMAT,
+    AnimationCache, FrameGraph, FramePass, FrameResource, GlobalBuffers, Gpu, GuiResources,
+    RenderResources, SharedResources, Uploader, SCREENSHOT_FORMAT,
 };
 use crate::input::{InputHandler, KeyActions};
-use crate::options::Options;
+use crate::map::Map;
+use crate::memory::CacheUsage;
+use crate::options::{Options, ScreenshotOptions};
+use crate::quality::Quality;
 use crate::{gpu, gui};
 
+/// Positions a tile's render unit, and turns it towards its target if it has one.
+pub(crate) fn place_unit(
+    resource_man: &ResourceManager,
+    camera: &Camera,
+    coord: TileCoord,
+    unit: &mut RenderUnit,
+    data: Option<&DataMap>,
+) {
+    let tile = resource_man.registry.tiles.get(&unit.tile_id).unwrap();
+
+    unit.instance = unit.instance.with_model_matrix(Matrix4::from_translation(
+        camera.rebase_coord(coord).extend(FAR as Float),
+    ));
+
+    if unit.crashed {
+        unit.instance = unit
+            .instance
+            .with_color_offset(colors::RED.with_alpha(0.5).to_array());
+    }
+
+    if let Some(theta) = data
+        .and_then(|data| data.get(&resource_man.registry.data_ids.target))
+        .and_then(|target| {
+            if let Data::Coord(target) = target {
+                math::tile_direction_to_angle(*target)
+            } else {
+                None
+            }
+        })
+    {
+        unit.instance = unit
+            .instance
+            .add_model_matrix(Matrix4::from_rotation_z(theta.to_radians()));
+    } else if let Some(Data::Id(inactive)) = tile
+        .data
+        .get(&resource_man.registry.data_ids.inactive_model)
+    {
+        unit.model = resource_man.get_model(*inactive);
+    }
+}
+
+/// What a tile's overlay is made from. The overlay is only remade when this changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OverlayKey {
+    tile_id: Option<Id>,
+    target: Option<TileCoord>,
+    link: Option<TileCoord>,
+    item: Option<Id>,
+}
+
+impl OverlayKey {
+    fn new(resource_man: &ResourceManager, tile_id: Option<Id>, data: &DataMap) -> Self {
+        let data_ids = &resource_man.registry.data_ids;
+
+        Self {
+            tile_id,
+            target: data
+                .get(&data_ids.target)
+                .cloned()
+                .and_then(Data::into_coord),
+            link: data.get(&data_ids.link).cloned().and_then(Data::into_coord),
+            item: data.get(&data_ids.item).cloned().and_then(Data::into_id),
+        }
+    }
+}
+
+/// The direction arrow, link line and item marker of a tile, without the light position and world matrix.
+#[derive(Debug, Clone)]
+struct Overlay {
+    key: OverlayKey,
+    extra_instances: Vec<(InstanceData, Id)>,
+    in_world_item_instances: Vec<(InstanceData, Id)>,
+}
+
+impl Overlay {
+    fn new(
+        resource_man: &ResourceManager,
+        camera: &Camera,
+        coord: TileCoord,
+        key: OverlayKey,
+    ) -> Self {
+        let world_coord = camera.rebase_coord(coord);
+        let mut extra_instances = vec![];
+        let mut in_world_item_instances = vec![];
+
+        if let Some((tile, theta)) = key
+            .tile_id
+            .and_then(|id| resource_man.registry.tiles.get(&id))
+            .zip(key.target.and_then(math::tile_direction_to_angle))
+        {
+            if let Data::Color(color) = tile
+                .data
+                .get(&resource_man.registry.data_ids.direction_color)
+                .unwrap_or(&Data::Color(colors::ORANGE))
+            {
+                extra_instances.push((
+                    InstanceData::default()
+                        .with_color_offset(color.to_array())
+                        .with_model_matrix(
+                            Matrix4::from_translation(world_coord.extend(FAR as Float))
+                                * Matrix4::from_rotation_z(theta.to_radians())
+                                * Matrix4::from_rotation_z(FRAC_PI_6 * 5.0)
+                                * Matrix4::from_scale(vec3(0.1, SQRT_3, LINE_DEPTH))
+                                * Matrix4::from_translation(vec3(0.0, 0.5, 0.0)),
+                        ),
+                    resource_man.registry.model_ids.cube1x1,
+                ));
+            }
+        }
+
+        if let Some(link) = key.link {
+            extra_instances.push((
+                InstanceData::default()
+                    .with_color_offset(colors::RED.to_array())
+                    .with_model_matrix(make_line(world_coord, camera.rebase_coord(link))),
+                resource_man.registry.model_ids.cube1x1,
+            ));
+        }
+
+        if let Some(item) = key.item.and_then(|id| resource_man.registry.items.get(&id)) {
+            in_world_item_instances.push((
+                InstanceData::default().with_model_matrix(
+                    Matrix4::from_translation(world_coord.extend(0.1))
+                        * Matrix4::from_scale(vec3(0.25, 0.25, 1.0)),
+                ),
+                item.model,
+            ));
+        }
+
+        Self {
+            key,
+            extra_instances,
+            in_world_item_instances,
+        }
+    }
+}
+
+/// The overlays of the tiles in view, kept across frames as they rarely change.
+#[derive(Debug, Default)]
+struct OverlayCache {
+    origin: Option<TileCoord>,
+    overlays: HashMap<TileCoord, Overlay>,
+}
+
+/// How wide the thumbnails taken of maps are, in pixels. Their height follows the shape of the window.
+pub const THUMBNAIL_CAPTURE_WIDTH: u32 = 320;
+
+/// A picture of a map, taken when it was last saved.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub map_name: String,
+    pub time: SystemTime,
+    /// The picture, encoded as a PNG.
+    pub png: Arc<[u8]>,
+}
+
 pub struct Renderer<'a> {
     pub gpu: Gpu<'a>,
     pub shared_resources: SharedResources,
     pub render_resources: RenderResources,
     pub global_buffers: Arc<GlobalBuffers>,
     pub fps_limit: Double,
-
-    render_info_cache:
-        Arc<Mutex<Option<(HashMap<TileCoord, RenderUnit>, HashMap<TileCoord, DataMap>)>>>,
+    pub quality: Quality,
+    /// Whether the bright parts of the world glow.
+    pub bloom: bool,
+    pub screenshot_options: ScreenshotOptions,
+    /// Where the last screenshot was saved, until the GUI has shown it.
+    pub saved_screenshot: Arc<Mutex<Option<PathBuf>>>,
+    /// The map to take a thumbnail of on the next frame.
+    pub thumbnail_request: Option<String>,
+    /// The last thumbnail taken. It is also saved next to its map.
+    pub last_thumbnail: Arc<Mutex<Option<Thumbnail>>>,
+
+    render_info_cache: Arc<Mutex<Option<(HashMap<TileCoord, RenderUnit>, Vec<(TileCoord, Id)>)>>>,
     render_info_updating: Arc<AtomicBool>,
+    uploader: Uploader,
+    tile_snapshot: TileSnapshot,
     transaction_records_cache: Arc<Mutex<TransactionRecords>>,
     transaction_records_updating: Arc<AtomicBool>,
+    overlay_cache: OverlayCache,
 
     pub tile_tints: HashMap<TileCoord, Rgba>,
+    /// The tiles drawn with an outline around them, in the color of the outline.
+    pub tile_outlines: HashMap<TileCoord, Rgba>,
     pub extra_instances: Vec<(InstanceData, Id)>,
     pub in_world_item_instances: Vec<(InstanceData, Id)>,
-
-    pub take_item_animations: HashMap<Item, VecDeque<(Instant, Rect)>>,
 }
 
 impl<'a> Renderer<'a> {
@@ -77,6 +251,7 @@ impl<'a> Renderer<'a> {
         shared_resources: SharedResources,
         render_resources: RenderResources,
         global_buffers: Arc<GlobalBuffers>,
+        tile_snapshot: TileSnapshot,
         options: &Options,
     ) -> Self {
         Self {
@@ -84,57 +259,66 @@ impl<'a> Renderer<'a> {
             shared_resources,
             render_resources,
             global_buffers,
-            fps_limit: options.graphics.fps_limit,
+            fps_limit: options.graphics.frame_rate_limit(),
+            quality: options.graphics.quality,
+            bloom: options.graphics.bloom,
+            screenshot_options: options.graphics.screenshot.clone(),
+            saved_screenshot: Arc::new(Default::default()),
+            thumbnail_request: None,
+            last_thumbnail: Arc::new(Default::default()),
 
             render_info_cache: Arc::new(Default::default()),
             render_info_updating: Arc::new(Default::default()),
+            uploader: Default::default(),
+            tile_snapshot,
             transaction_records_cache: Arc::new(Default::default()),
             transaction_records_updating: Arc::new(Default::default()),
+            overlay_cache: Default::default(),
 
             tile_tints: Default::default(),
+            tile_outlines: Default::default(),
             extra_instances: vec![],
             in_world_item_instances: vec![],
-
-            take_item_animations: Default::default(),
         }
     }
 }
 
-pub fn try_add_animation(
-    resource_man: &ResourceManager,
-    start_instant: Instant,
-    model: Id,
-    animation_map: &mut AnimationMap,
-) {
-    if !animation_map.contains_key(&model) {
-        let elapsed = Instant::now().duration_since(start_instant).as_secs_f32();
-
-        if let Some((_, anims)) = resource_man.all_models.get(&model) {
-            let anims = anims
-                .iter()
-                .map(|anim| {
-                    let last = anim.inputs.last().unwrap();
-                    let wrapped = elapsed % last;
-                    let index = anim.inputs.partition_point(|v| *v < wrapped);
-
-                    (anim.target, anim.outputs[index])
-                })
-                .collect::<Vec<_>>();
-
-            let anims = anims
-                .binary_group_by_key(|v| v.0)
-                .map(|v| (v[0].0, v.iter().fold(Matrix4::IDENTITY, |acc, v| acc * v.1)))
-                .collect::<HashMap<_, _>>();
-
-            animation_map.insert(model, anims);
-        }
+impl<'a> Renderer<'a> {
+    /// How much of the memory each of the renderer's caches is using, by name.
+    pub fn memory_usage(&self) -> Vec<(&'static str, CacheUsage)> {
+        let (render_units, ground_items) = self
+            .render_info_cache
+            .blocking_lock()
+            .as_ref()
+            .map(|(instances, ground_items)| {
+                (
+                    CacheUsage::of::<(TileCoord, RenderUnit)>(instances.len()),
+                    CacheUsage::of::<(TileCoord, Id)>(ground_items.len()),
+                )
+            })
+            .unwrap_or_default();
+
+        let transaction_records = CacheUsage::of::<(Duration, TransactionRecord)>(
+            self.transaction_records_cache
+                .blocking_lock()
+                .values()
+                .map(VecDeque::len)
+                .sum(),
+        );
+
+        let overlays = CacheUsage::of::<(TileCoord, Overlay)>(self.overlay_cache.overlays.len());
+
+        vec![
+            ("Render Units", render_units),
+            ("Ground Items", ground_items),
+            ("Overlays", overlays),
+            ("Transaction Records", transaction_records),
+        ]
     }
-}
 
-impl<'a> Renderer<'a> {
     pub fn render(
         &mut self,
-        start_instant: Instant,
+        clocks: &GameClocks,
         resource_man: &ResourceManager,
         tokio: &Runtime,
         input_handler: &InputHandler,
@@ -145,6 +329,7 @@ impl<'a> Renderer<'a> {
         gui::reset_callback_counter();
 
         let tile_tints = mem::take(&mut self.tile_tints);
+        let tile_outlines = mem::take(&mut self.tile_outlines);
         let mut extra_instances = mem::take(&mut self.extra_instances);
         let mut in_world_item_instances = mem::take(&mut self.in_world_item_instances);
 
@@ -154,6 +339,12 @@ impl<'a> Renderer<'a> {
             return Ok(());
         }
 
+        // the resize events can lag behind the actual size, so catch up before drawing
+        if self.gpu.is_outdated() {
+            self.gpu
+                .resize(&mut self.shared_resources, &mut self.render_resources, size);
+        }
+
         let culling_range = camera.culling_range;
 
         if !self.render_info_updating.load(Ordering::Relaxed) {
@@ -164,14 +355,20 @@ impl<'a> Renderer<'a> {
             updating.store(true, Ordering::Relaxed);
 
             tokio.spawn(async move {
-                let all_data = game
-                    .call(GameSystemMessage::GetAllData, None)
+                let instances = game
+                    .call(
+                        |reply| GameSystemMessage::GetAllRenderUnits {
+                            reply,
+                            culling_range,
+                        },
+                        None,
+                    )
                     .await
                     .unwrap()
                     .unwrap();
-                let instances = game
+                let ground_items = game
                     .call(
-                        |reply| GameSystemMessage::GetAllRenderUnits {
+                        |reply| GameSystemMessage::GetGroundItems {
                             reply,
                             culling_range,
                         },
@@ -181,15 +378,17 @@ impl<'a> Renderer<'a> {
                     .unwrap()
                     .unwrap();
 
-                *cache.lock().await = Some((instances, all_data));
+                *cache.lock().await = Some((instances, ground_items));
 
                 updating.store(false, Ordering::Relaxed);
             });
         }
 
-        let Some((mut instances, all_data)) = self.render_info_cache.blocking_lock().clone() else {
+        let Some((mut instances, ground_items)) = self.render_info_cache.blocking_lock().clone()
+        else {
             return Ok(());
         };
+        let all_data = self.tile_snapshot.get();
 
         if !self.transaction_records_updating.load(Ordering::Relaxed) {
             let cache = self.transaction_records_cache.clone();
@@ -211,130 +410,128 @@ impl<'a> Renderer<'a> {
             });
         }
 
-        let camera_pos_float = camera.get_pos().as_vec3();
+        let camera_pos_float = camera.get_relative_pos().as_vec3();
         let world_matrix = camera.get_matrix().as_mat4();
 
-        let mut animation_map = gui
+        let elapsed = clocks.simulation.now().as_secs_f32();
+        let animation_cache = gui
             .renderer
             .callback_resources
-            .insert(AnimationMap::new())
+            .get_mut::<AnimationCache>()
             .unwrap();
 
-        let mut direction_previews = Vec::new();
-
         for (coord, unit) in instances.iter_mut() {
-            let tile = resource_man.registry.tiles.get(&unit.tile_id).unwrap();
-
-            if let Some(theta) = all_data
-                .get(coord)
-                .and_then(|data| data.get(&resource_man.registry.data_ids.target))
-                .and_then(|target| {
-                    if let Data::Coord(target) = target {
-                        math::tile_direction_to_angle(*target)
-                    } else {
-                        None
-                    }
-                })
-            {
-                unit.instance = unit
-                    .instance
-                    .add_model_matrix(Matrix4::from_rotation_z(theta.to_radians()));
-
-                if let Data::Color(color) = tile
-                    .data
-                    .get(&resource_man.registry.data_ids.direction_color)
-                    .unwrap_or(&Data::Color(colors::ORANGE))
-                {
-                    direction_previews.push((
-                        InstanceData::default()
-                            .with_color_offset(color.to_array())
-                            .with_world_matrix(world_matrix)
-                            .with_light_pos(camera_pos_float, None)
-                            .with_model_matrix(
-                                unit.instance.get_model_matrix()
-                                    * Matrix4::from_rotation_z(FRAC_PI_6 * 5.0)
-                                    * Matrix4::from_scale(vec3(0.1, SQRT_3, LINE_DEPTH))
-                                    * Matrix4::from_translation(vec3(0.0, 0.5, 0.0)),
-                            ),
-                        resource_man.registry.model_ids.cube1x1,
-                        (),
-                    ))
-                }
-            } else if let Some(Data::Id(inactive)) = tile
-                .data
-                .get(&resource_man.registry.data_ids.inactive_model)
-            {
-                unit.model = resource_man.get_model(*inactive);
-            }
+            place_unit(resource_man, camera, *coord, unit, all_data.get(coord));
         }
 
-        for (coord, data) in all_data {
-            let world_coord = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
-            if let Some(Data::Coord(link)) = data.get(&resource_man.registry.data_ids.link) {
-                extra_instances.push((
-                    InstanceData::default()
-                        .with_color_offset(colors::RED.to_array())
-                        .with_light_pos(camera_pos_float, None)
-                        .with_world_matrix(world_matrix)
-                        .with_model_matrix(make_line(
-                            world_coord,
-                            HEX_GRID_LAYOUT.hex_to_world_pos(**link),
-                        )),
-                    resource_man.registry.model_ids.cube1x1,
-                ));
+        {
+            let cache = &mut self.overlay_cache;
+
+            // the overlays are relative to the origin, so they all move when it does
+            if cache.origin != Some(camera.get_origin()) {
+                cache.origin = Some(camera.get_origin());
+                cache.overlays.clear();
             }
+            cache
+                .overlays
+                .retain(|coord, _| culling_range.is_in_bounds(**coord));
+
+            for (coord, data) in all_data.in_range(culling_range) {
+                let key = OverlayKey::new(
+                    resource_man,
+                    instances.get(coord).map(|unit| unit.tile_id),
+                    data,
+                );
 
-            if let Some(Data::Id(id)) = data.get(&resource_man.registry.data_ids.item) {
-                in_world_item_instances.push((
-                    InstanceData::default()
-                        .with_light_pos(camera_pos_float, None)
-                        .with_world_matrix(world_matrix)
-                        .with_model_matrix(
-                            Matrix4::from_translation(world_coord.extend(0.1))
-                                * Matrix4::from_scale(vec3(0.25, 0.25, 1.0)),
-                        ),
-                    resource_man.registry.items[id].model,
-                ))
+                let overlay = cache
+                    .overlays
+                    .entry(*coord)
+                    .and_modify(|overlay| {
+                        if overlay.key != key {
+                            *overlay = Overlay::new(resource_man, camera, *coord, key);
+                        }
+                    })
+                    .or_insert_with(|| Overlay::new(resource_man, camera, *coord, key));
+
+                let in_view = |(instance, id): &(InstanceData, Id)| {
+                    (
+                        instance
+                            .with_light_pos(camera_pos_float, None)
+                            .with_world_matrix(world_matrix),
+                        *id,
+                    )
+                };
+
+                extra_instances.extend(overlay.extra_instances.iter().map(in_view));
+                in_world_item_instances.extend(overlay.in_world_item_instances.iter().map(in_view));
             }
         }
 
-        {
+        for (coord, item) in ground_items {
+            let Some(item) = resource_man.registry.items.get(&item) else {
+                continue;
+            };
+
+            in_world_item_instances.push((
+                InstanceData::default()
+                    .with_light_pos(camera_pos_float, None)
+                    .with_world_matrix(world_matrix)
+                    .with_model_matrix(
+                        Matrix4::from_translation(camera.rebase_coord(coord).extend(0.05))
+                            * Matrix4::from_scale(vec3(0.4, 0.4, 0.4)),
+                    ),
+                item.model,
+            ))
+        }
+
+        if self.quality.draw_transactions() {
             let transaction_records = self.transaction_records_cache.blocking_lock();
 
-            let now = Instant::now();
-
-            for ((source_coord, coord), instants) in transaction_records.iter() {
-                if culling_range.is_in_bounds(**source_coord) && culling_range.is_in_bounds(**coord)
-                {
-                    for (instant, TransactionRecord { stack, .. }) in instants {
-                        let duration = now.duration_since(*instant);
-                        let t = duration.as_secs_f64() / TRANSACTION_ANIMATION_SPEED.as_secs_f64();
-
-                        let point = lerp_coords_to_pixel(*source_coord, *coord, t as Float);
-
-                        let direction = *coord - *source_coord;
-                        let direction = HEX_GRID_LAYOUT.hex_to_world_pos(*direction);
-                        let theta = direction_to_angle(direction);
-
-                        let instance = InstanceData::default()
-                            .with_model_matrix(
-                                Matrix4::from_translation(vec3(
-                                    point.x as Float,
-                                    point.y as Float,
-                                    (FAR + 0.025) as Float,
-                                )) * Matrix4::from_rotation_z(theta)
-                                    * Matrix4::from_scale(vec3(0.3, 0.3, 0.3)),
-                            )
-                            .with_world_matrix(world_matrix)
-                            .with_light_pos(camera_pos_float, None);
-                        let model = resource_man.get_item_model(stack.item.model);
-
-                        in_world_item_instances.push((instance, model));
+            // interpolated between the last two ticks, so the items move smoothly even when the ticks don't
+            let now = self
+                .tile_snapshot
+                .last_tick()
+                .interpolate(clocks.simulation.now());
+
+            // the ones out of view are culled on the GPU, along with everything else
+            for ((source_coord, coord), records) in transaction_records.iter() {
+                for (time, TransactionRecord { stack, .. }) in records {
+                    let duration = now.saturating_sub(*time);
+                    // the cached records can be a few frames old, so some may be done already
+                    if duration >= TRANSACTION_ANIMATION_SPEED {
+                        continue;
                     }
+                    let t = duration.as_secs_f64() / TRANSACTION_ANIMATION_SPEED.as_secs_f64();
+
+                    let point = lerp_coords_to_pixel(
+                        *source_coord - camera.get_origin(),
+                        *coord - camera.get_origin(),
+                        t as Float,
+                    );
+
+                    let direction = *coord - *source_coord;
+                    let direction = HEX_GRID_LAYOUT.hex_to_world_pos(*direction);
+                    let theta = direction_to_angle(direction);
+
+                    let instance = InstanceData::default()
+                        .with_model_matrix(
+                            Matrix4::from_translation(vec3(
+                                point.x as Float,
+                                point.y as Float,
+                                (FAR + 0.025) as Float,
+                            )) * Matrix4::from_rotation_z(theta)
+                                * Matrix4::from_scale(vec3(0.3, 0.3, 0.3)),
+                        )
+                        .with_world_matrix(world_matrix)
+                        .with_light_pos(camera_pos_float, None);
+                    let model = resource_man.get_item_model(stack.item.model);
+
+                    in_world_item_instances.push((instance, model));
                 }
             }
         }
 
+        let mut outline_instances;
         let mut game_instances = {
             let none = resource_man
                 .registry
@@ -344,14 +541,15 @@ impl<'a> Renderer<'a> {
                 .model;
 
             for RenderUnit { model, .. } in instances.values() {
-                try_add_animation(&resource_man, start_instant, *model, &mut animation_map);
+                animation_cache.track(resource_man, *model, elapsed);
             }
 
             for hex in culling_range.all_coords() {
                 let coord = TileCoord::from(hex);
 
-                if !instances.contains_key(&coord) {
-                    let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+                // leave the ground out past the edge of the world, so the boundary is visible
+                if coord.is_in_world() && !instances.contains_key(&coord) {
+                    let p = camera.rebase_coord(coord);
 
                     instances.insert(
                         coord,
@@ -361,6 +559,7 @@ impl<'a> Renderer<'a> {
                             ),
                             tile_id: none,
                             model: none,
+                            crashed: false,
                         },
                     );
                 }
@@ -372,6 +571,26 @@ impl<'a> Renderer<'a> {
                 }
             }
 
+            // the outlined tiles are drawn again on their own, fully in the color of their outline
+            outline_instances = tile_outlines
+                .into_iter()
+                .filter_map(|(coord, color)| {
+                    let RenderUnit {
+                        instance, model, ..
+                    } = instances.get(&coord)?;
+
+                    Some((
+                        instance
+                            .with_color_offset(color.with_alpha(1.0).to_array())
+                            .with_light_pos(camera_pos_float, None)
+                            .with_world_matrix(world_matrix),
+                        *model,
+                        (),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            outline_instances.sort_by_key(|v| v.1);
+
             let mut map = HashMap::new();
 
             for RenderUnit {
@@ -393,11 +612,11 @@ impl<'a> Renderer<'a> {
         };
 
         for (_, model) in &extra_instances {
-            try_add_animation(&resource_man, start_instant, *model, &mut animation_map);
+            animation_cache.track(resource_man, *model, elapsed);
         }
 
         for (_, model) in &in_world_item_instances {
-            try_add_animation(&resource_man, start_instant, *model, &mut animation_map);
+            animation_cache.track(resource_man, *model, elapsed);
         }
 
         let mut extra_instances = extra_instances
@@ -405,7 +624,6 @@ impl<'a> Renderer<'a> {
             .map(|(instance, id)| (instance, id, ()))
             .collect::<Vec<_>>();
         game_instances.append(&mut extra_instances);
-        game_instances.append(&mut direction_previews);
         game_instances.sort_by_key(|v| v.1);
 
         let mut in_world_item_instances = in_world_item_instances
@@ -415,54 +633,78 @@ impl<'a> Renderer<'a> {
         in_world_item_instances.sort_by_key(|v| v.1);
 
         self.inner_render(
+            tokio,
             input_handler,
             gui,
             resource_man,
+            camera_pos_float,
             &game_instances,
             &in_world_item_instances,
-            &animation_map,
+            &outline_instances,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn inner_render(
         &mut self,
+        tokio: &Runtime,
         input_handler: &InputHandler,
         gui: &mut Gui,
         resource_man: &ResourceManager,
+        camera_pos: Vec3,
         game_instances: &[(InstanceData, Id, ())],
         in_world_item_instances: &[(InstanceData, Id, ())],
-        animation_map: &AnimationMap,
+        outline_instances: &[(InstanceData, Id, ())],
     ) -> Result<(), SurfaceError> {
-        let size = self.gpu.window.inner_size();
         let factor = gui.context.pixels_per_point();
 
-        let (game_instances, game_draws, game_draw_count, game_matrix_data) =
-            gpu::indirect_instance(resource_man, game_instances, true, animation_map);
+        let animation_map = &gui
+            .renderer
+            .callback_resources
+            .get::<AnimationCache>()
+            .unwrap()
+            .matrices;
+
+        let game_instances = gpu::indirect_instance(
+            resource_man,
+            game_instances,
+            true,
+            animation_map,
+            Some(camera_pos),
+        );
 
         let (
             in_world_item_instances,
             in_world_item_draws,
             in_world_item_draw_count,
-            in_world_item_matrix_data,
-        ) = gpu::indirect_instance(&resource_man, in_world_item_instances, true, animation_map);
+            in_world_item_matrices,
+        ) = gpu::indirect_instance(
+            &resource_man,
+            in_world_item_instances,
+            true,
+            animation_map,
+            Some(camera_pos),
+        );
+
+        let outline_instances = gpu::indirect_instance(
+            resource_man,
+            outline_instances,
+            true,
+            animation_map,
+            Some(camera_pos),
+        );
 
         let egui_out = gui.context.end_frame();
         let egui_primitives = gui.context.tessellate(egui_out.shapes, factor);
         let egui_desc = ScreenDescriptor {
-            size_in_pixels: [size.width, size.height],
+            size_in_pixels: [self.gpu.config.width, self.gpu.config.height],
             pixels_per_point: factor,
         };
 
+        // if the surface ends up a different size than the frame (as during live resizing),
+        // the present pass stretches the frame over it instead of leaving it blank
         let output = self.gpu.surface.get_current_texture()?;
 
-        {
-            let output_size = output.texture.size();
-
-            if output_size.width != size.width || output_size.height != size.height {
-                return Ok(());
-            }
-        }
-
         let mut encoder = self
             .gpu
             .device
@@ -470,387 +712,82 @@ impl<'a> Renderer<'a> {
                 label: Some("Render Encoder"),
             });
 
-        {
-            gpu::create_or_write_buffer(
-                &self.gpu.device,
-                &self.gpu.queue,
-                &mut self.render_resources.game_resources.instance_buffer,
-                bytemuck::cast_slice(game_instances.as_slice()),
-            );
-            let mut indirect_buffer = vec![];
-            game_draws
-                .into_iter()
-                .flat_map(|v| v.1)
-                .for_each(|v| indirect_buffer.extend_from_slice(v.0.as_bytes()));
-            gpu::create_or_write_buffer(
-                &self.gpu.device,
-                &self.gpu.queue,
-                &mut self.render_resources.game_resources.indirect_buffer,
-                indirect_buffer.as_slice(),
-            );
+        let game_draw_count = self.render_resources.game_resources.upload(
+            &self.gpu.device,
+            &mut encoder,
+            &mut self.uploader,
+            game_instances,
+        );
 
-            let mut game_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Game Render Pass"),
-                color_attachments: &[
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.game_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Clear(Color::BLACK),
-                            store: StoreOp::Store,
-                        },
-                    }),
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.normal_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Clear(NORMAL_CLEAR),
-                            store: StoreOp::Store,
-                        },
-                    }),
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.model_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Clear(Color::TRANSPARENT),
-                            store: StoreOp::Store,
-                        },
-                    }),
-                ],
-                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: &self.shared_resources.depth_texture().1,
-                    depth_ops: Some(Operations {
-                        load: LoadOp::Clear(1.0),
-                        store: StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            if game_draw_count > 0 {
-                self.gpu.queue.write_buffer(
-                    &self.render_resources.game_resources.uniform_buffer,
-                    0,
-                    bytemuck::cast_slice(&[GameUBO::default()]),
-                );
-                self.gpu.queue.write_buffer(
-                    &self.render_resources.game_resources.matrix_data_buffer,
-                    0,
-                    bytemuck::cast_slice(game_matrix_data.as_slice()),
-                );
-
-                game_pass.set_pipeline(&self.render_resources.game_resources.pipeline);
-                game_pass.set_bind_group(0, &self.render_resources.game_resources.bind_group, &[]);
-                game_pass.set_vertex_buffer(0, self.global_buffers.vertex_buffer.slice(..));
-                game_pass.set_vertex_buffer(
-                    1,
-                    self.render_resources
-                        .game_resources
-                        .instance_buffer
-                        .slice(..),
-                );
-                game_pass.set_index_buffer(
-                    self.global_buffers.index_buffer.slice(..),
-                    IndexFormat::Uint16,
-                );
-
-                game_pass.multi_draw_indexed_indirect(
-                    &self.render_resources.game_resources.indirect_buffer,
-                    0,
-                    game_draw_count,
-                );
-            }
-        }
+        let outline_draw_count = self.render_resources.outline_resources.upload(
+            &self.gpu.device,
+            &mut encoder,
+            &mut self.uploader,
+            outline_instances,
+        );
 
         {
-            gpu::create_or_write_buffer(
-                &self.gpu.device,
-                &self.gpu.queue,
-                &mut self
-                    .render_resources
-                    .in_world_item_resources
-                    .instance_buffer,
-                bytemuck::cast_slice(in_world_item_instances.as_slice()),
-            );
-            let mut indirect_buffer = vec![];
-            in_world_item_draws
-                .into_iter()
-                .flat_map(|v| v.1)
-                .for_each(|v| indirect_buffer.extend_from_slice(v.0.as_bytes()));
-            gpu::create_or_write_buffer(
-                &self.gpu.device,
-                &self.gpu.queue,
-                &mut self
-                    .render_resources
-                    .in_world_item_resources
-                    .indirect_buffer,
-                indirect_buffer.as_slice(),
-            );
-
-            let mut in_world_item_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("In-world Item Render Pass"),
-                color_attachments: &[
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.game_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Load,
-                            store: StoreOp::Store,
-                        },
-                    }),
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.normal_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Load,
-                            store: StoreOp::Store,
-                        },
-                    }),
-                    Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.model_texture().1,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Load,
-                            store: StoreOp::Store,
-                        },
-                    }),
-                ],
-                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: &self.shared_resources.depth_texture().1,
-                    depth_ops: Some(Operations {
-                        load: LoadOp::Load,
-                        store: StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+            let in_world_item_resources = &mut self.render_resources.in_world_item_resources;
 
             if in_world_item_draw_count > 0 {
-                self.gpu.queue.write_buffer(
-                    &self.render_resources.in_world_item_resources.uniform_buffer,
-                    0,
+                self.uploader.write(
+                    &self.gpu.device,
+                    &mut encoder,
+                    &in_world_item_resources.uniform_buffer,
                     bytemuck::cast_slice(&[GameUBO::default()]),
                 );
-                self.gpu.queue.write_buffer(
-                    &self
-                        .render_resources
-                        .in_world_item_resources
-                        .matrix_data_buffer,
-                    0,
-                    bytemuck::cast_slice(in_world_item_matrix_data.as_slice()),
-                );
-
-                in_world_item_pass
-                    .set_pipeline(&self.render_resources.in_world_item_resources.pipeline);
-                in_world_item_pass.set_bind_group(
-                    0,
-                    &self.render_resources.in_world_item_resources.bind_group,
-                    &[],
-                );
-                in_world_item_pass
-                    .set_vertex_buffer(0, self.global_buffers.vertex_buffer.slice(..));
-                in_world_item_pass.set_vertex_buffer(
-                    1,
-                    self.render_resources
-                        .in_world_item_resources
-                        .instance_buffer
-                        .slice(..),
-                );
-                in_world_item_pass.set_index_buffer(
-                    self.global_buffers.index_buffer.slice(..),
-                    IndexFormat::Uint16,
+                self.uploader.write(
+                    &self.gpu.device,
+                    &mut encoder,
+                    &in_world_item_resources.matrix_data_buffer,
+                    bytemuck::cast_slice(in_world_item_matrices.matrix_data.as_slice()),
                 );
-
-                in_world_item_pass.multi_draw_indexed_indirect(
-                    &self
-                        .render_resources
-                        .in_world_item_resources
-                        .indirect_buffer,
-                    0,
-                    in_world_item_draw_count,
+                self.uploader.write(
+                    &self.gpu.device,
+                    &mut encoder,
+                    &in_world_item_resources.world_matrix_data_buffer,
+                    bytemuck::cast_slice(in_world_item_matrices.world_matrix_data.as_slice()),
                 );
             }
-        }
 
-        {
-            let mut post_processing_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Game Post Processing Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &self
-                        .render_resources
-                        .game_resources
-                        .post_processing_texture()
-                        .1,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            post_processing_pass
-                .set_pipeline(&self.render_resources.post_processing_resources.pipeline);
-            post_processing_pass.set_bind_group(
-                0,
-                self.render_resources
-                    .game_resources
-                    .post_processing_bind_group(),
-                &[],
-            );
-            post_processing_pass.draw(0..3, 0..1);
-        }
-
-        {
-            let mut antialiasing_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Game Antialiasing Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &self
-                        .render_resources
-                        .game_resources
-                        .antialiasing_texture()
-                        .1,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+            let in_world_item_draws = in_world_item_draws
+                .into_values()
+                .flatten()
+                .map(|(draw, _)| draw)
+                .collect::<Vec<_>>();
 
-            antialiasing_pass
-                .set_pipeline(&self.render_resources.antialiasing_resources.fxaa_pipeline);
-            antialiasing_pass.set_bind_group(
-                0,
-                self.render_resources
-                    .game_resources
-                    .antialiasing_bind_group(),
-                &[],
+            in_world_item_resources.culling.cull(
+                &self.gpu.device,
+                &mut encoder,
+                &mut self.uploader,
+                (
+                    in_world_item_instances.as_slice(),
+                    in_world_item_draws.as_slice(),
+                ),
+                (
+                    &in_world_item_resources.matrix_data_buffer,
+                    &in_world_item_resources.world_matrix_data_buffer,
+                ),
+                &mut in_world_item_resources.instance_buffer,
+                &mut in_world_item_resources.indirect_buffer,
             );
-            antialiasing_pass.draw(0..3, 0..1);
         }
 
         let user_commands = {
-            let user_commands = {
-                for (id, delta) in egui_out.textures_delta.set {
-                    gui.renderer
-                        .update_texture(&self.gpu.device, &self.gpu.queue, id, &delta);
-                }
-
-                gui.renderer.update_buffers(
-                    &self.gpu.device,
-                    &self.gpu.queue,
-                    &mut encoder,
-                    &egui_primitives,
-                    &egui_desc,
-                )
-            };
-
-            {
-                let mut egui_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                    label: Some("Egui Render Pass"),
-                    color_attachments: &[Some(RenderPassColorAttachment {
-                        view: &self.shared_resources.multisampling_texture().1,
-                        resolve_target: Some(&self.render_resources.egui_resources.texture().1),
-                        ops: Operations {
-                            load: LoadOp::Clear(Color::TRANSPARENT),
-                            store: StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                        view: &self.shared_resources.multisampling_depth_texture().1,
-                        depth_ops: Some(Operations {
-                            load: LoadOp::Clear(1.0),
-                            store: StoreOp::Store,
-                        }),
-                        stencil_ops: None,
-                    }),
-                    occlusion_query_set: None,
-                    timestamp_writes: None,
-                });
-
+            for (id, delta) in egui_out.textures_delta.set {
                 gui.renderer
-                    .render(&mut egui_pass, &egui_primitives, &egui_desc);
+                    .update_texture(&self.gpu.device, &self.gpu.queue, id, &delta);
             }
 
-            for id in &egui_out.textures_delta.free {
-                gui.renderer.free_texture(id);
-            }
-
-            user_commands
+            gui.renderer.update_buffers(
+                &self.gpu.device,
+                &self.gpu.queue,
+                &mut encoder,
+                &egui_primitives,
+                &egui_desc,
+            )
         };
 
-        {
-            let mut combine_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Combine Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &self.render_resources.first_combine_resources.texture().1,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            combine_pass.set_pipeline(&self.render_resources.first_combine_resources.pipeline);
-            combine_pass.set_bind_group(
-                0,
-                self.render_resources.first_combine_resources.bind_group(),
-                &[],
-            );
-            combine_pass.draw(0..3, 0..1)
-        }
-
-        {
-            let view = output
-                .texture
-                .create_view(&TextureViewDescriptor::default());
-
-            let mut present_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Present Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            present_pass.set_pipeline(
-                &self
-                    .render_resources
-                    .intermediate_resources
-                    .present_pipeline,
-            );
-            present_pass.set_bind_group(
-                0,
-                self.render_resources
-                    .intermediate_resources
-                    .present_bind_group(),
-                &[],
-            );
-            present_pass.draw(0..3, 0..1)
-        }
-
         fn size_align<T: PrimInt>(size: T, alignment: T) -> T {
             ((size + alignment - T::one()) / alignment) * alignment
         }
@@ -860,8 +797,8 @@ impl<'a> Renderer<'a> {
         let buffer_dim = texture_dim.physical_size(output.texture.format());
         let padded_width = size_align(buffer_dim.width * block_size, COPY_BYTES_PER_ROW_ALIGNMENT);
 
-        let screenshot_buffer = if input_handler.key_active(KeyActions::Screenshot) {
-            let intermediate_texture = self.gpu.device.create_texture(&TextureDescriptor {
+        let screenshot = input_handler.key_active(KeyActions::Screenshot).then(|| {
+            let texture = self.gpu.device.create_texture(&TextureDescriptor {
                 label: Some("Screenshot Intermediate Texture"),
                 size: texture_dim,
                 mip_level_count: 1,
@@ -872,41 +809,6 @@ impl<'a> Renderer<'a> {
                 view_formats: &[],
             });
 
-            let intermediate_texture_view =
-                intermediate_texture.create_view(&TextureViewDescriptor::default());
-
-            {
-                let mut intermediate_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                    label: Some("Screenshot Intermediate Pass"),
-                    color_attachments: &[Some(RenderPassColorAttachment {
-                        view: &intermediate_texture_view,
-                        resolve_target: None,
-                        ops: Operations {
-                            load: LoadOp::Load,
-                            store: StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    occlusion_query_set: None,
-                    timestamp_writes: None,
-                });
-
-                intermediate_pass.set_pipeline(
-                    &self
-                        .render_resources
-                        .intermediate_resources
-                        .screenshot_pipeline,
-                );
-                intermediate_pass.set_bind_group(
-                    0,
-                    self.render_resources
-                        .intermediate_resources
-                        .present_bind_group(),
-                    &[],
-                );
-                intermediate_pass.draw(0..3, 0..1);
-            }
-
             let buffer = self.gpu.device.create_buffer(&BufferDescriptor {
                 label: Some("Screenshot Buffer"),
                 size: size_align(
@@ -917,69 +819,480 @@ impl<'a> Renderer<'a> {
                 mapped_at_creation: false,
             });
 
-            encoder.copy_texture_to_buffer(
-                intermediate_texture.as_image_copy(),
-                ImageCopyBuffer {
-                    buffer: &buffer,
-                    layout: ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(padded_width),
-                        rows_per_image: Some(buffer_dim.height),
+            (texture, buffer)
+        });
+
+        let thumbnail = self.thumbnail_request.take().map(|map_name| {
+            let size = Extent3d {
+                width: THUMBNAIL_CAPTURE_WIDTH,
+                height: (THUMBNAIL_CAPTURE_WIDTH * texture_dim.height / texture_dim.width).max(1),
+                depth_or_array_layers: 1,
+            };
+            let padded_width = size_align(
+                size.width * SCREENSHOT_FORMAT.block_copy_size(None).unwrap(),
+                COPY_BYTES_PER_ROW_ALIGNMENT,
+            );
+
+            // drawing the frame into a smaller texture scales it down
+            let texture = self.gpu.device.create_texture(&TextureDescriptor {
+                label: Some("Thumbnail Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: SCREENSHOT_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+
+            let buffer = self.gpu.device.create_buffer(&BufferDescriptor {
+                label: Some("Thumbnail Buffer"),
+                size: size_align(
+                    (padded_width * size.height) as BufferAddress,
+                    COPY_BUFFER_ALIGNMENT,
+                ),
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            (map_name, texture, buffer, size, padded_width)
+        });
+
+        let surface_view = output
+            .texture
+            .create_view(&TextureViewDescriptor::default());
+
+        {
+            let shared_resources = &self.shared_resources;
+            let render_resources = &self.render_resources;
+            let global_buffers = &self.global_buffers;
+            let gui_renderer = &gui.renderer;
+            let egui_primitives = &egui_primitives;
+            let egui_desc = &egui_desc;
+            let bloom = self.bloom;
+
+            let mut graph = FrameGraph::default();
+
+            graph.push(FramePass::new(
+                "game",
+                &[],
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                move |encoder| {
+                    gpu::world_pass(
+                        encoder,
+                        "Game Render Pass",
+                        shared_resources,
+                        global_buffers,
+                        render_resources.game_resources.world_draw(game_draw_count),
+                        true,
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "in_world_item",
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                move |encoder| {
+                    gpu::world_pass(
+                        encoder,
+                        "In-world Item Render Pass",
+                        shared_resources,
+                        global_buffers,
+                        render_resources
+                            .in_world_item_resources
+                            .world_draw(in_world_item_draw_count),
+                        false,
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "post_processing",
+                &[
+                    FrameResource::Game,
+                    FrameResource::Normal,
+                    FrameResource::Model,
+                    FrameResource::Depth,
+                ],
+                &[FrameResource::PostProcessing],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Game Post Processing Render Pass",
+                        &render_resources.game_resources.post_processing_texture().1,
+                        &render_resources.post_processing_resources.pipeline,
+                        render_resources.game_resources.post_processing_bind_group(),
+                    );
+                },
+            ));
+
+            if bloom {
+                graph.push(FramePass::new(
+                    "bloom",
+                    &[FrameResource::PostProcessing],
+                    &[FrameResource::PostProcessing],
+                    move |encoder| {
+                        gpu::bloom_pass(
+                            encoder,
+                            &render_resources.bloom_resources,
+                            &render_resources.game_resources.post_processing_texture().1,
+                        );
+                    },
+                ));
+            }
+
+            if outline_draw_count > 0 {
+                graph.push(FramePass::new(
+                    "outline",
+                    &[FrameResource::PostProcessing],
+                    &[FrameResource::PostProcessing],
+                    move |encoder| {
+                        gpu::outline_pass(
+                            encoder,
+                            global_buffers,
+                            &render_resources.outline_resources,
+                            outline_draw_count,
+                            &render_resources.game_resources.post_processing_texture().1,
+                        );
                     },
+                ));
+            }
+
+            graph.push(FramePass::new(
+                "antialiasing",
+                &[FrameResource::PostProcessing],
+                &[FrameResource::Antialiasing],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Game Antialiasing Render Pass",
+                        &render_resources.game_resources.antialiasing_texture().1,
+                        &render_resources.antialiasing_resources.fxaa_pipeline,
+                        render_resources.game_resources.antialiasing_bind_group(),
+                    );
                 },
-                buffer_dim,
-            );
+            ));
 
-            Some(buffer)
-        } else {
-            None
-        };
+            graph.push(FramePass::new(
+                "gui",
+                &[],
+                &[FrameResource::Gui],
+                move |encoder| {
+                    let mut egui_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("Egui Render Pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: &shared_resources.multisampling_texture().1,
+                            resolve_target: Some(&render_resources.egui_resources.texture().1),
+                            ops: Operations {
+                                load: LoadOp::Clear(Color::TRANSPARENT),
+                                store: StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                            view: &shared_resources.multisampling_depth_texture().1,
+                            depth_ops: Some(Operations {
+                                load: LoadOp::Clear(1.0),
+                                store: StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
+                        occlusion_query_set: None,
+                        timestamp_writes: None,
+                    });
+
+                    gui_renderer.render(&mut egui_pass, egui_primitives, egui_desc);
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "combine",
+                &[FrameResource::Antialiasing, FrameResource::Gui],
+                &[FrameResource::Combine],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Combine Render Pass",
+                        &render_resources.first_combine_resources.texture().1,
+                        &render_resources.first_combine_resources.pipeline,
+                        render_resources.first_combine_resources.bind_group(),
+                    );
+                },
+            ));
+
+            graph.push(FramePass::new(
+                "present",
+                &[FrameResource::Combine],
+                &[FrameResource::Surface],
+                move |encoder| {
+                    gpu::fullscreen_pass(
+                        encoder,
+                        "Present Pass",
+                        &surface_view,
+                        &render_resources.intermediate_resources.present_pipeline,
+                        render_resources.intermediate_resources.present_bind_group(),
+                    );
+                },
+            ));
+
+            if let Some((texture, buffer)) = &screenshot {
+                let (input, bind_group) = if self.screenshot_options.include_gui {
+                    (
+                        FrameResource::Combine,
+                        render_resources.intermediate_resources.present_bind_group(),
+                    )
+                } else {
+                    (
+                        FrameResource::Antialiasing,
+                        render_resources.intermediate_resources.game_bind_group(),
+                    )
+                };
+
+                graph.push(FramePass::new(
+                    "screenshot",
+                    &[input],
+                    &[FrameResource::Screenshot],
+                    move |encoder| {
+                        gpu::fullscreen_pass(
+                            encoder,
+                            "Screenshot Intermediate Pass",
+                            &texture.create_view(&TextureViewDescriptor::default()),
+                            &render_resources.intermediate_resources.screenshot_pipeline,
+                            bind_group,
+                        );
+
+                        encoder.copy_texture_to_buffer(
+                            texture.as_image_copy(),
+                            ImageCopyBuffer {
+                                buffer,
+                                layout: ImageDataLayout {
+                                    offset: 0,
+                                    bytes_per_row: Some(padded_width),
+                                    rows_per_image: Some(buffer_dim.height),
+                                },
+                            },
+                            buffer_dim,
+                        );
+                    },
+                ));
+            }
+
+            if let Some((_, texture, buffer, size, padded_width)) = &thumbnail {
+                graph.push(FramePass::new(
+                    "thumbnail",
+                    &[FrameResource::Antialiasing],
+                    &[FrameResource::Thumbnail],
+                    move |encoder| {
+                        gpu::fullscreen_pass(
+                            encoder,
+                            "Thumbnail Pass",
+                            &texture.create_view(&TextureViewDescriptor::default()),
+                            &render_resources.intermediate_resources.screenshot_pipeline,
+                            render_resources.intermediate_resources.game_bind_group(),
+                        );
+
+                        encoder.copy_texture_to_buffer(
+                            texture.as_image_copy(),
+                            ImageCopyBuffer {
+                                buffer,
+                                layout: ImageDataLayout {
+                                    offset: 0,
+                                    bytes_per_row: Some(*padded_width),
+                                    rows_per_image: Some(size.height),
+                                },
+                            },
+                            *size,
+                        );
+                    },
+                ));
+            }
+
+            graph.execute(&mut encoder);
+        }
+
+        for id in &egui_out.textures_delta.free {
+            gui.renderer.free_texture(id);
+        }
+
+        let screenshot_buffer = screenshot.map(|(_, buffer)| buffer);
+
+        self.uploader.finish();
+        if let Some(gui_resources) = gui.renderer.callback_resources.get_mut::<GuiResources>() {
+            gui_resources.uploader.finish();
+        }
 
         self.gpu
             .queue
             .submit(user_commands.into_iter().chain([encoder.finish()]));
 
+        self.uploader.recall();
+        if let Some(gui_resources) = gui.renderer.callback_resources.get_mut::<GuiResources>() {
+            gui_resources.uploader.recall();
+        }
+
         if let Some(buffer) = screenshot_buffer {
-            {
-                let slice = buffer.slice(..);
-
-                let (tx, rx) = oneshot::channel();
-
-                slice.map_async(MapMode::Read, move |result| {
-                    tx.send(result).unwrap();
-                });
-                self.gpu.device.poll(Maintain::Wait);
-                rx.blocking_recv().unwrap().unwrap();
-
-                let texture_width = (texture_dim.width * block_size) as usize;
-                let data = slice.get_mapped_range();
-                let mut result = Vec::<u8>::new();
-                for chunk in data.chunks_exact(padded_width as usize) {
-                    for pixel in chunk[..texture_width].chunks_exact(4) {
-                        result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+            let (tx, rx) = oneshot::channel();
+
+            buffer.slice(..).map_async(MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+
+            let options = self.screenshot_options.clone();
+            let saved_screenshot = self.saved_screenshot.clone();
+
+            // waiting for the copy and encoding the image takes a while, so it's done off the render thread
+            tokio.spawn_blocking(move || {
+                match rx.blocking_recv() {
+                    Ok(Ok(())) => {}
+                    result => {
+                        log::error!("Could not read back the screenshot: {result:?}");
+                        return;
                     }
                 }
 
-                if let Some(image) =
-                    RgbaImage::from_vec(texture_dim.width, texture_dim.height, result)
-                {
-                    let mut clipboard = Clipboard::new().unwrap();
+                let image = read_back_image(
+                    &buffer.slice(..).get_mapped_range(),
+                    padded_width,
+                    texture_dim.width,
+                    texture_dim.height,
+                );
+                buffer.unmap();
+
+                let Some(image) = image else {
+                    return;
+                };
+
+                if options.file {
+                    match save_screenshot(&image, &options.directory, "") {
+                        Ok(path) => *saved_screenshot.blocking_lock() = Some(path),
+                        Err(err) => log::error!("Could not save the screenshot: {err:?}"),
+                    }
+                }
 
-                    clipboard
-                        .set_image(ImageData {
+                if options.clipboard {
+                    let result = Clipboard::new().and_then(|mut clipboard| {
+                        clipboard.set_image(ImageData {
                             width: image.width() as usize,
                             height: image.height() as usize,
                             bytes: Cow::from(image.as_bytes()),
                         })
-                        .unwrap();
+                    });
+
+                    if let Err(err) = result {
+                        log::error!("Could not copy the screenshot to the clipboard: {err:?}");
+                    }
+                }
+            });
+        }
+
+        if let Some((map_name, _, buffer, size, padded_width)) = thumbnail {
+            let (tx, rx) = oneshot::channel();
+
+            buffer.slice(..).map_async(MapMode::Read, move |result| {
+                let _ = tx.send(result);
+            });
+
+            let last_thumbnail = self.last_thumbnail.clone();
+
+            tokio.spawn_blocking(move || {
+                match rx.blocking_recv() {
+                    Ok(Ok(())) => {}
+                    result => {
+                        log::error!("Could not read back the thumbnail: {result:?}");
+                        return;
+                    }
                 }
-            }
 
-            buffer.unmap();
+                let image = read_back_image(
+                    &buffer.slice(..).get_mapped_range(),
+                    padded_width,
+                    size.width,
+                    size.height,
+                );
+                buffer.unmap();
+
+                let Some(image) = image else {
+                    return;
+                };
+
+                match save_thumbnail(&map_name, &image) {
+                    Ok(png) => {
+                        *last_thumbnail.blocking_lock() = Some(Thumbnail {
+                            map_name,
+                            time: SystemTime::now(),
+                            png,
+                        });
+                    }
+                    Err(err) => {
+                        log::error!("Could not save the thumbnail of {map_name}: {err:?}");
+                    }
+                }
+            });
         }
 
+        // lets the screenshot and thumbnail readbacks finish without waiting on them
+        self.gpu.device.poll(Maintain::Poll);
+
         output.present();
 
         Ok(())
     }
 }
+
+/// Copies the pixels out of a texture read back into a buffer, whose rows are padded to `padded_width` bytes.
+fn read_back_image(data: &[u8], padded_width: u32, width: u32, height: u32) -> Option<RgbaImage> {
+    let mut result = Vec::<u8>::new();
+    for chunk in data.chunks_exact(padded_width as usize) {
+        for pixel in chunk[..(width * 4) as usize].chunks_exact(4) {
+            result.extend(&[pixel[0], pixel[1], pixel[2], 255]);
+        }
+    }
+
+    RgbaImage::from_vec(width, height, result)
+}
+
+/// Saves the thumbnail next to the map, and gives back the PNG it was saved as.
+fn save_thumbnail(map_name: &str, image: &RgbaImage) -> anyhow::Result<Arc<[u8]>> {
+    let mut png = Cursor::new(vec![]);
+    image.write_to(&mut png, ImageFormat::Png)?;
+    let png = png.into_inner();
+
+    fs::write(Map::thumbnail(map_name), &png)?;
+
+    log::info!("Saved the thumbnail of {map_name}");
+
+    Ok(Arc::from(png))
+}
+
+/// Saves the screenshot as a PNG in the folder, named after the time it was taken, after the prefix.
+pub fn save_screenshot(
+    image: &RgbaImage,
+    directory: &str,
+    prefix: &str,
+) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(directory)?;
+
+    let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    let path = Path::new(directory).join(format!("{prefix}{}.png", time.as_millis()));
+
+    image.save(&path)?;
+
+    log::info!("Saved screenshot to {}", path.display());
+
+    Ok(path)
+}
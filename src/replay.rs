@@ -0,0 +1,181 @@
+//! Recording the player's input to a file, and playing it back into the game later, for scripted smoke tests
+//! of the menus and of placing things without having to click through them by hand.
+//!
+//! Start the game with `--record-input <file>` to record, and with `--play-input <file>` to play a recording back.
+//! Each line of the file is one input, in RON, along with how many seconds into the recording it happened.
+//! The game closes by itself once a recording has played out.
+//!
+//! Only the inputs the game reacts to are recorded, and the pointer positions are in pixels, so recordings
+//! are best played back at the same window size and scale they were made at. The window is asked to take on
+//! the recorded size when playback starts.
+
+use std::collections::VecDeque;
+use std::fs::{read_to_string, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{
+    DeviceId, ElementState, Modifiers, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use winit::keyboard::{Key, ModifiersState, SmolStr};
+
+/// One recorded input. Everything but the keys is played back as the window event it was recorded from.
+/// Key events can't be made outside of winit, so those are played back by [`crate::event::on_event`] itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedInput {
+    /// the window has this size, which is recorded once at the start
+    Resized(PhysicalSize<u32>),
+    Focused(bool),
+    CursorMoved(PhysicalPosition<f64>),
+    MouseInput {
+        state: ElementState,
+        button: MouseButton,
+    },
+    MouseWheel(MouseScrollDelta),
+    Modifiers(ModifiersState),
+    Key {
+        state: ElementState,
+        key: Key,
+        repeat: bool,
+        /// the text typed by the key, if any
+        text: Option<SmolStr>,
+    },
+}
+
+impl RecordedInput {
+    /// Gets the input from the window event, if it is one the game reacts to.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::Resized(size) => Self::Resized(*size),
+            WindowEvent::Focused(focused) => Self::Focused(*focused),
+            WindowEvent::CursorMoved { position, .. } => Self::CursorMoved(*position),
+            WindowEvent::MouseInput { state, button, .. } => Self::MouseInput {
+                state: *state,
+                button: *button,
+            },
+            WindowEvent::MouseWheel { delta, .. } => Self::MouseWheel(*delta),
+            WindowEvent::ModifiersChanged(modifiers) => Self::Modifiers(modifiers.state()),
+            WindowEvent::KeyboardInput { event, .. } => Self::Key {
+                state: event.state,
+                key: event.logical_key.clone(),
+                repeat: event.repeat,
+                text: event.text.clone(),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Turns the input back into the window event it was recorded from.
+    /// Gives [`None`] for keys, and for resizes, which are asked of the window instead.
+    pub fn to_window_event(&self) -> Option<WindowEvent> {
+        // SAFETY: the dummy ID is only ever compared against, never passed to the platform
+        let device_id = unsafe { DeviceId::dummy() };
+
+        Some(match self {
+            Self::Focused(focused) => WindowEvent::Focused(*focused),
+            Self::CursorMoved(position) => WindowEvent::CursorMoved {
+                device_id,
+                position: *position,
+            },
+            Self::MouseInput { state, button } => WindowEvent::MouseInput {
+                device_id,
+                state: *state,
+                button: *button,
+            },
+            Self::MouseWheel(delta) => WindowEvent::MouseWheel {
+                device_id,
+                delta: *delta,
+                phase: TouchPhase::Moved,
+            },
+            Self::Modifiers(modifiers) => {
+                WindowEvent::ModifiersChanged(Modifiers::from(*modifiers))
+            }
+            Self::Resized(_) | Self::Key { .. } => return None,
+        })
+    }
+}
+
+/// Writes the inputs to a file as they come in.
+pub struct InputRecorder {
+    file: BufWriter<File>,
+    start: Instant,
+}
+
+impl InputRecorder {
+    /// Starts recording to the file, beginning with the size of the window.
+    pub fn create(path: &Path, window_size: PhysicalSize<u32>) -> anyhow::Result<Self> {
+        let mut recorder = Self {
+            file: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+        };
+        recorder.write(&RecordedInput::Resized(window_size))?;
+
+        Ok(recorder)
+    }
+
+    fn write(&mut self, input: &RecordedInput) -> anyhow::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+
+        writeln!(self.file, "{}", ron::to_string(&(time, input))?)?;
+        // flushed every time, so the recording survives the game being killed
+        self.file.flush()?;
+
+        Ok(())
+    }
+
+    /// Records the window event, if it is one the game reacts to.
+    pub fn record(&mut self, event: &WindowEvent) -> anyhow::Result<()> {
+        if let Some(input) = RecordedInput::from_window_event(event) {
+            self.write(&input)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hands out the inputs of a recording once it is time for them.
+pub struct InputPlayer {
+    inputs: VecDeque<(f64, RecordedInput)>,
+    start: Instant,
+}
+
+impl InputPlayer {
+    /// Reads the recording from the file. The time starts counting from when it is read.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    /// Reads the recording from its text. The time starts counting from when it is read.
+    pub fn parse(recording: &str) -> anyhow::Result<Self> {
+        let inputs = recording
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(ron::from_str::<(f64, RecordedInput)>)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            inputs,
+            start: Instant::now(),
+        })
+    }
+
+    /// Takes the inputs that are due by now, in the order they were recorded.
+    pub fn due(&mut self) -> Vec<RecordedInput> {
+        let now = self.start.elapsed().as_secs_f64();
+        let count = self
+            .inputs
+            .iter()
+            .take_while(|(time, _)| *time <= now)
+            .count();
+
+        self.inputs.drain(..count).map(|(_, input)| input).collect()
+    }
+
+    /// Whether every input has been played back.
+    pub fn is_finished(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
@@ -0,0 +1,326 @@
+//! The soak test, run with `--soak <minutes>`, where a bot keeps making random changes to a map for as long as asked,
+//! to catch the game crashing, leaking memory or going wrong only after a long time, before a release does.
+//!
+//! The bot places, removes and configures tiles, undoes and redoes, ticks the game, and saves and loads the map,
+//! all on a small area around the center so the map stays about the same size. Along the way it looks out for
+//! the game panicking, tiles crashing, the map coming back different from how it was saved, and the memory
+//! used growing for good.
+//!
+//! The bot is seeded, and the seed is logged, so a run can be repeated with `--soak-seed <seed>`.
+//! Two runs with the same seed aren't compared against each other, since the scripts' `random` isn't seeded.
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use automancy_defs::coord::{TileCoord, TileUnit};
+use automancy_defs::id::Id;
+use automancy_defs::log;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::ResourceManager;
+
+use crate::game::GameEvent;
+use crate::map::Map;
+use crate::memory::{self, format_bytes};
+use crate::simulation::Simulation;
+
+/// The name of the map the soak test plays on. It is cleared out when the test starts.
+pub const SOAK_MAP: &str = ".soak";
+/// How far from the center the bot makes its changes.
+const SOAK_RADIUS: TileUnit = 12;
+/// How often the progress is logged, and the memory checked.
+const REPORT_EVERY: Duration = Duration::from_secs(60);
+/// How much the memory used can grow between the first progress report and the end, before it counts as a leak.
+/// The first report is used instead of the start, so the map has had time to fill up.
+const LEAK_GROWTH: f64 = 1.5;
+/// The most tile crashes reported. Past this, they are only counted.
+const MAX_CRASHES_REPORTED: usize = 20;
+
+/// Something that went wrong during the soak test.
+#[derive(Debug, Clone)]
+pub enum SoakFailure {
+    /// the game panicked, which ends the test
+    Panicked { step: u64, message: String },
+    /// a tile entity panicked and was restarted
+    TileCrashed {
+        step: u64,
+        coord: TileCoord,
+        tile: Id,
+    },
+    /// the map was different after loading it than when it was saved
+    Desynced { step: u64, saved: u64, loaded: u64 },
+    /// the memory used kept growing
+    Leaked { from: u64, to: u64 },
+}
+
+impl Display for SoakFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SoakFailure::Panicked { step, message } => {
+                write!(f, "The game panicked at step {step}: {message}")
+            }
+            SoakFailure::TileCrashed { step, coord, tile } => {
+                write!(f, "The tile {tile:?} at {coord} crashed at step {step}")
+            }
+            SoakFailure::Desynced {
+                step,
+                saved,
+                loaded,
+            } => write!(
+                f,
+                "The map hashed {saved:016x} when saved and {loaded:016x} when loaded, at step {step}"
+            ),
+            SoakFailure::Leaked { from, to } => write!(
+                f,
+                "The memory used grew from {} to {}",
+                format_bytes(*from),
+                format_bytes(*to)
+            ),
+        }
+    }
+}
+
+/// What happened during a soak test.
+#[derive(Debug, Clone, Default)]
+pub struct SoakReport {
+    /// how many actions the bot took
+    pub steps: u64,
+    /// how many times the game was ticked
+    pub ticks: usize,
+    /// how many tiles crashed in total, including the ones not in `failures`
+    pub crashes: usize,
+    pub failures: Vec<SoakFailure>,
+}
+
+/// The things the bot does, along with how likely it is to do each.
+#[derive(Debug, Clone, Copy)]
+enum SoakAction {
+    Place,
+    Remove,
+    Configure,
+    Tick,
+    Undo,
+    Redo,
+    SaveAndLoad,
+}
+
+const ACTIONS: [(SoakAction, u32); 7] = [
+    (SoakAction::Place, 30),
+    (SoakAction::Remove, 10),
+    (SoakAction::Configure, 20),
+    (SoakAction::Tick, 30),
+    (SoakAction::Undo, 4),
+    (SoakAction::Redo, 4),
+    (SoakAction::SaveAndLoad, 2),
+];
+
+/// The bot, along with the game it plays.
+struct SoakBot {
+    sim: Simulation,
+    rng: StdRng,
+    /// the tiles that can be placed
+    tiles: Vec<Id>,
+    step: u64,
+}
+
+impl SoakBot {
+    fn random_coord(&mut self) -> TileCoord {
+        TileCoord::new(
+            self.rng.gen_range(-SOAK_RADIUS..=SOAK_RADIUS),
+            self.rng.gen_range(-SOAK_RADIUS..=SOAK_RADIUS),
+        )
+    }
+
+    /// Takes one random action. Gives the failure it ran into, if any.
+    fn act(&mut self) -> Option<SoakFailure> {
+        let action = ACTIONS
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map(|(action, _)| *action)
+            .unwrap_or(SoakAction::Tick);
+
+        let resource_man = self.sim.resource_man.clone();
+        let data_ids = resource_man.registry.data_ids;
+
+        match action {
+            SoakAction::Place => {
+                let coord = self.random_coord();
+                if let Some(tile) = self
+                    .tiles
+                    .choose(&mut self.rng)
+                    .and_then(|id| resource_man.interner.resolve(*id))
+                {
+                    self.sim.place(coord, tile, DataMap::default());
+                }
+            }
+            SoakAction::Remove => {
+                let coord = self.random_coord();
+                self.sim.remove(coord);
+            }
+            SoakAction::Configure => {
+                let coord = self.random_coord();
+                let Some(tile) = self.sim.tile(coord) else {
+                    return None;
+                };
+
+                // tiles that run scripts are given one of theirs, and the rest are pointed somewhere
+                match resource_man
+                    .registry
+                    .tiles
+                    .get(&tile)
+                    .and_then(|tile| tile.data.get(&data_ids.scripts))
+                {
+                    Some(Data::VecId(scripts)) if !scripts.is_empty() => {
+                        let script = scripts.choose(&mut self.rng).copied().map(Data::Id);
+                        self.sim.configure(coord, data_ids.script, script);
+                    }
+                    _ => {
+                        let target = TileCoord::ZERO
+                            .neighbors()
+                            .choose(&mut self.rng)
+                            .copied()
+                            .map(Data::Coord);
+                        self.sim.configure(coord, data_ids.target, target);
+                    }
+                }
+            }
+            SoakAction::Tick => {
+                let ticks = self.rng.gen_range(1..=20);
+                self.sim.run_ticks(ticks);
+            }
+            SoakAction::Undo => {
+                self.sim.undo();
+            }
+            SoakAction::Redo => {
+                self.sim.redo();
+            }
+            SoakAction::SaveAndLoad => {
+                let saved = self.sim.save().state_hash();
+                let loaded = self.sim.load(SOAK_MAP).state_hash();
+
+                if saved != loaded {
+                    return Some(SoakFailure::Desynced {
+                        step: self.step,
+                        saved,
+                        loaded,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Gets the message a panic was started with.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|v| v.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Runs the soak test for the given time, or until the game panics.
+pub fn soak(
+    resource_man: Arc<ResourceManager>,
+    duration: Duration,
+    seed: u64,
+) -> anyhow::Result<SoakReport> {
+    log::info!("Soak testing for {duration:?} with the seed {seed}");
+
+    let map_path = Map::path(SOAK_MAP);
+    if map_path.exists() {
+        fs::remove_dir_all(map_path)?;
+    }
+
+    let tiles = resource_man
+        .ordered_tiles
+        .iter()
+        .filter(|id| **id != resource_man.registry.none)
+        .copied()
+        .collect();
+
+    let mut sim = Simulation::new(resource_man)?;
+    sim.load(SOAK_MAP);
+
+    let mut bot = SoakBot {
+        sim,
+        rng: StdRng::seed_from_u64(seed),
+        tiles,
+        step: 0,
+    };
+    let mut report = SoakReport::default();
+
+    let start = Instant::now();
+    let mut last_report = start;
+    let mut first_memory = None;
+
+    while start.elapsed() < duration {
+        bot.step += 1;
+
+        match panic::catch_unwind(AssertUnwindSafe(|| bot.act())) {
+            Ok(failure) => report.failures.extend(failure),
+            Err(payload) => {
+                report.failures.push(SoakFailure::Panicked {
+                    step: bot.step,
+                    message: panic_message(payload.as_ref()),
+                });
+
+                break;
+            }
+        }
+
+        for event in bot.sim.events() {
+            if let GameEvent::TileCrashed(coord, tile) = event {
+                report.crashes += 1;
+
+                if report.crashes <= MAX_CRASHES_REPORTED {
+                    report.failures.push(SoakFailure::TileCrashed {
+                        step: bot.step,
+                        coord,
+                        tile,
+                    });
+                }
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_EVERY {
+            last_report = Instant::now();
+
+            let memory = memory::process_memory();
+            first_memory = first_memory.or(memory);
+
+            log::info!(
+                "Soak test at step {}, {} ticks in, {} elapsed, using {}",
+                bot.step,
+                bot.sim.ticks(),
+                format_elapsed(start.elapsed()),
+                memory.map_or("unknown memory".to_string(), format_bytes)
+            );
+        }
+    }
+
+    if let (Some(from), Some(to)) = (first_memory, memory::process_memory()) {
+        if to as f64 > from as f64 * LEAK_GROWTH {
+            report.failures.push(SoakFailure::Leaked { from, to });
+        }
+    }
+
+    report.steps = bot.step;
+    report.ticks = bot.sim.ticks();
+
+    Ok(report)
+}
+
+/// Formats the time as hours, minutes and seconds.
+fn format_elapsed(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -1,3 +1,7 @@
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
 use hashbrown::HashSet;
 
 use automancy_defs::id::Id;
@@ -23,3 +27,18 @@ pub fn is_research_unlocked(
 
     return false;
 }
+
+/// Opens the folder in the system's file manager.
+pub fn open_folder(path: &Path) -> io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    Command::new(program).arg(path).spawn()?;
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+//! Renders small fixture maps headlessly, and compares them against reference images.
+//!
+//! The references live in `tests/golden/references`, named after the maps. Every reference is
+//! written from the current render when `AUTOMANCY_BLESS` is set, which is what to do after
+//! changing how the game draws on purpose, or adding a map. When a render doesn't match,
+//! it is written to `target/golden` along with a diff, to see what changed.
+//!
+//! The maps are drawn with the fixture tiles in `tests/golden/resources`, so changing the game's own
+//! models doesn't change the renders. Only the engine's `core` namespace, with the shaders, is shared.
+//!
+//! Rendering needs a graphics adapter, so the test is ignored by default. Run it with
+//! `cargo test golden -- --ignored`, adding `AUTOMANCY_BLESS=1` to write the references.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use serde::Deserialize;
+
+use automancy::headless;
+use automancy::load_resources_from;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::dvec3;
+use automancy_defs::math::Double;
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::RESOURCES_PATH;
+
+static FIXTURE_NAMESPACE_PATH: &str = "tests/golden/resources/golden";
+static MAPS_PATH: &str = "tests/golden/maps";
+static REFERENCES_PATH: &str = "tests/golden/references";
+static OUTPUT_PATH: &str = "target/golden";
+
+const IMAGE_SIZE: (u32, u32) = (256, 256);
+/// How far apart a channel can be before the pixel counts as different.
+/// Drivers don't round the same, and FXAA spreads that around a little.
+const CHANNEL_TOLERANCE: u8 = 8;
+/// How many of the pixels can be different before the render counts as different.
+const PIXEL_TOLERANCE: f64 = 0.005;
+
+/// A small map to render, with the camera to render it from.
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    camera: (Double, Double, Double),
+    /// the position, tile ID, and optionally the direction the tile is pointing in
+    tiles: Vec<(i32, i32, String, Option<(i32, i32)>)>,
+}
+
+/// How a render differs from its reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    pub different_pixels: usize,
+    pub total_pixels: usize,
+}
+
+impl ImageDiff {
+    pub fn within_tolerance(&self) -> bool {
+        self.different_pixels as f64 <= self.total_pixels as f64 * PIXEL_TOLERANCE
+    }
+}
+
+/// Compares two images pixel by pixel, and makes an image highlighting the pixels that are different.
+/// Returns [`None`] if the images aren't the same size.
+pub fn compare_images(expected: &RgbaImage, actual: &RgbaImage) -> Option<(ImageDiff, RgbaImage)> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+
+    let mut diff_image = RgbaImage::new(expected.width(), expected.height());
+    let mut different_pixels = 0;
+
+    for ((a, b), diff) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let different =
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE);
+
+        *diff = if different {
+            different_pixels += 1;
+
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([a[0] / 4, a[1] / 4, a[2] / 4, 255])
+        };
+    }
+
+    Some((
+        ImageDiff {
+            different_pixels,
+            total_pixels: (expected.width() * expected.height()) as usize,
+        },
+        diff_image,
+    ))
+}
+
+fn fixtures() -> Vec<(String, PathBuf)> {
+    let mut fixtures = fs::read_dir(MAPS_PATH)
+        .expect("The golden test maps are missing")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .map(|path| {
+            (
+                path.file_stem().unwrap().to_string_lossy().to_string(),
+                path,
+            )
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+
+    fixtures
+}
+
+#[test]
+#[ignore = "needs a graphics adapter, run with `cargo test golden -- --ignored`"]
+fn test_golden_images() {
+    assert!(
+        headless::has_adapter(),
+        "No graphics adapter is available to render the golden images with"
+    );
+
+    let bless = env::var_os("AUTOMANCY_BLESS").is_some();
+    let (resource_man, vertices, indices) = load_resources_from(&[
+        &Path::new(RESOURCES_PATH).join("core"),
+        Path::new(FIXTURE_NAMESPACE_PATH),
+    ]);
+
+    fs::create_dir_all(REFERENCES_PATH).unwrap();
+    fs::create_dir_all(OUTPUT_PATH).unwrap();
+
+    let mut failures = vec![];
+
+    for (name, path) in fixtures() {
+        let fixture: Fixture = ron::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|err| panic!("Could not read the golden test map {name}: {err}"));
+
+        let tiles = fixture
+            .tiles
+            .into_iter()
+            .map(|(q, r, id, target)| {
+                let id = resource_man
+                    .interner
+                    .get(&id)
+                    .unwrap_or_else(|| panic!("The golden test map {name} uses unknown tile {id}"));
+
+                let mut data = DataMap::default();
+                if let Some((q, r)) = target {
+                    data.insert(
+                        resource_man.registry.data_ids.target,
+                        Data::Coord(TileCoord::new(q, r)),
+                    );
+                }
+
+                (TileCoord::new(q, r), id, data)
+            })
+            .collect::<Vec<_>>();
+
+        let (x, y, z) = fixture.camera;
+        let actual = headless::render_tiles_to_image(
+            &resource_man,
+            &vertices,
+            &indices,
+            &tiles,
+            dvec3(x, y, z),
+            IMAGE_SIZE,
+        )
+        .unwrap();
+
+        let reference_path = Path::new(REFERENCES_PATH).join(format!("{name}.png"));
+
+        if bless {
+            actual.save(&reference_path).unwrap();
+            eprintln!("Wrote the reference image for {name}");
+
+            continue;
+        }
+
+        if !reference_path.exists() {
+            actual
+                .save(Path::new(OUTPUT_PATH).join(format!("{name}.png")))
+                .unwrap();
+
+            failures.push(format!(
+                "{name}: there is no reference image, check the render in {OUTPUT_PATH} and bless it with AUTOMANCY_BLESS=1"
+            ));
+            continue;
+        }
+
+        let expected = image::open(&reference_path).unwrap().to_rgba8();
+
+        let Some((diff, diff_image)) = compare_images(&expected, &actual) else {
+            failures.push(format!(
+                "{name}: the render is {:?}, but the reference is {:?}",
+                actual.dimensions(),
+                expected.dimensions()
+            ));
+            continue;
+        };
+
+        if !diff.within_tolerance() {
+            let output = Path::new(OUTPUT_PATH);
+            actual.save(output.join(format!("{name}.png"))).unwrap();
+            diff_image
+                .save(output.join(format!("{name}.diff.png")))
+                .unwrap();
+
+            failures.push(format!(
+                "{name}: {} of {} pixels are different, see {OUTPUT_PATH}",
+                diff.different_pixels, diff.total_pixels
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "The renders don't match the references:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_golden_compare_tolerance() {
+    let expected = RgbaImage::from_pixel(10, 10, Rgba([100, 100, 100, 255]));
+
+    let mut actual = expected.clone();
+    actual.put_pixel(0, 0, Rgba([100 + CHANNEL_TOLERANCE, 100, 100, 255]));
+    let (diff, _) = compare_images(&expected, &actual).unwrap();
+    assert_eq!(diff.different_pixels, 0);
+
+    actual.put_pixel(1, 0, Rgba([100, 100 + CHANNEL_TOLERANCE + 1, 100, 255]));
+    let (diff, _) = compare_images(&expected, &actual).unwrap();
+    assert_eq!(diff.different_pixels, 1);
+    assert!(!diff.within_tolerance());
+
+    assert!(compare_images(&expected, &RgbaImage::new(5, 5)).is_none());
+}
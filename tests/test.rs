@@ -1,5 +1,44 @@
-use automancy_defs::coord::TileCoord;
+use std::collections::HashSet;
+use std::fs;
+use std::thread;
+use std::time::Duration;
 
+use hashbrown::HashMap;
+use winit::event::{ElementState, MouseButton, TouchPhase};
+use winit::keyboard::{Key, NamedKey};
+
+use automancy::blueprint::{Blueprint, BlueprintError};
+use automancy::clock::Clock;
+use automancy::determinism::{compare_hash_logs, Comparison};
+use automancy::game::{TickStamp, MAX_TRANSACTION_RECORDS_PER_EDGE, TICK_INTERVAL, TPS};
+use automancy::input::{Binding, GameInputEvent, InputHandler, KeyActions, KeyInput};
+use automancy::load_resources;
+use automancy::map::{Map, MapInfoRaw, MapRaw, Tiles, LOCAL_PLAYER};
+use automancy::marker::{MarkerIcon, Markers};
+use automancy::migration::MAP_VERSION;
+use automancy::options::Options;
+use automancy::replay::{InputPlayer, RecordedInput};
+use automancy::scenario::{Condition, Scenario, ScenarioOutcome};
+use automancy::simulation::Simulation;
+use automancy::suggestion::suggest_tiles;
+use automancy::tile_entity::Balancer;
+use automancy::zone::Zones;
+use automancy_defs::colors::Rgba;
+use automancy_defs::coord::{PackedCoords, TileBounds, TileCoord, CHUNK_SIZE, MAX_TILE_DISTANCE};
+use automancy_defs::flexstr::ToSharedStr;
+use automancy_defs::glam::dvec2;
+use automancy_defs::id::{id, namespace_counts, Id, Interner};
+use automancy_defs::math::Matrix4;
+use automancy_defs::rendering::{InstanceData, MatrixBuffers, RawInstanceData};
+use automancy_resources::data::inventory::Inventory;
+use automancy_resources::data::schema::DataKind;
+use automancy_resources::data::stack::ItemAmount;
+use automancy_resources::data::{Data, DataMap, DataMapRaw};
+use automancy_resources::types::shader::{preprocess_shader, ShaderIncludeError};
+use automancy_resources::types::translate::{Locale, DEFAULT_LANGUAGE};
+use automancy_resources::ResourceManager;
+
+pub mod golden;
 pub mod macros;
 
 #[test]
@@ -12,3 +51,1030 @@ fn test_tile_coord_serde() {
 
     assert_eq!(c, deserialized);
 }
+
+#[test]
+fn test_packed_coords() {
+    let coords = vec![
+        TileCoord::new(0, 0),
+        TileCoord::new(1, 0),
+        TileCoord::new(-5, 3),
+        TileCoord::new(-5, -300),
+        TileCoord::new(MAX_TILE_DISTANCE as i32, -(MAX_TILE_DISTANCE as i32)),
+    ];
+
+    let packed = PackedCoords::pack(coords.clone());
+    assert_eq!(packed.unpack(), Some(coords.clone()));
+
+    // tiles in a row take a byte for each of x and y
+    assert_eq!(
+        PackedCoords::pack((0..10).map(|x| TileCoord::new(x, 0)))
+            .as_bytes()
+            .len(),
+        20
+    );
+
+    let bytes = packed.as_bytes();
+    let cut = ron::to_string(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(ron::from_str::<PackedCoords>(&cut).unwrap().unpack(), None);
+}
+
+#[test]
+fn test_map_raw_serde() {
+    let storage = id("automancy", "small_storage");
+
+    let mut map = MapRaw::new();
+    map.tile_map.insert(Id::from(0), storage.clone());
+    for coord in [
+        TileCoord::new(2, 1),
+        TileCoord::new(-1, 1),
+        TileCoord::new(0, -3),
+    ] {
+        map.tiles.push((coord, Id::from(0), Default::default()));
+    }
+
+    let read = ron::from_str::<MapRaw>(&ron::to_string(&map).unwrap()).unwrap();
+    // saved row by row
+    assert_eq!(
+        read.tiles
+            .iter()
+            .map(|(coord, ..)| *coord)
+            .collect::<Vec<_>>(),
+        vec![
+            TileCoord::new(0, -3),
+            TileCoord::new(-1, 1),
+            TileCoord::new(2, 1)
+        ]
+    );
+    assert_eq!(read.tile_map[&Id::from(0)], storage);
+
+    // saved before the coordinates were packed
+    let tiles =
+        ron::to_string(&[(TileCoord::new(4, 5), Id::from(0), DataMapRaw::default())]).unwrap();
+    let old = ron::from_str::<MapRaw>(&format!(
+        "(version: 1, tiles: {tiles}, tile_map: {{0: \"automancy:small_storage\"}})"
+    ))
+    .unwrap();
+    assert_eq!(old.tiles.len(), 1);
+    assert_eq!(old.tiles[0].0, TileCoord::new(4, 5));
+}
+
+#[test]
+fn test_namespace_counts() {
+    let mut interner = Interner::new();
+    id("automancy", "node").to_id(&mut interner);
+    id("automancy", "small_storage").to_id(&mut interner);
+    id("some_mod", "machine").to_id(&mut interner);
+
+    let counts = namespace_counts(&interner);
+    assert_eq!(counts["automancy"], 2);
+    assert_eq!(counts["some_mod"], 1);
+}
+
+#[test]
+fn test_lod_model() {
+    let mut resource_man = ResourceManager::new();
+    let [model, near, far, missing] = ["model", "near", "far", "missing"]
+        .map(|name| id("test", name).to_id(&mut resource_man.interner));
+
+    for model in [model, near, far] {
+        resource_man.all_models.insert(model, Default::default());
+    }
+    resource_man
+        .model_lods
+        .insert(model, vec![(10.0, near), (20.0, far), (30.0, missing)]);
+
+    assert_eq!(resource_man.lod_model(model, 5.0), model);
+    assert_eq!(resource_man.lod_model(model, 10.0), near);
+    assert_eq!(resource_man.lod_model(model, 25.0), far);
+    // levels of detail that weren't loaded fall back to the next one in
+    assert_eq!(resource_man.lod_model(model, 40.0), far);
+    assert_eq!(resource_man.lod_model(near, 40.0), near);
+}
+
+#[test]
+fn test_preprocess_shader() {
+    let snippets = [
+        ("core:a", "#include \"core:b\"\nfn a() {}"),
+        ("core:b", "fn b() {}"),
+        ("core:loop", "#include \"core:loop\""),
+    ]
+    .into_iter()
+    .map(|(name, source)| (name.to_shared_str(), source.to_string()))
+    .collect::<HashMap<_, _>>();
+
+    // b is included by both the shader and a, but only ends up in it once
+    let result = preprocess_shader(
+        "test",
+        "#include \"core:b\"\n#include \"core:a\"\nfn main() {}",
+        &snippets,
+    )
+    .unwrap();
+    assert_eq!(result, "fn b() {}\nfn a() {}\nfn main() {}\n");
+
+    assert_eq!(
+        preprocess_shader("test", "#include \"core:missing\"", &snippets),
+        Err(ShaderIncludeError::UnknownSnippet {
+            shader: "test".to_string(),
+            snippet: "core:missing".to_string(),
+        })
+    );
+    assert!(matches!(
+        preprocess_shader("test", "#include \"core:loop\"", &snippets),
+        Err(ShaderIncludeError::Cycle { .. })
+    ));
+    assert!(matches!(
+        preprocess_shader("test", "#include core:a", &snippets),
+        Err(ShaderIncludeError::Malformed { .. })
+    ));
+}
+
+#[test]
+fn test_tile_coord_world_bounds() {
+    let edge = TileCoord::new(MAX_TILE_DISTANCE as i32, 0);
+
+    assert!(TileCoord::ZERO.is_in_world());
+    assert!(edge.is_in_world());
+    assert!(!(edge + TileCoord::RIGHT).is_in_world());
+}
+
+#[test]
+fn test_tile_coord_shapes() {
+    let center = TileCoord::new(3, -2);
+
+    assert_eq!(TileCoord::RIGHT.rotate(1), TileCoord::BOTTOM_RIGHT);
+    assert_eq!(TileCoord::RIGHT.rotate(-1), TileCoord::TOP_RIGHT);
+    assert_eq!(TileCoord::RIGHT.rotate(6), TileCoord::RIGHT);
+    assert_eq!(
+        (center + TileCoord::LEFT).rotate_around(center, 3),
+        center + TileCoord::RIGHT
+    );
+
+    assert_eq!(center.ring(0).collect::<Vec<_>>(), vec![center]);
+    for radius in 1..4 {
+        let ring = center.ring(radius).collect::<HashSet<_>>();
+
+        assert_eq!(ring.len(), 6 * radius as usize);
+        assert!(ring.iter().all(|v| v.distance(center) == radius));
+    }
+
+    let spiral = center.spiral(3).collect::<Vec<_>>();
+    assert_eq!(spiral[0], center);
+    assert_eq!(
+        spiral.iter().copied().collect::<HashSet<_>>(),
+        center.hexagon(3).collect::<HashSet<_>>()
+    );
+
+    let end = center + TileCoord::new(4, 1);
+    let line = center.line_to(end);
+    assert_eq!(line.len(), 6);
+    assert_eq!((line[0], line[5]), (center, end));
+    assert!(line.windows(2).all(|v| v[0].distance(v[1]) == 1));
+
+    // everything within 2 tiles, except a wall cutting off the right side
+    let filled = center.flood_fill(100, |v| v.distance(center) <= 2 && v.x != center.x + 1);
+    assert_eq!(filled[0], center);
+    assert!(filled.iter().all(|v| v.x <= center.x));
+    assert_eq!(center.flood_fill(3, |_| true).len(), 3);
+    assert!(center.flood_fill(100, |_| false).is_empty());
+}
+
+#[test]
+fn test_tile_coord_route_around_wall() {
+    let wall = [
+        TileCoord::new(1, -1),
+        TileCoord::new(1, 0),
+        TileCoord::new(0, 1),
+    ];
+    let destination = TileCoord::new(2, 0);
+
+    let path = TileCoord::ZERO
+        .route_to(destination, 4, |coord| !wall.contains(&coord))
+        .unwrap();
+
+    assert_eq!(path.first(), Some(&TileCoord::ZERO));
+    assert_eq!(path.last(), Some(&destination));
+    assert!(path.iter().all(|coord| !wall.contains(coord)));
+    assert!(path.windows(2).all(|w| w[0].neighbors().contains(&w[1])));
+
+    let enclosed = TileCoord::ZERO.route_to(destination, 4, |coord| {
+        !destination.neighbors().contains(&coord)
+    });
+
+    assert_eq!(enclosed, None);
+}
+
+#[test]
+fn test_tile_bounds_chunks_cover_bounds() {
+    let bounds = TileBounds::new(TileCoord::new(-20, 37), 25);
+    let chunks = bounds.chunks().collect::<Vec<_>>();
+
+    for coord in bounds {
+        assert!(chunks.contains(&coord.chunk()));
+    }
+}
+
+#[test]
+fn test_tiles_chunks() {
+    let a = TileCoord::new(0, 0);
+    let b = TileCoord::new(1, 2);
+    let c = TileCoord::new(CHUNK_SIZE * 3, -1);
+
+    let mut tiles = Tiles::default();
+    tiles.insert(a, Id::from(1));
+    tiles.insert(b, Id::from(2));
+    tiles.insert(c, Id::from(3));
+    assert_eq!(tiles.insert(b, Id::from(4)), Some(Id::from(2)));
+
+    assert_eq!(tiles.len(), 3);
+    assert_eq!(tiles.chunks().count(), 2);
+    assert_eq!(tiles.chunk(a.chunk()).unwrap().len(), 2);
+    assert_eq!(tiles.get(&b), Some(&Id::from(4)));
+
+    // empty chunks are dropped
+    tiles.remove(&c);
+    assert_eq!(tiles.chunks().count(), 1);
+
+    assert_eq!(tiles.unload_chunk(a.chunk()).unwrap().len(), 2);
+    assert!(tiles.is_empty());
+    assert_eq!(tiles.get(&a), None);
+}
+
+#[test]
+fn test_map_raw_merge_renumbers() {
+    let storage = id("automancy", "small_storage");
+    let node = id("automancy", "node");
+
+    let mut map = MapRaw::new();
+    map.tile_map.insert(Id::from(0), storage.clone());
+    map.tiles
+        .push((TileCoord::new(0, 0), Id::from(0), Default::default()));
+
+    // saved at another time, where the same number meant a different tile
+    let mut other = MapRaw::new();
+    other.tile_map.insert(Id::from(0), node.clone());
+    other.tile_map.insert(Id::from(1), storage.clone());
+    other
+        .tiles
+        .push((TileCoord::new(40, 0), Id::from(0), Default::default()));
+    other
+        .tiles
+        .push((TileCoord::new(41, 0), Id::from(1), Default::default()));
+
+    map.merge(other);
+
+    let names = map
+        .tiles
+        .iter()
+        .map(|(coord, id, _)| (*coord, map.tile_map[id].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            (TileCoord::new(0, 0), storage.clone()),
+            (TileCoord::new(40, 0), node),
+            (TileCoord::new(41, 0), storage),
+        ]
+    );
+}
+
+#[test]
+fn test_balancer_spreads_evenly() {
+    let outputs = [TileCoord::LEFT, TileCoord::RIGHT, TileCoord::TOP_LEFT];
+    let mut balancer = Balancer::default();
+
+    let mut counts = [0; 3];
+    for _ in 0..30 {
+        let picked = balancer.pick(&outputs, 1).unwrap();
+        counts[outputs.iter().position(|v| *v == picked).unwrap()] += 1;
+    }
+    assert_eq!(counts, [10, 10, 10]);
+
+    // an output that was missing for a while only catches up by one stack
+    for _ in 0..10 {
+        balancer.pick(&outputs[..2], 1).unwrap();
+    }
+    let picked = (0..3)
+        .map(|_| balancer.pick(&outputs, 1).unwrap())
+        .collect::<Vec<_>>();
+    assert!(picked.iter().filter(|v| **v == TileCoord::TOP_LEFT).count() < 3);
+
+    assert_eq!(balancer.pick(&[], 1), None);
+}
+
+#[test]
+fn test_color_srgb_round_trip() {
+    for v in [0, 5, 10, 11, 128, 200, 255] {
+        let color = Rgba::from_srgba_unmultiplied(v, 255 - v, v / 2, 128);
+        assert_eq!(color.to_srgba_unmultiplied(), [v, 255 - v, v / 2, 128]);
+    }
+
+    assert_eq!(Rgba::default().to_srgba_unmultiplied(), [0; 4]);
+}
+
+#[test]
+fn test_matrix_buffers_dedup() {
+    let camera = Matrix4::from_scale([2.0; 3].into());
+    let mut buffers = MatrixBuffers::default();
+
+    for x in 0..4 {
+        let instance = InstanceData::default()
+            .with_world_matrix(camera)
+            .with_model_matrix(Matrix4::from_translation([(x % 2) as f32, 0.0, 0.0].into()));
+
+        RawInstanceData::from_instance(instance, &mut buffers);
+    }
+
+    assert_eq!(buffers.world_matrix_data.len(), 1);
+    assert_eq!(buffers.matrix_data.len(), 2);
+}
+
+/// Places an extractor of white matter at `extractor`, feeding a storage that holds up to `capacity` of it at `storage`.
+fn place_extractor_and_storage(
+    sim: &mut Simulation,
+    extractor: TileCoord,
+    storage: TileCoord,
+    capacity: ItemAmount,
+) -> &mut Simulation {
+    let data_ids = sim.resource_man.registry.data_ids;
+    let white_matter = sim.id("automancy:white_matter");
+
+    let mut extractor_data = DataMap::default();
+    extractor_data.insert(data_ids.script, Data::Id(white_matter));
+    extractor_data.insert(data_ids.target, Data::Coord(storage - extractor));
+
+    let mut storage_data = DataMap::default();
+    storage_data.insert(data_ids.item, Data::Id(white_matter));
+    storage_data.insert(data_ids.amount, Data::Amount(capacity));
+
+    sim.place(extractor, "automancy:basic_extractor", extractor_data)
+        .place(storage, "automancy:small_storage", storage_data)
+}
+
+#[test]
+fn test_simulation_extractor_fills_storage() {
+    let (resource_man, ..) = load_resources();
+    let mut sim = Simulation::new(resource_man).unwrap();
+
+    let storage = TileCoord::new(1, 0);
+
+    place_extractor_and_storage(&mut sim, TileCoord::new(0, 0), storage, 64).run_ticks(100);
+
+    // the extractor makes 32 every tick, and the storage stops taking them once it's full
+    assert_eq!(sim.item_amount(storage, "automancy:white_matter"), 64);
+}
+
+#[test]
+fn test_simulation_transaction_record_limit() {
+    let (resource_man, ..) = load_resources();
+
+    let storage = TileCoord::new(1, 0);
+
+    let mut limited = Simulation::new(resource_man.clone()).unwrap();
+    limited.set_transaction_record_limit(0);
+    place_extractor_and_storage(&mut limited, TileCoord::new(0, 0), storage, 64).run_ticks(10);
+
+    let mut unlimited = Simulation::new(resource_man).unwrap();
+    place_extractor_and_storage(&mut unlimited, TileCoord::new(0, 0), storage, 64).run_ticks(10);
+
+    // the same items move either way, but with a limit of 0 the records are dropped as soon as they are made,
+    // along with their edges
+    assert!(!unlimited.transaction_records().is_empty());
+    assert!(limited.transaction_records().is_empty());
+    assert_eq!(
+        limited.item_amount(storage, "automancy:white_matter"),
+        unlimited.item_amount(storage, "automancy:white_matter")
+    );
+}
+
+#[test]
+fn test_simulation_transaction_record_limit_keeps_newest() {
+    let (resource_man, ..) = load_resources();
+
+    let place = |sim: &mut Simulation| {
+        // several extractors, so each tick makes several records at the same time
+        for i in 0..4 {
+            place_extractor_and_storage(
+                sim,
+                TileCoord::new(0, i * 3),
+                TileCoord::new(1, i * 3),
+                32 * 100,
+            );
+        }
+    };
+
+    let mut unlimited = Simulation::new(resource_man.clone()).unwrap();
+    place(&mut unlimited);
+    unlimited.run_ticks(10);
+
+    let records = unlimited.transaction_records();
+    let count = records.values().map(|v| v.len()).sum::<usize>();
+
+    // not a multiple of the records made in a tick, so the limit falls in the middle of one
+    let limit = 7;
+    assert!(count > limit);
+
+    let mut limited = Simulation::new(resource_man).unwrap();
+    limited.set_transaction_record_limit(limit);
+    place(&mut limited);
+    limited.run_ticks(10);
+
+    let kept = limited.transaction_records();
+
+    assert_eq!(kept.values().map(|v| v.len()).sum::<usize>(), limit);
+    assert_eq!(
+        kept.values().flatten().map(|(time, _)| *time).max(),
+        records.values().flatten().map(|(time, _)| *time).max()
+    );
+}
+
+#[test]
+fn test_simulation_transaction_records_per_edge() {
+    let (resource_man, ..) = load_resources();
+    let mut sim = Simulation::new(resource_man).unwrap();
+
+    // with room for everything the extractor makes, items are still moving when the records are checked
+    place_extractor_and_storage(
+        &mut sim,
+        TileCoord::new(0, 0),
+        TileCoord::new(1, 0),
+        32 * 100,
+    )
+    .run_ticks(100);
+
+    let records = sim.transaction_records();
+
+    assert!(!records.is_empty());
+
+    for records in records.values() {
+        assert!(!records.is_empty());
+        assert!(records.len() <= MAX_TRANSACTION_RECORDS_PER_EDGE);
+    }
+}
+
+#[test]
+fn test_simulation_is_deterministic() {
+    let (resource_man, ..) = load_resources();
+
+    let run = || {
+        let mut sim = Simulation::new(resource_man.clone()).unwrap();
+
+        place_extractor_and_storage(&mut sim, TileCoord::new(0, 0), TileCoord::new(1, 0), 64);
+
+        (0..10)
+            .map(|_| sim.run_ticks(5).state_hash())
+            .collect::<Vec<_>>()
+    };
+
+    let hashes = run();
+    assert_eq!(hashes, run());
+    assert_ne!(
+        hashes[0],
+        Simulation::new(resource_man.clone()).unwrap().state_hash()
+    );
+
+    let a = "map test\n1 00000000000000aa\n2 00000000000000bb\n3 00000000000000cc\n";
+    let b = "map test\n1 00000000000000aa\n2 00000000000000bf\n3 00000000000000cc\n";
+    assert_eq!(compare_hash_logs(a, a), Comparison::Same { ticks: 3 });
+    assert_eq!(
+        compare_hash_logs(a, b),
+        Comparison::Diverged {
+            map: "test".to_string(),
+            tick: 2
+        }
+    );
+}
+
+#[test]
+fn test_blueprint_round_trip() {
+    let (resource_man, ..) = load_resources();
+    let data_ids = resource_man.registry.data_ids;
+    let extractor = resource_man
+        .interner
+        .get("automancy:basic_extractor")
+        .unwrap();
+    let storage = resource_man
+        .interner
+        .get("automancy:small_storage")
+        .unwrap();
+
+    let mut extractor_data = DataMap::default();
+    extractor_data.insert(data_ids.target, Data::Coord(TileCoord::new(1, 0)));
+    extractor_data.insert(data_ids.buffer, Data::Amount(5));
+
+    let blueprint = Blueprint::new(
+        &resource_man,
+        vec![
+            (TileCoord::new(10, 10), extractor, extractor_data),
+            (TileCoord::new(11, 10), storage, DataMap::default()),
+        ],
+    );
+    let string = blueprint.encode(&resource_man);
+    assert!(string.starts_with("automancy-bp:1:"));
+
+    let mut tiles = Blueprint::decode(&resource_man, &format!("  {string}\n"))
+        .unwrap()
+        .tiles;
+    tiles.sort_by_key(|(coord, ..)| (coord.x, coord.y));
+
+    // moved to be around the center, and without the held items
+    assert_eq!(tiles.len(), 2);
+    assert_eq!(tiles[1].0 - tiles[0].0, TileCoord::new(1, 0));
+    assert_eq!((tiles[0].1, tiles[1].1), (extractor, storage));
+    assert_eq!(
+        tiles[0].2.get(&data_ids.target),
+        Some(&Data::Coord(TileCoord::new(1, 0)))
+    );
+    assert!(tiles[0].2.get(&data_ids.buffer).is_none());
+
+    assert!(matches!(
+        Blueprint::decode(&resource_man, "hello"),
+        Err(BlueprintError::NotABlueprint)
+    ));
+    assert!(matches!(
+        Blueprint::decode(&resource_man, "automancy-bp:99:AAAA"),
+        Err(BlueprintError::UnsupportedVersion(99))
+    ));
+}
+
+#[test]
+fn test_scenario_tick() {
+    let (resource_man, ..) = load_resources();
+    let data_ids = resource_man.registry.data_ids;
+    let white_matter = resource_man.interner.get("automancy:white_matter").unwrap();
+
+    let new_scenario = || Scenario {
+        victory: vec![Condition::DeliverItems(white_matter, 8)],
+        defeat: vec![Condition::TimeLimit(1)],
+        ..Default::default()
+    };
+    let player_data = |amount| {
+        let mut inventory = Inventory::default();
+        inventory.insert(white_matter, amount);
+
+        let mut data = DataMap::default();
+        data.insert(data_ids.player_inventory, Data::Inventory(inventory));
+        data
+    };
+
+    let mut scenario = new_scenario();
+
+    scenario.tick(&resource_man, &player_data(4));
+    assert_eq!(scenario.outcome, None);
+
+    scenario.tick(&resource_man, &player_data(8));
+    assert_eq!(scenario.outcome, Some(ScenarioOutcome::Victory));
+
+    // nothing changes once the scenario has ended
+    scenario.tick(&resource_man, &player_data(0));
+    assert_eq!(scenario.outcome, Some(ScenarioOutcome::Victory));
+    assert_eq!(scenario.elapsed_ticks, 2);
+
+    let mut scenario = new_scenario();
+
+    for _ in 1..TPS {
+        scenario.tick(&resource_man, &player_data(4));
+    }
+    assert_eq!(scenario.outcome, None);
+
+    scenario.tick(&resource_man, &player_data(4));
+    assert_eq!(scenario.outcome, Some(ScenarioOutcome::Defeat));
+}
+
+#[test]
+fn test_map_templates() {
+    let (resource_man, ..) = load_resources();
+
+    let template = &resource_man.map_templates["automancy:first_delivery"];
+    let info: MapInfoRaw =
+        ron::from_str(&fs::read_to_string(template.join("info.ron")).unwrap()).unwrap();
+    let info = info.to_info(&resource_man, None);
+
+    assert!(info.template);
+    assert_eq!(info.scenario.unwrap().victory.len(), 1);
+}
+
+#[test]
+fn test_map_reads_plain_info() {
+    let (resource_man, ..) = load_resources();
+
+    // the shipped templates have plain RON info, which maps made from them start out with
+    let name = ".test_plain_info";
+    drop(fs::remove_dir_all(Map::path(name)));
+    Map::create_from_template(
+        &resource_man.map_templates["automancy:first_delivery"],
+        name,
+    )
+    .unwrap();
+
+    let info = Map::read_info(&resource_man, name).map(|(info, _)| info);
+    fs::remove_dir_all(Map::path(name)).unwrap();
+
+    assert!(info.unwrap().template);
+}
+
+#[test]
+fn test_migrate_unversioned_map_info() {
+    let mut info: MapInfoRaw = ron::from_str(
+        r#"(data: {"automancy:unlocked_researches": VecId(["automancy:extractor"])})"#,
+    )
+    .unwrap();
+    assert_eq!(info.version, 0);
+
+    info.migrate();
+
+    // the player's data was moved out of the map data
+    assert_eq!(info.version, MAP_VERSION);
+    assert!(info.data.is_empty());
+    assert!(info.players[LOCAL_PLAYER].contains_key("automancy:unlocked_researches"));
+}
+
+#[test]
+fn test_suggest_tiles() {
+    let (resource_man, ..) = load_resources();
+    let data_ids = resource_man.registry.data_ids;
+    let extractor = resource_man
+        .interner
+        .get("automancy:basic_extractor")
+        .unwrap();
+    let node = resource_man.interner.get("automancy:node").unwrap();
+    let storage = resource_man
+        .interner
+        .get("automancy:small_storage")
+        .unwrap();
+
+    // an extractor to the left, pointing at the empty position
+    let mut data = DataMap::default();
+    data.insert(data_ids.target, Data::Coord(TileCoord::RIGHT));
+
+    let suggestions = suggest_tiles(&resource_man, &[(TileCoord::LEFT, extractor, data)]);
+    let ids = suggestions.iter().map(|v| v.id).collect::<Vec<_>>();
+
+    assert_eq!(ids.first(), Some(&node));
+    assert_eq!(
+        suggestions[0].data.get(&data_ids.target),
+        Some(&Data::Coord(TileCoord::RIGHT))
+    );
+    assert!(ids.contains(&storage));
+
+    assert!(suggest_tiles(&resource_man, &[]).is_empty());
+}
+
+#[test]
+fn test_data_schema() {
+    let (resource_man, ..) = load_resources();
+    let data_ids = resource_man.registry.data_ids;
+    let schema = &resource_man.registry.data_schema;
+    let interner = &resource_man.interner;
+
+    // the flags of the tiles that ship with the game all match the schema, so none of them were dropped
+    let storage = &resource_man.registry.tiles[&interner.get("automancy:small_storage").unwrap()];
+    assert!(storage.data.flag(data_ids.storage_takeable));
+    assert_eq!(storage.data.get_amount(data_ids.max_amount), Some(65536));
+
+    let mut data = DataMap::default();
+    data.insert(data_ids.target, Data::Coord(TileCoord::RIGHT));
+    data.insert(data_ids.amount, Data::Bool(true));
+    // not in the schema, so it can hold anything
+    data.insert(
+        interner.get("automancy:basic_extractor").unwrap(),
+        Data::Bool(true),
+    );
+
+    let errors = schema.validate(interner, &data);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key, "automancy:amount");
+    assert_eq!(errors[0].found, DataKind::Bool);
+    assert_eq!(errors[0].expected, DataKind::Amount);
+
+    assert_eq!(schema.retain_valid(interner, &mut data).len(), 1);
+    assert_eq!(data.len(), 2);
+    assert_eq!(data.get_coord(data_ids.target), Some(TileCoord::RIGHT));
+    assert_eq!(data.get_amount(data_ids.amount), None);
+}
+
+#[test]
+fn test_zones() {
+    let mut zones = Zones::default();
+    let farm = zones.add("Farm".to_string());
+    let smelting = zones.add("Smelting".to_string());
+    assert_ne!(
+        zones.get(farm).unwrap().color,
+        zones.get(smelting).unwrap().color
+    );
+
+    let coord = TileCoord::new(3, -2);
+    zones.paint(farm, coord);
+    assert_eq!(zones.zone_at(coord), Some(farm));
+
+    // a position is only ever in one zone
+    zones.paint(smelting, coord);
+    assert_eq!(zones.zone_at(coord), Some(smelting));
+    assert!(!zones.get(farm).unwrap().contains(coord));
+
+    let tiles = [(coord, 1), (TileCoord::new(0, 0), 2)];
+    let filtered = zones
+        .get(smelting)
+        .unwrap()
+        .filter(tiles)
+        .collect::<Vec<_>>();
+    assert_eq!(filtered, vec![(coord, 1)]);
+
+    let raw = ron::to_string(&zones).unwrap();
+    assert_eq!(ron::from_str::<Zones>(&raw).unwrap(), zones);
+
+    zones.erase(coord);
+    assert_eq!(zones.zone_at(coord), None);
+
+    zones.remove(farm);
+    assert_eq!(zones.get(0).unwrap().name, "Smelting");
+}
+
+#[test]
+fn test_markers() {
+    let mut markers = Markers::default();
+    let base = markers.add(TileCoord::new(0, 0), "Base".to_string());
+    let mine = markers.add(TileCoord::new(40, -12), "Mine".to_string());
+    assert_ne!(
+        markers.get(base).unwrap().color,
+        markers.get(mine).unwrap().color
+    );
+
+    let raw = ron::to_string(&markers).unwrap();
+    assert_eq!(ron::from_str::<Markers>(&raw).unwrap(), markers);
+
+    // markers saved without an icon get the default one
+    let coord = ron::to_string(&TileCoord::new(3, 4)).unwrap();
+    let raw = format!(r#"[(coord: {coord}, label: "Old", color: (255, 0, 0))]"#);
+    let old = ron::from_str::<Markers>(&raw).unwrap();
+    assert_eq!(old.get(0).unwrap().icon, MarkerIcon::Circle);
+
+    markers.remove(base);
+    assert_eq!(markers.get(0).unwrap().label, "Mine");
+    assert!(markers.remove(5).is_none());
+}
+
+#[test]
+fn test_undo_redo() {
+    let (resource_man, ..) = load_resources();
+    let mut sim = Simulation::new(resource_man).unwrap();
+
+    let coord = TileCoord::new(2, 3);
+    let storage = sim.id("automancy:small_storage");
+
+    sim.place(coord, "automancy:small_storage", DataMap::default());
+    assert_eq!(sim.tile(coord), Some(storage));
+
+    sim.undo();
+    assert_eq!(sim.tile(coord), None);
+
+    sim.redo();
+    assert_eq!(sim.tile(coord), Some(storage));
+
+    // the redone change can be undone again, and there is nothing left to redo after a new change
+    sim.undo();
+    assert_eq!(sim.tile(coord), None);
+
+    sim.place(
+        TileCoord::new(0, 0),
+        "automancy:small_storage",
+        DataMap::default(),
+    )
+    .redo();
+    assert_eq!(sim.tile(coord), None);
+}
+
+#[test]
+fn test_undo_placed_tiles_together() {
+    let (resource_man, ..) = load_resources();
+    let mut sim = Simulation::new(resource_man).unwrap();
+
+    let storage = sim.id("automancy:small_storage");
+    let brush = TileCoord::new(0, 0).hexagon(2).collect::<Vec<_>>();
+
+    sim.place_tiles(brush.clone(), "automancy:small_storage", DataMap::default());
+    assert!(brush.iter().all(|coord| sim.tile(*coord) == Some(storage)));
+
+    sim.undo();
+    assert!(brush.iter().all(|coord| sim.tile(*coord).is_none()));
+
+    sim.redo();
+    assert!(brush.iter().all(|coord| sim.tile(*coord) == Some(storage)));
+}
+
+#[test]
+fn test_move_tiles() {
+    let (resource_man, ..) = load_resources();
+    let mut sim = Simulation::new(resource_man).unwrap();
+
+    let storage = sim.id("automancy:small_storage");
+    let (a, b) = (TileCoord::new(0, 0), TileCoord::new(1, 0));
+
+    sim.place(a, "automancy:small_storage", DataMap::default())
+        .place(b, "automancy:small_storage", DataMap::default());
+
+    // a tile can't be moved onto one that isn't moved along with it
+    sim.move_tiles(vec![a], TileCoord::RIGHT);
+    assert_eq!(sim.tile(a), Some(storage));
+    assert_eq!(sim.tile(b), Some(storage));
+
+    sim.move_tiles(vec![a, b], TileCoord::RIGHT);
+    assert_eq!(sim.tile(a), None);
+    assert_eq!(sim.tile(b + TileCoord::RIGHT), Some(storage));
+
+    sim.undo();
+    assert_eq!(sim.tile(a), Some(storage));
+    assert_eq!(sim.tile(b + TileCoord::RIGHT), None);
+
+    // nothing moves if any of the tiles would leave the world
+    let edge = TileCoord::new(MAX_TILE_DISTANCE as i32, 0);
+    sim.place(edge, "automancy:small_storage", DataMap::default())
+        .move_tiles(vec![a, edge], TileCoord::RIGHT);
+    assert_eq!(sim.tile(a), Some(storage));
+    assert_eq!(sim.tile(edge), Some(storage));
+}
+
+#[test]
+fn test_clock_stops() {
+    let clock = Clock::default();
+    let shared = clock.clone();
+
+    shared.set_running(false);
+    assert!(!clock.is_running());
+
+    let stopped_at = clock.now();
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(clock.now(), stopped_at);
+
+    shared.set_running(true);
+    thread::sleep(Duration::from_millis(20));
+    assert!(clock.now() > stopped_at);
+}
+
+#[test]
+fn test_tick_interpolation() {
+    let stamp = TickStamp {
+        simulated: TICK_INTERVAL * 10,
+        at: Duration::from_secs(5),
+    };
+    let interpolated = |since_tick: f64| {
+        stamp
+            .interpolate(stamp.at + TICK_INTERVAL.mul_f64(since_tick))
+            .as_secs_f64()
+            / TICK_INTERVAL.as_secs_f64()
+    };
+
+    // drawn a tick behind, catching up to the last tick as the next one is due
+    assert!((interpolated(0.0) - 9.0).abs() < 1e-3);
+    assert!((interpolated(0.5) - 9.5).abs() < 1e-3);
+    assert!((interpolated(1.0) - 10.0).abs() < 1e-3);
+
+    // holding still while the next tick is late
+    assert!((interpolated(3.0) - 10.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_rebind_swaps_conflicting_actions() {
+    let mut input_handler = InputHandler::new(&Options::default());
+
+    let undo = input_handler.binding_for(KeyActions::Undo).unwrap();
+    let route = input_handler.binding_for(KeyActions::Route).unwrap();
+
+    input_handler.rebind(KeyActions::Undo, route.clone());
+
+    assert_eq!(input_handler.binding_for(KeyActions::Undo), Some(route));
+    assert_eq!(input_handler.binding_for(KeyActions::Route), Some(undo));
+
+    input_handler.rebind(KeyActions::Markers, Binding::Mouse(MouseButton::Back));
+
+    assert_eq!(
+        input_handler.binding_for(KeyActions::Markers),
+        Some(Binding::Mouse(MouseButton::Back))
+    );
+    assert_eq!(
+        input_handler.key_map.len() + input_handler.mouse_map.len(),
+        KeyActions::all().count()
+    );
+
+    // escape cancels capturing, instead of being captured
+    input_handler.start_capture();
+    input_handler.update(GameInputEvent::KeyboardEvent {
+        event: KeyInput {
+            state: ElementState::Pressed,
+            logical_key: Key::Named(NamedKey::Escape),
+            repeat: false,
+        },
+    });
+
+    assert!(!input_handler.capturing());
+    assert_eq!(input_handler.take_captured(), None);
+    assert!(!input_handler.key_active(KeyActions::Escape));
+}
+
+#[test]
+fn test_options_reset_and_restore() {
+    let mut options = Options::default();
+    let on_open = options.clone();
+
+    options.graphics.bloom = !options.graphics.bloom;
+    options.audio.sfx_volume = 0.1;
+
+    assert!(!options.is_default("Bloom"));
+    assert!(options.differs_from(&on_open));
+
+    options.reset("Bloom");
+
+    assert!(options.is_default("Bloom"));
+    assert!(!options.is_default("SFX Volume"));
+
+    options.restore(&on_open);
+
+    assert!(!options.differs_from(&on_open));
+}
+
+#[test]
+fn test_touch_tap_pan_and_pinch() {
+    let mut input_handler = InputHandler::new(&Options::default());
+    let touch = |id, phase, x, y| GameInputEvent::Touch(id, phase, dvec2(x, y));
+
+    input_handler.update(touch(0, TouchPhase::Started, 100.0, 100.0));
+    input_handler.reset();
+    input_handler.update(touch(0, TouchPhase::Ended, 102.0, 100.0));
+    assert!(input_handler.main_pressed);
+    assert!(input_handler.touch_active);
+
+    input_handler.reset();
+    input_handler.update(touch(0, TouchPhase::Started, 100.0, 100.0));
+    input_handler.update(touch(0, TouchPhase::Moved, 150.0, 100.0));
+    assert_eq!(input_handler.touch_pan, Some(dvec2(-50.0, 0.0)));
+    input_handler.reset();
+    input_handler.update(touch(0, TouchPhase::Ended, 150.0, 100.0));
+    assert!(!input_handler.main_pressed);
+
+    input_handler.update(touch(0, TouchPhase::Started, 100.0, 100.0));
+    input_handler.update(touch(1, TouchPhase::Started, 200.0, 100.0));
+    input_handler.update(touch(1, TouchPhase::Moved, 210.0, 100.0));
+    input_handler.reset();
+    input_handler.update(touch(1, TouchPhase::Moved, 260.0, 100.0));
+    assert!(input_handler.scroll.is_some_and(|v| v.y > 0.0));
+}
+
+#[test]
+fn test_locale_number_formatting() {
+    let locale = Locale {
+        decimal_separator: ',',
+        group_separator: '.',
+    };
+
+    assert_eq!(locale.count(1234567), "1.234.567");
+    assert_eq!(locale.count(-1000), "-1.000");
+    assert_eq!(locale.count(999), "999");
+    assert_eq!(locale.decimal(1234.5, 2), "1.234,50");
+    assert_eq!(locale.compact(950.0), "950");
+    assert_eq!(locale.compact(12500.0), "12,5k");
+    assert_eq!(locale.compact(3_000_000.0), "3M");
+    assert_eq!(Locale::default().compact(1500.0), "1.5k");
+}
+
+#[test]
+fn test_input_playback() {
+    let mut player = InputPlayer::parse(
+        "(0.0, Key(state: Pressed, key: Character(\"1\"), repeat: false, text: Some(\"1\")))\n\
+         (0.0, CursorMoved((x: 10.0, y: 20.0)))\n\
+         (3600.0, Focused(false))\n",
+    )
+    .unwrap();
+
+    let due = player.due();
+    assert_eq!(due.len(), 2);
+    assert!(!player.is_finished());
+
+    let mut input_handler = InputHandler::new(&Options::default());
+
+    let RecordedInput::Key {
+        state, key, repeat, ..
+    } = due[0].clone()
+    else {
+        panic!("the first input should be the key");
+    };
+    input_handler.update(GameInputEvent::KeyboardEvent {
+        event: KeyInput {
+            state,
+            logical_key: key,
+            repeat,
+        },
+    });
+    assert!(input_handler.key_active(KeyActions::Hotbar(0)));
+
+    assert!(due[1].to_window_event().is_some());
+}
+
+#[test]
+fn test_languages_listed() {
+    let (resource_man, ..) = load_resources();
+
+    assert_eq!(resource_man.translates().language, DEFAULT_LANGUAGE);
+
+    let language = &resource_man.languages[DEFAULT_LANGUAGE];
+    assert_eq!(language.name, "English (US)");
+    assert!(language.namespaces.contains(&"automancy".to_string()));
+}